@@ -0,0 +1,61 @@
+use super::{parse_chunk, post, read_lines, Prompt, Provider};
+use crate::error::{AppError, AppResult};
+use serde_json::json;
+
+pub const DEFAULT_MODEL: &str = "claude-3-5-haiku-latest";
+pub const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+
+const API_VERSION: &str = "2023-06-01";
+
+/// Enough room for translating a full-length note
+const MAX_TOKENS: u32 = 8192;
+
+/// Anthropic Messages API
+pub struct Anthropic {
+    pub api_key: String,
+    pub model: String,
+    pub base_url: String,
+}
+
+impl Provider for Anthropic {
+    fn name(&self) -> &'static str {
+        "Anthropic"
+    }
+
+    fn stream(&self, agent: &ureq::Agent, prompt: &Prompt, on_delta: &mut dyn FnMut(&str)) -> AppResult<()> {
+        let body = json!({
+            "model": self.model,
+            "max_tokens": MAX_TOKENS,
+            "stream": true,
+            "system": prompt.system,
+            "messages": [{ "role": "user", "content": prompt.user }],
+        });
+        let request = agent
+            .post(&format!("{}/v1/messages", self.base_url))
+            .set("x-api-key", &self.api_key)
+            .set("anthropic-version", API_VERSION);
+        let response = post(request, self.name(), &body)?;
+
+        // Server-sent events; text arrives in content_block_delta events
+        read_lines(response.into_reader(), self.name(), |line| {
+            let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+                return Ok(true);
+            };
+            let event = parse_chunk(data, self.name())?;
+            match event["type"].as_str() {
+                Some("content_block_delta") => {
+                    if let Some(delta) = event["delta"]["text"].as_str() {
+                        on_delta(delta);
+                    }
+                    Ok(true)
+                }
+                Some("message_stop") => Ok(false),
+                Some("error") => Err(AppError::Internal(format!(
+                    "Anthropic stream failed: {}",
+                    event["error"]["message"].as_str().unwrap_or("unknown error")
+                ))),
+                _ => Ok(true),
+            }
+        })
+    }
+}
@@ -0,0 +1,319 @@
+// AI features (summarize, suggest tags, suggest a title) run through a
+// provider trait so the backend can be switched in settings
+
+mod anthropic;
+mod ollama;
+mod openai;
+pub mod transcribe;
+
+use crate::db::queries::AppSettings;
+use crate::error::{AppError, AppResult};
+use crate::secrets;
+use std::io::{BufRead, BufReader, Read};
+use std::time::Duration;
+
+/// Streamed completion text, emitted while a feature runs
+pub const STREAM_EVENT: &str = "ai-stream";
+
+/// Note content beyond this many characters is cut off before prompting
+const MAX_INPUT_CHARS: usize = 24_000;
+
+/// Most tags kept from a suggestion
+const MAX_TAGS: usize = 5;
+
+/// Longest suggested title
+const MAX_TITLE_CHARS: usize = 80;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Local models can take a while to load before the first token
+const READ_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Completion request sent to a provider
+pub struct Prompt {
+    pub system: String,
+    pub user: String,
+}
+
+/// A chat completion backend
+pub trait Provider: Send + Sync {
+    /// Display name for error messages
+    fn name(&self) -> &'static str;
+
+    /// Run a completion, passing each piece of text to `on_delta` as it arrives
+    fn stream(&self, agent: &ureq::Agent, prompt: &Prompt, on_delta: &mut dyn FnMut(&str)) -> AppResult<()>;
+}
+
+/// A text embedding backend, used for semantic search
+pub trait Embedder: Send + Sync {
+    /// Model id stored with each vector; vectors from other models are stale
+    fn model(&self) -> &str;
+
+    /// One vector per input text, in order
+    fn embed(&self, agent: &ureq::Agent, texts: &[String]) -> AppResult<Vec<Vec<f32>>>;
+}
+
+/// Provider choices for the `ai_provider` setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    None,
+    OpenAi,
+    Anthropic,
+    Ollama,
+}
+
+impl ProviderKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(Self::None),
+            "openai" => Some(Self::OpenAi),
+            "anthropic" => Some(Self::Anthropic),
+            "ollama" => Some(Self::Ollama),
+            _ => None,
+        }
+    }
+}
+
+/// Keychain entry holding a provider's API key (see `set_secret`)
+pub const OPENAI_KEY_SECRET: &str = "openai.api_key";
+pub const ANTHROPIC_KEY_SECRET: &str = "anthropic.api_key";
+
+fn api_key(name: &'static str, secret: &str) -> AppResult<String> {
+    secrets::get(secret)?.ok_or_else(|| AppError::Validation(format!("No {} API key is set", name)))
+}
+
+/// Pick the override when set, otherwise the provider default
+fn or_default(value: &str, default: &str) -> String {
+    match value.trim() {
+        "" => default.to_string(),
+        value => value.trim_end_matches('/').to_string(),
+    }
+}
+
+/// The provider chosen in settings
+pub fn provider(settings: &AppSettings) -> AppResult<Box<dyn Provider>> {
+    let model = settings.ai_model.as_str();
+    let base_url = settings.ai_base_url.as_str();
+    match ProviderKind::parse(&settings.ai_provider) {
+        Some(ProviderKind::OpenAi) => Ok(Box::new(openai::OpenAi {
+            api_key: api_key("OpenAI", OPENAI_KEY_SECRET)?,
+            model: or_default(model, openai::DEFAULT_MODEL),
+            base_url: or_default(base_url, openai::DEFAULT_BASE_URL),
+        })),
+        Some(ProviderKind::Anthropic) => Ok(Box::new(anthropic::Anthropic {
+            api_key: api_key("Anthropic", ANTHROPIC_KEY_SECRET)?,
+            model: or_default(model, anthropic::DEFAULT_MODEL),
+            base_url: or_default(base_url, anthropic::DEFAULT_BASE_URL),
+        })),
+        Some(ProviderKind::Ollama) => Ok(Box::new(ollama::Ollama {
+            model: or_default(model, ollama::DEFAULT_MODEL),
+            base_url: or_default(base_url, ollama::DEFAULT_BASE_URL),
+        })),
+        Some(ProviderKind::None) | None => Err(AppError::Validation(
+            "AI features are off; choose a provider in settings".to_string(),
+        )),
+    }
+}
+
+/// The embedding backend for the provider chosen in settings
+pub fn embedder(settings: &AppSettings) -> AppResult<Box<dyn Embedder>> {
+    let model = settings.embedding_model.as_str();
+    let base_url = settings.ai_base_url.as_str();
+    match ProviderKind::parse(&settings.ai_provider) {
+        Some(ProviderKind::OpenAi) => Ok(Box::new(openai::OpenAi {
+            api_key: api_key("OpenAI", OPENAI_KEY_SECRET)?,
+            model: or_default(model, openai::DEFAULT_EMBEDDING_MODEL),
+            base_url: or_default(base_url, openai::DEFAULT_BASE_URL),
+        })),
+        Some(ProviderKind::Ollama) => Ok(Box::new(ollama::Ollama {
+            model: or_default(model, ollama::DEFAULT_EMBEDDING_MODEL),
+            base_url: or_default(base_url, ollama::DEFAULT_BASE_URL),
+        })),
+        Some(ProviderKind::Anthropic) => Err(AppError::Validation(
+            "Anthropic has no embeddings API; use OpenAI or Ollama for semantic search".to_string(),
+        )),
+        Some(ProviderKind::None) | None => Err(AppError::Validation(
+            "AI features are off; choose a provider in settings".to_string(),
+        )),
+    }
+}
+
+/// HTTP agent for provider requests
+pub fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(CONNECT_TIMEOUT)
+        .timeout_read(READ_TIMEOUT)
+        .build()
+}
+
+/// Run a completion to the end, returning the full text
+pub fn complete(provider: &dyn Provider, prompt: &Prompt, on_delta: &mut dyn FnMut(&str)) -> AppResult<String> {
+    let agent = agent();
+    let mut text = String::new();
+    provider.stream(&agent, prompt, &mut |delta| {
+        text.push_str(delta);
+        on_delta(delta);
+    })?;
+    Ok(text.trim().to_string())
+}
+
+/// Send a JSON request, turning HTTP failures into readable errors
+fn post(request: ureq::Request, provider: &'static str, body: &serde_json::Value) -> AppResult<ureq::Response> {
+    request
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string())
+        .map_err(|e| post_error(provider, e))
+}
+
+/// Readable error for a failed request, including the server's explanation
+fn post_error(provider: &'static str, error: ureq::Error) -> AppError {
+    match error {
+        ureq::Error::Status(code, response) => {
+            let detail = response.into_string().unwrap_or_default();
+            AppError::Internal(format!("{} request failed with status {}: {}", provider, code, detail.trim()))
+        }
+        e => AppError::Internal(format!("Could not reach {}: {}", provider, e)),
+    }
+}
+
+/// Each line of a streamed response body, until `on_line` returns false
+fn read_lines(
+    reader: impl Read,
+    provider: &'static str,
+    mut on_line: impl FnMut(&str) -> AppResult<bool>,
+) -> AppResult<()> {
+    for line in BufReader::new(reader).lines() {
+        let line = line.map_err(|e| AppError::io(format!("{} stream was interrupted", provider), e))?;
+        if !on_line(line.trim_end())? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Read a whole JSON response
+fn read_json(response: ureq::Response, provider: &'static str) -> AppResult<serde_json::Value> {
+    let body = response
+        .into_string()
+        .map_err(|e| AppError::io(format!("Failed to read {} response", provider), e))?;
+    serde_json::from_str(&body).map_err(|e| AppError::Internal(format!("Unexpected {} response: {}", provider, e)))
+}
+
+/// Vectors from JSON number arrays
+fn parse_vectors<'a>(
+    items: impl Iterator<Item = &'a serde_json::Value>,
+    expected: usize,
+    provider: &'static str,
+) -> AppResult<Vec<Vec<f32>>> {
+    let vectors: Vec<Vec<f32>> = items
+        .filter_map(|item| {
+            let numbers = item.as_array()?;
+            numbers.iter().map(|n| n.as_f64().map(|n| n as f32)).collect()
+        })
+        .collect();
+    if vectors.len() != expected {
+        return Err(AppError::Internal(format!(
+            "{} returned {} embeddings for {} texts",
+            provider,
+            vectors.len(),
+            expected
+        )));
+    }
+    Ok(vectors)
+}
+
+/// Parse one line of a streamed response
+fn parse_chunk(data: &str, provider: &'static str) -> AppResult<serde_json::Value> {
+    serde_json::from_str(data)
+        .map_err(|e| AppError::Internal(format!("Unexpected {} stream data: {}", provider, e)))
+}
+
+/// Cut note content down to the prompt budget
+fn clip(content: &str) -> &str {
+    match content.char_indices().nth(MAX_INPUT_CHARS) {
+        Some((end, _)) => &content[..end],
+        None => content,
+    }
+}
+
+/// The AI features built on a provider
+#[derive(Debug, Clone)]
+pub enum Feature {
+    Summarize,
+    Tags,
+    Title,
+    /// Translate into the named language ("German", "pt-BR")
+    Translate(String),
+}
+
+impl Feature {
+    /// Command name used for usage metrics
+    pub fn command(&self) -> &'static str {
+        match self {
+            Self::Summarize => "ai_summarize",
+            Self::Tags => "ai_suggest_tags",
+            Self::Title => "ai_suggest_title",
+            Self::Translate(_) => "translate_buffer",
+        }
+    }
+
+    pub fn prompt(&self, content: &str) -> AppResult<Prompt> {
+        let system = match self {
+            Self::Summarize => "Summarize the user's note in a few sentences of plain Markdown. \
+                 Reply with the summary only."
+                .to_string(),
+            Self::Tags => "Suggest up to five short topic tags for the user's note. \
+                 Reply with the tags only, lowercase, separated by spaces, each starting with #."
+                .to_string(),
+            Self::Title => "Suggest a short, descriptive title for the user's note. \
+                 Reply with the title only, on one line, without quotes."
+                .to_string(),
+            Self::Translate(language) => {
+                // A clipped translation would silently lose the rest of the note
+                if content.chars().nth(MAX_INPUT_CHARS).is_some() {
+                    return Err(AppError::Validation(format!(
+                        "Note is too long to translate ({} characters max)",
+                        MAX_INPUT_CHARS
+                    )));
+                }
+                format!(
+                    "Translate the user's note into {}. Keep the Markdown formatting, code blocks, \
+                     [[wiki links]] and #tags unchanged. Reply with the translation only.",
+                    language
+                )
+            }
+        };
+        Ok(Prompt {
+            system,
+            user: clip(content).to_string(),
+        })
+    }
+}
+
+/// Tags from a suggestion reply ("#rust #cli, notes" -> rust, cli, notes)
+pub fn parse_tags(reply: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for word in reply.split(|c: char| c.is_whitespace() || c == ',') {
+        let tag = word.trim_start_matches('#').trim_end_matches('.').to_lowercase();
+        let valid = tag.chars().any(char::is_alphabetic)
+            && tag.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'));
+        if valid && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+        if tags.len() == MAX_TAGS {
+            break;
+        }
+    }
+    tags
+}
+
+/// Title from a suggestion reply: its first line, without heading marks or quotes
+pub fn parse_title(reply: &str) -> String {
+    let line = reply.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or_default();
+    let title = line
+        .trim_start_matches('#')
+        .trim()
+        .trim_matches(|c| matches!(c, '"' | '\'' | '“' | '”' | '*'))
+        .trim();
+    title.chars().take(MAX_TITLE_CHARS).collect()
+}
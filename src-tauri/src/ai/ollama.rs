@@ -0,0 +1,60 @@
+use super::{parse_chunk, parse_vectors, post, read_json, read_lines, Embedder, Prompt, Provider};
+use crate::error::{AppError, AppResult};
+use serde_json::json;
+
+pub const DEFAULT_MODEL: &str = "llama3.2";
+pub const DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
+pub const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+/// A local Ollama server; no API key, nothing leaves the machine
+pub struct Ollama {
+    pub model: String,
+    pub base_url: String,
+}
+
+impl Provider for Ollama {
+    fn name(&self) -> &'static str {
+        "Ollama"
+    }
+
+    fn stream(&self, agent: &ureq::Agent, prompt: &Prompt, on_delta: &mut dyn FnMut(&str)) -> AppResult<()> {
+        let body = json!({
+            "model": self.model,
+            "stream": true,
+            "messages": [
+                { "role": "system", "content": prompt.system },
+                { "role": "user", "content": prompt.user },
+            ],
+        });
+        let response = post(agent.post(&format!("{}/api/chat", self.base_url)), self.name(), &body)?;
+
+        // Newline-delimited JSON, the last object has `"done": true`
+        read_lines(response.into_reader(), self.name(), |line| {
+            if line.is_empty() {
+                return Ok(true);
+            }
+            let chunk = parse_chunk(line, self.name())?;
+            if let Some(error) = chunk["error"].as_str() {
+                return Err(AppError::Internal(format!("Ollama failed: {}", error)));
+            }
+            if let Some(delta) = chunk["message"]["content"].as_str() {
+                on_delta(delta);
+            }
+            Ok(!chunk["done"].as_bool().unwrap_or(false))
+        })
+    }
+}
+
+impl Embedder for Ollama {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&self, agent: &ureq::Agent, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        let body = json!({ "model": self.model, "input": texts });
+        let response = post(agent.post(&format!("{}/api/embed", self.base_url)), self.name(), &body)?;
+        let json = read_json(response, self.name())?;
+        let items = json["embeddings"].as_array().into_iter().flatten();
+        parse_vectors(items, texts.len(), self.name())
+    }
+}
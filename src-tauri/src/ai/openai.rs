@@ -0,0 +1,68 @@
+use super::{parse_chunk, parse_vectors, post, read_json, read_lines, Embedder, Prompt, Provider};
+use crate::error::AppResult;
+use serde_json::json;
+
+pub const DEFAULT_MODEL: &str = "gpt-4o-mini";
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// OpenAI chat completions (also works with OpenAI-compatible endpoints)
+pub struct OpenAi {
+    pub api_key: String,
+    pub model: String,
+    pub base_url: String,
+}
+
+impl Provider for OpenAi {
+    fn name(&self) -> &'static str {
+        "OpenAI"
+    }
+
+    fn stream(&self, agent: &ureq::Agent, prompt: &Prompt, on_delta: &mut dyn FnMut(&str)) -> AppResult<()> {
+        let body = json!({
+            "model": self.model,
+            "stream": true,
+            "messages": [
+                { "role": "system", "content": prompt.system },
+                { "role": "user", "content": prompt.user },
+            ],
+        });
+        let request = agent
+            .post(&format!("{}/chat/completions", self.base_url))
+            .set("Authorization", &format!("Bearer {}", self.api_key));
+        let response = post(request, self.name(), &body)?;
+
+        // Server-sent events: `data: {chunk}` lines, ending with `data: [DONE]`
+        read_lines(response.into_reader(), self.name(), |line| {
+            let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+                return Ok(true);
+            };
+            if data == "[DONE]" {
+                return Ok(false);
+            }
+            let chunk = parse_chunk(data, self.name())?;
+            if let Some(delta) = chunk["choices"][0]["delta"]["content"].as_str() {
+                on_delta(delta);
+            }
+            Ok(true)
+        })
+    }
+}
+
+impl Embedder for OpenAi {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&self, agent: &ureq::Agent, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        let body = json!({ "model": self.model, "input": texts });
+        let request = agent
+            .post(&format!("{}/embeddings", self.base_url))
+            .set("Authorization", &format!("Bearer {}", self.api_key));
+        let response = post(request, self.name(), &body)?;
+        let json = read_json(response, self.name())?;
+
+        let items = json["data"].as_array().into_iter().flatten().map(|item| &item["embedding"]);
+        parse_vectors(items, texts.len(), self.name())
+    }
+}
@@ -0,0 +1,127 @@
+// Speech-to-text for audio attachments, through the OpenAI transcription API
+// or a local whisper.cpp server (`whisper-server`)
+
+use super::{openai, post_error, read_json, OPENAI_KEY_SECRET};
+use crate::db::queries::AppSettings;
+use crate::error::{AppError, AppResult};
+use crate::secrets;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Extensions accepted as audio memos
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "m4a", "mp4", "mpeg", "mpga", "wav", "webm", "ogg", "oga", "flac"];
+
+/// Upload limit of the OpenAI transcription API
+const MAX_AUDIO_BYTES: u64 = 25 * 1024 * 1024;
+
+const OPENAI_MODEL: &str = "whisper-1";
+
+pub const DEFAULT_WHISPER_CPP_URL: &str = "http://localhost:8080";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Long memos on a local CPU model take a while
+const READ_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Choices for the `transcription_provider` setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptionProvider {
+    None,
+    OpenAi,
+    WhisperCpp,
+}
+
+impl TranscriptionProvider {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(Self::None),
+            "openai" => Some(Self::OpenAi),
+            "whisper_cpp" => Some(Self::WhisperCpp),
+            _ => None,
+        }
+    }
+}
+
+pub fn is_audio(file_name: &str) -> bool {
+    Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// `multipart/form-data` body with text fields and one file part
+fn multipart(boundary: &str, fields: &[(&str, &str)], file_name: &str, data: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(data.len() + 512);
+    for (name, value) in fields {
+        let part = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+            boundary, name, value
+        );
+        body.extend_from_slice(part.as_bytes());
+    }
+    let file_name = file_name.replace(['"', '\r', '\n'], "_");
+    let header = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+         Content-Type: application/octet-stream\r\n\r\n",
+        boundary, file_name
+    );
+    body.extend_from_slice(header.as_bytes());
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+/// Transcribe the audio file at `path` with the provider chosen in settings
+pub fn transcribe(settings: &AppSettings, path: &Path, file_name: &str) -> AppResult<String> {
+    let provider = TranscriptionProvider::parse(&settings.transcription_provider);
+    if matches!(provider, Some(TranscriptionProvider::None) | None) {
+        return Err(AppError::Validation(
+            "Transcription is off; choose a provider in settings".to_string(),
+        ));
+    }
+
+    let size = fs::metadata(path)
+        .map_err(|e| AppError::io(format!("Failed to read {}", file_name), e))?
+        .len();
+    if size > MAX_AUDIO_BYTES {
+        return Err(AppError::Validation(format!(
+            "Audio file is too large to transcribe ({} MB max)",
+            MAX_AUDIO_BYTES / 1024 / 1024
+        )));
+    }
+    let data = fs::read(path).map_err(|e| AppError::io(format!("Failed to read {}", file_name), e))?;
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(CONNECT_TIMEOUT)
+        .timeout_read(READ_TIMEOUT)
+        .build();
+    let boundary = format!("flashnotes-{}", Uuid::new_v4().simple());
+    let (name, request, body) = if provider == Some(TranscriptionProvider::OpenAi) {
+        let api_key = secrets::get(OPENAI_KEY_SECRET)?
+            .ok_or_else(|| AppError::Validation("No OpenAI API key is set".to_string()))?;
+        let request = agent
+            .post(&format!("{}/audio/transcriptions", openai::DEFAULT_BASE_URL))
+            .set("Authorization", &format!("Bearer {}", api_key));
+        let fields = [("model", OPENAI_MODEL), ("response_format", "json")];
+        ("OpenAI", request, multipart(&boundary, &fields, file_name, &data))
+    } else {
+        let base_url = match settings.transcription_url.trim() {
+            "" => DEFAULT_WHISPER_CPP_URL,
+            url => url.trim_end_matches('/'),
+        };
+        let request = agent.post(&format!("{}/inference", base_url));
+        let fields = [("response_format", "json")];
+        ("whisper.cpp", request, multipart(&boundary, &fields, file_name, &data))
+    };
+
+    let response = request
+        .set("Content-Type", &format!("multipart/form-data; boundary={}", boundary))
+        .send_bytes(&body)
+        .map_err(|e| post_error(name, e))?;
+    read_json(response, name)?["text"]
+        .as_str()
+        .map(|text| text.trim().to_string())
+        .ok_or_else(|| AppError::Internal(format!("{} returned no transcript", name)))
+}
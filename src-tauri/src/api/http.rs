@@ -0,0 +1,220 @@
+// Just enough HTTP/1.1 for the API: one request per connection, a
+// Content-Length body, JSON responses.
+
+use crate::error::AppError;
+use serde::Serialize;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+use tauri::Url;
+
+/// Slow clients are dropped after this, so one can't stall the server
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Upper bound on the request line plus headers
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+
+/// Upper bound on a body: a maximum-size note plus JSON escaping
+const MAX_BODY_BYTES: usize = 12 * 1024 * 1024;
+
+pub struct Request {
+    pub method: String,
+    /// Path without the query string, still percent-encoded
+    pub path: String,
+    /// Decoded query parameters
+    pub query: Vec<(String, String)>,
+    /// Bearer token from the Authorization header
+    pub token: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+pub fn read_request(stream: &mut TcpStream) -> io::Result<Request> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let mut reader = BufReader::new(&*stream);
+    let mut head_bytes = 0;
+    let mut read_line = |reader: &mut BufReader<&TcpStream>| -> io::Result<String> {
+        let mut line = String::new();
+        head_bytes += reader.by_ref().take((MAX_HEADER_BYTES - head_bytes) as u64).read_line(&mut line)?;
+        if !line.ends_with('\n') {
+            return Err(invalid("request headers too large or incomplete"));
+        }
+        Ok(line.trim_end().to_string())
+    };
+
+    let request_line = read_line(&mut reader)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let target = parts.next().ok_or_else(|| invalid("missing request target"))?;
+    let url = Url::parse(&format!("http://127.0.0.1{}", target)).map_err(|_| invalid("invalid request target"))?;
+
+    let mut content_length = 0;
+    let mut token = None;
+    loop {
+        let header = read_line(&mut reader)?;
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().map_err(|_| invalid("invalid Content-Length"))?;
+        } else if name.eq_ignore_ascii_case("authorization") {
+            token = value.strip_prefix("Bearer ").map(|t| t.trim().to_string());
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Err(invalid("request body too large"));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Request {
+        method,
+        path: url.path().to_string(),
+        query: url.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())).collect(),
+        token,
+        body,
+    })
+}
+
+pub struct Response {
+    status: &'static str,
+    body: String,
+}
+
+impl Response {
+    pub fn json<T: Serialize>(status: &'static str, value: &T) -> Self {
+        match serde_json::to_string(value) {
+            Ok(body) => Self { status, body },
+            Err(e) => Self::error(&AppError::Internal(format!("Failed to encode response: {}", e))),
+        }
+    }
+
+    pub fn ok<T: Serialize>(value: &T) -> Self {
+        Self::json("200 OK", value)
+    }
+
+    /// Errors have the same `{ code, message, context }` shape as command errors
+    pub fn error(error: &AppError) -> Self {
+        let status = match error {
+            AppError::Validation(_) => "400 Bad Request",
+            AppError::NotFound { .. } => "404 Not Found",
+            AppError::Conflict(_) => "409 Conflict",
+            AppError::DatabaseLocked => "423 Locked",
+            AppError::Unsupported(_) => "501 Not Implemented",
+            _ => "500 Internal Server Error",
+        };
+        Self::json(status, error)
+    }
+
+    /// Acknowledges a message that has no reply (e.g. an MCP notification)
+    pub fn accepted() -> Self {
+        Self {
+            status: "202 Accepted",
+            body: String::new(),
+        }
+    }
+
+    /// An error of the API itself rather than of a command
+    fn api_error(status: &'static str, code: &str, message: &str) -> Self {
+        Self::json(status, &serde_json::json!({ "code": code, "message": message, "context": null }))
+    }
+
+    pub fn unauthorized() -> Self {
+        Self::api_error("401 Unauthorized", "unauthorized", "Missing or wrong API token")
+    }
+
+    pub fn not_found() -> Self {
+        Self::api_error("404 Not Found", "not_found", "No such endpoint")
+    }
+
+    pub fn method_not_allowed() -> Self {
+        Self::api_error("405 Method Not Allowed", "method_not_allowed", "Method not allowed")
+    }
+
+    pub fn write(&self, stream: &mut TcpStream) -> io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\n\
+             Content-Type: application/json; charset=utf-8\r\n\
+             Content-Length: {}\r\n\
+             Cache-Control: no-store\r\n\
+             Connection: close\r\n\r\n",
+            self.status,
+            self.body.len(),
+        )?;
+        stream.write_all(self.body.as_bytes())?;
+        stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Shutdown, TcpListener};
+
+    /// Send `raw` over a local connection and parse it on the other end
+    fn parse(raw: &[u8]) -> io::Result<Request> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let mut client = TcpStream::connect(listener.local_addr()?)?;
+        client.write_all(raw)?;
+        client.shutdown(Shutdown::Write)?;
+        let (mut stream, _) = listener.accept()?;
+        read_request(&mut stream)
+    }
+
+    #[test]
+    fn parses_path_query_and_token() {
+        let request = parse(
+            b"GET /v1/buffers?q=hello%20world&limit=5 HTTP/1.1\r\n\
+              Host: localhost\r\nauthorization: Bearer abc \r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/v1/buffers");
+        assert_eq!(request.param("q"), Some("hello world"));
+        assert_eq!(request.param("limit"), Some("5"));
+        assert_eq!(request.param("missing"), None);
+        assert_eq!(request.token.as_deref(), Some("abc"));
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn reads_exactly_the_content_length() {
+        let request = parse(b"POST /v1/buffers HTTP/1.1\r\nContent-Length: 11\r\n\r\nhello worldEXTRA").unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.body, b"hello world");
+        assert_eq!(request.token, None);
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        let error_kind = |raw: &[u8]| parse(raw).err().map(|e| e.kind());
+        assert_eq!(error_kind(b"GET\r\n\r\n"), Some(io::ErrorKind::InvalidData));
+        assert_eq!(error_kind(b"GET / HTTP/1.1\r\nContent-Length: abc\r\n\r\n"), Some(io::ErrorKind::InvalidData));
+        let too_large = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_BYTES + 1);
+        assert_eq!(error_kind(too_large.as_bytes()), Some(io::ErrorKind::InvalidData));
+        // Headers cut off before the blank line
+        assert_eq!(error_kind(b"GET / HTTP/1.1\r\nHost: local"), Some(io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn caps_header_size() {
+        let raw = format!("GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "a".repeat(MAX_HEADER_BYTES));
+        assert_eq!(parse(raw.as_bytes()).err().map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+    }
+}
@@ -0,0 +1,189 @@
+// MCP (Model Context Protocol) over the API server: JSON-RPC 2.0 messages
+// POSTed to `/mcp`, answered with a single JSON response (the streamable
+// HTTP transport without streaming). Tools let an assistant search, list,
+// read and create notes; created notes get the source `api:mcp`.
+
+use super::http::Response;
+use crate::commands::buffer::{insert_buffer, search_notes};
+use crate::db::activity::Origin;
+use crate::db::queries;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::AppState;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+
+/// Protocol revision implemented here
+const PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// Results of `search_notes` and `list_notes`
+const RESULT_LIMIT: usize = 20;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Deserialize)]
+struct Message {
+    jsonrpc: String,
+    /// Absent for notifications, which get no response
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+fn success(id: Value, result: Value) -> Response {
+    Response::ok(&json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn failure(id: Value, code: i64, message: &str) -> Response {
+    Response::ok(&json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }))
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "search_notes",
+            "description": "Full-text search across all notes. Returns matching note ids with their titles.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "query": { "type": "string", "description": "Words to search for" } },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "list_notes",
+            "description": "List the notes in the sidebar, pinned and most recently edited first.",
+            "inputSchema": { "type": "object", "properties": {} }
+        },
+        {
+            "name": "read_note",
+            "description": "Read the full Markdown content of a note by id.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "id": { "type": "string" } },
+                "required": ["id"]
+            }
+        },
+        {
+            "name": "create_note",
+            "description": "Create a new note. The first line becomes its title.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "content": { "type": "string", "description": "Markdown content" } },
+                "required": ["content"]
+            }
+        }
+    ])
+}
+
+/// A string argument of a tool call
+fn argument<'a>(arguments: &'a Value, name: &str) -> AppResult<&'a str> {
+    arguments
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| AppError::Validation(format!("Missing argument: {}", name)))
+}
+
+/// Run a tool and return its text output
+fn call_tool(app: &AppHandle, call: &ToolCall) -> AppResult<String> {
+    let state = app.state::<AppState>();
+    state.ready.wait()?;
+    // Refused like a command while auto-locked, without counting as activity
+    if state.idle.is_locked() {
+        return Err(AppError::DatabaseLocked);
+    }
+    match call.name.as_str() {
+        "search_notes" => {
+            let query = argument(&call.arguments, "query")?;
+            let results = search_notes(&state, query, None, RESULT_LIMIT)?;
+            let lines: Vec<String> = results
+                .iter()
+                .map(|result| {
+                    let text = result.snippet.replace("<mark>", "").replace("</mark>", "");
+                    format!("{}\t{}", result.id, queries::extract_title_preview(&text).0)
+                })
+                .collect();
+            Ok(if lines.is_empty() { "No matching notes".to_string() } else { lines.join("\n") })
+        }
+        "list_notes" => {
+            let buffers = state
+                .read(|conn| queries::get_sidebar_buffers(conn, RESULT_LIMIT, 0))
+                .db_context("Failed to list buffers")?;
+            Ok(buffers
+                .iter()
+                .map(|summary| format!("{}\t{}", summary.id, summary.title))
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        "read_note" => {
+            let id = argument(&call.arguments, "id")?;
+            let buffer = state
+                .read(|conn| queries::get_buffer_content(conn, id))
+                .db_context("Failed to get buffer")?
+                .ok_or_else(|| AppError::not_found("Buffer", id))?;
+            if buffer.is_locked {
+                return Err(AppError::Conflict("Note is locked".to_string()));
+            }
+            Ok(buffer.content)
+        }
+        "create_note" => {
+            let content = argument(&call.arguments, "content")?;
+            let summary = insert_buffer(app, &state, content, "api:mcp", Origin::Api)?;
+            Ok(format!("Created note {}", summary.id))
+        }
+        _ => Err(AppError::Validation(format!("Unknown tool: {}", call.name))),
+    }
+}
+
+/// Answer one JSON-RPC message
+pub fn handle(app: &AppHandle, body: &[u8]) -> Response {
+    let message: Message = match serde_json::from_slice(body) {
+        Ok(message) => message,
+        Err(e) if e.is_syntax() || e.is_eof() => return failure(Value::Null, PARSE_ERROR, "Parse error"),
+        Err(_) => return failure(Value::Null, INVALID_REQUEST, "Invalid request"),
+    };
+    if message.jsonrpc != "2.0" {
+        return failure(message.id.unwrap_or(Value::Null), INVALID_REQUEST, "Invalid request");
+    }
+    let Some(id) = message.id else {
+        // Notifications (e.g. `notifications/initialized`) need no answer
+        return Response::accepted();
+    };
+
+    match message.method.as_str() {
+        "initialize" => success(
+            id,
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "flashnotes", "version": app.package_info().version.to_string() }
+            }),
+        ),
+        "ping" => success(id, json!({})),
+        "tools/list" => success(id, json!({ "tools": tools() })),
+        "tools/call" => {
+            let call: ToolCall = match serde_json::from_value(message.params) {
+                Ok(call) => call,
+                Err(e) => return failure(id, INVALID_PARAMS, &e.to_string()),
+            };
+            // Tool failures are results the assistant can read, not protocol errors
+            let (text, is_error) = match call_tool(app, &call) {
+                Ok(text) => (text, false),
+                Err(e) => (e.message(), true),
+            };
+            success(id, json!({ "content": [{ "type": "text", "text": text }], "isError": is_error }))
+        }
+        _ => failure(id, METHOD_NOT_FOUND, "Method not found"),
+    }
+}
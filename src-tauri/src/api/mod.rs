@@ -0,0 +1,229 @@
+// Local HTTP API for launchers (Raycast, Alfred) and scripts: JSON endpoints
+// to list, search, read, create and update notes, served on 127.0.0.1 only.
+// Off unless the `api_enabled` setting is on. The same server answers MCP
+// (Model Context Protocol) requests at `/mcp` when `mcp_enabled` is on, so
+// assistants can use the notes as scratch memory (see `mcp`). Every request
+// needs the token from `get_api_token` as `Authorization: Bearer <token>`;
+// it is kept in the keychain so integrations keep working across restarts
+// until it is regenerated. No CORS headers are sent, so web pages can't use
+// the API.
+
+mod http;
+mod mcp;
+mod routes;
+
+use crate::db::queries::{self, AppSettings};
+use crate::error::{AppError, AppResult};
+use crate::secrets;
+use crate::state::AppState;
+use parking_lot::Mutex;
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Setting that turns the server on
+pub const API_ENABLED_SETTING: &str = "api_enabled";
+
+/// Setting that turns on the MCP endpoint
+pub const MCP_ENABLED_SETTING: &str = "mcp_enabled";
+
+/// Setting holding the port on 127.0.0.1
+pub const API_PORT_SETTING: &str = "api_port";
+
+/// Keychain name of the auth token
+const TOKEN_SECRET: &str = "api.token";
+
+/// Ports below this need privileges on most systems
+const MIN_PORT: u16 = 1024;
+
+struct Running {
+    port: u16,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Running {
+    /// End the accept loop (the connection wakes it up) and wait until the
+    /// port is released, so it can be bound again right away
+    fn stop(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = TcpStream::connect((Ipv4Addr::LOCALHOST, self.port));
+        let _ = self.thread.join();
+    }
+}
+
+/// The running server, if enabled, which endpoints it answers and the token
+/// requests are checked against
+pub struct ApiServer {
+    running: Mutex<Option<Running>>,
+    token: Mutex<Option<String>>,
+    rest_enabled: AtomicBool,
+    mcp_enabled: AtomicBool,
+}
+
+impl ApiServer {
+    pub fn new() -> Self {
+        Self {
+            running: Mutex::new(None),
+            token: Mutex::new(None),
+            rest_enabled: AtomicBool::new(false),
+            mcp_enabled: AtomicBool::new(false),
+        }
+    }
+
+    fn rest_enabled(&self) -> bool {
+        self.rest_enabled.load(Ordering::SeqCst)
+    }
+
+    fn mcp_enabled(&self) -> bool {
+        self.mcp_enabled.load(Ordering::SeqCst)
+    }
+
+    fn set_endpoints(&self, settings: &AppSettings) {
+        self.rest_enabled.store(settings.api_enabled, Ordering::SeqCst);
+        self.mcp_enabled.store(settings.mcp_enabled, Ordering::SeqCst);
+    }
+
+    /// Compare in constant time so the token can't be guessed byte by byte
+    fn authorized(&self, presented: Option<&str>) -> bool {
+        let token = self.token.lock();
+        match (token.as_deref(), presented) {
+            (Some(token), Some(presented)) if token.len() == presented.len() => token
+                .bytes()
+                .zip(presented.bytes())
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0,
+            _ => false,
+        }
+    }
+}
+
+impl Default for ApiServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Ports are unprivileged TCP ports
+pub fn validate_port(value: &str) -> AppResult<u16> {
+    value
+        .parse::<u16>()
+        .ok()
+        .filter(|port| *port >= MIN_PORT)
+        .ok_or_else(|| AppError::Validation(format!("Invalid API port: {}", value)))
+}
+
+fn generate_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// The auth token, created on first use
+pub fn token(state: &AppState) -> AppResult<String> {
+    let mut cached = state.api.token.lock();
+    if let Some(token) = cached.as_ref() {
+        return Ok(token.clone());
+    }
+    let token = match secrets::get(TOKEN_SECRET)? {
+        Some(token) => token,
+        None => {
+            let token = generate_token();
+            secrets::set(TOKEN_SECRET, &token)?;
+            token
+        }
+    };
+    *cached = Some(token.clone());
+    Ok(token)
+}
+
+/// Replace the auth token; integrations using the old one stop working
+pub fn regenerate_token(state: &AppState) -> AppResult<String> {
+    let token = generate_token();
+    secrets::set(TOKEN_SECRET, &token)?;
+    *state.api.token.lock() = Some(token.clone());
+    info!("API token regenerated");
+    Ok(token)
+}
+
+/// Start, move or stop the server to match the settings. It runs while
+/// either the HTTP API or the MCP endpoint is on.
+pub fn apply(app: &AppHandle, settings: &AppSettings) -> AppResult<()> {
+    let state = app.state::<AppState>();
+    let mut running = state.api.running.lock();
+    let port = validate_port(&settings.api_port.to_string())?;
+    let enabled = settings.api_enabled || settings.mcp_enabled;
+    if enabled && running.as_ref().is_some_and(|r| r.port == port) {
+        state.api.set_endpoints(settings);
+        return Ok(());
+    }
+    if let Some(previous) = running.take() {
+        info!("API server on port {} stopped", previous.port);
+        previous.stop();
+    }
+    state.api.set_endpoints(settings);
+    if !enabled {
+        return Ok(());
+    }
+
+    token(&state)?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .map_err(|e| AppError::io(format!("Failed to start the API server on port {}", port), e))?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let app = app.clone();
+    let stopped = stop.clone();
+    let thread = thread::Builder::new()
+        .name("api-server".to_string())
+        .spawn(move || serve(&app, listener, &stopped))
+        .map_err(|e| AppError::io("Failed to start the API server", e))?;
+
+    info!("API server listening on 127.0.0.1:{}", port);
+    *running = Some(Running { port, stop, thread });
+    Ok(())
+}
+
+/// Start the server on launch when it is enabled
+pub fn start(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = state.read(queries::get_settings).unwrap_or_default();
+    if let Err(e) = apply(app, &settings) {
+        warn!("Failed to start the API server: {}", e);
+    }
+}
+
+fn serve(app: &AppHandle, listener: TcpListener, stop: &AtomicBool) {
+    for stream in listener.incoming() {
+        if stop.load(Ordering::SeqCst) {
+            return;
+        }
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle(app, stream) {
+                    debug!("API request failed: {}", e);
+                }
+            }
+            Err(e) => warn!("API server accept failed: {}", e),
+        }
+    }
+}
+
+fn handle(app: &AppHandle, mut stream: TcpStream) -> std::io::Result<()> {
+    let request = match http::read_request(&mut stream) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = AppError::Validation(format!("Malformed request: {}", e));
+            return http::Response::error(&error).write(&mut stream);
+        }
+    };
+
+    let state = app.state::<AppState>();
+    let response = if !state.api.authorized(request.token.as_deref()) {
+        http::Response::unauthorized()
+    } else {
+        debug!("API {} {}", request.method, request.path);
+        routes::route(app, &request).unwrap_or_else(|e| http::Response::error(&e))
+    };
+    response.write(&mut stream)
+}
@@ -0,0 +1,107 @@
+// API endpoints:
+//   GET  /buffers?limit=&offset=  sidebar notes (summaries)
+//   GET  /buffers/<id>            one note with its content
+//   POST /buffers                 create: {"content": "...", "source": "api"}
+//   PUT  /buffers/<id>            replace content: {"content": "..."}
+//   GET  /search?q=&source=       full-text search
+//   POST /mcp                     MCP JSON-RPC (only with `mcp_enabled`)
+
+use super::http::{Request, Response};
+use super::mcp;
+use crate::commands::buffer::{insert_buffer, replace_buffer_content, search_notes};
+use crate::db::activity::Origin;
+use crate::db::queries;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::AppState;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+
+/// Notes listed when no limit is given
+const DEFAULT_LIST_LIMIT: usize = 50;
+
+/// Most notes one list request returns
+const MAX_LIST_LIMIT: usize = 500;
+
+/// Search results returned, as in the app
+const SEARCH_LIMIT: usize = 20;
+
+#[derive(Deserialize)]
+struct NewBuffer {
+    #[serde(default)]
+    content: String,
+    source: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UpdateBuffer {
+    content: String,
+}
+
+fn parse_body<T: DeserializeOwned>(request: &Request) -> AppResult<T> {
+    serde_json::from_slice(&request.body).map_err(|e| AppError::Validation(format!("Invalid JSON body: {}", e)))
+}
+
+fn number_param(request: &Request, name: &str) -> AppResult<Option<usize>> {
+    request
+        .param(name)
+        .map(|value| value.parse().map_err(|_| AppError::Validation(format!("Invalid {}: {}", name, value))))
+        .transpose()
+}
+
+fn get_buffer(state: &AppState, id: &str) -> AppResult<Response> {
+    let buffer = state
+        .read(|conn| queries::get_buffer_content(conn, id))
+        .db_context("Failed to get buffer")?
+        .ok_or_else(|| AppError::not_found("Buffer", id))?;
+    Ok(Response::ok(&buffer))
+}
+
+pub fn route(app: &AppHandle, request: &Request) -> AppResult<Response> {
+    let state = app.state::<AppState>();
+    if request.path == "/mcp" && state.api.mcp_enabled() {
+        return Ok(match request.method.as_str() {
+            "POST" => mcp::handle(app, &request.body),
+            _ => Response::method_not_allowed(),
+        });
+    }
+    if !state.api.rest_enabled() {
+        return Ok(Response::not_found());
+    }
+    state.ready.wait()?;
+    // Refused like a command while auto-locked, without counting as activity
+    if state.idle.is_locked() {
+        return Err(AppError::DatabaseLocked);
+    }
+
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["buffers"]) => {
+            let limit = number_param(request, "limit")?.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT);
+            let offset = number_param(request, "offset")?.unwrap_or(0);
+            let buffers = state
+                .read(|conn| queries::get_sidebar_buffers(conn, limit, offset))
+                .db_context("Failed to list buffers")?;
+            Ok(Response::ok(&buffers))
+        }
+        ("POST", ["buffers"]) => {
+            let new: NewBuffer = parse_body(request)?;
+            let source = new.source.unwrap_or_else(|| "api".to_string());
+            let summary = insert_buffer(app, &state, &new.content, &source, Origin::Api)?;
+            Ok(Response::json("201 Created", &summary))
+        }
+        ("GET", ["buffers", id]) => get_buffer(&state, id),
+        ("PUT", ["buffers", id]) => {
+            let update: UpdateBuffer = parse_body(request)?;
+            replace_buffer_content(app, &state, id, &update.content, Origin::Api)?;
+            get_buffer(&state, id)
+        }
+        ("GET", ["search"]) => {
+            let query = request.param("q").unwrap_or("");
+            let results = search_notes(&state, query, request.param("source"), SEARCH_LIMIT)?;
+            Ok(Response::ok(&results))
+        }
+        (_, ["buffers"] | ["buffers", _] | ["search"]) => Ok(Response::method_not_allowed()),
+        _ => Ok(Response::not_found()),
+    }
+}
@@ -0,0 +1,112 @@
+use crate::error::{AppError, AppResult};
+use std::fs;
+use std::panic;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Largest file that can be attached (50MB)
+pub const MAX_ATTACHMENT_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Extracted text beyond this many bytes isn't indexed
+const MAX_INDEXED_TEXT: usize = 1024 * 1024;
+
+/// Extensions indexed as plain UTF-8 text
+const TEXT_EXTENSIONS: &[&str] = &[
+    "txt", "md", "markdown", "csv", "tsv", "json", "yaml", "yml", "toml", "xml", "html", "htm",
+    "log", "ini", "cfg", "conf", "sql", "sh", "rs", "py", "js", "ts", "go", "java", "c", "h",
+    "cpp", "swift", "rb",
+];
+
+/// Directory holding attachment files
+pub fn attachments_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("attachments")
+}
+
+/// Lowercased file extension, if any
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+}
+
+/// Copy a file into the attachments directory as `<id>.<ext>`
+pub fn store_file(app_data_dir: &Path, source: &Path, id: &str) -> AppResult<PathBuf> {
+    let metadata = fs::metadata(source)
+        .map_err(|e| AppError::io("Failed to read attachment", e))?;
+    if !metadata.is_file() {
+        return Err(AppError::Validation(format!("Not a file: {}", source.display())));
+    }
+    if metadata.len() > MAX_ATTACHMENT_SIZE {
+        return Err(AppError::Validation(format!(
+            "Attachment too large ({:.1}MB). Maximum size is 50MB.",
+            metadata.len() as f64 / (1024.0 * 1024.0)
+        )));
+    }
+
+    let dir = attachments_dir(app_data_dir);
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::io("Failed to create attachments directory", e))?;
+
+    let file_name = match extension(source) {
+        Some(ext) => format!("{}.{}", id, ext),
+        None => id.to_string(),
+    };
+    let dest = dir.join(file_name);
+    fs::copy(source, &dest).map_err(|e| AppError::io("Failed to copy attachment", e))?;
+
+    Ok(dest)
+}
+
+/// Remove a stored attachment file (best effort)
+pub fn remove_file(path: &Path) {
+    if let Err(e) = fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove attachment file {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Extract searchable text from a stored attachment.
+/// Plain-text formats are read directly and PDFs via their text layer; other
+/// files (including images, which would need OCR) index by file name only.
+pub fn extract_text(path: &Path) -> String {
+    let text = match extension(path).as_deref() {
+        Some("pdf") => extract_pdf_text(path),
+        Some(ext) if TEXT_EXTENSIONS.contains(&ext) => fs::read(path)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_else(|e| {
+                warn!("Failed to read attachment text {:?}: {}", path, e);
+                String::new()
+            }),
+        _ => String::new(),
+    };
+
+    truncate_text(text)
+}
+
+/// PDF text layer; the extractor can panic on malformed files, so isolate it
+fn extract_pdf_text(path: &Path) -> String {
+    match panic::catch_unwind(|| pdf_extract::extract_text(path)) {
+        Ok(Ok(text)) => text,
+        Ok(Err(e)) => {
+            warn!("Failed to extract PDF text {:?}: {}", path, e);
+            String::new()
+        }
+        Err(_) => {
+            warn!("PDF text extraction panicked for {:?}", path);
+            String::new()
+        }
+    }
+}
+
+/// Cap indexed text on a char boundary
+fn truncate_text(mut text: String) -> String {
+    if text.len() > MAX_INDEXED_TEXT {
+        let mut end = MAX_INDEXED_TEXT;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    text
+}
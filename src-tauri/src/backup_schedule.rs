@@ -0,0 +1,85 @@
+// Automatic backups. Checked on launch and then every hour: a backup is made
+// once `backup_interval_hours` have passed since the newest one, and only the
+// newest `backup_retention_count` regular backups are kept. Each backup is
+// also copied to `backup_destination` when that folder is set.
+
+use crate::db::backup;
+use crate::db::queries::{self, AppSettings};
+use crate::error::{AppError, AppResult};
+use crate::state::AppState;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// How often the scheduler checks whether a backup is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const HOUR_SECS: u64 = 60 * 60;
+
+/// Setting holding the external folder backups are copied to (empty = none)
+pub const BACKUP_DESTINATION_SETTING: &str = "backup_destination";
+
+/// Check a folder chosen as backup destination
+pub fn validate_destination(value: &str) -> AppResult<()> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    let path = Path::new(value);
+    if !path.is_absolute() || !path.is_dir() {
+        return Err(AppError::Validation(format!("Backup folder must be an existing directory: {}", value)));
+    }
+    Ok(())
+}
+
+/// Create a backup now and copy it to the backup destination, if one is set.
+/// Returns the backup in the app data directory.
+pub fn backup_now(state: &AppState, settings: &AppSettings) -> Result<PathBuf, String> {
+    let keep = usize::try_from(settings.backup_retention_count).unwrap_or(1).max(1);
+    let path = backup::create_backup(&state.writer.lock(), &state.app_data_dir, keep)?;
+    if !settings.backup_destination.is_empty() {
+        backup::copy_to_destination(&path, Path::new(&settings.backup_destination), keep)?;
+    }
+    Ok(path)
+}
+
+/// Make a backup when one is due (an interval of 0 turns automatic backups off)
+pub fn run_if_due(state: &AppState) {
+    let settings = match state.read(queries::get_settings) {
+        Ok(settings) => settings,
+        Err(e) => {
+            warn!("Failed to read backup settings: {}", e);
+            return;
+        }
+    };
+    let Ok(hours) = u64::try_from(settings.backup_interval_hours) else {
+        return;
+    };
+    if hours == 0 || !backup::needs_backup(&state.app_data_dir, hours * HOUR_SECS) {
+        return;
+    }
+
+    match backup_now(state, &settings) {
+        Ok(path) => info!("Scheduled backup created: {:?}", path),
+        Err(e) => warn!("Failed to create scheduled backup: {}", e),
+    }
+}
+
+/// Spawn the thread that keeps backups on schedule
+pub fn spawn(app: AppHandle) {
+    let spawned = thread::Builder::new()
+        .name("backup-schedule".to_string())
+        .spawn(move || loop {
+            let state = app.state::<AppState>();
+            // Nothing to back up while the encrypted database is locked
+            if !state.ready.is_locked() {
+                run_if_due(&state);
+            }
+            thread::sleep(CHECK_INTERVAL);
+        });
+
+    if let Err(e) = spawned {
+        warn!("Failed to start backup scheduler: {}", e);
+    }
+}
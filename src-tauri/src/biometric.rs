@@ -0,0 +1,134 @@
+// Touch ID unlock for the encrypted database and locked notes. Turning it
+// on files the passphrase in the keychain (see `secrets`); it is only read
+// back after LocalAuthentication has verified the user's fingerprint.
+// Other platforms report Touch ID as unavailable.
+
+use crate::error::{AppError, AppResult};
+use crate::note_lock;
+use crate::secrets;
+use crate::state::AppState;
+use tracing::info;
+
+/// Touch ID prompt text, shown as "Flashnotes is trying to <reason>"
+const DATABASE_REASON: &str = "unlock your notes database";
+const NOTE_REASON: &str = "unlock a locked note";
+
+/// Keychain name of the database passphrase (None) or a locked note's passphrase
+fn secret_name(buffer_id: Option<&str>) -> String {
+    match buffer_id {
+        Some(id) => format!("biometric.note.{}", id),
+        None => "biometric.database".to_string(),
+    }
+}
+
+/// Whether Touch ID unlock is set up for the database (None) or a note
+pub fn is_enabled(buffer_id: Option<&str>) -> AppResult<bool> {
+    Ok(available() && secrets::get(&secret_name(buffer_id))?.is_some())
+}
+
+/// Turn on Touch ID unlock for the database (None) or a locked note; the
+/// passphrase must be the current one
+pub fn enable(state: &AppState, buffer_id: Option<&str>, passphrase: &str) -> AppResult<()> {
+    if !available() {
+        return Err(AppError::Unsupported("Touch ID"));
+    }
+    match buffer_id {
+        Some(id) => note_lock::verify(state, id, passphrase)?,
+        None => match state.db_key.get() {
+            Some(key) if key == passphrase => {}
+            Some(_) => return Err(AppError::Validation("Wrong passphrase".to_string())),
+            None => return Err(AppError::Validation("The database is not encrypted".to_string())),
+        },
+    }
+    secrets::set(&secret_name(buffer_id), passphrase)?;
+    info!("Touch ID unlock enabled for {}", buffer_id.unwrap_or("the database"));
+    Ok(())
+}
+
+/// Turn off Touch ID unlock for the database (None) or a note
+pub fn forget(buffer_id: Option<&str>) -> AppResult<()> {
+    secrets::delete(&secret_name(buffer_id))
+}
+
+/// Keep the stored passphrase of the database (None) or a note in step after it was changed
+pub fn update_passphrase(buffer_id: Option<&str>, passphrase: &str) -> AppResult<()> {
+    let name = secret_name(buffer_id);
+    if secrets::get(&name)?.is_some() {
+        secrets::set(&name, passphrase)?;
+    }
+    Ok(())
+}
+
+/// Ask for Touch ID and return the stored passphrase of the database (None)
+/// or a note. Blocks until the user responds, so never call it on the main thread.
+pub fn passphrase(buffer_id: Option<&str>) -> AppResult<String> {
+    let passphrase = secrets::get(&secret_name(buffer_id))?
+        .ok_or_else(|| AppError::Validation("Touch ID unlock is not set up".to_string()))?;
+    authenticate(if buffer_id.is_some() { NOTE_REASON } else { DATABASE_REASON })?;
+    Ok(passphrase)
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "LocalAuthentication", kind = "framework")]
+extern "C" {}
+
+/// LAPolicyDeviceOwnerAuthenticationWithBiometrics
+#[cfg(target_os = "macos")]
+const POLICY_BIOMETRICS: i64 = 1;
+
+/// Whether the Mac has Touch ID set up
+#[cfg(target_os = "macos")]
+pub fn available() -> bool {
+    use cocoa::base::{id, BOOL, YES};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    unsafe {
+        let context: id = msg_send![class!(LAContext), new];
+        let error: *mut id = std::ptr::null_mut();
+        let can: BOOL = msg_send![context, canEvaluatePolicy: POLICY_BIOMETRICS error: error];
+        let _: () = msg_send![context, release];
+        can == YES
+    }
+}
+
+/// Show the Touch ID prompt and wait for the result
+#[cfg(target_os = "macos")]
+fn authenticate(reason: &str) -> AppResult<()> {
+    use block::ConcreteBlock;
+    use cocoa::base::{id, nil, BOOL, YES};
+    use cocoa::foundation::NSString;
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::sync::mpsc;
+
+    let (tx, rx) = mpsc::channel();
+    let reply = ConcreteBlock::new(move |success: BOOL, _error: id| {
+        let _ = tx.send(success == YES);
+    })
+    .copy();
+
+    let verified = unsafe {
+        let context: id = msg_send![class!(LAContext), new];
+        let reason = NSString::alloc(nil).init_str(reason);
+        let _: () = msg_send![context, evaluatePolicy: POLICY_BIOMETRICS localizedReason: reason reply: &*reply];
+        // The reply arrives on a LocalAuthentication queue; keep the context alive until then
+        let verified = rx.recv().unwrap_or(false);
+        let _: () = msg_send![reason, release];
+        let _: () = msg_send![context, release];
+        verified
+    };
+    if verified {
+        Ok(())
+    } else {
+        Err(AppError::Validation("Touch ID was cancelled or not recognized".to_string()))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn available() -> bool {
+    false
+}
+
+#[cfg(not(target_os = "macos"))]
+fn authenticate(_reason: &str) -> AppResult<()> {
+    Err(AppError::Unsupported("Touch ID"))
+}
@@ -0,0 +1,103 @@
+// Note properties for board views: a `#property/value` hashtag or a
+// Dataview-style `property:: value` line
+
+/// Whether a property name or value is usable in a hashtag
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().any(char::is_alphabetic)
+        && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Value from a `property:: value` line, if the line is one
+fn field_value<'a>(line: &'a str, property: &str) -> Option<&'a str> {
+    let trimmed = line.trim_start();
+    let key = trimmed.get(..property.len())?;
+    if !key.eq_ignore_ascii_case(property) {
+        return None;
+    }
+    trimmed[property.len()..].strip_prefix("::").map(str::trim)
+}
+
+/// Byte range and value of the first `#property/value` hashtag in a line
+fn hashtag_value(line: &str, property: &str) -> Option<(usize, usize, String)> {
+    let prefix = format!("#{}/", property.to_lowercase());
+    let lower = line.to_lowercase();
+    // Lowercasing can change byte lengths; only trust positions when it didn't
+    if lower.len() != line.len() {
+        return None;
+    }
+    lower.match_indices(&prefix).find_map(|(i, _)| {
+        if line[..i].chars().next_back().is_some_and(|c| !c.is_whitespace()) {
+            return None;
+        }
+        let start = i + prefix.len();
+        let len = line[start..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(line.len() - start);
+        (len > 0).then(|| (i, start + len, lower[start..start + len].to_string()))
+    })
+}
+
+/// Lines outside code fences, with their byte offsets
+fn prose_lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut in_fence = false;
+    let mut offset = 0;
+    content.split_inclusive('\n').filter_map(move |line| {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            return None;
+        }
+        (!in_fence).then_some((start, line))
+    })
+}
+
+/// A note's value for `property` (lowercased), from the first hashtag or field
+pub fn property_value(content: &str, property: &str) -> Option<String> {
+    prose_lines(content).find_map(|(_, line)| {
+        field_value(line, property)
+            .filter(|v| !v.is_empty())
+            .map(str::to_lowercase)
+            .or_else(|| hashtag_value(line, property).map(|(_, _, value)| value))
+    })
+}
+
+/// Content with `property` set to `value`: the first hashtag or field is
+/// rewritten in place, or a `#property/value` tag is appended when the note
+/// has neither.
+pub fn set_property(content: &str, property: &str, value: &str) -> String {
+    for (offset, line) in prose_lines(content) {
+        if field_value(line, property).is_some() {
+            let indent = line.len() - line.trim_start().len();
+            let key = &line[indent..indent + property.len()];
+            let ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+            return format!(
+                "{}{}{}:: {}{}{}",
+                &content[..offset],
+                &line[..indent],
+                key,
+                value,
+                ending,
+                &content[offset + line.len()..]
+            );
+        }
+        if let Some((start, end, _)) = hashtag_value(line, property) {
+            return format!(
+                "{}#{}/{}{}",
+                &content[..offset + start],
+                property,
+                value,
+                &content[offset + end..]
+            );
+        }
+    }
+
+    let mut updated = content.trim_end().to_string();
+    if !updated.is_empty() {
+        updated.push_str("\n\n");
+    }
+    updated.push_str(&format!("#{}/{}\n", property, value));
+    updated
+}
@@ -0,0 +1,195 @@
+/// Fence info strings that mark a flashcard block
+const CARD_FENCES: &[&str] = &["card", "flashcard"];
+
+/// Separator between question and answer inside a fenced card
+const FENCE_SEPARATOR: &str = "---";
+
+/// Starting ease factor for new cards
+pub const INITIAL_EASE: f64 = 2.5;
+
+/// Ease never drops below this, so hard cards still spread out over time
+const MIN_EASE: f64 = 1.3;
+
+/// Highest grade accepted by `review`
+pub const MAX_GRADE: u8 = 5;
+
+/// Grades below this count as a lapse and restart the card
+const PASSING_GRADE: u8 = 3;
+
+/// Question/answer pair found in a note
+#[derive(Debug, PartialEq)]
+pub struct ParsedCard {
+    pub question: String,
+    pub answer: String,
+}
+
+/// Extract flashcards from note content. Two forms are recognized:
+///
+/// ```text
+/// Q: question          ```card
+/// A: answer            question
+///                      ---
+///                      answer
+///                      ```
+/// ```
+///
+/// `Q:`/`A:` text continues onto following lines until a blank line or the
+/// next `Q:`. Questions are unique per note; the first occurrence wins.
+pub fn parse_cards(content: &str) -> Vec<ParsedCard> {
+    let mut cards: Vec<ParsedCard> = Vec::new();
+    let mut push = |question: &[&str], answer: &[&str]| {
+        let question = question.join("\n").trim().to_string();
+        let answer = answer.join("\n").trim().to_string();
+        if !question.is_empty() && !answer.is_empty() && !cards.iter().any(|c| c.question == question) {
+            cards.push(ParsedCard { question, answer });
+        }
+    };
+
+    let mut lines = content.lines().peekable();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        if let Some(info) = trimmed.strip_prefix("```") {
+            let is_card = CARD_FENCES.contains(&info.trim());
+            let mut body = Vec::new();
+            for inner in lines.by_ref() {
+                if inner.trim() == "```" {
+                    break;
+                }
+                body.push(inner);
+            }
+            if is_card {
+                if let Some(split) = body.iter().position(|l| l.trim() == FENCE_SEPARATOR) {
+                    push(&body[..split], &body[split + 1..]);
+                }
+            }
+            continue;
+        }
+
+        let Some(first) = trimmed.strip_prefix("Q:") else {
+            continue;
+        };
+
+        let mut question = vec![first];
+        let mut answer = Vec::new();
+        let mut in_answer = false;
+        while let Some(next) = lines.peek() {
+            let next = next.trim();
+            if next.is_empty() || next.starts_with("Q:") || next.starts_with("```") {
+                break;
+            }
+            lines.next();
+            match next.strip_prefix("A:") {
+                Some(rest) if !in_answer => {
+                    in_answer = true;
+                    answer.push(rest);
+                }
+                _ if in_answer => answer.push(next),
+                _ => question.push(next),
+            }
+        }
+        push(&question, &answer);
+    }
+
+    cards
+}
+
+/// SM-2 scheduling state of a card
+#[derive(Debug, Clone, Copy)]
+pub struct Schedule {
+    pub ease: f64,
+    pub interval_days: i64,
+    pub repetitions: i64,
+}
+
+/// Apply a review graded 0 (blackout) to 5 (perfect recall) using SM-2.
+/// A lapse (grade < 3) resets repetitions and brings the card back tomorrow.
+pub fn review(schedule: Schedule, grade: u8) -> Schedule {
+    let grade = grade.min(MAX_GRADE);
+    let q = f64::from(grade);
+    let ease = (schedule.ease + (0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02))).max(MIN_EASE);
+
+    if grade < PASSING_GRADE {
+        return Schedule { ease, interval_days: 1, repetitions: 0 };
+    }
+
+    let interval_days = match schedule.repetitions {
+        0 => 1,
+        1 => 6,
+        _ => ((schedule.interval_days as f64) * schedule.ease).round() as i64,
+    };
+
+    Schedule {
+        ease,
+        interval_days: interval_days.max(1),
+        repetitions: schedule.repetitions + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_card() -> Schedule {
+        Schedule {
+            ease: INITIAL_EASE,
+            interval_days: 0,
+            repetitions: 0,
+        }
+    }
+
+    #[test]
+    fn parses_both_card_forms() {
+        let content = "Q: Capital of France?\nA: Paris\n\n\
+                       ```card\nTwo\nlines\n---\nanswer\n```\n\n\
+                       Q: Capital of France?\nA: Lyon";
+        let cards = parse_cards(content);
+        assert_eq!(
+            cards,
+            [
+                ParsedCard {
+                    question: "Capital of France?".to_string(),
+                    answer: "Paris".to_string(),
+                },
+                ParsedCard {
+                    question: "Two\nlines".to_string(),
+                    answer: "answer".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn intervals_grow_with_successful_reviews() {
+        let first = review(new_card(), 5);
+        assert_eq!((first.interval_days, first.repetitions), (1, 1));
+        assert!((first.ease - 2.6).abs() < 1e-9);
+
+        let second = review(first, 4);
+        assert_eq!((second.interval_days, second.repetitions), (6, 2));
+        assert!((second.ease - 2.6).abs() < 1e-9);
+
+        let third = review(second, 4);
+        assert_eq!((third.interval_days, third.repetitions), (16, 3));
+    }
+
+    #[test]
+    fn lapse_restarts_the_card_and_ease_has_a_floor() {
+        let schedule = Schedule {
+            ease: 1.4,
+            interval_days: 30,
+            repetitions: 5,
+        };
+        let lapsed = review(schedule, 1);
+        assert_eq!((lapsed.interval_days, lapsed.repetitions), (1, 0));
+        assert!((lapsed.ease - MIN_EASE).abs() < 1e-9);
+    }
+
+    #[test]
+    fn grades_above_the_maximum_count_as_perfect() {
+        let perfect = review(new_card(), MAX_GRADE);
+        let clamped = review(new_card(), 9);
+        assert!((perfect.ease - clamped.ease).abs() < 1e-9);
+        assert_eq!(perfect.interval_days, clamped.interval_days);
+    }
+}
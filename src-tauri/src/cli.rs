@@ -0,0 +1,228 @@
+// Command line entry: `flashnotes new "text"`, `echo text | flashnotes new`
+// and `flashnotes search foo`. The process started from the terminal does
+// not open a window. It saves piped stdin to a file, relaunches the app with
+// `--reply <file>` (plus `--content-file` for stdin) and waits for that file:
+// the relaunched process forwards its arguments to the running instance
+// through the single-instance plugin, or becomes the app itself, and either
+// way the instance that handles the command writes the output there. On
+// Windows, release builds have no console, so nothing is printed.
+
+use crate::commands::buffer::insert_buffer;
+use crate::db::activity::Origin;
+use crate::db::queries;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::AppState;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Emitted when a command line invocation created a note
+pub const CLI_NOTE_EVENT: &str = "cli-note-created";
+
+/// Where the handling instance writes a command's output
+const REPLY_FLAG: &str = "--reply";
+
+/// File holding the content piped into `flashnotes new`
+const CONTENT_FILE_FLAG: &str = "--content-file";
+
+/// How long the terminal process waits for the app (it may have to start up)
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Search results printed by `flashnotes search`
+const SEARCH_LIMIT: usize = 20;
+
+/// A command given on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Create a note; the content comes from the arguments or `--content-file`
+    New { content: String, content_file: Option<PathBuf> },
+    Search { query: String },
+}
+
+/// A parsed command line: the command and where to write its output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub command: Command,
+    pub reply: Option<PathBuf>,
+}
+
+/// Parse the arguments (without the program name). None when they hold no
+/// command, e.g. a plain launch or a flashnotes:// link.
+pub fn parse(args: &[String]) -> Option<Invocation> {
+    let mut words = Vec::new();
+    let mut reply = None;
+    let mut content_file = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            REPLY_FLAG => reply = args.next().map(PathBuf::from),
+            CONTENT_FILE_FLAG => content_file = args.next().map(PathBuf::from),
+            // Flags added by the OS (e.g. macOS `-psn_...`) are not ours
+            _ if arg.starts_with('-') => {}
+            _ => words.push(arg.as_str()),
+        }
+    }
+
+    let (name, rest) = words.split_first()?;
+    let text = rest.join(" ");
+    let command = match *name {
+        "new" => Command::New { content: text, content_file },
+        "search" => Command::Search { query: text },
+        _ => return None,
+    };
+    Some(Invocation { command, reply })
+}
+
+/// Prefix of the files exchanged with the terminal process
+const TEMP_FILE_PREFIX: &str = "flashnotes-cli-";
+
+/// Only files made by `temp_file` are read, deleted or written on behalf of
+/// another process, never a path that merely arrived as an argument
+fn is_exchange_file(path: &Path) -> bool {
+    path.parent() == Some(std::env::temp_dir().as_path())
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(TEMP_FILE_PREFIX))
+}
+
+/// Write output for the terminal process; the rename makes it appear complete
+fn write_reply(path: &Path, output: &str) {
+    let staged = path.with_extension("tmp");
+    if let Err(e) = fs::write(&staged, output).and_then(|()| fs::rename(&staged, path)) {
+        warn!("Failed to write command line reply {:?}: {}", path, e);
+    }
+}
+
+/// Run a command in the app and return what to print
+fn execute(app: &AppHandle, command: Command) -> AppResult<String> {
+    let state = app.state::<AppState>();
+    state.ready.wait()?;
+    match command {
+        Command::New { content, content_file } => {
+            let content = match content_file {
+                Some(path) if !is_exchange_file(&path) => {
+                    return Err(AppError::Validation(format!("Not a piped content file: {}", path.display())));
+                }
+                Some(path) => {
+                    let piped = fs::read_to_string(&path).map_err(|e| AppError::io("Failed to read piped content", e));
+                    let _ = fs::remove_file(&path);
+                    piped?
+                }
+                None => content,
+            };
+            let summary = insert_buffer(app, &state, &content, "cli", Origin::Cli)?;
+            if let Err(e) = app.emit(CLI_NOTE_EVENT, &summary) {
+                warn!("Failed to emit {} event: {}", CLI_NOTE_EVENT, e);
+            }
+            Ok(format!("{}\n", summary.id))
+        }
+        Command::Search { query } => {
+            let Some(sanitized) = queries::sanitize_search_query(&query) else {
+                return Err(AppError::Validation(format!("Invalid search query: {}", query)));
+            };
+            let results = state
+                .read(|conn| queries::search_buffers(conn, &sanitized, None, SEARCH_LIMIT))
+                .db_context("Failed to search buffers")?;
+            Ok(results
+                .iter()
+                .map(|result| {
+                    let text = result.snippet.replace("<mark>", "").replace("</mark>", "");
+                    format!("{}\t{}\n", result.id, queries::extract_title_preview(&text).0)
+                })
+                .collect())
+        }
+    }
+}
+
+/// Handle a command among the arguments (program name first) of this
+/// instance or a second one. Returns false when they hold no command.
+pub fn handle_args(app: &AppHandle, args: &[String]) -> bool {
+    let Some(invocation) = parse(args.get(1..).unwrap_or_default()) else {
+        return false;
+    };
+
+    let app = app.clone();
+    let spawned = thread::Builder::new().name("cli".to_string()).spawn(move || {
+        info!("Running command line: {:?}", invocation.command);
+        let output = execute(&app, invocation.command).unwrap_or_else(|e| {
+            warn!("Command line failed: {}", e);
+            format!("error: {}\n", e)
+        });
+        match invocation.reply {
+            Some(reply) if is_exchange_file(&reply) => write_reply(&reply, &output),
+            Some(reply) => warn!("Refusing to write command line reply to {:?}", reply),
+            None => {}
+        }
+    });
+    if let Err(e) = spawned {
+        warn!("Failed to run command line: {}", e);
+    }
+    true
+}
+
+/// Reply and piped content files of one terminal invocation
+fn temp_file(kind: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    std::env::temp_dir().join(format!("{}{}-{}.{}", TEMP_FILE_PREFIX, process::id(), nanos, kind))
+}
+
+/// In the process started from the terminal: hand the command to the app and
+/// print its output. Returns the exit code, or None to start the app normally
+/// (no command, or this process is the relaunch that carries `--reply`).
+pub fn run_from_terminal() -> Option<i32> {
+    let args: Vec<String> = std::env::args().collect();
+    let invocation = parse(args.get(1..).unwrap_or_default())?;
+    if invocation.reply.is_some() {
+        return None;
+    }
+
+    let mut extra = Vec::new();
+    let stdin = io::stdin();
+    let is_empty_new = matches!(&invocation.command, Command::New { content, .. } if content.is_empty());
+    if is_empty_new && !stdin.is_terminal() {
+        let mut piped = String::new();
+        let content_file = temp_file("in");
+        if let Err(e) = stdin.lock().read_to_string(&mut piped).and_then(|_| fs::write(&content_file, piped)) {
+            eprintln!("flashnotes: failed to read stdin: {}", e);
+            return Some(1);
+        }
+        extra.push(CONTENT_FILE_FLAG.to_string());
+        extra.push(content_file.to_string_lossy().to_string());
+    }
+    let reply = temp_file("out");
+    extra.push(REPLY_FLAG.to_string());
+    extra.push(reply.to_string_lossy().to_string());
+
+    let relaunched = std::env::current_exe().and_then(|exe| {
+        process::Command::new(exe)
+            .args(&args[1..])
+            .args(&extra)
+            .stdin(process::Stdio::null())
+            .spawn()
+    });
+    if let Err(e) = relaunched {
+        eprintln!("flashnotes: failed to reach the app: {}", e);
+        return Some(1);
+    }
+
+    let start = Instant::now();
+    while start.elapsed() < REPLY_TIMEOUT {
+        if let Ok(output) = fs::read_to_string(&reply) {
+            let _ = fs::remove_file(&reply);
+            print!("{}", output);
+            return Some(if output.starts_with("error: ") { 1 } else { 0 });
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    eprintln!("flashnotes: no answer from the app (is the database locked?)");
+    Some(1)
+}
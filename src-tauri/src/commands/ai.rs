@@ -0,0 +1,175 @@
+use super::buffer::{insert_buffer, validate_buffer_size};
+use crate::ai::{self, transcribe, Feature};
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::attachments as db_attachments;
+use crate::db::queries::{self, AppSettings, Buffer, BufferSummary};
+use crate::db::webhooks::WebhookEvent;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::events::{self, Change};
+use crate::state::AppState;
+use crate::webhooks;
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Text streamed for one AI request
+#[derive(Clone, Serialize)]
+struct StreamPayload<'a> {
+    stream_id: &'a str,
+    delta: &'a str,
+}
+
+/// Longest accepted target language name
+const MAX_LANGUAGE_LEN: usize = 64;
+
+/// Result of `translate_buffer`
+#[derive(Debug, Serialize)]
+pub struct Translation {
+    pub text: String,
+    /// The translated copy, when one was requested
+    pub buffer: Option<BufferSummary>,
+}
+
+fn load(conn: &Connection, id: &str) -> AppResult<(AppSettings, Buffer)> {
+    let settings = queries::get_settings(conn).db_context("Failed to get settings")?;
+    let buffer = queries::get_buffer_content(conn, id)
+        .db_context("Failed to get buffer")?
+        .ok_or_else(|| AppError::not_found("Buffer", id))?;
+    Ok((settings, buffer))
+}
+
+/// Run an AI feature on a buffer off the main thread, returning the buffer
+/// and the reply. When `stream_id` is given, text is emitted as `ai-stream`
+/// events while it arrives.
+async fn run(app: AppHandle, id: String, feature: Feature, stream_id: Option<String>) -> AppResult<(Buffer, String)> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        state.track(feature.command(), || {
+            let (settings, buffer) = match state.get_reader() {
+                Ok(conn) => load(&conn, &id)?,
+                Err(_) => load(&state.writer.lock(), &id)?,
+            };
+            let provider = ai::provider(&settings)?;
+
+            let prompt = feature.prompt(&buffer.content)?;
+            let reply = ai::complete(provider.as_ref(), &prompt, &mut |delta| {
+                if let Some(stream_id) = &stream_id {
+                    if let Err(e) = app.emit(ai::STREAM_EVENT, StreamPayload { stream_id, delta }) {
+                        warn!("Failed to emit {} event: {}", ai::STREAM_EVENT, e);
+                    }
+                }
+            })?;
+            Ok((buffer, reply))
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("AI task failed: {}", e)))?
+}
+
+/// Summarize a buffer with the configured AI provider
+#[tauri::command]
+pub async fn ai_summarize(app: AppHandle, id: String, stream_id: Option<String>) -> AppResult<String> {
+    let (_, summary) = run(app, id, Feature::Summarize, stream_id).await?;
+    Ok(summary)
+}
+
+/// Suggest tags (without `#`) for a buffer
+#[tauri::command]
+pub async fn ai_suggest_tags(app: AppHandle, id: String, stream_id: Option<String>) -> AppResult<Vec<String>> {
+    let (_, reply) = run(app, id, Feature::Tags, stream_id).await?;
+    Ok(ai::parse_tags(&reply))
+}
+
+/// Suggest a title for a buffer
+#[tauri::command]
+pub async fn ai_suggest_title(app: AppHandle, id: String, stream_id: Option<String>) -> AppResult<String> {
+    let (_, reply) = run(app, id, Feature::Title, stream_id).await?;
+    Ok(ai::parse_title(&reply))
+}
+
+/// Translate a buffer into `target_lang`. With `create_copy`, the translation
+/// is also saved as a new note linking back to the original.
+#[tauri::command]
+pub async fn translate_buffer(
+    app: AppHandle,
+    id: String,
+    target_lang: String,
+    create_copy: Option<bool>,
+    stream_id: Option<String>,
+) -> AppResult<Translation> {
+    let language = target_lang.trim().to_string();
+    if language.is_empty() || language.chars().count() > MAX_LANGUAGE_LEN || language.contains(char::is_control) {
+        return Err(AppError::Validation(format!("Invalid target language: {}", target_lang)));
+    }
+
+    let (original, text) = run(app.clone(), id, Feature::Translate(language), stream_id).await?;
+    if !create_copy.unwrap_or(false) {
+        return Ok(Translation { text, buffer: None });
+    }
+
+    let title = original
+        .title_override
+        .unwrap_or_else(|| queries::extract_title_preview(&original.content).0);
+    let content = format!("{}\n\n---\n*Translated from [[{}]]*\n", text, title);
+    let state = app.state::<AppState>();
+    let summary = state.track("translate_buffer", || insert_buffer(&app, &state, &content, "manual", Origin::Ui))?;
+    Ok(Translation {
+        text,
+        buffer: Some(summary),
+    })
+}
+
+/// Transcribe an audio attachment and append the transcript to its buffer,
+/// so spoken captures become searchable. Returns the buffer's new content.
+#[tauri::command]
+pub async fn transcribe_attachment(app: AppHandle, id: String) -> AppResult<String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        state.track("transcribe_attachment", || {
+            let (settings, attachment) = state.read(|conn| {
+                let settings = queries::get_settings(conn).db_context("Failed to get settings")?;
+                let attachment = db_attachments::get_attachment(conn, &id)
+                    .db_context("Failed to get attachment")?
+                    .ok_or_else(|| AppError::not_found("Attachment", id.clone()))?;
+                Ok::<_, AppError>((settings, attachment))
+            })?;
+            if !transcribe::is_audio(&attachment.file_name) {
+                return Err(AppError::Validation(format!("Not an audio file: {}", attachment.file_name)));
+            }
+
+            // The upload can take minutes; don't hold the writer meanwhile
+            let path = Path::new(&attachment.stored_path);
+            let transcript = transcribe::transcribe(&settings, path, &attachment.file_name)?;
+            if transcript.is_empty() {
+                return Err(AppError::Validation(format!("No speech found in {}", attachment.file_name)));
+            }
+
+            // Re-read the buffer: it may have been edited during transcription
+            let conn = state.writer.lock();
+            let buffer = queries::get_buffer_content(&conn, &attachment.buffer_id)
+                .db_context("Failed to get buffer")?
+                .ok_or_else(|| AppError::not_found("Buffer", attachment.buffer_id.clone()))?;
+            let mut content = buffer.content.trim_end().to_string();
+            if !content.is_empty() {
+                content.push_str("\n\n");
+            }
+            content.push_str(&format!("**Transcript ({}):**\n\n{}\n", attachment.file_name, transcript));
+            validate_buffer_size(&content)?;
+
+            queries::update_buffer_content(&conn, &buffer.id, &content, Utc::now().timestamp())
+                .db_context("Failed to save buffer")?;
+            activity::record(&conn, Some(&buffer.id), Operation::Save, Origin::Ui, Some("transcript"));
+            drop(conn);
+            webhooks::notify(&app, WebhookEvent::Updated, &buffer.id);
+            events::updated(&app, &buffer.id, Change::Content);
+
+            info!("Transcribed {} into buffer {}", attachment.file_name, buffer.id);
+            Ok(content)
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Transcription failed: {}", e)))?
+}
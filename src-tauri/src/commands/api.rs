@@ -0,0 +1,17 @@
+use crate::api;
+use crate::error::AppResult;
+use crate::state::AppState;
+use tauri::State;
+
+/// Token integrations send as `Authorization: Bearer <token>` to the local
+/// HTTP API (created on first use, kept in the keychain)
+#[tauri::command]
+pub fn get_api_token(state: State<'_, AppState>) -> AppResult<String> {
+    state.track("get_api_token", || api::token(&state))
+}
+
+/// Replace the API token; integrations using the old one stop working
+#[tauri::command]
+pub fn regenerate_api_token(state: State<'_, AppState>) -> AppResult<String> {
+    state.track("regenerate_api_token", || api::regenerate_token(&state))
+}
@@ -0,0 +1,88 @@
+use crate::attachments;
+use crate::db::attachments::{self as db_attachments, Attachment};
+use crate::db::connection;
+use crate::db::queries;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::track_blocking;
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tracing::info;
+use uuid::Uuid;
+
+/// Attach a file to a buffer. The file is copied into app data and its text
+/// (plain-text formats and PDFs) is indexed for search.
+#[tauri::command]
+pub async fn add_attachment(app: AppHandle, buffer_id: String, path: String) -> AppResult<Attachment> {
+    track_blocking(app, "add_attachment", move |_, state| {
+        {
+            let conn = state.writer.lock();
+            if queries::get_buffer_content(&conn, &buffer_id)
+                .db_context("Failed to get buffer")?
+                .is_none()
+            {
+                return Err(AppError::not_found("Buffer", buffer_id));
+            }
+        }
+
+        let source = PathBuf::from(&path);
+        let id = Uuid::new_v4().to_string();
+        let stored = attachments::store_file(&state.app_data_dir, &source, &id)?;
+
+        // Extract outside the writer lock: PDFs can take a while
+        let text = attachments::extract_text(&stored);
+
+        let attachment = Attachment {
+            id,
+            buffer_id,
+            file_name: source
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "attachment".to_string()),
+            stored_path: stored.to_string_lossy().into_owned(),
+            size: stored.metadata().map(|m| m.len() as i64).unwrap_or(0),
+            has_text: !text.is_empty(),
+            created_at: Utc::now().timestamp(),
+        };
+
+        let conn = state.writer.lock();
+        if let Err(e) = db_attachments::insert_attachment(&conn, &attachment, &text) {
+            attachments::remove_file(&stored);
+            return Err(e).db_context("Failed to save attachment");
+        }
+
+        info!("Attached {} to buffer {}", attachment.file_name, attachment.buffer_id);
+        Ok(attachment)
+    })
+    .await
+}
+
+/// List a buffer's attachments
+#[tauri::command]
+pub async fn list_attachments(app: AppHandle, buffer_id: String) -> AppResult<Vec<Attachment>> {
+    track_blocking(app, "list_attachments", move |_, state| match state.get_reader() {
+        Ok(conn) => db_attachments::list_attachments(&conn, &buffer_id).db_context("Failed to list attachments"),
+        Err(_) => {
+            let conn = state.writer.lock();
+            db_attachments::list_attachments(&conn, &buffer_id).db_context("Failed to list attachments")
+        }
+    })
+    .await
+}
+
+/// Remove an attachment and its stored file
+#[tauri::command]
+pub async fn delete_attachment(app: AppHandle, id: String) -> AppResult<()> {
+    track_blocking(app, "delete_attachment", move |_, state| {
+        let conn = state.writer.lock();
+        let attachment = db_attachments::get_attachment(&conn, &id)
+            .db_context("Failed to get attachment")?
+            .ok_or_else(|| AppError::not_found("Attachment", id.clone()))?;
+
+        db_attachments::delete_attachment(&conn, &id).db_context("Failed to delete attachment")?;
+        connection::scrub_after_delete(&conn);
+        attachments::remove_file(Path::new(&attachment.stored_path));
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,74 @@
+use crate::backup_schedule;
+use crate::db::backup::{self, BackupInfo};
+use crate::db::connection;
+use crate::db::queries;
+use crate::db::recovery::RecoveryReport;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::startup;
+use crate::state::{track_blocking, AppState};
+use std::path::Path;
+use tauri::{AppHandle, Emitter, State};
+use tracing::{info, warn};
+
+/// Emitted after a backup replaced the database; the frontend reloads everything
+pub const DATABASE_RESTORED_EVENT: &str = "database-restored";
+
+/// What happened if the database was found corrupt on launch (None when it was fine)
+#[tauri::command]
+pub async fn get_recovery_report(app: AppHandle) -> AppResult<Option<RecoveryReport>> {
+    track_blocking(app, "get_recovery_report", |_, state| Ok(state.recovery.lock().clone())).await
+}
+
+/// Backups in the app data directory, newest first
+#[tauri::command]
+pub fn list_backups(state: State<'_, AppState>) -> AppResult<Vec<BackupInfo>> {
+    state.track("list_backups", || Ok(backup::list_backups(&state.app_data_dir)))
+}
+
+/// Back up the database right away (also to the backup destination, if set)
+/// and return the path of the new backup
+#[tauri::command]
+pub async fn backup_now(app: AppHandle) -> AppResult<String> {
+    track_blocking(app, "backup_now", move |_, state| {
+        let settings = state.read(queries::get_settings).db_context("Failed to get settings")?;
+        let path = backup_schedule::backup_now(state, &settings).map_err(AppError::Internal)?;
+        info!("Manual backup created: {:?}", path);
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Replace the database with a backup and return the path of the safety copy
+/// taken of the current database first. The backup must pass an integrity
+/// check; backups from older versions are migrated like on startup.
+#[tauri::command]
+pub async fn restore_backup(app: AppHandle, path: String) -> AppResult<String> {
+    track_blocking(app, "restore_backup", move |app, state| {
+        let source = Path::new(&path);
+        if !source.is_file() {
+            return Err(AppError::not_found("Backup", path.clone()));
+        }
+        // Backups of an encrypted database share its key
+        let key = state.db_key.get();
+        backup::validate_backup(source, key.as_deref()).map_err(AppError::Validation)?;
+
+        let mut conn = state.writer.lock();
+        let safety = backup::create_snapshot(&conn, &state.app_data_dir, "prerestore").map_err(AppError::Internal)?;
+        backup::restore_backup(&mut conn, source, key.as_deref()).map_err(AppError::Internal)?;
+        // The restored settings may turn secure deletion on or off
+        if let Err(e) = connection::apply_secure_delete(&conn) {
+            warn!("Failed to apply secure deletion: {}", e);
+        }
+        drop(conn);
+
+        // Migrate the restored schema and reload settings, recent buffers and webhooks
+        startup::init_schema(state).map_err(AppError::Internal)?;
+        info!("Restored database from {:?}; previous database kept at {:?}", source, safety);
+
+        if let Err(e) = app.emit(DATABASE_RESTORED_EVENT, ()) {
+            warn!("Failed to emit {} event: {}", DATABASE_RESTORED_EVENT, e);
+        }
+        Ok(safety.to_string_lossy().to_string())
+    })
+    .await
+}
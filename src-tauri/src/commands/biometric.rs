@@ -0,0 +1,69 @@
+use crate::biometric;
+use crate::encryption;
+use crate::error::{AppError, AppResult};
+use crate::note_lock;
+use crate::state::{track_blocking, AppState};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+/// Whether Touch ID can be used, and whether it is set up for the requested target
+#[derive(Debug, Serialize)]
+pub struct BiometricStatus {
+    pub available: bool,
+    pub enabled: bool,
+}
+
+/// Touch ID status for the database (no `buffer_id`) or a locked note.
+/// Not tracked so the database unlock prompt can offer Touch ID.
+#[tauri::command]
+pub fn get_biometric_status(buffer_id: Option<String>) -> AppResult<BiometricStatus> {
+    Ok(BiometricStatus {
+        available: biometric::available(),
+        enabled: biometric::is_enabled(buffer_id.as_deref())?,
+    })
+}
+
+/// Remember the passphrase of the database (no `buffer_id`) or a locked note
+/// for Touch ID unlock
+#[tauri::command]
+pub async fn enable_biometric_unlock(
+    app: AppHandle,
+    buffer_id: Option<String>,
+    passphrase: String,
+) -> AppResult<()> {
+    track_blocking(app, "enable_biometric_unlock", move |_, state| {
+        biometric::enable(state, buffer_id.as_deref(), &passphrase)
+    })
+    .await
+}
+
+/// Forget the passphrase stored for Touch ID unlock
+#[tauri::command]
+pub fn disable_biometric_unlock(state: State<'_, AppState>, buffer_id: Option<String>) -> AppResult<()> {
+    state.track("disable_biometric_unlock", || biometric::forget(buffer_id.as_deref()))
+}
+
+/// Unlock the encrypted database with Touch ID. Not tracked, like `unlock_database`.
+#[tauri::command]
+pub async fn unlock_database_with_biometrics(app: AppHandle) -> AppResult<()> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let passphrase = biometric::passphrase(None)?;
+        encryption::unlock(&app, &passphrase)
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Touch ID unlock failed: {}", e)))?
+}
+
+/// Unlock a locked note with Touch ID and return its content
+#[tauri::command]
+pub async fn unlock_buffer_with_biometrics(app: AppHandle, id: String) -> AppResult<String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        state.track("unlock_buffer_with_biometrics", || {
+            let passphrase = biometric::passphrase(Some(&id))?;
+            note_lock::unlock(&app, &state, &id, &passphrase)
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Touch ID unlock failed: {}", e)))?
+}
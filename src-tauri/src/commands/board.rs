@@ -0,0 +1,124 @@
+use crate::board;
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::queries::{self, BufferSummary};
+use crate::db::webhooks::WebhookEvent;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::events::{self, Change};
+use crate::state::track_blocking;
+use crate::webhooks;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+/// Notes sharing one property value
+#[derive(Debug, Serialize)]
+pub struct BoardColumn {
+    pub value: String,
+    pub cards: Vec<BufferSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Board {
+    pub property: String,
+    pub columns: Vec<BoardColumn>,
+}
+
+fn validate_name(kind: &str, name: &str) -> AppResult<String> {
+    let name = name.trim().trim_start_matches('#').to_lowercase();
+    if board::is_valid_name(&name) {
+        Ok(name)
+    } else {
+        Err(AppError::Validation(format!("Invalid board {}: {}", kind, name)))
+    }
+}
+
+/// Group non-archived notes into columns by `property`, set with a
+/// `#property/value` hashtag or a `property:: value` line (e.g.
+/// `#status/doing`). Columns listed in `columns` come first, in that order
+/// and even when empty; other values found follow alphabetically. Cards keep
+/// sidebar order. Notes without the property are left out.
+#[tauri::command]
+pub async fn get_board(app: AppHandle, property: String, columns: Option<Vec<String>>) -> AppResult<Board> {
+    track_blocking(app, "get_board", move |_, state| {
+        let property = validate_name("property", &property)?;
+        let mut board = Board {
+            property: property.clone(),
+            columns: Vec::new(),
+        };
+        for value in columns.unwrap_or_default() {
+            let value = validate_name("column", &value)?;
+            if !board.columns.iter().any(|c| c.value == value) {
+                board.columns.push(BoardColumn { value, cards: Vec::new() });
+            }
+        }
+        let configured = board.columns.len();
+
+        let load = |conn: &rusqlite::Connection| {
+            let mut values = HashMap::new();
+            let mut ids = Vec::new();
+            for (id, content) in queries::get_board_candidates(conn, &property)? {
+                if let Some(value) = board::property_value(&content, &property) {
+                    values.insert(id.clone(), value);
+                    ids.push(id);
+                }
+            }
+            let summaries = queries::get_buffer_summaries(conn, &ids, ids.len())?;
+            Ok::<_, rusqlite::Error>(
+                summaries
+                    .into_iter()
+                    .filter_map(|summary| Some((values.remove(&summary.id)?, summary)))
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let cards = match state.get_reader() {
+            Ok(conn) => load(&conn),
+            Err(_) => {
+                let conn = state.writer.lock();
+                load(&conn)
+            }
+        }
+        .db_context("Failed to get board")?;
+
+        for (value, card) in cards {
+            match board.columns.iter_mut().find(|c| c.value == value) {
+                Some(column) => column.cards.push(card),
+                None => board.columns.push(BoardColumn { value, cards: vec![card] }),
+            }
+        }
+        board.columns[configured..].sort_by(|a, b| a.value.cmp(&b.value));
+
+        Ok(board)
+    })
+    .await
+}
+
+/// Move a note to another board column by setting its `property` to
+/// `value`, rewriting the existing hashtag or field (or appending a
+/// `#property/value` tag). Returns the updated content.
+#[tauri::command]
+pub async fn move_card(app: AppHandle, id: String, property: String, value: String) -> AppResult<String> {
+    track_blocking(app, "move_card", move |app, state| {
+        let property = validate_name("property", &property)?;
+        let value = validate_name("column", &value)?;
+
+        let conn = state.writer.lock();
+        let buffer = queries::get_buffer_content(&conn, &id)
+            .db_context("Failed to get buffer")?
+            .ok_or_else(|| AppError::not_found("Buffer", id.clone()))?;
+        if board::property_value(&buffer.content, &property).as_deref() == Some(value.as_str()) {
+            return Ok(buffer.content);
+        }
+
+        let content = board::set_property(&buffer.content, &property, &value);
+        queries::update_buffer_content(&conn, &id, &content, Utc::now().timestamp())
+            .db_context("Failed to save buffer")?;
+        activity::record(&conn, Some(&id), Operation::Save, Origin::Ui, Some("board"));
+        drop(conn);
+        webhooks::notify(app, WebhookEvent::Updated, &id);
+        events::updated(app, &id, Change::Content);
+
+        Ok(content)
+    })
+    .await
+}
@@ -1,16 +1,78 @@
-use crate::db::queries::{self, BufferSummary, SearchResult};
-use crate::state::AppState;
+use crate::db::activity::{self, ActivityEntry, NewEntry, Operation, Origin};
+use crate::db::backup;
+use crate::db::queries::{self, Buffer, BufferSummary, DedupeReport, Direction, SearchResult, SortMode};
+use crate::db::revisions;
+use crate::db::tags;
+use crate::db::webhooks::WebhookEvent;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::events::{self, Change};
+use crate::find::{self, FindOptions, FindResult};
+use crate::journal;
+use crate::language::{self, LanguageGuess};
+use crate::live_merge;
+use crate::maintenance;
+use crate::paste::{self, PasteSuggestion};
+use crate::plugins::{self, Hook};
+use crate::save_hook;
+use crate::state::{track_blocking, AppState};
+use crate::tasks;
+use crate::trash;
+use crate::undo::{self, ReorderData, UndoResult};
+use crate::webhooks;
 use chrono::Utc;
-use tauri::State;
+use serde::Serialize;
+use std::collections::HashSet;
+use tauri::{AppHandle, Emitter, State, WebviewWindow};
 use uuid::Uuid;
 use tracing::{info, warn};
 
 /// Maximum buffer size in bytes (10MB)
 const MAX_BUFFER_SIZE: usize = 10 * 1024 * 1024;
 
-/// Convert rusqlite errors to user-friendly strings
-fn map_db_error<T>(result: Result<T, rusqlite::Error>, context: &str) -> Result<T, String> {
-    result.map_err(|e| format!("{}: {}", context, e))
+/// Kinds of note origin. A source is a kind, optionally followed by
+/// `:detail` (e.g. "import:notes.md", "web:https://example.com").
+const SOURCE_KINDS: &[&str] = &[
+    "manual", "clipboard", "cli", "deep_link", "share", "import", "web", "script", "digest", "sync", "api",
+];
+
+/// Maximum length of a source value
+const MAX_SOURCE_LEN: usize = 1024;
+
+/// Sidebar buffers returned per `get_sidebar_data` call by default
+const SIDEBAR_PAGE_SIZE: usize = 100;
+
+/// Most sidebar buffers returned by one call (e.g. reloading everything loaded so far)
+const MAX_SIDEBAR_PAGE_SIZE: usize = 5000;
+
+/// Event emitted when a save or rename gives a note the same title as another
+/// (with the `duplicate_titles` setting on "warn")
+pub const DUPLICATE_TITLE_EVENT: &str = "duplicate-title";
+
+/// What to do when two notes would share an effective title (ambiguous for `[[links]]`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateTitlePolicy {
+    Allow,
+    Warn,
+    Block,
+}
+
+impl DuplicateTitlePolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "allow" => Some(Self::Allow),
+            "warn" => Some(Self::Warn),
+            "block" => Some(Self::Block),
+            _ => None,
+        }
+    }
+}
+
+/// Payload for `DUPLICATE_TITLE_EVENT`
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateTitle {
+    pub id: String,
+    pub title: String,
+    pub duplicate_ids: Vec<String>,
 }
 
 /// Get current Unix timestamp
@@ -19,201 +81,853 @@ fn now() -> i64 {
 }
 
 /// Validate buffer content size
-fn validate_buffer_size(content: &str) -> Result<(), String> {
+pub(crate) fn validate_buffer_size(content: &str) -> AppResult<()> {
     let size = content.len();
     if size > MAX_BUFFER_SIZE {
         let size_mb = size as f64 / (1024.0 * 1024.0);
-        return Err(format!(
+        return Err(AppError::Validation(format!(
             "Buffer content too large ({:.2}MB). Maximum size is 10MB. \
             Consider splitting your note into smaller parts.",
             size_mb
-        ));
+        )));
+    }
+    Ok(())
+}
+
+/// Check a buffer's new effective title against the `duplicate_titles` setting.
+/// Only titles that change into a clash count, so notes that already shared a
+/// title (or placeholder "Untitled" notes) keep saving normally. Returns the
+/// clash to warn about, or a Conflict error when blocking.
+fn check_duplicate_title(conn: &rusqlite::Connection, id: &str, title: &str) -> AppResult<Option<DuplicateTitle>> {
+    let policy = queries::get_setting(conn, "duplicate_titles")
+        .db_context("Failed to get settings")?
+        .and_then(|v| DuplicateTitlePolicy::parse(&v))
+        .unwrap_or(DuplicateTitlePolicy::Allow);
+    if policy == DuplicateTitlePolicy::Allow {
+        return Ok(None);
+    }
+
+    let slug = queries::slugify(title);
+    if slug == "untitled" || queries::get_slug(conn, id).db_context("Failed to get slug")?.as_deref() == Some(&slug) {
+        return Ok(None);
+    }
+
+    let duplicate_ids = queries::find_buffers_by_slug(conn, &slug, id).db_context("Failed to check titles")?;
+    if duplicate_ids.is_empty() {
+        return Ok(None);
+    }
+    if policy == DuplicateTitlePolicy::Block {
+        return Err(AppError::Conflict(format!("Another note is already titled \"{}\"", title)));
+    }
+
+    Ok(Some(DuplicateTitle {
+        id: id.to_string(),
+        title: title.to_string(),
+        duplicate_ids,
+    }))
+}
+
+/// Tell the frontend about a duplicate title (best effort)
+fn emit_duplicate_title(app: &AppHandle, duplicate: Option<DuplicateTitle>) {
+    if let Some(duplicate) = duplicate {
+        if let Err(e) = app.emit(DUPLICATE_TITLE_EVENT, duplicate) {
+            warn!("Failed to emit {} event: {}", DUPLICATE_TITLE_EVENT, e);
+        }
+    }
+}
+
+/// Validate a note source ("kind" or "kind:detail")
+pub(crate) fn validate_source(source: &str) -> AppResult<()> {
+    let kind = source.split_once(':').map_or(source, |(kind, _)| kind);
+    if !SOURCE_KINDS.contains(&kind) || source.len() > MAX_SOURCE_LEN || source.chars().any(char::is_control) {
+        return Err(AppError::Validation(format!("Invalid note source: {}", source)));
     }
     Ok(())
 }
 
-/// Create a new buffer with optional content, return summary for immediate UI update
+/// Create a new buffer with optional content and source (default "manual"),
+/// return summary for immediate UI update
 #[tauri::command]
-pub fn create_buffer(state: State<'_, AppState>, content: Option<String>) -> Result<BufferSummary, String> {
-    let content = content.unwrap_or_default();
+pub async fn create_buffer(
+    app: AppHandle,
+    content: Option<String>,
+    source: Option<String>,
+) -> AppResult<BufferSummary> {
+    track_blocking(app, "create_buffer", move |app, state| {
+        let content = content.unwrap_or_default();
+        let source = source.unwrap_or_else(|| "manual".to_string());
+        insert_buffer(app, state, &content, &source, Origin::Ui)
+    })
+    .await
+}
 
+/// Create a buffer with the given content, running create hooks. Returns the
+/// summary for immediate UI update (no refetch needed).
+pub(crate) fn insert_buffer(
+    app: &AppHandle,
+    state: &AppState,
+    content: &str,
+    source: &str,
+    origin: Origin,
+) -> AppResult<BufferSummary> {
     // Validate size before creating
-    validate_buffer_size(&content)?;
+    validate_buffer_size(content)?;
+    validate_source(source)?;
 
     let id = Uuid::new_v4().to_string();
     let timestamp = now();
     let conn = state.writer.lock();
 
-    map_db_error(
-        queries::create_buffer(&conn, &id, &content, timestamp),
-        "Failed to create buffer",
-    )?;
+    queries::create_buffer(&conn, &id, content, source, timestamp).db_context("Failed to create buffer")?;
+    activity::record(&conn, Some(&id), Operation::Create, origin, Some(source));
+
+    drop(conn);
 
     info!("Created buffer: {}", id);
+    state.mru.push(&id);
+    plugins::run_buffer_hook(app, state, Hook::Create, &id, content);
+    webhooks::notify(app, WebhookEvent::Created, &id);
 
-    // Return summary for immediate UI update (no refetch needed)
-    let (title, preview) = queries::extract_title_preview(&content);
-    Ok(BufferSummary {
+    let (title, preview) = queries::extract_title_preview(content);
+    let (tasks_open, tasks_total) = tasks::count(content);
+    let summary = BufferSummary {
         id,
         title,
         preview,
         updated_at: timestamp,
         is_pinned: false,
-    })
+        word_count: queries::count_words(content),
+        tasks_open,
+        tasks_total,
+        has_title_override: false,
+        is_locked: false,
+    };
+    events::created(app, &summary);
+    Ok(summary)
 }
 
-/// Save buffer content and return updated title/preview for sidebar
-#[tauri::command]
-pub fn save_buffer(state: State<'_, AppState>, id: String, content: String) -> Result<(String, String), String> {
-    // Validate size before saving
-    validate_buffer_size(&content)?;
+/// Replace a buffer's content from outside the editor (e.g. the HTTP API),
+/// running save hooks and telling open windows to reload it
+pub(crate) fn replace_buffer_content(
+    app: &AppHandle,
+    state: &AppState,
+    id: &str,
+    content: &str,
+    origin: Origin,
+) -> AppResult<()> {
+    validate_buffer_size(content)?;
 
     let conn = state.writer.lock();
-    map_db_error(
-        queries::update_buffer_content(&conn, &id, &content, now()),
-        "Failed to save buffer",
-    )?;
-    // Return new title/preview so frontend can update sidebar without refetch
-    Ok(queries::extract_title_preview(&content))
-}
-
-/// Get buffer content by ID
-#[tauri::command]
-pub fn get_buffer_content(state: State<'_, AppState>, id: String) -> Result<String, String> {
-    // Try reader pool first, fall back to writer
-    let content = match state.get_reader() {
-        Ok(conn) => {
-            // Touch using writer (it's a write operation)
-            {
-                let writer = state.writer.lock();
-                map_db_error(
-                    queries::touch_buffer(&writer, &id, now()),
-                    "Failed to touch buffer",
-                )?;
+    let stored = queries::get_buffer_content(&conn, id)
+        .db_context("Failed to get buffer")?
+        .ok_or_else(|| AppError::not_found("Buffer", id))?;
+    if stored.is_locked {
+        return Err(AppError::Conflict("Note is locked".to_string()));
+    }
+    let (title, _) = queries::buffer_title_preview(&conn, id, content).db_context("Failed to get buffer title")?;
+    let duplicate = check_duplicate_title(&conn, id, &title)?;
+
+    queries::update_buffer_content(&conn, id, content, now()).db_context("Failed to save buffer")?;
+    activity::record(&conn, Some(id), Operation::Save, origin, None);
+    drop(conn);
+
+    emit_duplicate_title(app, duplicate);
+    plugins::run_buffer_hook(app, state, Hook::Save, id, content);
+    save_hook::schedule(app, id);
+    webhooks::notify(app, WebhookEvent::Updated, id);
+    events::updated(app, id, Change::Content);
+    live_merge::notify(app, vec![id.to_string()], None);
+    Ok(())
+}
+
+/// Save buffer content and return the updated title, preview and checklist
+/// counts (open, total) for the sidebar. `base` is the content the window
+/// started editing from; if the buffer changed since (in another window or
+/// via sync), the edit is merged with that change instead of replacing it
+/// and the merged content is returned last.
+#[tauri::command]
+pub async fn save_buffer(
+    app: AppHandle,
+    window: WebviewWindow,
+    id: String,
+    content: String,
+    base: Option<String>,
+) -> AppResult<(String, String, i64, i64, Option<String>)> {
+    track_blocking(app, "save_buffer", move |app, state| {
+        let conn = state.writer.lock();
+        let stored = queries::get_buffer_content(&conn, &id).db_context("Failed to get buffer")?;
+        if stored.as_ref().is_some_and(|b| b.is_locked) {
+            return Err(AppError::Conflict("Note is locked".to_string()));
+        }
+        let merge = stored.and_then(|stored| live_merge::reconcile(&stored.content, base.as_deref(), &content));
+        if let Some(merge) = &merge {
+            info!("Merged concurrent edits of buffer {} ({} conflicts)", id, merge.conflicts);
+        }
+        let merged = merge.map(|m| m.text);
+        let content = merged.as_deref().unwrap_or(&content);
+
+        // Validate size before saving
+        validate_buffer_size(content)?;
+
+        let (title, preview) =
+            queries::buffer_title_preview(&conn, &id, content).db_context("Failed to get buffer title")?;
+        let duplicate = check_duplicate_title(&conn, &id, &title)?;
+
+        queries::update_buffer_content(&conn, &id, content, now())
+            .db_context("Failed to save buffer")?;
+        activity::record(&conn, Some(&id), Operation::Save, Origin::Ui, None);
+        // Saved content supersedes any crash-recovery journal entry
+        journal::remove_entry(&journal::journal_dir(&state.app_data_dir), &id);
+        drop(conn);
+        emit_duplicate_title(app, duplicate);
+        plugins::run_buffer_hook(app, state, Hook::Save, &id, content);
+        save_hook::schedule(app, &id);
+        webhooks::notify(app, WebhookEvent::Updated, &id);
+        events::updated(app, &id, Change::Content);
+        live_merge::notify(app, vec![id.clone()], Some(window.label()));
+
+        // Return new title/preview/tasks so frontend can update sidebar without refetch
+        let (tasks_open, tasks_total) = tasks::count(content);
+        Ok((title, preview, tasks_open, tasks_total, merged))
+    })
+    .await
+}
+
+/// Get buffer content by ID (read-only; use `mark_accessed` when the user opens a note)
+#[tauri::command]
+pub async fn get_buffer_content(app: AppHandle, id: String) -> AppResult<String> {
+    track_blocking(app, "get_buffer_content", move |_, state| {
+        // Try reader pool first, fall back to writer
+        let content = match state.get_reader() {
+            Ok(conn) => queries::get_buffer_content(&conn, &id),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_buffer_content(&conn, &id)
             }
+        }
+        .db_context("Failed to get buffer")?;
 
-            map_db_error(
-                queries::get_buffer_content(&conn, &id),
-                "Failed to get buffer",
-            )?
+        content
+            .map(|b| b.content)
+            .ok_or_else(|| AppError::not_found("Buffer", id))
+    })
+    .await
+}
+
+/// Record that the user opened a buffer (drives "recently used" ordering)
+#[tauri::command]
+pub async fn mark_accessed(app: AppHandle, id: String) -> AppResult<()> {
+    track_blocking(app, "mark_accessed", move |_, state| {
+        let conn = state.writer.lock();
+        if queries::touch_buffer(&conn, &id, now()).db_context("Failed to touch buffer")? {
+            state.mru.push(&id);
         }
-        Err(_) => {
-            // Fall back to writer
-            let conn = state.writer.lock();
-            map_db_error(
-                queries::touch_buffer(&conn, &id, now()),
-                "Failed to touch buffer",
-            )?;
+        Ok(())
+    })
+    .await
+}
 
-            map_db_error(
-                queries::get_buffer_content(&conn, &id),
-                "Failed to get buffer",
-            )?
+/// Get a buffer with all of its metadata (read-only, doesn't affect access order)
+#[tauri::command]
+pub async fn get_buffer(app: AppHandle, id: String) -> AppResult<Buffer> {
+    track_blocking(app, "get_buffer", move |_, state| {
+        let buffer = match state.get_reader() {
+            Ok(conn) => queries::get_buffer_content(&conn, &id),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_buffer_content(&conn, &id)
+            }
         }
+        .db_context("Failed to get buffer")?;
+
+        buffer.ok_or_else(|| AppError::not_found("Buffer", id))
+    })
+    .await
+}
+
+/// Get a page of sidebar data (buffer summaries in sidebar order), optionally
+/// only buffers with `tag`. `limit` defaults to `SIDEBAR_PAGE_SIZE`; use
+/// `get_buffer_count` to know when everything is loaded.
+#[tauri::command]
+pub async fn get_sidebar_data(
+    app: AppHandle,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    tag: Option<String>,
+) -> AppResult<Vec<BufferSummary>> {
+    track_blocking(app, "get_sidebar_data", move |_, state| {
+        let offset = offset.unwrap_or(0);
+        let limit = limit.unwrap_or(SIDEBAR_PAGE_SIZE).clamp(1, MAX_SIDEBAR_PAGE_SIZE);
+        let tag = tag.as_deref().map(|t| tags::normalize_name(t).unwrap_or_default());
+
+        state
+            .read(|conn| match &tag {
+                Some(tag) => queries::get_sidebar_buffers_by_tag(conn, tag, limit, offset),
+                None => queries::get_sidebar_buffers(conn, limit, offset),
+            })
+            .db_context("Failed to get sidebar data")
+    })
+    .await
+}
+
+/// Number of buffers in the sidebar (optionally only those with `tag`), for
+/// paging through it with `get_sidebar_data`
+#[tauri::command]
+pub async fn get_buffer_count(app: AppHandle, tag: Option<String>) -> AppResult<i64> {
+    track_blocking(app, "get_buffer_count", move |_, state| {
+        let tag = tag.as_deref().map(|t| tags::normalize_name(t).unwrap_or_default());
+        state
+            .read(|conn| queries::count_sidebar_buffers(conn, tag.as_deref()))
+            .db_context("Failed to count buffers")
+    })
+    .await
+}
+
+/// Run a full-text search the way the app does: plugins may rewrite the
+/// query before it is validated. Fails with Validation for a query that
+/// can't be searched.
+pub(crate) fn search_notes(
+    state: &AppState,
+    query: &str,
+    source: Option<&str>,
+    limit: usize,
+) -> AppResult<Vec<SearchResult>> {
+    let query = state.plugins.transform_search(&state.reader_pool(), query);
+    let Some(sanitized) = queries::sanitize_search_query(&query) else {
+        return Err(AppError::Validation(format!("Invalid search query: {}", query)));
     };
+    state
+        .read(|conn| queries::search_buffers(conn, &sanitized, source, limit))
+        .db_context("Failed to search buffers")
+}
+
+/// Search buffers using FTS5, optionally filtered by source (a kind like
+/// "import" or an exact source like "import:notes.md")
+#[tauri::command]
+pub async fn search_buffers(
+    app: AppHandle,
+    query: String,
+    source: Option<String>,
+) -> AppResult<Vec<SearchResult>> {
+    track_blocking(app, "search_buffers", move |_, state| {
+        match search_notes(state, &query, source.as_deref(), 20) {
+            Err(AppError::Validation(message)) => {
+                warn!("{}", message);
+                Ok(Vec::new()) // Return empty results for invalid queries
+            }
+            result => result,
+        }
+    })
+    .await
+}
 
-    content
-        .map(|b| b.content)
-        .ok_or_else(|| format!("Buffer not found: {}", id))
+/// Find all occurrences of a literal or regex query within one buffer
+#[tauri::command]
+pub async fn find_in_buffer(
+    app: AppHandle,
+    id: String,
+    query: String,
+    options: Option<FindOptions>,
+) -> AppResult<FindResult> {
+    track_blocking(app, "find_in_buffer", move |_, state| {
+        let buffer = match state.get_reader() {
+            Ok(conn) => queries::get_buffer_content(&conn, &id),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_buffer_content(&conn, &id)
+            }
+        }
+        .db_context("Failed to get buffer")?
+        .ok_or_else(|| AppError::not_found("Buffer", id))?;
+
+        find::find_matches(&buffer.content, &query, &options.unwrap_or_default())
+    })
+    .await
 }
 
-/// Get sidebar data (list of buffer summaries)
+/// Classify pasted text and suggest a Markdown formatting the frontend can
+/// apply with one keystroke
 #[tauri::command]
-pub fn get_sidebar_data(state: State<'_, AppState>, offset: Option<usize>) -> Result<Vec<BufferSummary>, String> {
-    let offset = offset.unwrap_or(0);
-    let limit = 100; // Initial load and each subsequent batch
+pub fn analyze_paste(state: State<'_, AppState>, text: String) -> AppResult<PasteSuggestion> {
+    state.track("analyze_paste", || Ok(paste::analyze(&text)))
+}
 
-    // Try reader pool first
-    match state.get_reader() {
-        Ok(conn) => map_db_error(
-            queries::get_sidebar_buffers(&conn, limit, offset),
-            "Failed to get sidebar data",
-        ),
-        Err(_) => {
-            let conn = state.writer.lock();
-            map_db_error(
-                queries::get_sidebar_buffers(&conn, limit, offset),
-                "Failed to get sidebar data",
-            )
+/// Guess the programming language of a code snippet for its fence annotation
+#[tauri::command]
+pub fn detect_language(state: State<'_, AppState>, text: String) -> AppResult<Option<LanguageGuess>> {
+    state.track("detect_language", || Ok(language::detect(&text)))
+}
+
+/// Get the buffer to switch to when stepping up/down from `id` in the given
+/// ordering (sidebar order by default). Wraps around; None if there's no other buffer.
+#[tauri::command]
+pub async fn get_adjacent_buffer(
+    app: AppHandle,
+    id: String,
+    direction: Direction,
+    sort_mode: Option<SortMode>,
+) -> AppResult<Option<String>> {
+    track_blocking(app, "get_adjacent_buffer", move |_, state| {
+        let sort_mode = sort_mode.unwrap_or_default();
+        match state.get_reader() {
+            Ok(conn) => queries::get_adjacent_buffer_id(&conn, &id, direction, sort_mode),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_adjacent_buffer_id(&conn, &id, direction, sort_mode)
+            }
         }
-    }
+        .db_context("Failed to get adjacent buffer")
+    })
+    .await
 }
 
-/// Search buffers using FTS5
+/// Get up to `n` (default 10) recently opened buffers, most recent first.
+/// Follows the order notes were actually opened in, for Ctrl+Tab switching.
 #[tauri::command]
-pub fn search_buffers(state: State<'_, AppState>, query: String) -> Result<Vec<SearchResult>, String> {
-    // Validate and sanitize query
-    let sanitized = match queries::sanitize_search_query(&query) {
-        Some(q) => q,
-        None => {
-            warn!("Invalid search query rejected: {:?}", query);
-            return Ok(Vec::new()); // Return empty results for invalid queries
+pub async fn get_mru_buffers(app: AppHandle, n: Option<usize>) -> AppResult<Vec<BufferSummary>> {
+    track_blocking(app, "get_mru_buffers", move |_, state| {
+        let n = n.unwrap_or(10);
+        let ids = state.mru.ids();
+        match state.get_reader() {
+            Ok(conn) => queries::get_buffer_summaries(&conn, &ids, n),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_buffer_summaries(&conn, &ids, n)
+            }
         }
-    };
+        .db_context("Failed to get recent buffers")
+    })
+    .await
+}
+
+/// Activity log entries, newest first: one buffer's history (`id`), or all
+/// operations. `limit` defaults to 100.
+#[tauri::command]
+pub async fn get_activity(
+    app: AppHandle,
+    id: Option<String>,
+    limit: Option<usize>,
+) -> AppResult<Vec<ActivityEntry>> {
+    track_blocking(app, "get_activity", move |_, state| {
+        let limit = limit.unwrap_or(100).clamp(1, 1000);
+        match state.get_reader() {
+            Ok(conn) => activity::get_activity(&conn, id.as_deref(), limit),
+            Err(_) => {
+                let conn = state.writer.lock();
+                activity::get_activity(&conn, id.as_deref(), limit)
+            }
+        }
+        .db_context("Failed to get activity")
+    })
+    .await
+}
 
-    // Try reader pool first
-    match state.get_reader() {
-        Ok(conn) => map_db_error(
-            queries::search_buffers(&conn, &sanitized, 20),
-            "Failed to search buffers",
-        ),
-        Err(_) => {
-            let conn = state.writer.lock();
-            map_db_error(
-                queries::search_buffers(&conn, &sanitized, 20),
-                "Failed to search buffers",
-            )
+/// Tell windows about the buffers an undo or redo changed
+fn emit_undone(app: &AppHandle, result: Option<&UndoResult>) {
+    let Some(result) = result else {
+        return;
+    };
+    if result.operation == "reorder" {
+        events::reordered(app, &result.buffer_ids);
+    } else {
+        for id in &result.buffer_ids {
+            events::updated(app, id, Change::Undo);
         }
     }
 }
 
-/// Delete a buffer and return the next buffer ID to select (if any)
+/// Undo the latest pin, archive or reorder. A bulk operation (dedupe,
+/// auto-archive, a script run) is undone as a whole. Returns None when there
+/// is nothing to undo.
 #[tauri::command]
-pub fn delete_buffer(state: State<'_, AppState>, id: String) -> Result<Option<String>, String> {
-    let conn = state.writer.lock();
+pub async fn undo_last_operation(app: AppHandle) -> AppResult<Option<UndoResult>> {
+    track_blocking(app, "undo_last_operation", move |app, state| {
+        let mut conn = state.writer.lock();
+        let result = undo::undo(&mut conn).db_context("Failed to undo")?;
+        emit_undone(app, result.as_ref());
+        Ok(result)
+    })
+    .await
+}
+
+/// Redo the latest undone operation. Returns None when there is nothing to redo.
+#[tauri::command]
+pub async fn redo(app: AppHandle) -> AppResult<Option<UndoResult>> {
+    track_blocking(app, "redo", move |app, state| {
+        let mut conn = state.writer.lock();
+        let result = undo::redo(&mut conn).db_context("Failed to redo")?;
+        emit_undone(app, result.as_ref());
+        Ok(result)
+    })
+    .await
+}
+
+/// Permanently delete a buffer, bypassing the trash, and return the next buffer ID to select (if any)
+#[tauri::command]
+pub async fn delete_buffer(app: AppHandle, id: String) -> AppResult<Option<String>> {
+    track_blocking(app, "delete_buffer", move |app, state| {
+        let conn = state.writer.lock();
 
-    // Get next buffer before deleting
-    let next_id = map_db_error(
-        queries::get_next_buffer_id(&conn, &id),
-        "Failed to get next buffer",
-    )?;
+        // Get next buffer before deleting
+        let next_id = queries::get_next_buffer_id(&conn, &id).db_context("Failed to get next buffer")?;
 
-    // Delete the buffer
-    map_db_error(queries::delete_buffer(&conn, &id), "Failed to delete buffer")?;
+        trash::delete_permanently(state, &conn, std::slice::from_ref(&id), Origin::Ui)?;
+        webhooks::notify(app, WebhookEvent::Deleted, &id);
+        events::deleted(app, &id, false);
 
-    info!("Deleted buffer: {}", id);
+        info!("Deleted buffer: {}", id);
 
-    Ok(next_id)
+        Ok(next_id)
+    })
+    .await
+}
+
+/// Give a buffer a custom sidebar title, or clear it (empty/None) to fall back
+/// to the title extracted from content. Returns the effective title.
+#[tauri::command]
+pub async fn rename_buffer(app: AppHandle, id: String, title: Option<String>) -> AppResult<String> {
+    track_blocking(app, "rename_buffer", move |app, state| {
+        let title = title
+            .map(|t| t.trim().chars().take(queries::TITLE_PREVIEW_MAX_CHARS).collect::<String>())
+            .filter(|t| !t.is_empty());
+
+        let conn = state.writer.lock();
+        let buffer = queries::get_buffer_content(&conn, &id)
+            .db_context("Failed to get buffer")?
+            .ok_or_else(|| AppError::not_found("Buffer", id.clone()))?;
+        let effective_title = title
+            .clone()
+            .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+        let duplicate = check_duplicate_title(&conn, &id, &effective_title)?;
+
+        if !queries::rename_buffer(&conn, &id, title.as_deref(), &effective_title)
+            .db_context("Failed to rename buffer")?
+        {
+            return Err(AppError::not_found("Buffer", id));
+        }
+        activity::record(&conn, Some(&id), Operation::Rename, Origin::Ui, title.as_deref());
+        emit_duplicate_title(app, duplicate);
+        webhooks::notify(app, WebhookEvent::Updated, &id);
+        events::updated(app, &id, Change::Title);
+
+        Ok(effective_title)
+    })
+    .await
 }
 
 /// Toggle pin status and return new state
 #[tauri::command]
-pub fn toggle_pin(state: State<'_, AppState>, id: String) -> Result<bool, String> {
-    let conn = state.writer.lock();
-    map_db_error(queries::toggle_pin(&conn, &id), "Failed to toggle pin")
+pub async fn toggle_pin(app: AppHandle, id: String) -> AppResult<bool> {
+    track_blocking(app, "toggle_pin", move |app, state| {
+        let conn = state.writer.lock();
+        let pinned = queries::toggle_pin(&conn, &id).db_context("Failed to toggle pin")?;
+        let operation = if pinned { Operation::Pin } else { Operation::Unpin };
+        activity::record(&conn, Some(&id), operation, Origin::Ui, None);
+        events::updated(app, &id, Change::Pin);
+        Ok(pinned)
+    })
+    .await
 }
 
 /// Reorder buffers by setting sort_order
 #[tauri::command]
-pub fn reorder_buffers(state: State<'_, AppState>, ids: Vec<String>) -> Result<(), String> {
-    let mut conn = state.writer.lock();
-    map_db_error(
-        queries::reorder_buffers(&mut conn, &ids),
-        "Failed to reorder buffers",
-    )?;
-    Ok(())
+pub async fn reorder_buffers(app: AppHandle, ids: Vec<String>) -> AppResult<()> {
+    track_blocking(app, "reorder_buffers", move |app, state| {
+        let mut conn = state.writer.lock();
+        let before = queries::get_sort_orders(&conn, &ids).db_context("Failed to get buffer order")?;
+        queries::reorder_buffers(&mut conn, &ids).db_context("Failed to reorder buffers")?;
+
+        let after = ids.iter().enumerate().map(|(index, id)| (id.clone(), index as i64)).collect();
+        let data = serde_json::to_string(&ReorderData { before, after }).ok();
+        activity::log(
+            &conn,
+            &NewEntry {
+                buffer_id: None,
+                operation: Operation::Reorder,
+                origin: Origin::Ui,
+                detail: None,
+                batch_id: None,
+                data: data.as_deref(),
+            },
+        );
+        events::reordered(app, &ids);
+        Ok(())
+    })
+    .await
 }
 
-/// Delete all empty buffers
+/// Get archived buffers (e.g. to review what auto-archive moved away)
 #[tauri::command]
-pub fn cleanup_empty_buffers(state: State<'_, AppState>) -> Result<usize, String> {
+pub async fn get_archived_buffers(app: AppHandle, offset: Option<usize>) -> AppResult<Vec<BufferSummary>> {
+    track_blocking(app, "get_archived_buffers", move |_, state| {
+        let offset = offset.unwrap_or(0);
+        let limit = 100;
+
+        match state.get_reader() {
+            Ok(conn) => queries::get_archived_buffers(&conn, limit, offset)
+                .db_context("Failed to get archived buffers"),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_archived_buffers(&conn, limit, offset)
+                    .db_context("Failed to get archived buffers")
+            }
+        }
+    })
+    .await
+}
+
+/// Move a buffer into or out of the archive (shared by the archive commands)
+fn update_archived(app: &AppHandle, state: &AppState, id: String, archived: bool) -> AppResult<()> {
     let conn = state.writer.lock();
-    let count = map_db_error(
-        queries::delete_empty_buffers(&conn),
-        "Failed to cleanup empty buffers",
-    )?;
-    if count > 0 {
-        info!("Cleaned up {} empty buffers", count);
+    if !queries::set_archived(&conn, &id, archived, now()).db_context("Failed to update buffer")? {
+        return Err(AppError::not_found("Buffer", id));
+    }
+    let operation = if archived { Operation::Archive } else { Operation::Unarchive };
+    activity::record(&conn, Some(&id), operation, Origin::Ui, None);
+    info!("{} buffer: {}", if archived { "Archived" } else { "Restored" }, id);
+    if archived {
+        webhooks::notify(app, WebhookEvent::Archived, &id);
     }
-    Ok(count)
+    events::updated(app, &id, if archived { Change::Archive } else { Change::Unarchive });
+    Ok(())
+}
+
+/// Archive or restore a buffer
+#[tauri::command]
+pub async fn set_buffer_archived(app: AppHandle, id: String, archived: bool) -> AppResult<()> {
+    track_blocking(app, "set_buffer_archived", move |app, state| update_archived(app, state, id, archived)).await
+}
+
+/// Move a buffer out of the sidebar into the archive (kept, searchable via
+/// `get_archived_buffers`, and restorable with `unarchive_buffer`)
+#[tauri::command]
+pub async fn archive_buffer(app: AppHandle, id: String) -> AppResult<()> {
+    track_blocking(app, "archive_buffer", move |app, state| update_archived(app, state, id, true)).await
+}
+
+/// Restore an archived buffer to the sidebar
+#[tauri::command]
+pub async fn unarchive_buffer(app: AppHandle, id: String) -> AppResult<()> {
+    track_blocking(app, "unarchive_buffer", move |app, state| update_archived(app, state, id, false)).await
+}
+
+/// Move a buffer to the trash. It can be restored with `restore_buffer` until
+/// the trash is emptied or the retention period passes. Returns the buffer to
+/// select next, like `delete_buffer`.
+#[tauri::command]
+pub async fn trash_buffer(app: AppHandle, id: String) -> AppResult<Option<String>> {
+    track_blocking(app, "trash_buffer", move |app, state| {
+        let conn = state.writer.lock();
+
+        let next_id = queries::get_next_buffer_id(&conn, &id).db_context("Failed to get next buffer")?;
+        if !queries::trash_buffer(&conn, &id, now()).db_context("Failed to trash buffer")? {
+            return Err(AppError::not_found("Buffer", id));
+        }
+        activity::record(&conn, Some(&id), Operation::Trash, Origin::Ui, None);
+        webhooks::notify(app, WebhookEvent::Deleted, &id);
+        events::deleted(app, &id, true);
+
+        info!("Trashed buffer: {}", id);
+        Ok(next_id)
+    })
+    .await
+}
+
+/// Take a buffer out of the trash, back where it was (sidebar or archive)
+#[tauri::command]
+pub async fn restore_buffer(app: AppHandle, id: String) -> AppResult<()> {
+    track_blocking(app, "restore_buffer", move |app, state| {
+        let conn = state.writer.lock();
+        if !queries::restore_buffer(&conn, &id).db_context("Failed to restore buffer")? {
+            return Err(AppError::not_found("Trashed buffer", id));
+        }
+        activity::record(&conn, Some(&id), Operation::Restore, Origin::Ui, None);
+        events::updated(app, &id, Change::Restore);
+        info!("Restored buffer from trash: {}", id);
+        Ok(())
+    })
+    .await
+}
+
+/// Get trashed buffers, most recently deleted first
+#[tauri::command]
+pub async fn get_trashed_buffers(app: AppHandle, offset: Option<usize>) -> AppResult<Vec<BufferSummary>> {
+    track_blocking(app, "get_trashed_buffers", move |_, state| {
+        let offset = offset.unwrap_or(0);
+        let limit = 100;
+
+        match state.get_reader() {
+            Ok(conn) => queries::get_trashed_buffers(&conn, limit, offset)
+                .db_context("Failed to get trashed buffers"),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_trashed_buffers(&conn, limit, offset)
+                    .db_context("Failed to get trashed buffers")
+            }
+        }
+    })
+    .await
+}
+
+/// Permanently delete everything in the trash. Returns the number of buffers removed.
+#[tauri::command]
+pub async fn empty_trash(app: AppHandle) -> AppResult<usize> {
+    track_blocking(app, "empty_trash", move |app, state| {
+        let conn = state.writer.lock();
+        let ids = queries::get_trashed_ids(&conn, None).db_context("Failed to get trashed buffers")?;
+        let deleted = trash::delete_permanently(state, &conn, &ids, Origin::Ui)?;
+        for id in &deleted {
+            events::deleted(app, id, false);
+        }
+        info!("Emptied trash: {} buffers", deleted.len());
+        Ok(deleted.len())
+    })
+    .await
+}
+
+/// Merge duplicate buffers into `keep_id`, moving the rest to the trash.
+/// With `dry_run` the returned report shows what would happen without changing anything.
+#[tauri::command]
+pub async fn dedupe_buffers(
+    app: AppHandle,
+    keep_id: String,
+    remove_ids: Vec<String>,
+    dry_run: Option<bool>,
+) -> AppResult<DedupeReport> {
+    track_blocking(app, "dedupe_buffers", move |app, state| {
+        let dry_run = dry_run.unwrap_or(false);
+        let mut conn = state.writer.lock();
+
+        let report = queries::dedupe_buffers(&mut conn, &keep_id, &remove_ids, now(), dry_run)
+            .db_context("Failed to merge duplicates")?
+            .ok_or_else(|| AppError::not_found("Buffer", keep_id.clone()))?;
+
+        if !dry_run {
+            let detail = format!("dedupe:{}", keep_id);
+            activity::record_batch(&conn, &report.removed_ids, Operation::Trash, Origin::Ui, Some(&detail));
+            events::updated(app, &keep_id, Change::Content);
+            for id in &report.removed_ids {
+                webhooks::notify(app, WebhookEvent::Deleted, id);
+                events::deleted(app, id, true);
+            }
+            info!("Merged {} duplicates into buffer: {}", report.removed_ids.len(), keep_id);
+        }
+        Ok(report)
+    })
+    .await
+}
+
+/// Planned (or applied) replacement in one buffer
+#[derive(Debug, Serialize)]
+pub struct BufferTransform {
+    pub id: String,
+    pub title: String,
+    pub replacements: usize,
+    pub preview: Vec<find::LineChange>,
+}
+
+/// Outcome of `batch_transform`
+#[derive(Debug, Serialize)]
+pub struct BatchTransformReport {
+    /// Buffers with at least one change
+    pub buffers: Vec<BufferTransform>,
+    pub total_replacements: usize,
+    pub missing_ids: Vec<String>,
+    pub dry_run: bool,
+    /// Database snapshot taken before applying
+    pub backup_path: Option<String>,
+}
+
+/// Replace a regex across several buffers. With `dry_run` (the default) only
+/// a preview is returned; otherwise the database is snapshotted and all
+/// changes are applied in one transaction.
+#[tauri::command]
+pub async fn batch_transform(
+    app: AppHandle,
+    ids: Vec<String>,
+    pattern: String,
+    replacement: String,
+    dry_run: Option<bool>,
+) -> AppResult<BatchTransformReport> {
+    track_blocking(app, "batch_transform", move |app, state| {
+        let dry_run = dry_run.unwrap_or(true);
+        let regex = find::compile_regex(&pattern)?;
+
+        let mut conn = state.writer.lock();
+        let mut planned = Vec::new();
+        let mut missing_ids = Vec::new();
+        let mut seen = HashSet::new();
+        for id in ids.iter().filter(|id| seen.insert(id.as_str())) {
+            let Some(buffer) = queries::get_buffer_content(&conn, id).db_context("Failed to get buffer")? else {
+                missing_ids.push(id.clone());
+                continue;
+            };
+            if let Some(replaced) = find::replace_all(&buffer.content, &regex, &replacement) {
+                validate_buffer_size(&replaced.content)?;
+                planned.push((buffer, replaced));
+            }
+        }
+
+        let mut backup_path = None;
+        if !dry_run && !planned.is_empty() {
+            let path = backup::create_snapshot(&conn, &state.app_data_dir, "prebatch").map_err(AppError::Internal)?;
+            backup_path = Some(path.to_string_lossy().into_owned());
+
+            let timestamp = now();
+            let tx = conn.transaction().db_context("Failed to start batch transform")?;
+            let mut changed = Vec::with_capacity(planned.len());
+            for (buffer, replaced) in planned {
+                // Every transformed buffer gets a revision, however recent its last one
+                revisions::snapshot(&tx, &buffer.id, &replaced.content, timestamp, true)
+                    .db_context("Failed to save revision")?;
+                // Locked buffers are not updated; they are left out of the report
+                if queries::update_buffer_content(&tx, &buffer.id, &replaced.content, timestamp)
+                    .db_context("Failed to save buffer")?
+                {
+                    changed.push((buffer, replaced));
+                }
+            }
+            tx.commit().db_context("Failed to commit batch transform")?;
+            planned = changed;
+            for (buffer, _) in &planned {
+                activity::record(&conn, Some(&buffer.id), Operation::Save, Origin::Ui, Some("batch_transform"));
+            }
+            info!("Batch transform changed {} buffers", planned.len());
+
+            for (buffer, _) in &planned {
+                webhooks::notify(app, WebhookEvent::Updated, &buffer.id);
+                events::updated(app, &buffer.id, Change::Content);
+            }
+        }
+
+        let buffers: Vec<BufferTransform> = planned
+            .into_iter()
+            .map(|(buffer, replaced)| BufferTransform {
+                title: buffer
+                    .title_override
+                    .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0),
+                id: buffer.id,
+                replacements: replaced.count,
+                preview: replaced.preview,
+            })
+            .collect();
+
+        Ok(BatchTransformReport {
+            total_replacements: buffers.iter().map(|b| b.replacements).sum(),
+            buffers,
+            missing_ids,
+            dry_run,
+            backup_path,
+        })
+    })
+    .await
+}
+
+/// Delete all empty, unpinned buffers
+#[tauri::command]
+pub async fn cleanup_empty_buffers(app: AppHandle) -> AppResult<usize> {
+    track_blocking(app, "cleanup_empty_buffers", move |_, state| {
+        maintenance::cleanup_empty_buffers(state, Origin::Ui)
+    })
+    .await
 }
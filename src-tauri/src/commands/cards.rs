@@ -0,0 +1,66 @@
+use crate::cards;
+use crate::db::cards::{self as db_cards, Card};
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::track_blocking;
+use chrono::Utc;
+use tauri::AppHandle;
+
+/// Default number of due cards returned in one review session
+const DEFAULT_DUE_LIMIT: usize = 50;
+
+/// Seconds per scheduling day
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// Get flashcards that are due for review, most overdue first
+#[tauri::command]
+pub async fn get_due_cards(app: AppHandle, limit: Option<usize>) -> AppResult<Vec<Card>> {
+    track_blocking(app, "get_due_cards", move |_, state| {
+        let limit = limit.unwrap_or(DEFAULT_DUE_LIMIT);
+        let now = Utc::now().timestamp();
+        match state.get_reader() {
+            Ok(conn) => db_cards::get_due_cards(&conn, now, limit).db_context("Failed to get due cards"),
+            Err(_) => {
+                let conn = state.writer.lock();
+                db_cards::get_due_cards(&conn, now, limit).db_context("Failed to get due cards")
+            }
+        }
+    })
+    .await
+}
+
+/// Record a review of a card graded 0 (forgot) to 5 (perfect) and
+/// reschedule it. Returns the card with its new schedule.
+#[tauri::command]
+pub async fn grade_card(app: AppHandle, id: i64, grade: u8) -> AppResult<Card> {
+    track_blocking(app, "grade_card", move |_, state| {
+        if grade > cards::MAX_GRADE {
+            return Err(AppError::Validation(format!(
+                "Invalid grade {}. Expected 0 to {}.",
+                grade,
+                cards::MAX_GRADE
+            )));
+        }
+
+        let conn = state.writer.lock();
+        let card = db_cards::get_card(&conn, id)
+            .db_context("Failed to get card")?
+            .ok_or_else(|| AppError::not_found("Card", id.to_string()))?;
+
+        let schedule = cards::review(
+            cards::Schedule {
+                ease: card.ease,
+                interval_days: card.interval_days,
+                repetitions: card.repetitions,
+            },
+            grade,
+        );
+        let now = Utc::now().timestamp();
+        db_cards::update_schedule(&conn, id, schedule, now + schedule.interval_days * DAY_SECS, now)
+            .db_context("Failed to update card schedule")?;
+
+        db_cards::get_card(&conn, id)
+            .db_context("Failed to get card")?
+            .ok_or_else(|| AppError::not_found("Card", id.to_string()))
+    })
+    .await
+}
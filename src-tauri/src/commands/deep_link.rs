@@ -0,0 +1,10 @@
+use crate::error::AppResult;
+use crate::state::AppState;
+use tauri::State;
+
+/// Note opened by the flashnotes:// link the app was launched with, for the
+/// frontend to select once it has loaded (later links arrive as events)
+#[tauri::command]
+pub fn take_launch_link(state: State<'_, AppState>) -> AppResult<Option<String>> {
+    state.track("take_launch_link", || Ok(state.deep_link.take()))
+}
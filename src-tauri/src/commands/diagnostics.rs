@@ -0,0 +1,134 @@
+use crate::ai;
+use crate::db::fts;
+use crate::db::queries::{self, CommandUsage};
+use crate::embeddings;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::jobs;
+use crate::logging;
+use crate::metrics::{self, CommandTiming, SlowQuery};
+use crate::maintenance;
+use crate::progress;
+use crate::state::{run_blocking, track_blocking, AppState};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use tracing::info;
+
+/// Maximum number of log lines returned in one call
+const MAX_LOG_LINES: usize = 5000;
+
+/// Database and background-maintenance health summary
+#[derive(Debug, Serialize)]
+pub struct DiagnosticsReport {
+    pub buffer_count: i64,
+    pub archived_count: i64,
+    pub last_fts_merge_at: Option<i64>,
+    pub last_fts_optimize_at: Option<i64>,
+    /// Command execution times this session, most total time first
+    pub commands: Vec<CommandTiming>,
+    /// Recent statements over the slow-query threshold, newest first
+    pub slow_queries: Vec<SlowQuery>,
+}
+
+/// Local usage metrics report
+#[derive(Debug, Serialize)]
+pub struct UsageReport {
+    pub enabled: bool,
+    pub commands: Vec<CommandUsage>,
+}
+
+/// Get the most recent log lines (oldest first) for attaching to bug reports
+#[tauri::command]
+pub fn get_recent_logs(state: State<'_, AppState>, n: Option<usize>) -> AppResult<Vec<String>> {
+    state.track("get_recent_logs", || {
+        let n = n.unwrap_or(200).min(MAX_LOG_LINES);
+        Ok(logging::read_recent_lines(&state.app_data_dir, n)?)
+    })
+}
+
+/// Get database and maintenance diagnostics
+#[tauri::command]
+pub async fn get_diagnostics(app: AppHandle) -> AppResult<DiagnosticsReport> {
+    track_blocking(app, "get_diagnostics", move |_, state| {
+        state.read(|conn| {
+            let (buffer_count, archived_count) =
+                queries::get_buffer_counts(conn).db_context("Failed to count buffers")?;
+
+            Ok(DiagnosticsReport {
+                buffer_count,
+                archived_count,
+                last_fts_merge_at: maintenance::last_run_at(conn, maintenance::LAST_FTS_MERGE_KEY),
+                last_fts_optimize_at: maintenance::last_run_at(conn, maintenance::LAST_FTS_OPTIMIZE_KEY),
+                commands: state.timings.snapshot(),
+                slow_queries: metrics::slow_queries(),
+            })
+        })
+    })
+    .await
+}
+
+/// Get locally recorded usage metrics (empty unless the user opted in)
+#[tauri::command]
+pub async fn get_usage_metrics(app: AppHandle) -> AppResult<UsageReport> {
+    run_blocking("get_usage_metrics", move || {
+        let state = app.state::<AppState>();
+        state.ready.wait()?;
+        // Persist anything still pending so the report is complete
+        state.flush_metrics();
+
+        let commands = match state.get_reader() {
+            Ok(conn) => queries::get_usage_metrics(&conn).db_context("Failed to get usage metrics")?,
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_usage_metrics(&conn).db_context("Failed to get usage metrics")?
+            }
+        };
+
+        Ok(UsageReport {
+            enabled: state.metrics.is_enabled(),
+            commands,
+        })
+    })
+    .await
+}
+
+/// Rebuild the full-text search index from scratch, reporting task progress.
+/// Recovers notes missing from search after trigger drift or index corruption.
+#[tauri::command]
+pub async fn rebuild_search_index(app: AppHandle) -> AppResult<usize> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        state.track("rebuild_search_index", || {
+            progress::run(&app, "search_index", |progress| {
+                let mut conn = state.writer.lock();
+                let indexed = fts::rebuild_index(&mut conn, |indexed, total| {
+                    progress.update("indexing", indexed, total)
+                })
+                .db_context("Failed to rebuild search index")?;
+
+                info!("Rebuilt search index ({} buffers)", indexed);
+                Ok(indexed)
+            })
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Search index rebuild failed: {}", e)))?
+}
+
+/// Rebuild the semantic search index from scratch with the configured
+/// provider, reporting task progress. Runs as a cancellable "embeddings"
+/// job. Returns the notes embedded.
+#[tauri::command]
+pub async fn reindex_embeddings(app: AppHandle) -> AppResult<usize> {
+    jobs::run(app, "embeddings", |app, cancel| {
+        let state = app.state::<AppState>();
+        state.track("reindex_embeddings", || {
+            let settings = {
+                let conn = state.writer.lock();
+                queries::get_settings(&conn).db_context("Failed to get settings")?
+            };
+            let embedder = ai::embedder(&settings)?;
+            embeddings::refresh(app, embedder.as_ref(), true, cancel)
+        })
+    })
+    .await
+}
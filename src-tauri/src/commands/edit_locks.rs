@@ -0,0 +1,31 @@
+use crate::edit_locks::{self, EditLockStatus};
+use crate::error::AppResult;
+use crate::state::AppState;
+use tauri::{AppHandle, State, WebviewWindow};
+
+/// Take the advisory edit lock on a buffer for the calling window. Call again
+/// every few seconds while editing as a heartbeat. When another window holds
+/// the lock, `acquired` is false and the buffer should be shown read-only.
+#[tauri::command]
+pub fn acquire_edit_lock(
+    app: AppHandle,
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+    id: String,
+) -> AppResult<EditLockStatus> {
+    state.track("acquire_edit_lock", || Ok(edit_locks::acquire(&app, &id, window.label())))
+}
+
+/// Release the calling window's edit lock on a buffer (e.g. when switching notes)
+#[tauri::command]
+pub fn release_edit_lock(
+    app: AppHandle,
+    window: WebviewWindow,
+    state: State<'_, AppState>,
+    id: String,
+) -> AppResult<()> {
+    state.track("release_edit_lock", || {
+        edit_locks::release(&app, &id, window.label());
+        Ok(())
+    })
+}
@@ -0,0 +1,39 @@
+use crate::encryption::{self, RotationReport};
+use crate::error::AppResult;
+use crate::state::{run_blocking, track_blocking, AppState};
+use tauri::{AppHandle, State};
+
+/// Whether the database is encrypted and still waiting for its passphrase.
+/// Not tracked: tracked commands fail until the database is unlocked.
+#[tauri::command]
+pub fn is_database_locked(state: State<'_, AppState>) -> AppResult<bool> {
+    Ok(state.ready.is_locked())
+}
+
+/// Unlock the encrypted database; commands work once startup has finished.
+/// Not tracked for the same reason as `is_database_locked`.
+#[tauri::command]
+pub async fn unlock_database(app: AppHandle, passphrase: String) -> AppResult<()> {
+    run_blocking("unlock_database", move || encryption::unlock(&app, &passphrase)).await
+}
+
+/// Encrypt the database with a passphrase (SQLCipher builds only)
+#[tauri::command]
+pub async fn enable_encryption(app: AppHandle, passphrase: String) -> AppResult<()> {
+    track_blocking(app, "enable_encryption", move |_, state| encryption::enable(state, &passphrase))
+    .await
+}
+
+/// Re-encrypt the database with a new passphrase
+#[tauri::command]
+pub async fn change_passphrase(app: AppHandle, current: String, new: String) -> AppResult<()> {
+    track_blocking(app, "change_passphrase", move |_, state| encryption::change_passphrase(state, &current, &new))
+    .await
+}
+
+/// Replace a passphrase on the database and every locked note sealed with it,
+/// after taking a snapshot. Repeat the call to finish an interrupted rotation.
+#[tauri::command]
+pub async fn rotate_encryption_key(app: AppHandle, old: String, new: String) -> AppResult<RotationReport> {
+    track_blocking(app, "rotate_encryption_key", move |_, state| encryption::rotate_key(state, &old, &new)).await
+}
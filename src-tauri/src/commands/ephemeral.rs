@@ -0,0 +1,82 @@
+use super::buffer::{insert_buffer, validate_buffer_size};
+use crate::db::activity::Origin;
+use crate::db::queries::BufferSummary;
+use crate::ephemeral::EphemeralBuffer;
+use crate::error::{AppError, AppResult};
+use crate::state::{track_blocking, AppState};
+use chrono::Utc;
+use tauri::{AppHandle, State};
+use tracing::info;
+use uuid::Uuid;
+
+/// Create an incognito scratch buffer that is only kept in memory
+#[tauri::command]
+pub fn create_ephemeral(state: State<'_, AppState>, content: Option<String>) -> AppResult<EphemeralBuffer> {
+    state.track("create_ephemeral", || {
+        let content = content.unwrap_or_default();
+        validate_buffer_size(&content)?;
+
+        let timestamp = Utc::now().timestamp();
+        let buffer = EphemeralBuffer {
+            id: Uuid::new_v4().to_string(),
+            content,
+            created_at: timestamp,
+            updated_at: timestamp,
+        };
+        state.ephemeral.insert(buffer.clone());
+        Ok(buffer)
+    })
+}
+
+/// Get an ephemeral buffer
+#[tauri::command]
+pub fn get_ephemeral(state: State<'_, AppState>, id: String) -> AppResult<EphemeralBuffer> {
+    state.track("get_ephemeral", || {
+        state
+            .ephemeral
+            .get(&id)
+            .ok_or_else(|| AppError::not_found("Ephemeral buffer", id))
+    })
+}
+
+/// Replace an ephemeral buffer's content
+#[tauri::command]
+pub fn update_ephemeral(state: State<'_, AppState>, id: String, content: String) -> AppResult<()> {
+    state.track("update_ephemeral", || {
+        validate_buffer_size(&content)?;
+        if !state.ephemeral.update(&id, content, Utc::now().timestamp()) {
+            return Err(AppError::not_found("Ephemeral buffer", id));
+        }
+        Ok(())
+    })
+}
+
+/// Throw away an ephemeral buffer
+#[tauri::command]
+pub fn discard_ephemeral(state: State<'_, AppState>, id: String) -> AppResult<()> {
+    state.track("discard_ephemeral", || {
+        state
+            .ephemeral
+            .remove(&id)
+            .map(|_| ())
+            .ok_or_else(|| AppError::not_found("Ephemeral buffer", id))
+    })
+}
+
+/// Turn an ephemeral buffer into a regular (saved) note. The ephemeral copy
+/// is dropped once the note exists.
+#[tauri::command]
+pub async fn promote_to_buffer(app: AppHandle, id: String) -> AppResult<BufferSummary> {
+    track_blocking(app, "promote_to_buffer", move |app, state| {
+        let buffer = state
+            .ephemeral
+            .get(&id)
+            .ok_or_else(|| AppError::not_found("Ephemeral buffer", id.clone()))?;
+
+        let summary = insert_buffer(app, state, &buffer.content, "manual", Origin::Ui)?;
+        state.ephemeral.remove(&id);
+        info!("Promoted ephemeral buffer to {}", summary.id);
+        Ok(summary)
+    })
+    .await
+}
@@ -0,0 +1,554 @@
+use super::buffer::{validate_buffer_size, validate_source};
+use crate::attachments;
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::attachments::{self as db_attachments, Attachment};
+use crate::db::queries::{self, Buffer, BufferSummary};
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::export::blog::{BlogExportReport, BlogFlavor};
+use crate::export::obsidian::VaultExportReport;
+use crate::export::{self, archive, blog, frontmatter, metadata, obsidian, site};
+use crate::jobs;
+use crate::progress;
+use crate::state::{track_blocking, AppState};
+use crate::tasks;
+use crate::vault;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tracing::info;
+use uuid::Uuid;
+
+/// File extensions `import_files` accepts (plain text and Markdown)
+pub const IMPORTABLE_EXTENSIONS: &[&str] = &["txt", "md", "markdown"];
+
+/// Pick a file stem not used yet in this export ("note", "note-2", ...)
+fn unique_stem(used: &mut HashSet<String>, name: &str) -> String {
+    let base = export::file_stem(name);
+    let mut stem = base.clone();
+    let mut n = 2;
+    while !used.insert(stem.to_lowercase()) {
+        stem = format!("{}-{}", base, n);
+        n += 1;
+    }
+    stem
+}
+
+/// Markdown files (name, contents) for `buffers`, named by slug with
+/// duplicates numbered. With `front_matter`, each file records the note's id,
+/// timestamps and flags so `import_files` can restore it losslessly.
+fn markdown_files(buffers: &[Buffer], front_matter: bool) -> Vec<(String, String)> {
+    let mut used = HashSet::new();
+    buffers
+        .iter()
+        .map(|buffer| {
+            let title = buffer
+                .title_override
+                .clone()
+                .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+            let mut data = String::new();
+            if front_matter {
+                data.push_str(&frontmatter::render(buffer, &title));
+            }
+            data.push_str(&buffer.content);
+            (format!("{}.md", unique_stem(&mut used, &buffer.slug)), data)
+        })
+        .collect()
+}
+
+/// Notes chosen by `ids`, or by `tag` (non-archived notes containing the
+/// `#tag` hashtag). Exactly one of the two must be given.
+fn select_buffers(buffers: Vec<Buffer>, ids: Option<&[String]>, tag: Option<&str>) -> AppResult<Vec<Buffer>> {
+    let selected: Vec<Buffer> = match (ids, tag) {
+        (Some(ids), None) => buffers.into_iter().filter(|b| ids.contains(&b.id)).collect(),
+        (None, Some(tag)) => buffers
+            .into_iter()
+            .filter(|b| !b.is_archived && queries::extract_hashtags(&b.content).iter().any(|t| t == tag))
+            .collect(),
+        _ => return Err(AppError::Validation("Select notes by either ids or tag".to_string())),
+    };
+    if selected.is_empty() {
+        return Err(AppError::Validation("No notes to export".to_string()));
+    }
+    Ok(selected)
+}
+
+/// Normalize a `#tag` argument ("#Work " -> "work")
+fn normalize_tag(tag: Option<&str>) -> Option<String> {
+    tag.map(|t| t.trim().trim_start_matches('#').to_lowercase())
+        .filter(|t| !t.is_empty())
+}
+
+/// Export every buffer (archived included) to `path` as Markdown files named
+/// by slug. With front matter (the default), each file records the note's id,
+/// timestamps and flags so `import_files` can restore it losslessly.
+/// Returns the number of files written.
+#[tauri::command]
+pub async fn export_all_buffers(
+    app: AppHandle,
+    path: String,
+    front_matter: Option<bool>,
+) -> AppResult<usize> {
+    track_blocking(app, "export_all_buffers", move |app, state| progress::run(app, "export", |progress| {
+        let front_matter = front_matter.unwrap_or(true);
+        let buffers = match state.get_reader() {
+            Ok(conn) => queries::get_all_buffers(&conn),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_all_buffers(&conn)
+            }
+        }
+        .db_context("Failed to get buffers")?;
+
+        let dir = PathBuf::from(&path);
+        let files = markdown_files(&buffers, front_matter);
+        write_files(&dir, &files, progress)?;
+
+        info!("Exported {} buffers to {:?}", buffers.len(), dir);
+        Ok(buffers.len())
+    }))
+    .await
+}
+
+/// Write exported files into `dir`, reporting progress per file
+fn write_files(dir: &Path, files: &[(String, String)], progress: &mut progress::Progress) -> AppResult<()> {
+    fs::create_dir_all(dir).map_err(|e| AppError::io("Failed to create export directory", e))?;
+    for (index, (name, data)) in files.iter().enumerate() {
+        progress.update("writing", index, files.len());
+        fs::write(dir.join(name), data).map_err(|e| AppError::io("Failed to write export file", e))?;
+    }
+    Ok(())
+}
+
+/// Container for `export_buffers`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportContainer {
+    Folder,
+    Zip,
+}
+
+impl ExportContainer {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "folder" | "dir" => Some(Self::Folder),
+            "zip" => Some(Self::Zip),
+            _ => None,
+        }
+    }
+}
+
+/// Export only the chosen notes, picked by `ids` (e.g. a sidebar
+/// multi-select) or by `tag`, as Markdown files named and formatted like
+/// `export_all_buffers`. `format` is "folder" (files written into the
+/// directory `path`) or "zip" (a single archive at `path`).
+/// Returns the number of notes exported.
+#[tauri::command]
+pub async fn export_buffers(
+    app: AppHandle,
+    ids: Option<Vec<String>>,
+    tag: Option<String>,
+    format: String,
+    path: String,
+    front_matter: Option<bool>,
+) -> AppResult<usize> {
+    track_blocking(app, "export_buffers", move |app, state| progress::run(app, "export", |progress| {
+        let container = ExportContainer::parse(&format)
+            .ok_or_else(|| AppError::Validation(format!("Unsupported export format: {}", format)))?;
+        let tag = normalize_tag(tag.as_deref());
+
+        let buffers = state.read(queries::get_all_buffers).db_context("Failed to get buffers")?;
+        let selected = select_buffers(buffers, ids.as_deref(), tag.as_deref())?;
+        let files = markdown_files(&selected, front_matter.unwrap_or(true));
+
+        let target = PathBuf::from(&path);
+        match container {
+            ExportContainer::Folder => write_files(&target, &files, progress)?,
+            ExportContainer::Zip => {
+                progress.step("writing", format!("{} notes", files.len()));
+                archive::write_zip(&target, &files)?;
+            }
+        }
+
+        info!("Exported {} selected buffers to {:?}", selected.len(), target);
+        Ok(selected.len())
+    }))
+    .await
+}
+
+/// Write one CSV row of metadata per buffer (archived included) to the file
+/// `path`: id, title, timestamps, word count, #hashtags and pinned/archived
+/// flags. Note bodies are not exported. Returns the number of rows written.
+#[tauri::command]
+pub async fn export_metadata_csv(app: AppHandle, path: String) -> AppResult<usize> {
+    track_blocking(app, "export_metadata_csv", move |_, state| {
+        let buffers = match state.get_reader() {
+            Ok(conn) => queries::get_all_buffers(&conn),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_all_buffers(&conn)
+            }
+        }
+        .db_context("Failed to get buffers")?;
+
+        let file = PathBuf::from(&path);
+        if let Some(parent) = file.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| AppError::io("Failed to create export directory", e))?;
+        }
+        fs::write(&file, metadata::render(&buffers)).map_err(|e| AppError::io("Failed to write CSV file", e))?;
+
+        info!("Exported metadata for {} buffers to {:?}", buffers.len(), file);
+        Ok(buffers.len())
+    })
+    .await
+}
+
+/// Export selected notes as a static HTML site in `path`: an index page, a
+/// page per note with wiki links resolved between them, and theme CSS.
+/// Notes are chosen by `ids`, or by `tag` (non-archived notes containing the
+/// `#tag` hashtag). Returns the number of note pages written.
+#[tauri::command]
+pub async fn export_site(
+    app: AppHandle,
+    path: String,
+    ids: Option<Vec<String>>,
+    tag: Option<String>,
+    title: Option<String>,
+) -> AppResult<usize> {
+    track_blocking(app, "export_site", move |_, state| {
+        let tag = normalize_tag(tag.as_deref());
+        let buffers = match state.get_reader() {
+            Ok(conn) => queries::get_all_buffers(&conn),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_all_buffers(&conn)
+            }
+        }
+        .db_context("Failed to get buffers")?;
+
+        let selected = select_buffers(buffers, ids.as_deref(), tag.as_deref())?;
+
+        let title = title
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .or(tag.as_deref())
+            .unwrap_or("Notes");
+        let dir = PathBuf::from(&path);
+        let count = site::write_site(&dir, title, &selected)?;
+
+        info!("Exported {} buffers as a site to {:?}", count, dir);
+        Ok(count)
+    })
+    .await
+}
+
+/// Export notes as Hugo or Jekyll blog posts under `path` (`flavor` is
+/// "hugo" or "jekyll"). Exports `ids`, or every non-archived note. Front
+/// matter carries the title, dates and #hashtags as tags; a mapping file in
+/// `path` makes re-exports update the existing posts.
+#[tauri::command]
+pub async fn export_blog(
+    app: AppHandle,
+    path: String,
+    flavor: String,
+    ids: Option<Vec<String>>,
+) -> AppResult<BlogExportReport> {
+    track_blocking(app, "export_blog", move |_, state| {
+        let flavor = BlogFlavor::parse(&flavor)
+            .ok_or_else(|| AppError::Validation(format!("Unsupported blog flavor: {}", flavor)))?;
+
+        let buffers = match state.get_reader() {
+            Ok(conn) => queries::get_all_buffers(&conn),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_all_buffers(&conn)
+            }
+        }
+        .db_context("Failed to get buffers")?;
+
+        let selected: Vec<Buffer> = match &ids {
+            Some(ids) => buffers.into_iter().filter(|b| ids.contains(&b.id)).collect(),
+            None => buffers.into_iter().filter(|b| !b.is_archived).collect(),
+        };
+
+        let root = PathBuf::from(&path);
+        let report = blog::write_posts(&root, flavor, &selected)?;
+
+        info!(
+            "Exported blog posts to {:?} ({} new, {} updated)",
+            root, report.created, report.updated
+        );
+        Ok(report)
+    })
+    .await
+}
+
+/// Export every note (archived ones into `Archive/`) to `path` as an
+/// Obsidian-compatible vault, copying attachments into `assets/`
+#[tauri::command]
+pub async fn export_obsidian_vault(app: AppHandle, path: String) -> AppResult<VaultExportReport> {
+    track_blocking(app, "export_obsidian_vault", move |app, state| progress::run(app, "export", |progress| {
+        let load = |conn: &rusqlite::Connection| {
+            Ok::<_, rusqlite::Error>((queries::get_all_buffers(conn)?, db_attachments::list_all_attachments(conn)?))
+        };
+        let (buffers, attachments) = match state.get_reader() {
+            Ok(conn) => load(&conn),
+            Err(_) => {
+                let conn = state.writer.lock();
+                load(&conn)
+            }
+        }
+        .db_context("Failed to get buffers")?;
+
+        let dir = PathBuf::from(&path);
+        progress.step("writing", format!("{} notes, {} attachments", buffers.len(), attachments.len()));
+        let report = obsidian::write_vault(&dir, &buffers, &attachments)?;
+
+        info!(
+            "Exported {} buffers and {} attachments as a vault to {:?}",
+            report.notes, report.attachments, dir
+        );
+        Ok(report)
+    }))
+    .await
+}
+
+/// Turn a file's text into the buffer to store. Files exported with
+/// flashnotes front matter keep their id, timestamps, pin/archive state and
+/// source; other files get an "import:<file name>" source.
+fn buffer_from_file(text: &str, file_name: &str, now: i64) -> Buffer {
+    let import_source = format!("import:{}", file_name);
+    let Some((meta, body)) = frontmatter::parse(text) else {
+        return Buffer {
+            id: Uuid::new_v4().to_string(),
+            content: text.to_string(),
+            created_at: now,
+            updated_at: now,
+            accessed_at: now,
+            is_archived: false,
+            is_pinned: false,
+            archived_at: None,
+            title_override: None,
+            word_count: 0,
+            slug: String::new(),
+            source: import_source,
+            is_locked: false,
+        };
+    };
+
+    let updated_at = meta.updated_at.unwrap_or(now);
+    let extracted_title = queries::extract_title_preview(body).0;
+    Buffer {
+        id: meta.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        content: body.to_string(),
+        created_at: meta.created_at.unwrap_or(updated_at),
+        updated_at,
+        accessed_at: updated_at,
+        is_archived: meta.archived,
+        is_pinned: meta.pinned,
+        archived_at: meta.archived.then_some(updated_at),
+        // Only a title that differs from the content's own was a custom one
+        title_override: meta.title.filter(|t| !t.is_empty() && *t != extracted_title),
+        word_count: 0,
+        slug: String::new(),
+        source: meta
+            .source
+            .filter(|s| validate_source(s).is_ok())
+            .unwrap_or(import_source),
+        is_locked: false,
+    }
+}
+
+/// Sidebar summary of an imported buffer
+fn summarize(buffer: Buffer) -> BufferSummary {
+    let (title, preview) = queries::extract_title_preview(&buffer.content);
+    let (tasks_open, tasks_total) = tasks::count(&buffer.content);
+    BufferSummary {
+        has_title_override: buffer.title_override.is_some(),
+        title: buffer.title_override.unwrap_or(title),
+        preview,
+        updated_at: buffer.updated_at,
+        is_pinned: buffer.is_pinned,
+        word_count: queries::count_words(&buffer.content),
+        tasks_open,
+        tasks_total,
+        is_locked: false,
+        id: buffer.id,
+    }
+}
+
+/// Import Markdown/text files as buffers in a single transaction.
+/// Files carrying flashnotes front matter restore the original note: an
+/// existing buffer with the same id is updated (unless it was edited more
+/// recently than the file) instead of creating a duplicate.
+/// Runs as a cancellable "import" job; cancelling leaves the database untouched.
+/// Returns summaries of the imported buffers.
+#[tauri::command]
+pub async fn import_files(app: AppHandle, paths: Vec<String>) -> AppResult<Vec<BufferSummary>> {
+    jobs::run(app, "import", move |app, cancel| {
+        let state = app.state::<AppState>();
+        state.track("import_files", || progress::run(app, "import", |progress| {
+            let now = Utc::now().timestamp();
+
+            // Read everything up front so a bad file doesn't leave a partial import
+            let mut buffers = Vec::with_capacity(paths.len());
+            for (index, path) in paths.iter().enumerate() {
+                cancel.check()?;
+                progress.update("reading", index, paths.len());
+                let path = Path::new(path);
+                let extension = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+                if !extension.is_some_and(|e| IMPORTABLE_EXTENSIONS.contains(&e.as_str())) {
+                    return Err(AppError::Validation(format!(
+                        "Only .md and .txt files can be imported: {}",
+                        path.display()
+                    )));
+                }
+                let text = fs::read_to_string(path)
+                    .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?;
+                validate_buffer_size(&text)?;
+                let file_name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                buffers.push(buffer_from_file(&text, &file_name, now));
+            }
+
+            let mut conn = state.writer.lock();
+            let tx = conn.transaction().db_context("Failed to start import")?;
+            let mut summaries = Vec::with_capacity(buffers.len());
+            let mut created = Vec::new();
+            let total = buffers.len();
+            for (index, mut buffer) in buffers.into_iter().enumerate() {
+                cancel.check()?;
+                progress.update("saving", index, total);
+                let existing = queries::get_buffer_content(&tx, &buffer.id).db_context("Failed to get buffer")?;
+                if let Some(existing) = existing {
+                    if existing.updated_at >= buffer.updated_at {
+                        // Already up to date (or edited since the export): keep it
+                        buffer = existing;
+                    } else {
+                        buffer.accessed_at = existing.accessed_at;
+                        if buffer.is_archived {
+                            buffer.archived_at = existing.archived_at.or(buffer.archived_at);
+                        }
+                        queries::upsert_imported_buffer(&tx, &buffer).db_context("Failed to import buffer")?;
+                    }
+                    // Importing a note again brings it back from the trash
+                    queries::restore_buffer(&tx, &buffer.id).db_context("Failed to import buffer")?;
+                } else {
+                    queries::upsert_imported_buffer(&tx, &buffer).db_context("Failed to import buffer")?;
+                    created.push(buffer.id.clone());
+                }
+
+                summaries.push(summarize(buffer));
+            }
+            activity::record_batch(&tx, &created, Operation::Create, Origin::Ui, Some("import"));
+            tx.commit().db_context("Failed to commit import")?;
+
+            info!("Imported {} files", summaries.len());
+            Ok(summaries)
+        }))
+    })
+    .await
+}
+
+/// Result of importing an Obsidian vault
+#[derive(Debug, Serialize)]
+pub struct VaultImportReport {
+    pub buffers: Vec<BufferSummary>,
+    pub attachments: usize,
+    /// Embedded files not found in the vault ("note.md: image.png")
+    pub missing_embeds: Vec<String>,
+}
+
+/// Store imported vault notes and their attachments in one transaction
+fn insert_vault(
+    conn: &mut rusqlite::Connection,
+    notes: &[vault::VaultNote],
+    stored: &[(Attachment, String)],
+) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    for note in notes {
+        queries::upsert_imported_buffer(&tx, &note.buffer)?;
+    }
+    for (attachment, text) in stored {
+        db_attachments::insert_attachment(&tx, attachment, text)?;
+    }
+    tx.commit()
+}
+
+/// Import an Obsidian vault: every Markdown file becomes a buffer titled by
+/// its file name, `[[links]]` become flashnotes wiki links, embedded images
+/// become attachments and folder names are added as #hashtags. Buffers are
+/// written in a single transaction.
+#[tauri::command]
+pub async fn import_obsidian_vault(app: AppHandle, path: String) -> AppResult<VaultImportReport> {
+    track_blocking(app, "import_obsidian_vault", move |_, state| {
+        let scan = vault::scan(Path::new(&path))?;
+        for note in &scan.notes {
+            validate_buffer_size(&note.buffer.content)?;
+        }
+
+        // Copy embedded files before taking the writer lock; they are removed
+        // again if the import fails
+        let now = Utc::now().timestamp();
+        let mut stored: Vec<(Attachment, String)> = Vec::new();
+        let remove_stored = |stored: &[(Attachment, String)]| {
+            for (attachment, _) in stored {
+                attachments::remove_file(Path::new(&attachment.stored_path));
+            }
+        };
+        for note in &scan.notes {
+            for source in &note.embeds {
+                let id = Uuid::new_v4().to_string();
+                let file = match attachments::store_file(&state.app_data_dir, source, &id) {
+                    Ok(file) => file,
+                    Err(e) => {
+                        remove_stored(&stored);
+                        return Err(e);
+                    }
+                };
+                let text = attachments::extract_text(&file);
+                stored.push((
+                    Attachment {
+                        id,
+                        buffer_id: note.buffer.id.clone(),
+                        file_name: source
+                            .file_name()
+                            .map(|n| n.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| "attachment".to_string()),
+                        stored_path: file.to_string_lossy().into_owned(),
+                        size: file.metadata().map(|m| m.len() as i64).unwrap_or(0),
+                        has_text: !text.is_empty(),
+                        created_at: now,
+                    },
+                    text,
+                ));
+            }
+        }
+
+        let result = {
+            let mut conn = state.writer.lock();
+            insert_vault(&mut conn, &scan.notes, &stored)
+        };
+        if let Err(e) = result {
+            remove_stored(&stored);
+            return Err(e).db_context("Failed to import vault");
+        }
+
+        info!(
+            "Imported {} notes and {} attachments from vault {}",
+            scan.notes.len(),
+            stored.len(),
+            path
+        );
+        Ok(VaultImportReport {
+            buffers: scan.notes.into_iter().map(|note| summarize(note.buffer)).collect(),
+            attachments: stored.len(),
+            missing_embeds: scan.missing_embeds,
+        })
+    })
+    .await
+}
@@ -0,0 +1,24 @@
+use crate::error::{AppError, AppResult};
+use crate::jobs::JobInfo;
+use crate::state::AppState;
+use tauri::State;
+use tracing::info;
+
+/// List running background jobs (imports, embedding runs, maintenance)
+#[tauri::command]
+pub fn list_jobs(state: State<'_, AppState>) -> AppResult<Vec<JobInfo>> {
+    state.track("list_jobs", || Ok(state.jobs.list()))
+}
+
+/// Ask a running job to stop. It stops at its next checkpoint and fails
+/// with a "cancelled" error.
+#[tauri::command]
+pub fn cancel_job(state: State<'_, AppState>, id: String) -> AppResult<()> {
+    state.track("cancel_job", || {
+        if !state.jobs.cancel(&id) {
+            return Err(AppError::not_found("Job", id));
+        }
+        info!("Cancellation requested for job {}", id);
+        Ok(())
+    })
+}
@@ -0,0 +1,66 @@
+use super::buffer::insert_buffer;
+use crate::db::activity::Origin;
+use crate::db::queries::{self, BufferSummary};
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::lint::{self, BrokenLink, LintFinding};
+use crate::state::track_blocking;
+use chrono::Utc;
+use tauri::AppHandle;
+
+/// Check notes against the configured lint rules (`lint_rules`,
+/// `lint_todo_days` settings). Lints every non-archived note unless `ids` is given.
+#[tauri::command]
+pub async fn lint_buffers(app: AppHandle, ids: Option<Vec<String>>) -> AppResult<Vec<LintFinding>> {
+    track_blocking(app, "lint_buffers", move |_, state| {
+        let now = Utc::now().timestamp();
+        match state.get_reader() {
+            Ok(conn) => lint::run(&conn, ids.as_deref(), now),
+            Err(_) => {
+                let conn = state.writer.lock();
+                lint::run(&conn, ids.as_deref(), now)
+            }
+        }
+        .db_context("Failed to lint buffers")
+    })
+    .await
+}
+
+/// List `[[links]]` whose target no longer resolves to any note (after
+/// renames or deletions), in non-archived notes
+#[tauri::command]
+pub async fn get_broken_wikilinks(app: AppHandle) -> AppResult<Vec<BrokenLink>> {
+    track_blocking(app, "get_broken_wikilinks", move |_, state| {
+        match state.get_reader() {
+            Ok(conn) => lint::broken_links(&conn),
+            Err(_) => {
+                let conn = state.writer.lock();
+                lint::broken_links(&conn)
+            }
+        }
+        .db_context("Failed to find broken links")
+    })
+    .await
+}
+
+/// Create a note titled `title` so `[[title]]` links resolve again
+#[tauri::command]
+pub async fn create_missing_target(app: AppHandle, title: String) -> AppResult<BufferSummary> {
+    track_blocking(app, "create_missing_target", move |app, state| {
+        let title = title.trim();
+        let slug = queries::slugify(title);
+        if slug.is_empty() || title.contains(['\n', '\r']) {
+            return Err(AppError::Validation(format!("Invalid note title: {}", title)));
+        }
+
+        {
+            let conn = state.writer.lock();
+            let existing = queries::find_buffers_by_slug(&conn, &slug, "").db_context("Failed to check titles")?;
+            if !existing.is_empty() {
+                return Err(AppError::Conflict(format!("A note titled \"{}\" already exists", title)));
+            }
+        }
+
+        insert_buffer(app, state, &format!("# {}\n\n", title), "manual", Origin::Ui)
+    })
+    .await
+}
@@ -0,0 +1,42 @@
+use crate::encryption;
+use crate::error::{AppError, AppResult};
+use crate::idle;
+use crate::state::{run_blocking, AppState};
+use tauri::{AppHandle, Manager, State};
+use tracing::info;
+
+// Not tracked: tracked commands fail while the app is locked
+
+/// Lock the app immediately (same as reaching the idle timeout)
+#[tauri::command]
+pub async fn lock_app(app: AppHandle) -> AppResult<()> {
+    run_blocking("lock_app", move || {
+        idle::lock_app(&app);
+        Ok(())
+    })
+    .await
+}
+
+/// Clear the lock and restart the idle timer. An encrypted database is
+/// closed while locked and needs its `passphrase` (or
+/// `unlock_database_with_biometrics`) to open again.
+#[tauri::command]
+pub async fn unlock_app(app: AppHandle, passphrase: Option<String>) -> AppResult<()> {
+    run_blocking("unlock_app", move || {
+        let state = app.state::<AppState>();
+        if state.ready.is_locked() {
+            let passphrase = passphrase.ok_or_else(|| AppError::Validation("Passphrase required".to_string()))?;
+            encryption::unlock(&app, &passphrase)?;
+        }
+        state.idle.unlock();
+        info!("App unlocked");
+        Ok(())
+    })
+    .await
+}
+
+/// Whether the app (or the encrypted database) is currently locked, e.g. after a reload while locked
+#[tauri::command]
+pub fn is_app_locked(state: State<'_, AppState>) -> AppResult<bool> {
+    Ok(state.idle.is_locked() || state.ready.is_locked())
+}
@@ -1,5 +1,67 @@
+pub mod ai;
+pub mod api;
+pub mod attachments;
+pub mod backup;
+pub mod biometric;
+pub mod board;
 pub mod buffer;
+pub mod deep_link;
+pub mod cards;
+pub mod diagnostics;
+pub mod edit_locks;
+pub mod encryption;
+pub mod ephemeral;
+pub mod import_export;
+pub mod jobs;
+pub mod lint;
+pub mod lock;
+pub mod note_lock;
+pub mod note_window;
+pub mod platform;
+pub mod plugins;
+pub mod recovery;
+pub mod revisions;
+pub mod scripts;
+pub mod search;
+pub mod secrets;
 pub mod settings;
+pub mod share;
+pub mod sync;
+pub mod tags;
+pub mod tasks;
+pub mod updater;
+pub mod webhooks;
 
+pub use ai::*;
+pub use api::*;
+pub use attachments::*;
+pub use backup::*;
+pub use biometric::*;
+pub use board::*;
 pub use buffer::*;
+pub use deep_link::*;
+pub use cards::*;
+pub use diagnostics::*;
+pub use edit_locks::*;
+pub use encryption::*;
+pub use ephemeral::*;
+pub use import_export::*;
+pub use jobs::*;
+pub use lint::*;
+pub use lock::*;
+pub use note_lock::*;
+pub use note_window::*;
+pub use platform::*;
+pub use plugins::*;
+pub use recovery::*;
+pub use revisions::*;
+pub use scripts::*;
+pub use search::*;
+pub use secrets::*;
 pub use settings::*;
+pub use share::*;
+pub use sync::*;
+pub use tags::*;
+pub use tasks::*;
+pub use updater::*;
+pub use webhooks::*;
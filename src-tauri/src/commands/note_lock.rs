@@ -0,0 +1,20 @@
+use crate::error::AppResult;
+use crate::note_lock;
+use crate::state::track_blocking;
+use tauri::AppHandle;
+
+/// Encrypt a buffer's content with a passphrase. The note stays in the sidebar
+/// (untitled, or under its custom title) but can't be read, searched or edited
+/// until it is unlocked.
+#[tauri::command]
+pub async fn lock_buffer(app: AppHandle, id: String, passphrase: String) -> AppResult<()> {
+    track_blocking(app, "lock_buffer", move |app, state| note_lock::lock(app, state, &id, &passphrase))
+    .await
+}
+
+/// Decrypt a locked buffer with its passphrase, remove the lock and return its content
+#[tauri::command]
+pub async fn unlock_buffer(app: AppHandle, id: String, passphrase: String) -> AppResult<String> {
+    track_blocking(app, "unlock_buffer", move |app, state| note_lock::unlock(app, state, &id, &passphrase))
+    .await
+}
@@ -0,0 +1,21 @@
+use crate::error::AppResult;
+#[cfg(mobile)]
+use crate::error::AppError;
+use crate::state::AppState;
+use tauri::{AppHandle, State};
+
+/// Open a buffer in its own small always-on-top window, or focus the one
+/// already showing it. Returns the window label.
+/// Async because creating a window from a sync command deadlocks on Windows.
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn open_buffer_window(app: AppHandle, state: State<'_, AppState>, id: String) -> AppResult<String> {
+    state.track("open_buffer_window", || crate::note_window::open(&app, &state, &id))
+}
+
+/// Mobile has a single window
+#[cfg(mobile)]
+#[tauri::command]
+pub async fn open_buffer_window(_app: AppHandle, _state: State<'_, AppState>, _id: String) -> AppResult<String> {
+    Err(AppError::Unsupported("Note windows"))
+}
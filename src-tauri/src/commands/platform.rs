@@ -0,0 +1,32 @@
+use crate::error::AppResult;
+use crate::state::AppState;
+use serde::Serialize;
+use tauri::State;
+
+/// Platform and feature availability, so the UI can hide desktop-only
+/// controls and switch to touch layouts on phones
+#[derive(Debug, Serialize)]
+pub struct PlatformInfo {
+    pub os: &'static str,
+    pub is_mobile: bool,
+    pub supports_always_on_top: bool,
+    pub supports_updates: bool,
+    pub supports_share: bool,
+    pub supports_window_hide: bool,
+}
+
+/// Describe the running platform
+#[tauri::command]
+pub fn get_platform_info(state: State<'_, AppState>) -> AppResult<PlatformInfo> {
+    state.track("get_platform_info", || {
+        let is_mobile = cfg!(mobile);
+        Ok(PlatformInfo {
+            os: std::env::consts::OS,
+            is_mobile,
+            supports_always_on_top: !is_mobile,
+            supports_updates: !is_mobile,
+            supports_share: !is_mobile,
+            supports_window_hide: !is_mobile,
+        })
+    })
+}
@@ -0,0 +1,21 @@
+use crate::error::AppResult;
+use crate::plugins::{self, PluginInfo};
+use crate::state::AppState;
+use tauri::State;
+use tracing::info;
+
+/// List loaded WASM plugins with their hooks and capabilities
+#[tauri::command]
+pub fn list_plugins(state: State<'_, AppState>) -> AppResult<Vec<PluginInfo>> {
+    state.track("list_plugins", || Ok(state.plugins.list()))
+}
+
+/// Reload plugins from the plugins directory (after adding or removing one)
+#[tauri::command]
+pub fn reload_plugins(state: State<'_, AppState>) -> AppResult<Vec<PluginInfo>> {
+    state.track("reload_plugins", || {
+        let count = state.plugins.load(&plugins::plugins_dir(&state.app_data_dir));
+        info!("Reloaded {} plugins", count);
+        Ok(state.plugins.list())
+    })
+}
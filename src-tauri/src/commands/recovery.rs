@@ -0,0 +1,102 @@
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::queries;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::events::{self, Change};
+use crate::journal::{self, JournalEntry};
+use crate::state::{track_blocking, AppState};
+use chrono::Utc;
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use tracing::info;
+
+/// Unsaved edit found in the journal on launch
+#[derive(Debug, Serialize)]
+pub struct RecoverableEdit {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    pub journaled_at: i64,
+    pub saved_at: i64,
+}
+
+/// Record the latest unsaved content for a buffer.
+/// Called on a short debounce while typing; cleared again by `save_buffer`.
+#[tauri::command]
+pub fn journal_edit(state: State<'_, AppState>, id: String, content: String) -> AppResult<()> {
+    state.track("journal_edit", || {
+        // Ephemeral buffers must never touch the disk
+        if state.ephemeral.contains(&id) {
+            return Ok(());
+        }
+        let entry = JournalEntry {
+            buffer_id: id,
+            content,
+            journaled_at: Utc::now().timestamp(),
+        };
+        journal::write_entry(&journal::journal_dir(&state.app_data_dir), &entry)
+    })
+}
+
+/// List journaled edits that never made it into the database.
+/// Stale entries (buffer deleted or content already saved) are dropped.
+#[tauri::command]
+pub async fn get_recoverable_edits(app: AppHandle) -> AppResult<Vec<RecoverableEdit>> {
+    track_blocking(app, "get_recoverable_edits", move |_, state| {
+        let dir = journal::journal_dir(&state.app_data_dir);
+        let conn = state.writer.lock();
+
+        let mut edits = Vec::new();
+        for entry in journal::read_entries(&dir) {
+            let saved = queries::get_buffer_content(&conn, &entry.buffer_id)
+                .db_context("Failed to get buffer")?;
+
+            match saved {
+                Some(buffer) if buffer.content != entry.content => {
+                    let (title, _) = queries::extract_title_preview(&entry.content);
+                    edits.push(RecoverableEdit {
+                        id: entry.buffer_id,
+                        title,
+                        content: entry.content,
+                        journaled_at: entry.journaled_at,
+                        saved_at: buffer.updated_at,
+                    });
+                }
+                _ => journal::remove_entry(&dir, &entry.buffer_id),
+            }
+        }
+
+        edits.sort_by_key(|e| std::cmp::Reverse(e.journaled_at));
+        Ok(edits)
+    })
+    .await
+}
+
+/// Apply a journaled edit to its buffer and return the new title/preview
+#[tauri::command]
+pub async fn recover_edit(app: AppHandle, id: String) -> AppResult<(String, String)> {
+    track_blocking(app, "recover_edit", move |app, state| {
+        let dir = journal::journal_dir(&state.app_data_dir);
+        let entry = journal::read_entry(&dir, &id)
+            .ok_or_else(|| AppError::not_found("Journal entry", id.clone()))?;
+
+        let conn = state.writer.lock();
+        queries::update_buffer_content(&conn, &id, &entry.content, Utc::now().timestamp())
+            .db_context("Failed to recover buffer")?;
+        activity::record(&conn, Some(&id), Operation::Save, Origin::Ui, Some("recovery"));
+        journal::remove_entry(&dir, &id);
+        events::updated(app, &id, Change::Content);
+
+        info!("Recovered unsaved edit for buffer: {}", id);
+        queries::buffer_title_preview(&conn, &id, &entry.content).db_context("Failed to get buffer title")
+    })
+    .await
+}
+
+/// Throw away a journaled edit
+#[tauri::command]
+pub fn discard_edit(state: State<'_, AppState>, id: String) -> AppResult<()> {
+    state.track("discard_edit", || {
+        journal::remove_entry(&journal::journal_dir(&state.app_data_dir), &id);
+        Ok(())
+    })
+}
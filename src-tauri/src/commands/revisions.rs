@@ -0,0 +1,69 @@
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::queries;
+use crate::db::revisions::{self, RevisionSummary};
+use crate::db::webhooks::WebhookEvent;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::events::{self, Change};
+use crate::journal;
+use crate::live_merge;
+use crate::state::track_blocking;
+use crate::webhooks;
+use chrono::Utc;
+use tauri::AppHandle;
+use tracing::info;
+
+/// Earlier versions of a buffer, newest first
+#[tauri::command]
+pub async fn get_buffer_history(app: AppHandle, id: String) -> AppResult<Vec<RevisionSummary>> {
+    track_blocking(app, "get_buffer_history", move |_, state| {
+        state
+            .read(|conn| revisions::get_history(conn, &id))
+            .db_context("Failed to get buffer history")
+    })
+    .await
+}
+
+/// Full content of a revision
+#[tauri::command]
+pub async fn get_revision_content(app: AppHandle, revision_id: i64) -> AppResult<String> {
+    track_blocking(app, "get_revision_content", move |_, state| {
+        state
+            .read(|conn| revisions::get_revision(conn, revision_id))
+            .db_context("Failed to get revision")?
+            .map(|(_, content)| content)
+            .ok_or_else(|| AppError::not_found("Revision", revision_id.to_string()))
+    })
+    .await
+}
+
+/// Replace a buffer's content with a revision and return the restored content.
+/// The content being replaced is kept as a revision first, so a restore can
+/// itself be undone from the history.
+#[tauri::command]
+pub async fn restore_revision(app: AppHandle, revision_id: i64) -> AppResult<String> {
+    track_blocking(app, "restore_revision", move |app, state| {
+        let conn = state.writer.lock();
+        let (id, content) = revisions::get_revision(&conn, revision_id)
+            .db_context("Failed to get revision")?
+            .ok_or_else(|| AppError::not_found("Revision", revision_id.to_string()))?;
+
+        let timestamp = Utc::now().timestamp();
+        revisions::snapshot(&conn, &id, &content, timestamp, true).db_context("Failed to save revision")?;
+        let restored = queries::update_buffer_content(&conn, &id, &content, timestamp)
+            .db_context("Failed to restore revision")?;
+        if !restored {
+            return Err(AppError::not_found("Buffer", id));
+        }
+        let detail = format!("revision:{}", revision_id);
+        activity::record(&conn, Some(&id), Operation::Save, Origin::Ui, Some(&detail));
+        journal::remove_entry(&journal::journal_dir(&state.app_data_dir), &id);
+        drop(conn);
+
+        webhooks::notify(app, WebhookEvent::Updated, &id);
+        events::updated(app, &id, Change::Content);
+        live_merge::notify(app, vec![id.clone()], None);
+        info!("Restored buffer {} to revision {}", id, revision_id);
+        Ok(content)
+    })
+    .await
+}
@@ -0,0 +1,112 @@
+use crate::db::scripts::{self as db_scripts, Script};
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::scripting::{self, ScriptReport};
+use crate::state::track_blocking;
+use chrono::Utc;
+use tauri::AppHandle;
+use tracing::info;
+use uuid::Uuid;
+
+/// Shortest schedule interval (scheduled scripts run from the maintenance
+/// thread, which wakes every 15 minutes)
+const MIN_SCHEDULE_MINUTES: i64 = 15;
+
+/// Create a script, or update it when `id` is given. The source must parse.
+/// `schedule_minutes` > 0 runs it automatically at that interval.
+#[tauri::command]
+pub async fn save_script(
+    app: AppHandle,
+    id: Option<String>,
+    name: String,
+    source: String,
+    schedule_minutes: Option<i64>,
+) -> AppResult<Script> {
+    track_blocking(app, "save_script", move |_, state| {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::Validation("Script name is required".to_string()));
+        }
+        let schedule_minutes = schedule_minutes.unwrap_or(0);
+        if schedule_minutes != 0 && schedule_minutes < MIN_SCHEDULE_MINUTES {
+            return Err(AppError::Validation(format!(
+                "Scheduled scripts run at most every {} minutes",
+                MIN_SCHEDULE_MINUTES
+            )));
+        }
+        scripting::validate_source(&source)?;
+
+        let conn = state.writer.lock();
+        let now = Utc::now().timestamp();
+        let existing = match &id {
+            Some(id) => Some(
+                db_scripts::get_script(&conn, id)
+                    .db_context("Failed to get script")?
+                    .ok_or_else(|| AppError::not_found("Script", id.clone()))?,
+            ),
+            None => None,
+        };
+
+        let script = Script {
+            id: id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+            name,
+            source,
+            schedule_minutes,
+            last_run_at: existing.as_ref().and_then(|s| s.last_run_at),
+            created_at: existing.as_ref().map_or(now, |s| s.created_at),
+            updated_at: now,
+        };
+
+        if let Some(other) = db_scripts::find_script_by_name(&conn, &script.name).db_context("Failed to get script")? {
+            if other != script.id {
+                return Err(AppError::Conflict(format!("A script named {} already exists", script.name)));
+            }
+        }
+
+        db_scripts::upsert_script(&conn, &script).db_context("Failed to save script")?;
+        info!("Saved script {}", script.name);
+        Ok(script)
+    })
+    .await
+}
+
+/// List stored scripts
+#[tauri::command]
+pub async fn list_scripts(app: AppHandle) -> AppResult<Vec<Script>> {
+    track_blocking(app, "list_scripts", move |_, state| match state.get_reader() {
+        Ok(conn) => db_scripts::list_scripts(&conn).db_context("Failed to list scripts"),
+        Err(_) => {
+            let conn = state.writer.lock();
+            db_scripts::list_scripts(&conn).db_context("Failed to list scripts")
+        }
+    })
+    .await
+}
+
+/// Delete a stored script
+#[tauri::command]
+pub async fn delete_script(app: AppHandle, id: String) -> AppResult<()> {
+    track_blocking(app, "delete_script", move |_, state| {
+        let conn = state.writer.lock();
+        if !db_scripts::delete_script(&conn, &id).db_context("Failed to delete script")? {
+            return Err(AppError::not_found("Script", id));
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Run a stored script now. Its changes are applied all at once when it
+/// finishes, or not at all if it fails.
+#[tauri::command]
+pub async fn run_script(app: AppHandle, id: String) -> AppResult<ScriptReport> {
+    track_blocking(app, "run_script", move |app, state| {
+        let script = {
+            let conn = state.writer.lock();
+            db_scripts::get_script(&conn, &id)
+                .db_context("Failed to get script")?
+                .ok_or_else(|| AppError::not_found("Script", id.clone()))?
+        };
+        scripting::run(app, state, &script)
+    })
+    .await
+}
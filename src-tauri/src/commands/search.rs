@@ -0,0 +1,163 @@
+use crate::ai;
+use crate::db::embeddings as db_embeddings;
+use crate::db::queries::{self, Buffer, SearchResult};
+use crate::embeddings;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::related::{self, RelatedNote};
+use crate::search::{self, HybridResult};
+use crate::state::{track_blocking, AppState};
+use rusqlite::Connection;
+use std::collections::{HashMap, HashSet};
+use std::thread;
+use tauri::AppHandle;
+use tracing::warn;
+
+/// Results per source before fusion, and the most returned after
+const SEARCH_LIMIT: usize = 20;
+
+fn keyword_search(state: &AppState, query: &str) -> AppResult<Vec<SearchResult>> {
+    // Invalid queries still get semantic results
+    let Some(sanitized) = queries::sanitize_search_query(query) else {
+        warn!("Invalid search query rejected: {:?}", query);
+        return Ok(Vec::new());
+    };
+    match state.get_reader() {
+        Ok(conn) => queries::search_buffers(&conn, &sanitized, None, SEARCH_LIMIT),
+        Err(_) => queries::search_buffers(&state.writer.lock(), &sanitized, None, SEARCH_LIMIT),
+    }
+    .db_context("Failed to search buffers")
+}
+
+/// Notes nearest to the query, or nothing when semantic search is off or
+/// unavailable (keyword results are still returned)
+fn semantic_search(state: &AppState, query: &str) -> Vec<(String, f32)> {
+    let load = |conn: &Connection| -> AppResult<_> {
+        let settings = queries::get_settings(conn).db_context("Failed to get settings")?;
+        let embedder = ai::embedder(&settings)?;
+        let vectors = db_embeddings::get_vectors(conn, embedder.model()).db_context("Failed to load embeddings")?;
+        Ok((settings.semantic_search, embedder, vectors))
+    };
+    let result = match state.get_reader() {
+        Ok(conn) => load(&conn),
+        Err(_) => load(&state.writer.lock()),
+    };
+
+    let (embedder, vectors) = match result {
+        Ok((true, embedder, vectors)) if !vectors.is_empty() => (embedder, vectors),
+        Ok(_) => return Vec::new(),
+        Err(e) => {
+            warn!("Semantic search unavailable: {}", e);
+            return Vec::new();
+        }
+    };
+    match embeddings::embed_query(embedder.as_ref(), query) {
+        Ok(vector) => embeddings::nearest(&vectors, &vector, SEARCH_LIMIT),
+        Err(e) => {
+            warn!("Semantic search failed: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Default number of related notes returned
+const DEFAULT_RELATED: usize = 5;
+
+/// Most related notes returned
+const MAX_RELATED: usize = 50;
+
+fn related(conn: &Connection, id: &str, limit: usize) -> AppResult<Vec<RelatedNote>> {
+    let buffers: Vec<Buffer> = queries::get_all_buffers(conn)
+        .db_context("Failed to get buffers")?
+        .into_iter()
+        .filter(|b| !b.is_archived || b.id == id)
+        .collect();
+    let target = buffers
+        .iter()
+        .find(|b| b.id == id)
+        .ok_or_else(|| AppError::not_found("Buffer", id))?;
+
+    // Only vectors from the note's own model are comparable
+    let mut vectors = HashMap::new();
+    if let Some(model) = db_embeddings::get_model(conn, id).db_context("Failed to load embeddings")? {
+        let stored = db_embeddings::get_vectors(conn, &model).db_context("Failed to load embeddings")?;
+        for (buffer_id, bytes) in stored {
+            vectors.insert(buffer_id, embeddings::decode(&bytes));
+        }
+    }
+
+    Ok(related::rank(target, &buffers, &vectors, limit))
+}
+
+/// Notes related to a buffer for a "See also" panel, ranked by embedding
+/// similarity, shared #tags and wiki link proximity
+#[tauri::command]
+pub async fn get_related(app: AppHandle, id: String, n: Option<usize>) -> AppResult<Vec<RelatedNote>> {
+    track_blocking(app, "get_related", move |_, state| {
+        let limit = n.unwrap_or(DEFAULT_RELATED).min(MAX_RELATED);
+        match state.get_reader() {
+            Ok(conn) => related(&conn, &id, limit),
+            Err(_) => related(&state.writer.lock(), &id, limit),
+        }
+    })
+    .await
+}
+
+/// Search by keywords and meaning at once: full-text and semantic searches
+/// run in parallel and their rankings are fused, with each source's score
+/// reported per result. Falls back to keyword results alone when semantic
+/// search is off.
+#[tauri::command]
+pub async fn hybrid_search(app: AppHandle, query: String) -> AppResult<Vec<HybridResult>> {
+    track_blocking(app, "hybrid_search", move |_, state| {
+        // Plugins may rewrite the query, as for keyword search
+        let query = state.plugins.transform_search(&state.reader_pool(), &query);
+        if query.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (keyword, semantic) = thread::scope(|scope| {
+            let semantic = scope.spawn(|| semantic_search(state, &query));
+            let keyword = keyword_search(state, &query);
+            (keyword, semantic.join().unwrap_or_default())
+        });
+        // Attachment matches can repeat a buffer; keep its best hit
+        let mut keyword = keyword?;
+        let mut seen = HashSet::new();
+        keyword.retain(|r| seen.insert(r.id.clone()));
+
+        let keyword_ids: Vec<String> = keyword.iter().map(|r| r.id.clone()).collect();
+        let semantic_ids: Vec<String> = semantic.iter().map(|(id, _)| id.clone()).collect();
+        let mut fused = search::fuse(&keyword_ids, &semantic_ids);
+        fused.truncate(SEARCH_LIMIT);
+
+        let ids: Vec<String> = fused.iter().map(|(id, _)| id.clone()).collect();
+        let summaries = match state.get_reader() {
+            Ok(conn) => queries::get_buffer_summaries(&conn, &ids, ids.len()),
+            Err(_) => queries::get_buffer_summaries(&state.writer.lock(), &ids, ids.len()),
+        }
+        .db_context("Failed to get search results")?;
+        let mut summaries: HashMap<String, _> = summaries.into_iter().map(|s| (s.id.clone(), s)).collect();
+
+        let mut results = Vec::with_capacity(fused.len());
+        for (id, score) in fused {
+            // Skip notes archived or deleted since they were indexed
+            let Some(summary) = summaries.remove(&id) else {
+                continue;
+            };
+            let keyword_hit = keyword.iter().position(|r| r.id == id);
+            let hit = keyword_hit.map(|i| &keyword[i]);
+            results.push(HybridResult {
+                snippet: hit.map_or_else(|| summary.preview.clone(), |h| h.snippet.clone()),
+                attachment_name: hit.and_then(|h| h.attachment_name.clone()),
+                keyword_rank: keyword_hit.map(|i| i + 1),
+                semantic_score: semantic.iter().find(|(s, _)| *s == id).map(|(_, score)| *score),
+                id,
+                title: summary.title,
+                updated_at: summary.updated_at,
+                score,
+            });
+        }
+        Ok(results)
+    })
+    .await
+}
@@ -0,0 +1,35 @@
+use crate::error::AppResult;
+use crate::secrets;
+use crate::state::AppState;
+use tauri::State;
+use tracing::info;
+
+/// Store an integration secret in the OS keychain. An empty or missing value
+/// removes it. Secrets are write-only from the frontend; use `has_secret` to
+/// check whether one is configured.
+#[tauri::command]
+pub fn set_secret(state: State<'_, AppState>, name: String, value: Option<String>) -> AppResult<()> {
+    state.track("set_secret", || {
+        secrets::validate_name(&name)?;
+        match value.filter(|v| !v.trim().is_empty()) {
+            Some(value) => {
+                secrets::set(&name, &value)?;
+                info!("Stored secret: {}", name);
+            }
+            None => {
+                secrets::delete(&name)?;
+                info!("Removed secret: {}", name);
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Whether a secret is stored (its value is never returned)
+#[tauri::command]
+pub fn has_secret(state: State<'_, AppState>, name: String) -> AppResult<bool> {
+    state.track("has_secret", || {
+        secrets::validate_name(&name)?;
+        Ok(secrets::get(&name)?.is_some())
+    })
+}
@@ -1,54 +1,289 @@
+use super::buffer::DuplicateTitlePolicy;
+use crate::ai::transcribe::TranscriptionProvider;
+use crate::ai::ProviderKind;
+use crate::api;
+use crate::backup_schedule;
+use crate::db::connection;
 use crate::db::queries::{self, AppSettings};
-use crate::state::AppState;
-use tauri::{Manager, State, WebviewWindow};
+use crate::error::{AppError, AppResult, DbResultExt};
+#[cfg(desktop)]
+use crate::hotkey;
+use crate::i18n::{self, Locale};
+use crate::inbox_watch::{self, AfterImport, WatchResult};
+use crate::lint::LintRule;
+use crate::logging;
+use crate::maintenance;
+use crate::menu;
+use crate::relocate;
+use crate::save_hook;
+use crate::state::{track_blocking, AppState};
+use crate::sync;
+use chrono::Weekday;
+use std::path::PathBuf;
+#[cfg(desktop)]
+use tauri::Manager;
+use tauri::{AppHandle, State, WebviewWindow};
 
-/// Convert rusqlite errors to user-friendly strings
-fn map_db_error<T>(result: Result<T, rusqlite::Error>, context: &str) -> Result<T, String> {
-    result.map_err(|e| format!("{}: {}", context, e))
-}
+/// Settings holding global shortcut accelerators
+const SHORTCUT_SETTINGS: &[&str] = &["global_shortcut", "clipboard_shortcut"];
 
 /// Get all app settings
 #[tauri::command]
-pub fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
-    // Try reader pool first
-    match state.get_reader() {
-        Ok(conn) => map_db_error(queries::get_settings(&conn), "Failed to get settings"),
-        Err(_) => {
-            let conn = state.writer.lock();
-            map_db_error(queries::get_settings(&conn), "Failed to get settings")
+pub async fn get_settings(app: AppHandle) -> AppResult<AppSettings> {
+    track_blocking(app, "get_settings", move |_, state| {
+        // Try reader pool first
+        match state.get_reader() {
+            Ok(conn) => queries::get_settings(&conn).db_context("Failed to get settings"),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_settings(&conn).db_context("Failed to get settings")
+            }
         }
-    }
+    })
+    .await
 }
 
 /// Update a single setting
 #[tauri::command]
-pub fn set_setting(state: State<'_, AppState>, key: String, value: String) -> Result<(), String> {
-    let conn = state.writer.lock();
-    map_db_error(
-        queries::set_setting(&conn, &key, &value),
-        "Failed to save setting",
-    )
+pub async fn set_setting(app: AppHandle, key: String, value: String) -> AppResult<()> {
+    track_blocking(app, "set_setting", move |app, state| {
+        // Log level takes effect immediately (and is validated before persisting)
+        if key == "log_level" {
+            logging::set_level(&value).map_err(AppError::Validation)?;
+        }
+        if key == "empty_buffer_cleanup" && maintenance::CleanupPolicy::parse(&value).is_none() {
+            return Err(AppError::Validation(format!("Invalid cleanup policy: {}", value)));
+        }
+        if key == "duplicate_titles" && DuplicateTitlePolicy::parse(&value).is_none() {
+            return Err(AppError::Validation(format!("Invalid duplicate title policy: {}", value)));
+        }
+        if key == "auto_lock_minutes" && value.parse::<u32>().is_err() {
+            return Err(AppError::Validation(format!("Invalid auto-lock minutes: {}", value)));
+        }
+        if key == "auto_archive_days" && value.parse::<u32>().is_err() {
+            return Err(AppError::Validation(format!("Invalid auto-archive days: {}", value)));
+        }
+        if key == "trash_retention_days" && value.parse::<u32>().is_err() {
+            return Err(AppError::Validation(format!("Invalid trash retention days: {}", value)));
+        }
+        if key == "backup_interval_hours" && value.parse::<u32>().is_err() {
+            return Err(AppError::Validation(format!("Invalid backup interval: {}", value)));
+        }
+        if key == "backup_retention_count" && !value.parse::<u32>().is_ok_and(|n| n > 0) {
+            return Err(AppError::Validation(format!("Invalid backup retention count: {}", value)));
+        }
+        if key == "lint_rules" && LintRule::parse_list(&value).is_none() {
+            return Err(AppError::Validation(format!("Invalid lint rules: {}", value)));
+        }
+        if key == "lint_todo_days" && value.parse::<u32>().is_err() {
+            return Err(AppError::Validation(format!("Invalid lint TODO age: {}", value)));
+        }
+        if key == "ai_provider" && ProviderKind::parse(&value).is_none() {
+            return Err(AppError::Validation(format!("Invalid AI provider: {}", value)));
+        }
+        if (key == "ai_base_url" || key == "transcription_url")
+            && !(value.is_empty() || value.starts_with("https://") || value.starts_with("http://"))
+        {
+            return Err(AppError::Validation(format!("URL must be http(s): {}", value)));
+        }
+        if key == "transcription_provider" && TranscriptionProvider::parse(&value).is_none() {
+            return Err(AppError::Validation(format!("Invalid transcription provider: {}", value)));
+        }
+        if key == "digest_day" && value.parse::<Weekday>().is_err() {
+            return Err(AppError::Validation(format!("Invalid digest day: {}", value)));
+        }
+        if key == "digest_hour" && !value.parse::<u32>().is_ok_and(|h| h < 24) {
+            return Err(AppError::Validation(format!("Invalid digest hour: {}", value)));
+        }
+        if key == "locale" && !i18n::is_valid_setting(&value) {
+            return Err(AppError::Validation(format!("Invalid locale: {}", value)));
+        }
+        if key == inbox_watch::WATCH_DIR_SETTING {
+            inbox_watch::validate_dir(&value)?;
+        }
+        if key == "inbox_after_import" && AfterImport::parse(&value).is_none() {
+            return Err(AppError::Validation(format!("Invalid inbox after-import action: {}", value)));
+        }
+        if key == backup_schedule::BACKUP_DESTINATION_SETTING {
+            backup_schedule::validate_destination(&value)?;
+        }
+        if key == sync::SYNC_DIR_SETTING {
+            sync::validate_dir(&value)?;
+        }
+        if key == sync::CLOUD_SYNC_SETTING && value == "true" && !sync::cloud_available() {
+            return Err(AppError::Unsupported("iCloud sync"));
+        }
+        // Registered (or refused) right away; saved only once it is active
+        if SHORTCUT_SETTINGS.contains(&key.as_str()) {
+            return apply_shortcut(app, &key, &value);
+        }
+        // Started, moved or stopped before saving, so a port in use leaves the setting as it was
+        if [api::API_ENABLED_SETTING, api::MCP_ENABLED_SETTING, api::API_PORT_SETTING].contains(&key.as_str()) {
+            apply_api(app, state, &key, &value)?;
+        }
+        if key == save_hook::SAVE_HOOK_SETTING {
+            // iOS/Android apps can't run user scripts
+            if cfg!(mobile) && !value.trim().is_empty() {
+                return Err(AppError::Unsupported("Save hook scripts"));
+            }
+            save_hook::validate_script(&value).map_err(AppError::Validation)?;
+        }
+
+        let conn = state.writer.lock();
+        queries::set_setting(&conn, &key, &value).db_context("Failed to save setting")?;
+
+        // Metrics off is a hard off switch: stop recording and drop everything stored
+        if key == "usage_metrics_enabled" {
+            let enabled = value == "true";
+            state.metrics.set_enabled(enabled);
+            if !enabled {
+                queries::clear_usage_metrics(&conn).db_context("Failed to clear usage metrics")?;
+            }
+        }
+
+        if key == "auto_lock_minutes" || key == "auto_lock_hide_window" {
+            let settings = queries::get_settings(&conn).db_context("Failed to get settings")?;
+            state
+                .idle
+                .configure(settings.auto_lock_minutes.max(0) as u32, settings.auto_lock_hide_window);
+        }
+
+        if key == save_hook::SAVE_HOOK_SETTING {
+            state.save_hook.configure(&value);
+        }
+
+        if key == inbox_watch::WATCH_DIR_SETTING {
+            state.inbox_watch.reset();
+        }
+
+        // A new sync folder starts without knowledge of the old one's files
+        if key == sync::SYNC_DIR_SETTING {
+            sync::reset(state, &conn)?;
+        }
+        if key == sync::CLOUD_SYNC_SETTING && value != "true" {
+            sync::reset_cloud(state, &conn)?;
+        }
+
+        if key == "secure_delete" {
+            connection::set_secure_delete(&conn, value == "true").db_context("Failed to apply secure deletion")?;
+        }
+
+        // Switch the menu and error messages over immediately
+        if key == "locale" {
+            i18n::set_locale(Locale::from_setting(&value));
+            let settings = queries::get_settings(&conn).db_context("Failed to get settings")?;
+            menu::install(app, settings.always_on_top)
+                .map_err(|e| AppError::Internal(format!("Failed to rebuild menu: {}", e)))?;
+        }
+
+        Ok(())
+    })
+    .await
+}
+
+/// Recent results of the inbox folder watcher, newest first
+#[tauri::command]
+pub fn get_inbox_watch_log(state: State<'_, AppState>) -> AppResult<Vec<WatchResult>> {
+    state.track("get_inbox_watch_log", || Ok(state.inbox_watch.log()))
 }
 
 /// Toggle always on top window state
+#[cfg(desktop)]
 #[tauri::command]
-pub fn toggle_always_on_top(window: WebviewWindow, state: State<'_, AppState>) -> Result<bool, String> {
-    let is_on_top = window.is_always_on_top().map_err(|e| e.to_string())?;
-    let new_state = !is_on_top;
-    window.set_always_on_top(new_state).map_err(|e| e.to_string())?;
+pub async fn toggle_always_on_top(window: WebviewWindow, app: AppHandle) -> AppResult<bool> {
+    track_blocking(app, "toggle_always_on_top", move |_, state| {
+        let is_on_top = window.is_always_on_top().map_err(|e| AppError::Internal(e.to_string()))?;
+        let new_state = !is_on_top;
+        window.set_always_on_top(new_state).map_err(|e| AppError::Internal(e.to_string()))?;
 
-    // Persist the setting
-    let conn = state.writer.lock();
-    let _ = queries::set_setting(&conn, "always_on_top", if new_state { "true" } else { "false" });
+        // Persist the setting
+        let conn = state.writer.lock();
+        let _ = queries::set_setting(&conn, "always_on_top", if new_state { "true" } else { "false" });
 
-    // Update menu checkmark
-    if let Some(menu) = window.app_handle().menu() {
-        if let Some(item) = menu.get("stay_on_top") {
-            if let Some(check_item) = item.as_check_menuitem() {
-                let _ = check_item.set_checked(new_state);
+        // Update menu checkmark
+        if let Some(menu) = window.app_handle().menu() {
+            if let Some(item) = menu.get("stay_on_top") {
+                if let Some(check_item) = item.as_check_menuitem() {
+                    let _ = check_item.set_checked(new_state);
+                }
             }
         }
+
+        Ok(new_state)
+    })
+    .await
+}
+
+/// Windows can't float on mobile
+#[cfg(mobile)]
+#[tauri::command]
+pub fn toggle_always_on_top(_window: WebviewWindow, state: State<'_, AppState>) -> AppResult<bool> {
+    state.track("toggle_always_on_top", || Err(AppError::Unsupported("Stay on Top")))
+}
+
+fn apply_api(app: &AppHandle, state: &AppState, key: &str, value: &str) -> AppResult<()> {
+    let mut settings = state.read(queries::get_settings).db_context("Failed to get settings")?;
+    match key {
+        api::API_PORT_SETTING => settings.api_port = api::validate_port(value)?.into(),
+        api::MCP_ENABLED_SETTING => settings.mcp_enabled = value == "true",
+        _ => settings.api_enabled = value == "true",
     }
+    api::apply(app, &settings)
+}
+
+#[cfg(desktop)]
+fn apply_shortcut(app: &AppHandle, key: &str, accelerator: &str) -> AppResult<()> {
+    let action = hotkey::Action::from_setting(key)
+        .ok_or_else(|| AppError::Internal(format!("Not a shortcut setting: {}", key)))?;
+    hotkey::set(app, action, accelerator)
+}
 
-    Ok(new_state)
+/// Mobile has no global shortcuts
+#[cfg(mobile)]
+fn apply_shortcut(_app: &AppHandle, _key: &str, _accelerator: &str) -> AppResult<()> {
+    Err(AppError::Unsupported("Global shortcuts"))
+}
+
+/// Change the shortcut that shows/hides the window from any app (e.g.
+/// "CommandOrControl+Shift+Space"; empty turns it off). An invalid accelerator
+/// is a validation error, one the OS won't register (often because another
+/// app uses it) a conflict; either way the previous shortcut stays active.
+#[tauri::command]
+pub async fn set_global_shortcut(app: AppHandle, accelerator: String) -> AppResult<()> {
+    track_blocking(app, "set_global_shortcut", move |app, _| {
+        apply_shortcut(app, "global_shortcut", &accelerator)
+    })
+    .await
+}
+
+/// Change the shortcut that creates a note from the clipboard text and opens
+/// it (empty turns it off). Errors as for `set_global_shortcut`.
+#[tauri::command]
+pub async fn set_clipboard_shortcut(app: AppHandle, accelerator: String) -> AppResult<()> {
+    track_blocking(app, "set_clipboard_shortcut", move |app, _| {
+        apply_shortcut(app, "clipboard_shortcut", &accelerator)
+    })
+    .await
+}
+
+/// Path of the database file in use
+#[tauri::command]
+pub async fn get_database_path(app: AppHandle) -> AppResult<String> {
+    track_blocking(app, "get_database_path", move |_, state| {
+        Ok(relocate::current_path(state)?.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Move the database into another folder (empty = back to the app data
+/// directory) and return its new path. The folder must not already contain
+/// a Flashnotes database.
+#[tauri::command]
+pub async fn set_database_path(app: AppHandle, dir: String) -> AppResult<String> {
+    track_blocking(app, "set_database_path", move |_, state| {
+        let dir = (!dir.trim().is_empty()).then(|| PathBuf::from(dir.trim()));
+        let path = relocate::move_database(state, dir.as_deref())?;
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
 }
@@ -0,0 +1,207 @@
+use crate::db::queries;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::export::{self, ExportFormat};
+use crate::lan_share::{self, ShareLink};
+use crate::qr::{self, NoteQr, QrPayload};
+use crate::state::{track_blocking, AppState};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, State, WebviewWindow};
+use tracing::{info, warn};
+
+/// Shared files older than this are removed on the next share
+const SHARE_FILE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Temp directory holding files handed to the share sheet
+fn share_dir() -> PathBuf {
+    std::env::temp_dir().join("flashnotes-share")
+}
+
+/// Remove stale files from previous shares (best effort)
+fn cleanup_share_dir(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > SHARE_FILE_TTL);
+
+        if expired {
+            if let Err(e) = fs::remove_file(entry.path()) {
+                warn!("Failed to remove old share file {:?}: {}", entry.path(), e);
+            }
+        }
+    }
+}
+
+/// Export a buffer to a temp file and open the native share sheet for it.
+/// Returns the path of the exported file.
+#[tauri::command]
+pub async fn share_buffer(
+    window: WebviewWindow,
+    app: AppHandle,
+    id: String,
+    format: Option<String>,
+) -> AppResult<String> {
+    track_blocking(app, "share_buffer", move |_, state| {
+        let format = match format {
+            Some(f) => ExportFormat::parse(&f)
+                .ok_or_else(|| AppError::Validation(format!("Unsupported share format: {}", f)))?,
+            None => ExportFormat::Markdown,
+        };
+
+        // Try reader pool first, fall back to writer
+        let buffer = match state.get_reader() {
+            Ok(conn) => queries::get_buffer_content(&conn, &id).db_context("Failed to get buffer")?,
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_buffer_content(&conn, &id).db_context("Failed to get buffer")?
+            }
+        }
+        .ok_or_else(|| AppError::not_found("Buffer", &id))?;
+
+        let (title, _) = queries::extract_title_preview(&buffer.content);
+
+        let dir = share_dir();
+        cleanup_share_dir(&dir);
+        let path = export::write_to_dir(&dir, &buffer.slug, &buffer.content, &title, format)?;
+
+        info!("Sharing buffer {} as {:?}", id, path);
+        show_share_sheet(&window, &path)?;
+
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Serve a read-only rendering of a buffer on the local network for `ttl`
+/// seconds. The link always shows the note's current content.
+#[tauri::command]
+pub async fn share_temporarily(app: AppHandle, id: String, ttl: u64) -> AppResult<ShareLink> {
+    track_blocking(app, "share_temporarily", move |app, state| {
+        if !(lan_share::MIN_TTL_SECS..=lan_share::MAX_TTL_SECS).contains(&ttl) {
+            return Err(AppError::Validation(format!(
+                "Share duration must be between {} and {} seconds",
+                lan_share::MIN_TTL_SECS,
+                lan_share::MAX_TTL_SECS
+            )));
+        }
+
+        let exists = state
+            .read(|conn| queries::get_buffer_content(conn, &id))
+            .db_context("Failed to get buffer")?
+            .is_some();
+        if !exists {
+            return Err(AppError::not_found("Buffer", id));
+        }
+
+        lan_share::share(app, &id, ttl)
+    })
+    .await
+}
+
+/// Stop serving a temporary share link before it expires
+#[tauri::command]
+pub fn revoke_share_link(state: State<'_, AppState>, token: String) -> AppResult<()> {
+    state.track("revoke_share_link", || {
+        if state.share_links.revoke(&token) {
+            Ok(())
+        } else {
+            Err(AppError::not_found("Share link", token))
+        }
+    })
+}
+
+/// QR code for moving a note to a phone. Short notes are encoded as text;
+/// longer ones as a temporary share link reachable on the local network.
+#[tauri::command]
+pub async fn generate_note_qr(app: AppHandle, id: String) -> AppResult<NoteQr> {
+    track_blocking(app, "generate_note_qr", move |app, state| {
+        let buffer = state
+            .read(|conn| queries::get_buffer_content(conn, &id))
+            .db_context("Failed to get buffer")?
+            .ok_or_else(|| AppError::not_found("Buffer", &id))?;
+
+        if qr::fits_inline(&buffer.content) {
+            return Ok(NoteQr {
+                svg: qr::render_svg(&buffer.content)?,
+                payload: QrPayload::Text,
+                share: None,
+            });
+        }
+
+        let link = lan_share::share(app, &id, qr::LINK_TTL_SECS)?;
+        let Some(lan_url) = link.lan_url.clone() else {
+            state.share_links.revoke(&link.token);
+            return Err(AppError::Validation(
+                "Note is too long for a QR code and no network address is available".to_string(),
+            ));
+        };
+        Ok(NoteQr {
+            svg: qr::render_svg(&lan_url)?,
+            payload: QrPayload::Link,
+            share: Some(link),
+        })
+    })
+    .await
+}
+
+/// Present the macOS sharing service picker anchored to the window
+#[cfg(target_os = "macos")]
+fn show_share_sheet(window: &WebviewWindow, path: &Path) -> AppResult<()> {
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSArray, NSPoint, NSRect, NSSize, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    let path = path.to_string_lossy().to_string();
+    let target = window.clone();
+
+    window
+        .run_on_main_thread(move || unsafe {
+            let ns_window = match target.ns_window() {
+                Ok(w) => w as id,
+                Err(e) => {
+                    warn!("Failed to get native window for share sheet: {}", e);
+                    return;
+                }
+            };
+
+            let ns_path = NSString::alloc(nil).init_str(&path);
+            let url: id = msg_send![class!(NSURL), fileURLWithPath: ns_path];
+            let items = NSArray::arrayWithObject(nil, url);
+
+            // The picker must outlive this closure while it is on screen,
+            // so it is intentionally not released here
+            let picker: id = msg_send![class!(NSSharingServicePicker), alloc];
+            let picker: id = msg_send![picker, initWithItems: items];
+
+            let content_view: id = msg_send![ns_window, contentView];
+            let bounds: NSRect = msg_send![content_view, bounds];
+            let anchor = NSRect::new(
+                NSPoint::new(bounds.size.width / 2.0, bounds.size.height / 2.0),
+                NSSize::new(1.0, 1.0),
+            );
+            // NSRectEdge: NSMinYEdge = 1
+            let _: () = msg_send![picker, showRelativeToRect: anchor ofView: content_view preferredEdge: 1u64];
+        })
+        .map_err(|e| AppError::Internal(format!("Failed to open share sheet: {}", e)))
+}
+
+/// Other desktop platforms have no share sheet; reveal the exported file instead
+#[cfg(all(desktop, not(target_os = "macos")))]
+fn show_share_sheet(_window: &WebviewWindow, path: &Path) -> AppResult<()> {
+    tauri_plugin_opener::reveal_item_in_dir(path)
+        .map_err(|e| AppError::Internal(format!("Failed to reveal shared file: {}", e)))
+}
+
+/// The mobile share sheet needs a native plugin that isn't wired up yet
+#[cfg(mobile)]
+fn show_share_sheet(_window: &WebviewWindow, _path: &Path) -> AppResult<()> {
+    Err(AppError::Unsupported("Sharing"))
+}
@@ -0,0 +1,32 @@
+use crate::error::AppResult;
+use crate::state::{track_blocking, AppState};
+use crate::sync::{self, CloudSyncStatus, SyncReport, SyncStatus};
+use tauri::{AppHandle, State};
+
+/// Folder sync status: the folder, when it last ran and what it last changed
+#[tauri::command]
+pub fn get_sync_status(state: State<'_, AppState>) -> AppResult<SyncStatus> {
+    state.track("get_sync_status", || Ok(state.folder_sync.status()))
+}
+
+/// Sync with the folder now instead of waiting for the next poll.
+/// Returns None when no sync folder is set.
+#[tauri::command]
+pub async fn sync_now(app: AppHandle) -> AppResult<Option<SyncReport>> {
+    track_blocking(app, "sync_now", move |app, _| sync::sync_now(app))
+    .await
+}
+
+/// iCloud sync status: whether it is available, when it last ran and what it last changed
+#[tauri::command]
+pub fn get_cloud_sync_status(state: State<'_, AppState>) -> AppResult<CloudSyncStatus> {
+    state.track("get_cloud_sync_status", || Ok(state.cloud_sync.status()))
+}
+
+/// Sync with iCloud now instead of waiting for the next poll.
+/// Returns None when iCloud sync is off.
+#[tauri::command]
+pub async fn cloud_sync_now(app: AppHandle) -> AppResult<Option<SyncReport>> {
+    track_blocking(app, "cloud_sync_now", move |app, _| sync::cloud_sync_now(app))
+    .await
+}
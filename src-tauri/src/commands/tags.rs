@@ -0,0 +1,93 @@
+use crate::db::queries;
+use crate::db::tags::{self, TagCount, TagStats, TagSummary};
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::track_blocking;
+use chrono::Utc;
+use tauri::AppHandle;
+use tracing::info;
+
+/// Normalize a tag argument, rejecting names a #hashtag couldn't have
+fn tag_name(name: &str) -> AppResult<String> {
+    tags::normalize_name(name).ok_or_else(|| {
+        AppError::Validation(format!(
+            "Invalid tag '{}'. Use up to {} letters, digits, '-', '_' or '/'.",
+            name,
+            tags::MAX_TAG_LEN
+        ))
+    })
+}
+
+/// Tag a buffer and return all of its tags
+#[tauri::command]
+pub async fn add_tag(app: AppHandle, id: String, tag: String) -> AppResult<Vec<String>> {
+    track_blocking(app, "add_tag", move |_, state| {
+        let name = tag_name(&tag)?;
+        let conn = state.writer.lock();
+        if queries::get_slug(&conn, &id).db_context("Failed to get buffer")?.is_none() {
+            return Err(AppError::not_found("Buffer", id));
+        }
+        tags::add_tag(&conn, &id, &name, Utc::now().timestamp()).db_context("Failed to add tag")?;
+        info!("Tagged buffer {} with #{}", id, name);
+        tags::get_buffer_tags(&conn, &id).db_context("Failed to get tags")
+    })
+    .await
+}
+
+/// Remove a hand-assigned tag from a buffer and return its remaining tags
+/// (#hashtags in the content stay)
+#[tauri::command]
+pub async fn remove_tag(app: AppHandle, id: String, tag: String) -> AppResult<Vec<String>> {
+    track_blocking(app, "remove_tag", move |_, state| {
+        let name = tag_name(&tag)?;
+        let conn = state.writer.lock();
+        if !tags::remove_tag(&conn, &id, &name).db_context("Failed to remove tag")? {
+            return Err(AppError::not_found("Tag", format!("{} on {}", name, id)));
+        }
+        info!("Removed #{} from buffer {}", name, id);
+        tags::get_buffer_tags(&conn, &id).db_context("Failed to get tags")
+    })
+    .await
+}
+
+/// A buffer's tags, alphabetically
+#[tauri::command]
+pub async fn get_buffer_tags(app: AppHandle, id: String) -> AppResult<Vec<String>> {
+    track_blocking(app, "get_buffer_tags", move |_, state| {
+        state.read(|conn| tags::get_buffer_tags(conn, &id)).db_context("Failed to get tags")
+    })
+    .await
+}
+
+/// Every tag with its number of active notes, most used first
+#[tauri::command]
+pub async fn list_tags(app: AppHandle) -> AppResult<Vec<TagSummary>> {
+    track_blocking(app, "list_tags", move |_, state| state.read(tags::list_tags).db_context("Failed to list tags"))
+    .await
+}
+
+/// #hashtag usage across active notes: per-tag note counts and last use
+/// (for a tag cloud or finding stale tags) and co-occurring tag pairs
+#[tauri::command]
+pub async fn get_tag_stats(app: AppHandle) -> AppResult<TagStats> {
+    track_blocking(app, "get_tag_stats", move |_, state| {
+        match state.get_reader() {
+            Ok(conn) => tags::get_tag_stats(&conn),
+            Err(_) => {
+                let conn = state.writer.lock();
+                tags::get_tag_stats(&conn)
+            }
+        }
+        .db_context("Failed to compute tag stats")
+    })
+    .await
+}
+
+/// #hashtags written in active notes with usage counts, most used first
+/// (for a tag cloud or tag filter)
+#[tauri::command]
+pub async fn get_all_hashtags(app: AppHandle) -> AppResult<Vec<TagCount>> {
+    track_blocking(app, "get_all_hashtags", move |_, state| {
+        state.read(tags::get_all_hashtags).db_context("Failed to get hashtags")
+    })
+    .await
+}
@@ -0,0 +1,78 @@
+use crate::db::queries::{self, Buffer};
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::track_blocking;
+use crate::tasks;
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+use tauri::AppHandle;
+
+/// An open task with a due date
+#[derive(Debug, Serialize)]
+pub struct DueTask {
+    pub buffer_id: String,
+    /// Title of the note the task is in
+    pub title: String,
+    /// 0-based line in the note
+    pub line: usize,
+    pub text: String,
+    /// `YYYY-MM-DD`
+    pub due: String,
+    /// Due before today
+    pub overdue: bool,
+}
+
+fn due_tasks(buffers: Vec<Buffer>, before: NaiveDate, today: NaiveDate) -> Vec<DueTask> {
+    let mut due: Vec<(NaiveDate, DueTask)> = Vec::new();
+    for buffer in &buffers {
+        let title = buffer
+            .title_override
+            .clone()
+            .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+        for task in tasks::parse_tasks(&buffer.content).into_iter().filter(|t| !t.done) {
+            let Some(date) = tasks::due_date(task.text).filter(|d| *d <= before) else {
+                continue;
+            };
+            due.push((
+                date,
+                DueTask {
+                    buffer_id: buffer.id.clone(),
+                    title: title.clone(),
+                    line: task.line,
+                    text: task.text.to_string(),
+                    due: date.format("%Y-%m-%d").to_string(),
+                    overdue: date < today,
+                },
+            ));
+        }
+    }
+
+    due.sort_by(|(a, a_task), (b, b_task)| a.cmp(b).then_with(|| a_task.title.cmp(&b_task.title)));
+    due.into_iter().map(|(_, task)| task).collect()
+}
+
+/// Open tasks across all notes due on or before `before` (`YYYY-MM-DD`,
+/// default today), earliest first. Due dates are written inline as
+/// `@due(2024-05-01)` or `📅 2024-05-01`.
+#[tauri::command]
+pub async fn get_tasks_due(app: AppHandle, before: Option<String>) -> AppResult<Vec<DueTask>> {
+    track_blocking(app, "get_tasks_due", move |_, state| {
+        let today = Local::now().date_naive();
+        let before = match before.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+            Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| AppError::Validation(format!("Invalid date: {}", date)))?,
+            None => today,
+        };
+
+        let buffers = match state.get_reader() {
+            Ok(conn) => queries::get_buffers_with_due_dates(&conn),
+            Err(_) => {
+                let conn = state.writer.lock();
+                queries::get_buffers_with_due_dates(&conn)
+            }
+        }
+        .db_context("Failed to get buffers")?;
+
+        Ok(due_tasks(buffers, before, today))
+    })
+    .await
+}
@@ -0,0 +1,139 @@
+#[cfg(desktop)]
+use crate::db::{backup, queries};
+use crate::error::{AppError, AppResult};
+#[cfg(desktop)]
+use crate::state::AppState;
+use serde::Serialize;
+use tauri::AppHandle;
+#[cfg(desktop)]
+use tauri::{Manager, Url};
+#[cfg(desktop)]
+use tauri_plugin_updater::{Update, UpdaterExt};
+#[cfg(desktop)]
+use tracing::{info, warn};
+
+/// Release feed for the stable channel
+#[cfg(desktop)]
+const STABLE_ENDPOINT: &str =
+    "https://github.com/garyblankenship/flashnotes/releases/latest/download/latest.json";
+
+/// Release feed for the beta channel (rolling `beta` release tag)
+#[cfg(desktop)]
+const BETA_ENDPOINT: &str =
+    "https://github.com/garyblankenship/flashnotes/releases/download/beta/latest.json";
+
+/// Updater signing key, provided at build time for release builds
+#[cfg(desktop)]
+const UPDATER_PUBKEY: Option<&str> = option_env!("FLASHNOTES_UPDATER_PUBKEY");
+
+/// Available update details for the frontend
+#[derive(Debug, Serialize)]
+#[cfg_attr(mobile, allow(dead_code))]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub channel: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+}
+
+/// Resolve the configured update channel to its feed URL
+#[cfg(desktop)]
+fn channel_endpoint(channel: &str) -> &'static str {
+    match channel {
+        "beta" => BETA_ENDPOINT,
+        "stable" => STABLE_ENDPOINT,
+        other => {
+            warn!("Unknown update channel '{}', using stable", other);
+            STABLE_ENDPOINT
+        }
+    }
+}
+
+/// Read the update channel from settings
+#[cfg(desktop)]
+fn update_channel(state: &AppState) -> String {
+    state
+        .read(queries::get_settings)
+        .map(|s| s.update_channel)
+        .unwrap_or_else(|_| "stable".to_string())
+}
+
+/// Query the channel's release feed for a newer version
+#[cfg(desktop)]
+async fn fetch_update(app: &AppHandle, channel: &str) -> AppResult<Option<Update>> {
+    let pubkey = UPDATER_PUBKEY
+        .ok_or_else(|| AppError::Internal("Updates are not configured for this build".to_string()))?;
+
+    let endpoint = Url::parse(channel_endpoint(channel))
+        .map_err(|e| AppError::Internal(format!("Invalid update endpoint: {}", e)))?;
+
+    app.updater_builder()
+        .pubkey(pubkey)
+        .endpoints(vec![endpoint])
+        .map_err(|e| AppError::Internal(format!("Failed to configure updater: {}", e)))?
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to configure updater: {}", e)))?
+        .check()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to check for updates: {}", e)))
+}
+
+/// Check the configured channel for an available update
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> AppResult<Option<UpdateInfo>> {
+    let channel = update_channel(&app.state::<AppState>());
+    let update = fetch_update(&app, &channel).await?;
+
+    Ok(update.map(|u| UpdateInfo {
+        version: u.version.clone(),
+        current_version: u.current_version.clone(),
+        channel,
+        notes: u.body.clone(),
+        date: u.date.map(|d| d.to_string()),
+    }))
+}
+
+/// Back up the database, then download and install the available update.
+/// Restarts the app on success.
+#[cfg(desktop)]
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> AppResult<()> {
+    let channel = update_channel(&app.state::<AppState>());
+    let update = fetch_update(&app, &channel)
+        .await?
+        .ok_or_else(|| AppError::Conflict("No update available".to_string()))?;
+
+    // Snapshot the database before any version change so a bad migration
+    // in the new release can always be rolled back
+    {
+        let state = app.state::<AppState>();
+        let conn = state.writer.lock();
+        let path = backup::create_pre_update_backup(&conn, &state.app_data_dir, &update.current_version)?;
+        info!("Pre-update backup created: {:?}", path);
+    }
+
+    info!("Installing update {} -> {}", update.current_version, update.version);
+
+    update
+        .download_and_install(|_, _| {}, || {})
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to install update: {}", e)))?;
+
+    app.restart();
+}
+
+/// Updates on mobile go through the app stores
+#[cfg(mobile)]
+#[tauri::command]
+pub async fn check_for_updates(_app: AppHandle) -> AppResult<Option<UpdateInfo>> {
+    Err(AppError::Unsupported("In-app updates"))
+}
+
+/// Updates on mobile go through the app stores
+#[cfg(mobile)]
+#[tauri::command]
+pub async fn install_update(_app: AppHandle) -> AppResult<()> {
+    Err(AppError::Unsupported("In-app updates"))
+}
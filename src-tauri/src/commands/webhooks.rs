@@ -0,0 +1,105 @@
+use crate::db::webhooks::{self as db_webhooks, Webhook, WebhookEvent};
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::track_blocking;
+use crate::webhooks;
+use chrono::Utc;
+use tauri::AppHandle;
+use tracing::info;
+use uuid::Uuid;
+
+/// Register a webhook for note events. Payloads are signed with `secret`
+/// (generated when omitted); the returned webhook is the only place the
+/// secret is shown.
+#[tauri::command]
+pub async fn add_webhook(
+    app: AppHandle,
+    url: String,
+    events: Vec<WebhookEvent>,
+    secret: Option<String>,
+) -> AppResult<Webhook> {
+    track_blocking(app, "add_webhook", move |_, state| {
+        let url = url.trim().to_string();
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            return Err(AppError::Validation(format!("Webhook URL must be http(s): {}", url)));
+        }
+        if events.is_empty() {
+            return Err(AppError::Validation("Webhook needs at least one event".to_string()));
+        }
+
+        let mut unique_events = Vec::new();
+        for event in events {
+            if !unique_events.contains(&event) {
+                unique_events.push(event);
+            }
+        }
+
+        let webhook = Webhook {
+            id: Uuid::new_v4().to_string(),
+            url,
+            secret: secret
+                .filter(|s| !s.trim().is_empty())
+                .unwrap_or_else(webhooks::generate_secret),
+            events: unique_events,
+            enabled: true,
+            created_at: Utc::now().timestamp(),
+        };
+
+        let conn = state.writer.lock();
+        db_webhooks::insert_webhook(&conn, &webhook).db_context("Failed to save webhook")?;
+        state.webhooks.refresh(&conn);
+
+        info!("Added webhook {} for {}", webhook.id, webhook.url);
+        Ok(webhook)
+    })
+    .await
+}
+
+/// List webhooks (secrets are not included)
+#[tauri::command]
+pub async fn list_webhooks(app: AppHandle) -> AppResult<Vec<Webhook>> {
+    track_blocking(app, "list_webhooks", move |_, state| {
+        let webhooks = match state.get_reader() {
+            Ok(conn) => db_webhooks::list_webhooks(&conn),
+            Err(_) => {
+                let conn = state.writer.lock();
+                db_webhooks::list_webhooks(&conn)
+            }
+        }
+        .db_context("Failed to list webhooks")?;
+
+        Ok(webhooks
+            .into_iter()
+            .map(|w| Webhook { secret: String::new(), ..w })
+            .collect())
+    })
+    .await
+}
+
+/// Enable or disable a webhook without deleting it
+#[tauri::command]
+pub async fn set_webhook_enabled(app: AppHandle, id: String, enabled: bool) -> AppResult<()> {
+    track_blocking(app, "set_webhook_enabled", move |_, state| {
+        let conn = state.writer.lock();
+        if !db_webhooks::set_webhook_enabled(&conn, &id, enabled).db_context("Failed to update webhook")? {
+            return Err(AppError::not_found("Webhook", id));
+        }
+        state.webhooks.refresh(&conn);
+        Ok(())
+    })
+    .await
+}
+
+/// Remove a webhook
+#[tauri::command]
+pub async fn delete_webhook(app: AppHandle, id: String) -> AppResult<()> {
+    track_blocking(app, "delete_webhook", move |_, state| {
+        let conn = state.writer.lock();
+        if !db_webhooks::delete_webhook(&conn, &id).db_context("Failed to delete webhook")? {
+            return Err(AppError::not_found("Webhook", id));
+        }
+        state.webhooks.refresh(&conn);
+        info!("Deleted webhook {}", id);
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,254 @@
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension, Result, params};
+use serde::Serialize;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Saves of one buffer closer together than this share a log entry
+const SAVE_COALESCE_SECS: i64 = 5 * 60;
+
+/// Kind of operation recorded in the activity log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Create,
+    Save,
+    Rename,
+    Delete,
+    Archive,
+    Unarchive,
+    Trash,
+    Restore,
+    Pin,
+    Unpin,
+    Reorder,
+    Undo,
+    Redo,
+}
+
+impl Operation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Save => "save",
+            Self::Rename => "rename",
+            Self::Delete => "delete",
+            Self::Archive => "archive",
+            Self::Unarchive => "unarchive",
+            Self::Trash => "trash",
+            Self::Restore => "restore",
+            Self::Pin => "pin",
+            Self::Unpin => "unpin",
+            Self::Reorder => "reorder",
+            Self::Undo => "undo",
+            Self::Redo => "redo",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "create" => Some(Self::Create),
+            "save" => Some(Self::Save),
+            "rename" => Some(Self::Rename),
+            "delete" => Some(Self::Delete),
+            "archive" => Some(Self::Archive),
+            "unarchive" => Some(Self::Unarchive),
+            "trash" => Some(Self::Trash),
+            "restore" => Some(Self::Restore),
+            "pin" => Some(Self::Pin),
+            "unpin" => Some(Self::Unpin),
+            "reorder" => Some(Self::Reorder),
+            "undo" => Some(Self::Undo),
+            "redo" => Some(Self::Redo),
+            _ => None,
+        }
+    }
+}
+
+/// Where an operation came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Ui,
+    Cli,
+    DeepLink,
+    /// The local HTTP API
+    Api,
+    Sync,
+    Script,
+    Plugin,
+    /// Background jobs: cleanup, auto-archive, digests, inbox capture
+    System,
+}
+
+impl Origin {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ui => "ui",
+            Self::Cli => "cli",
+            Self::DeepLink => "deep_link",
+            Self::Api => "api",
+            Self::Sync => "sync",
+            Self::Script => "script",
+            Self::Plugin => "plugin",
+            Self::System => "system",
+        }
+    }
+}
+
+/// One activity log entry. `buffer_id` is None for operations on many
+/// buffers at once (reorder, undo, redo). Entries written by one bulk
+/// operation share a `batch_id`.
+#[derive(Debug, Serialize)]
+pub struct ActivityEntry {
+    pub id: i64,
+    pub buffer_id: Option<String>,
+    pub operation: String,
+    pub origin: String,
+    pub detail: Option<String>,
+    pub batch_id: Option<String>,
+    pub created_at: i64,
+}
+
+/// An entry to append
+pub struct NewEntry<'a> {
+    pub buffer_id: Option<&'a str>,
+    pub operation: Operation,
+    pub origin: Origin,
+    pub detail: Option<&'a str>,
+    pub batch_id: Option<&'a str>,
+    /// JSON needed to undo or redo the operation (reorder: old and new positions)
+    pub data: Option<&'a str>,
+}
+
+/// A logged operation as undo/redo sees it
+pub struct LoggedOperation {
+    pub id: i64,
+    pub buffer_id: Option<String>,
+    pub operation: Operation,
+    pub batch_id: Option<String>,
+    pub data: Option<String>,
+}
+
+/// Id shared by the entries of one bulk operation
+pub fn new_batch_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Append an entry. A save within `SAVE_COALESCE_SECS` of the buffer's
+/// previous logged save is skipped, so autosave doesn't flood the log.
+pub fn insert_entry(conn: &Connection, entry: &NewEntry, timestamp: i64) -> Result<()> {
+    let NewEntry { buffer_id, operation, origin, detail, batch_id, data } = *entry;
+    if operation == Operation::Save {
+        let last: Option<(String, i64)> = conn
+            .query_row(
+                "
+                SELECT operation, created_at FROM activity_log
+                WHERE buffer_id = ?
+                ORDER BY id DESC
+                LIMIT 1
+                ",
+                params![buffer_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        if matches!(last, Some((op, at)) if op == operation.as_str() && timestamp - at < SAVE_COALESCE_SECS) {
+            return Ok(());
+        }
+    }
+
+    conn.execute(
+        "
+        INSERT INTO activity_log (buffer_id, operation, origin, detail, batch_id, data, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ",
+        params![buffer_id, operation.as_str(), origin.as_str(), detail, batch_id, data, timestamp],
+    )?;
+    Ok(())
+}
+
+/// Append an entry now. Logging failures are only warned about: the
+/// operation itself already succeeded.
+pub fn log(conn: &Connection, entry: &NewEntry) {
+    if let Err(e) = insert_entry(conn, entry, Utc::now().timestamp()) {
+        warn!("Failed to record {} activity: {}", entry.operation.as_str(), e);
+    }
+}
+
+/// Record a single-buffer operation now
+pub fn record(conn: &Connection, buffer_id: Option<&str>, operation: Operation, origin: Origin, detail: Option<&str>) {
+    log(conn, &NewEntry { buffer_id, operation, origin, detail, batch_id: None, data: None });
+}
+
+/// Record the same operation on several buffers as one batch
+pub fn record_batch(conn: &Connection, ids: &[String], operation: Operation, origin: Origin, detail: Option<&str>) {
+    let batch_id = new_batch_id();
+    for id in ids {
+        log(
+            conn,
+            &NewEntry {
+                buffer_id: Some(id),
+                operation,
+                origin,
+                detail,
+                batch_id: Some(&batch_id),
+                data: None,
+            },
+        );
+    }
+}
+
+/// The latest `limit` entries with one of `operations`, oldest first
+pub fn get_operations(conn: &Connection, operations: &[Operation], limit: usize) -> Result<Vec<LoggedOperation>> {
+    // Operation names are fixed identifiers, safe to inline
+    let names: Vec<String> = operations.iter().map(|op| format!("'{}'", op.as_str())).collect();
+    let mut stmt = conn.prepare(&format!(
+        "
+        SELECT id, buffer_id, operation, batch_id, data
+        FROM activity_log
+        WHERE operation IN ({})
+        ORDER BY id DESC
+        LIMIT {}
+        ",
+        names.join(", "),
+        limit
+    ))?;
+
+    let rows = stmt.query_map([], |row| {
+        let operation: String = row.get(2)?;
+        Ok(LoggedOperation {
+            id: row.get(0)?,
+            buffer_id: row.get(1)?,
+            operation: Operation::parse(&operation).unwrap_or(Operation::Save),
+            batch_id: row.get(3)?,
+            data: row.get(4)?,
+        })
+    })?;
+    let mut operations = rows.collect::<Result<Vec<_>>>()?;
+    operations.reverse();
+    Ok(operations)
+}
+
+/// Latest entries, newest first: a buffer's history, or everything
+pub fn get_activity(conn: &Connection, buffer_id: Option<&str>, limit: usize) -> Result<Vec<ActivityEntry>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT id, buffer_id, operation, origin, detail, batch_id, created_at
+        FROM activity_log
+        WHERE ?1 IS NULL OR buffer_id = ?1
+        ORDER BY id DESC
+        LIMIT ?2
+        ",
+    )?;
+
+    let rows = stmt.query_map(params![buffer_id, limit as i64], |row| {
+        Ok(ActivityEntry {
+            id: row.get(0)?,
+            buffer_id: row.get(1)?,
+            operation: row.get(2)?,
+            origin: row.get(3)?,
+            detail: row.get(4)?,
+            batch_id: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    })?;
+    rows.collect()
+}
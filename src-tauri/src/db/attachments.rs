@@ -0,0 +1,165 @@
+use rusqlite::{Connection, OptionalExtension, Result, params};
+use serde::{Deserialize, Serialize};
+
+/// File attached to a buffer (text content is kept for search, not returned)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Attachment {
+    pub id: String,
+    pub buffer_id: String,
+    pub file_name: String,
+    pub stored_path: String,
+    pub size: i64,
+    pub has_text: bool,
+    pub created_at: i64,
+}
+
+/// Attachment search hit, tagged with its owning buffer
+#[derive(Debug)]
+pub struct AttachmentMatch {
+    pub buffer_id: String,
+    pub file_name: String,
+    pub snippet: String,
+    pub updated_at: i64,
+    pub rank: f64,
+}
+
+/// Escape attachment text for display and turn the FTS match markers
+/// (\x01 / \x02) into `<mark>` tags. Attachment text comes from arbitrary
+/// files, so it must never reach the frontend as raw HTML.
+fn highlight_html(snippet: &str) -> String {
+    let mut out = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            '\u{1}' => out.push_str("<mark>"),
+            '\u{2}' => out.push_str("</mark>"),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn row_to_attachment(row: &rusqlite::Row) -> Result<Attachment> {
+    Ok(Attachment {
+        id: row.get(0)?,
+        buffer_id: row.get(1)?,
+        file_name: row.get(2)?,
+        stored_path: row.get(3)?,
+        size: row.get(4)?,
+        has_text: row.get::<_, i64>(5)? != 0,
+        created_at: row.get(6)?,
+    })
+}
+
+/// Insert an attachment record
+pub fn insert_attachment(conn: &Connection, attachment: &Attachment, text_content: &str) -> Result<()> {
+    conn.execute(
+        "
+        INSERT INTO attachments (id, buffer_id, file_name, stored_path, size, text_content, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ",
+        params![
+            attachment.id,
+            attachment.buffer_id,
+            attachment.file_name,
+            attachment.stored_path,
+            attachment.size,
+            text_content,
+            attachment.created_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// List a buffer's attachments, oldest first
+pub fn list_attachments(conn: &Connection, buffer_id: &str) -> Result<Vec<Attachment>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT id, buffer_id, file_name, stored_path, size, text_content != '', created_at
+        FROM attachments
+        WHERE buffer_id = ?
+        ORDER BY created_at ASC
+        "
+    )?;
+
+    let rows = stmt.query_map([buffer_id], row_to_attachment)?;
+    rows.collect()
+}
+
+/// List every attachment, grouped by buffer and oldest first
+pub fn list_all_attachments(conn: &Connection) -> Result<Vec<Attachment>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT id, buffer_id, file_name, stored_path, size, text_content != '', created_at
+        FROM attachments
+        ORDER BY buffer_id, created_at ASC
+        "
+    )?;
+
+    let rows = stmt.query_map([], row_to_attachment)?;
+    rows.collect()
+}
+
+/// Get a single attachment
+pub fn get_attachment(conn: &Connection, id: &str) -> Result<Option<Attachment>> {
+    conn.query_row(
+        "
+        SELECT id, buffer_id, file_name, stored_path, size, text_content != '', created_at
+        FROM attachments
+        WHERE id = ?
+        ",
+        [id],
+        row_to_attachment,
+    )
+    .optional()
+}
+
+/// Delete an attachment record. Returns false if it doesn't exist.
+pub fn delete_attachment(conn: &Connection, id: &str) -> Result<bool> {
+    let rows_affected = conn.execute("DELETE FROM attachments WHERE id = ?", [id])?;
+    Ok(rows_affected > 0)
+}
+
+/// Stored file paths of a buffer's attachments (to remove files alongside the buffer)
+pub fn get_stored_paths(conn: &Connection, buffer_id: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT stored_path FROM attachments WHERE buffer_id = ?")?;
+    let rows = stmt.query_map([buffer_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Search attachment names and text of non-archived buffers.
+/// `query` must already be in FTS5 syntax and `source` filters the same way
+/// as in `queries::search_buffers`.
+pub fn search_attachments(conn: &Connection, query: &str, source: Option<&str>, limit: usize) -> Result<Vec<AttachmentMatch>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT a.buffer_id, a.file_name,
+               snippet(attachments_fts, 1, char(1), char(2), '…', 16) as snippet,
+               b.updated_at, attachments_fts.rank
+        FROM attachments_fts
+        JOIN attachments a ON attachments_fts.rowid = a.rowid
+        JOIN buffers b ON a.buffer_id = b.id
+        WHERE attachments_fts MATCH ?1
+        AND b.is_archived = 0 AND b.deleted_at IS NULL
+        AND (?2 IS NULL OR b.source = ?2 OR substr(b.source, 1, length(?2) + 1) = ?2 || ':')
+        ORDER BY rank
+        LIMIT ?3
+        "
+    )?;
+
+    let rows = stmt.query_map(params![query, source, limit as i64], |row| {
+        Ok(AttachmentMatch {
+            buffer_id: row.get(0)?,
+            file_name: row.get(1)?,
+            snippet: highlight_html(&row.get::<_, String>(2)?),
+            updated_at: row.get(3)?,
+            rank: row.get(4)?,
+        })
+    })?;
+
+    rows.collect()
+}
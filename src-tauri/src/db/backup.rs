@@ -1,11 +1,21 @@
-use rusqlite::Connection;
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
 use std::fs;
-use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracing::{info, warn};
 
-const MAX_BACKUPS: usize = 7;
-const BACKUP_INTERVAL_SECS: u64 = 24 * 60 * 60; // 24 hours
+/// A backup file as listed for restoring
+#[derive(Debug, Serialize)]
+pub struct BackupInfo {
+    pub path: String,
+    pub file_name: String,
+    /// daily, premigration, preupdate, prebatch, prerestore or prerotation
+    pub kind: String,
+    pub created_at: i64,
+    pub size: u64,
+}
 
 /// Get the backup directory path
 pub fn get_backup_dir(app_data_dir: &PathBuf) -> PathBuf {
@@ -36,8 +46,8 @@ fn get_last_backup_time(backup_dir: &PathBuf) -> u64 {
         .unwrap_or(0)
 }
 
-/// Check if backup is needed (more than 24h since last backup)
-pub fn needs_backup(app_data_dir: &PathBuf) -> bool {
+/// Check if backup is needed (more than `interval_secs` since last backup)
+pub fn needs_backup(app_data_dir: &PathBuf, interval_secs: u64) -> bool {
     let backup_dir = get_backup_dir(app_data_dir);
     let last_backup = get_last_backup_time(&backup_dir);
     let now = SystemTime::now()
@@ -45,11 +55,11 @@ pub fn needs_backup(app_data_dir: &PathBuf) -> bool {
         .unwrap_or_default()
         .as_secs();
 
-    now - last_backup >= BACKUP_INTERVAL_SECS
+    now.saturating_sub(last_backup) >= interval_secs
 }
 
-/// Create a backup using VACUUM INTO
-pub fn create_backup(conn: &Connection, app_data_dir: &PathBuf) -> Result<PathBuf, String> {
+/// Create a backup using VACUUM INTO, keeping the newest `keep` regular backups
+pub fn create_backup(conn: &Connection, app_data_dir: &PathBuf, keep: usize) -> Result<PathBuf, String> {
     let backup_dir = get_backup_dir(app_data_dir);
 
     // Ensure backup directory exists
@@ -62,23 +72,38 @@ pub fn create_backup(conn: &Connection, app_data_dir: &PathBuf) -> Result<PathBu
         .as_secs();
 
     let backup_path = backup_dir.join(format!("flashnotes_{}.db", timestamp));
-    let backup_path_str = backup_path.to_string_lossy();
 
-    info!("Creating database backup: {}", backup_path_str);
+    info!("Creating database backup: {:?}", backup_path);
 
-    conn.execute(&format!("VACUUM INTO '{}'", backup_path_str), [])
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
+    vacuum_into(conn, &backup_path).map_err(|e| format!("Failed to create backup: {}", e))?;
 
     info!("Backup created successfully");
 
     // Cleanup old backups
-    cleanup_old_backups(&backup_dir);
+    cleanup_old_backups(&backup_dir, keep);
 
     Ok(backup_path)
 }
 
-/// Remove backups older than MAX_BACKUPS
-fn cleanup_old_backups(backup_dir: &PathBuf) {
+/// Copy a backup into an external folder (e.g. a Dropbox or iCloud Drive
+/// directory), keeping the newest `keep` regular backups there as well
+pub fn copy_to_destination(backup_path: &Path, destination: &Path, keep: usize) -> Result<PathBuf, String> {
+    let file_name = backup_path
+        .file_name()
+        .ok_or_else(|| format!("Invalid backup path: {:?}", backup_path))?;
+    let target = destination.join(file_name);
+
+    info!("Copying backup to {:?}", target);
+
+    fs::copy(backup_path, &target)
+        .map_err(|e| format!("Failed to copy backup to {:?}: {}", destination, e))?;
+
+    cleanup_old_backups(destination, keep);
+    Ok(target)
+}
+
+/// Remove all but the newest `keep` regular backups
+fn cleanup_old_backups(backup_dir: &Path, keep: usize) {
     let mut backups: Vec<_> = fs::read_dir(backup_dir)
         .ok()
         .into_iter()
@@ -100,8 +125,8 @@ fn cleanup_old_backups(backup_dir: &PathBuf) {
     // Sort by timestamp descending (newest first)
     backups.sort_by(|a, b| b.1.cmp(&a.1));
 
-    // Remove backups beyond MAX_BACKUPS
-    for (path, _) in backups.into_iter().skip(MAX_BACKUPS) {
+    // Remove backups beyond the retention count
+    for (path, _) in backups.into_iter().skip(keep) {
         if let Err(e) = fs::remove_file(&path) {
             warn!("Failed to remove old backup {:?}: {}", path, e);
         } else {
@@ -110,9 +135,16 @@ fn cleanup_old_backups(backup_dir: &PathBuf) {
     }
 }
 
-/// Create pre-migration backup
-#[allow(dead_code)]
-pub fn create_migration_backup(conn: &Connection, app_data_dir: &PathBuf) -> Result<PathBuf, String> {
+/// Copy the database behind `conn` to `path` through SQLite, so the copy is
+/// consistent (WAL contents included)
+fn vacuum_into(conn: &Connection, path: &Path) -> rusqlite::Result<usize> {
+    let path = path.to_string_lossy().replace('\'', "''");
+    conn.execute(&format!("VACUUM INTO '{}'", path), [])
+}
+
+/// Take a snapshot named after its kind (e.g. `flashnotes_prebatch_<timestamp>.db`)
+/// before an operation that changes much at once. Snapshots are never rotated out.
+pub fn create_snapshot(conn: &Connection, app_data_dir: &PathBuf, kind: &str) -> Result<PathBuf, String> {
     let backup_dir = get_backup_dir(app_data_dir);
 
     fs::create_dir_all(&backup_dir)
@@ -123,13 +155,110 @@ pub fn create_migration_backup(conn: &Connection, app_data_dir: &PathBuf) -> Res
         .unwrap_or_default()
         .as_secs();
 
-    let backup_path = backup_dir.join(format!("flashnotes_premigration_{}.db", timestamp));
-    let backup_path_str = backup_path.to_string_lossy();
+    let backup_path = backup_dir.join(format!("flashnotes_{}_{}.db", kind, timestamp));
 
-    info!("Creating pre-migration backup: {}", backup_path_str);
+    info!("Creating {} backup: {:?}", kind, backup_path);
 
-    conn.execute(&format!("VACUUM INTO '{}'", backup_path_str), [])
-        .map_err(|e| format!("Failed to create migration backup: {}", e))?;
+    vacuum_into(conn, &backup_path).map_err(|e| format!("Failed to create {} backup: {}", kind, e))?;
 
     Ok(backup_path)
 }
+
+/// Create a snapshot before installing an app update, named with the version it replaces
+#[cfg(desktop)]
+pub fn create_pre_update_backup(conn: &Connection, app_data_dir: &PathBuf, version: &str) -> Result<PathBuf, String> {
+    // Version strings are semver (digits, dots, hyphens); keep the file name safe regardless
+    let version: String = version
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    create_snapshot(conn, app_data_dir, &format!("preupdate_{}", version))
+}
+
+/// Kind and timestamp from a backup file name, e.g. `flashnotes_prebatch_1700000000.db`
+fn parse_backup_name(name: &str) -> Option<(String, i64)> {
+    let stem = name.strip_prefix("flashnotes_")?.strip_suffix(".db")?;
+    let (kind, ts) = match stem.rsplit_once('_') {
+        // Pre-update backups carry the version between kind and timestamp
+        Some((kind, ts)) => (kind.split('_').next().unwrap_or(kind), ts),
+        None => ("daily", stem),
+    };
+    Some((kind.to_string(), ts.parse().ok()?))
+}
+
+/// All backups in the backup directory, newest first
+pub fn list_backups(app_data_dir: &PathBuf) -> Vec<BackupInfo> {
+    let mut backups: Vec<BackupInfo> = fs::read_dir(get_backup_dir(app_data_dir))
+        .ok()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let file_name = e.file_name().to_str()?.to_string();
+            let (kind, created_at) = parse_backup_name(&file_name)?;
+            let size = e.metadata().ok().filter(|m| m.is_file())?.len();
+            Some(BackupInfo {
+                path: e.path().to_string_lossy().to_string(),
+                file_name,
+                kind,
+                created_at,
+                size,
+            })
+        })
+        .collect();
+
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    backups
+}
+
+/// Open a backup read-only, with the passphrase when the database is encrypted
+/// (backups of an encrypted database are encrypted with the same key)
+fn open_backup(path: &Path, key: Option<&str>) -> Result<Connection, String> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open backup: {}", e))?;
+    if let Some(key) = key {
+        conn.pragma_update(None, "key", key)
+            .map_err(|e| format!("Failed to open backup: {}", e))?;
+    }
+    Ok(conn)
+}
+
+/// Check that a file is an intact Flashnotes database before restoring it
+pub fn validate_backup(path: &Path, key: Option<&str>) -> Result<(), String> {
+    let conn = open_backup(path, key)?;
+
+    let result: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| format!("Backup is not a readable database: {}", e))?;
+    if result != "ok" {
+        return Err(format!("Backup failed integrity check: {}", result));
+    }
+
+    let has_buffers: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'buffers')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to read backup: {}", e))?;
+    if !has_buffers {
+        return Err("Backup is not a Flashnotes database".to_string());
+    }
+    Ok(())
+}
+
+/// Replace the database behind `conn` with a backup. Uses SQLite's backup API,
+/// which copies every page in one write transaction: other connections see
+/// either the old or the restored database, never a mix.
+pub fn restore_backup(conn: &mut Connection, path: &Path, key: Option<&str>) -> Result<(), String> {
+    info!("Restoring database from backup: {:?}", path);
+
+    let source = open_backup(path, key)?;
+    // All pages in one step (-1), retried while readers hold locks
+    Backup::new(&source, conn)
+        .and_then(|backup| backup.run_to_completion(-1, Duration::from_millis(50), None))
+        .map_err(|e| format!("Failed to restore backup: {}", e))?;
+
+    info!("Backup restored successfully");
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use crate::cards::{self, Schedule};
+use rusqlite::{Connection, OptionalExtension, Result, params};
+use serde::{Deserialize, Serialize};
+
+/// Flashcard with its review schedule
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Card {
+    pub id: i64,
+    pub buffer_id: String,
+    pub question: String,
+    pub answer: String,
+    pub ease: f64,
+    pub interval_days: i64,
+    pub repetitions: i64,
+    pub due_at: i64,
+    pub last_reviewed_at: Option<i64>,
+}
+
+const CARD_COLUMNS: &str =
+    "c.id, c.buffer_id, c.question, c.answer, c.ease, c.interval_days, c.repetitions, c.due_at, c.last_reviewed_at";
+
+fn row_to_card(row: &rusqlite::Row) -> Result<Card> {
+    Ok(Card {
+        id: row.get(0)?,
+        buffer_id: row.get(1)?,
+        question: row.get(2)?,
+        answer: row.get(3)?,
+        ease: row.get(4)?,
+        interval_days: row.get(5)?,
+        repetitions: row.get(6)?,
+        due_at: row.get(7)?,
+        last_reviewed_at: row.get(8)?,
+    })
+}
+
+/// Bring a buffer's cards in line with its content. Cards are keyed by
+/// question, so editing an answer keeps the schedule; new questions are due
+/// immediately and removed ones are dropped.
+pub fn sync_buffer_cards(conn: &Connection, buffer_id: &str, content: &str, timestamp: i64) -> Result<()> {
+    let parsed = cards::parse_cards(content);
+
+    let mut existing: Vec<String> = {
+        let mut stmt = conn.prepare_cached("SELECT question FROM cards WHERE buffer_id = ?")?;
+        let rows = stmt.query_map([buffer_id], |row| row.get(0))?;
+        rows.collect::<Result<_>>()?
+    };
+    existing.retain(|q| !parsed.iter().any(|c| &c.question == q));
+    for question in existing {
+        conn.execute(
+            "DELETE FROM cards WHERE buffer_id = ? AND question = ?",
+            params![buffer_id, question],
+        )?;
+    }
+
+    for card in parsed {
+        conn.execute(
+            "
+            INSERT INTO cards (buffer_id, question, answer, ease, due_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(buffer_id, question) DO UPDATE SET answer = excluded.answer
+            ",
+            params![buffer_id, card.question, card.answer, cards::INITIAL_EASE, timestamp, timestamp],
+        )?;
+    }
+    Ok(())
+}
+
+/// Cards due at or before `now` from non-archived buffers, most overdue first
+pub fn get_due_cards(conn: &Connection, now: i64, limit: usize) -> Result<Vec<Card>> {
+    let mut stmt = conn.prepare(&format!(
+        "
+        SELECT {}
+        FROM cards c
+        JOIN buffers b ON c.buffer_id = b.id
+        WHERE c.due_at <= ?
+        AND b.is_archived = 0 AND b.deleted_at IS NULL
+        ORDER BY c.due_at ASC, c.id ASC
+        LIMIT ?
+        ",
+        CARD_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map(params![now, limit as i64], row_to_card)?;
+    rows.collect()
+}
+
+/// Get a single card
+pub fn get_card(conn: &Connection, id: i64) -> Result<Option<Card>> {
+    conn.query_row(
+        &format!("SELECT {} FROM cards c WHERE c.id = ?", CARD_COLUMNS),
+        [id],
+        row_to_card,
+    )
+    .optional()
+}
+
+/// Store a card's new schedule after a review
+pub fn update_schedule(conn: &Connection, id: i64, schedule: Schedule, due_at: i64, reviewed_at: i64) -> Result<bool> {
+    let rows_affected = conn.execute(
+        "
+        UPDATE cards
+        SET ease = ?, interval_days = ?, repetitions = ?, due_at = ?, last_reviewed_at = ?
+        WHERE id = ?
+        ",
+        params![schedule.ease, schedule.interval_days, schedule.repetitions, due_at, reviewed_at, id],
+    )?;
+    Ok(rows_affected > 0)
+}
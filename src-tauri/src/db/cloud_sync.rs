@@ -0,0 +1,73 @@
+use rusqlite::{Connection, OptionalExtension, Result, params};
+
+/// A buffer mirrored to a cloud record, with the state both sides had when
+/// they were last in sync
+#[derive(Debug, Clone)]
+pub struct CloudRecord {
+    pub buffer_id: String,
+    pub content_hash: String,
+    /// Buffer `updated_at` at the last sync
+    pub buffer_updated_at: i64,
+    /// The record's server state as of the last sync (opaque to us)
+    pub system_fields: Option<Vec<u8>>,
+}
+
+/// Every buffer-to-record mapping
+pub fn get_cloud_records(conn: &Connection) -> Result<Vec<CloudRecord>> {
+    let mut stmt =
+        conn.prepare("SELECT buffer_id, content_hash, buffer_updated_at, system_fields FROM cloud_records")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(CloudRecord {
+            buffer_id: row.get(0)?,
+            content_hash: row.get(1)?,
+            buffer_updated_at: row.get(2)?,
+            system_fields: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Record that a buffer and its cloud record are in sync
+pub fn upsert_cloud_record(conn: &Connection, record: &CloudRecord) -> Result<()> {
+    conn.execute(
+        "
+        INSERT INTO cloud_records (buffer_id, content_hash, buffer_updated_at, system_fields)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT(buffer_id) DO UPDATE SET
+            content_hash = excluded.content_hash,
+            buffer_updated_at = excluded.buffer_updated_at,
+            system_fields = excluded.system_fields
+        ",
+        params![record.buffer_id, record.content_hash, record.buffer_updated_at, record.system_fields],
+    )?;
+    Ok(())
+}
+
+/// Forget a buffer's cloud record
+pub fn delete_cloud_record(conn: &Connection, buffer_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM cloud_records WHERE buffer_id = ?", params![buffer_id])?;
+    Ok(())
+}
+
+/// The server change token of the last pass that applied every remote change
+pub fn get_change_token(conn: &Connection) -> Result<Option<Vec<u8>>> {
+    conn.query_row("SELECT change_token FROM cloud_sync_state WHERE id = 1", [], |row| row.get(0))
+        .optional()
+        .map(Option::flatten)
+}
+
+pub fn set_change_token(conn: &Connection, token: Option<&[u8]>) -> Result<()> {
+    conn.execute(
+        "
+        INSERT INTO cloud_sync_state (id, change_token) VALUES (1, ?)
+        ON CONFLICT(id) DO UPDATE SET change_token = excluded.change_token
+        ",
+        params![token],
+    )?;
+    Ok(())
+}
+
+/// Forget all mappings and the change token, so the next pass starts over
+pub fn clear(conn: &Connection) -> Result<()> {
+    conn.execute_batch("DELETE FROM cloud_records; DELETE FROM cloud_sync_state;")
+}
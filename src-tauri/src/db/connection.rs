@@ -1,10 +1,13 @@
+use crate::db::queries;
+use crate::metrics;
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::{Connection, Result};
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tauri::{AppHandle, Manager};
-use tracing::error;
+use tracing::{error, warn};
 
 /// Database initialization error
 #[derive(Debug)]
@@ -21,7 +24,37 @@ impl std::fmt::Display for DbInitError {
 
 impl std::error::Error for DbInitError {}
 
-/// Get the database path in the app's data directory
+/// File name of the database, in the app data directory or a custom folder
+pub const DB_FILE_NAME: &str = "flashnotes.db";
+
+/// File in the app data directory naming a custom database folder. Kept
+/// outside the database, which has to be found before it can be opened.
+const LOCATION_FILE: &str = "database_location";
+
+/// The custom database folder, if one is set
+pub fn custom_db_dir(app_data_dir: &Path) -> Option<PathBuf> {
+    let dir = fs::read_to_string(app_data_dir.join(LOCATION_FILE)).ok()?;
+    let dir = dir.trim();
+    (!dir.is_empty()).then(|| PathBuf::from(dir))
+}
+
+/// Remember a custom database folder, or go back to the app data directory with None
+pub fn set_custom_db_dir(app_data_dir: &Path, dir: Option<&Path>) -> std::io::Result<()> {
+    let location = app_data_dir.join(LOCATION_FILE);
+    let Some(dir) = dir else {
+        return match fs::remove_file(&location) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    };
+    // Written next to the final file and renamed so it is never half-written
+    let staged = location.with_extension("tmp");
+    fs::write(&staged, dir.to_string_lossy().as_bytes())?;
+    fs::rename(&staged, &location)
+}
+
+/// Get the database path: in the custom database folder when one is set,
+/// otherwise in the app's data directory
 pub fn get_db_path(app: &AppHandle) -> std::result::Result<PathBuf, DbInitError> {
     let app_data_dir = app
         .path()
@@ -37,7 +70,18 @@ pub fn get_db_path(app: &AppHandle) -> std::result::Result<PathBuf, DbInitError>
         details: e.to_string(),
     })?;
 
-    Ok(app_data_dir.join("flashnotes.db"))
+    if let Some(dir) = custom_db_dir(&app_data_dir) {
+        // Don't start over with an empty database while e.g. a synced drive is missing
+        if !dir.is_dir() {
+            return Err(DbInitError {
+                message: "Database folder is not available".to_string(),
+                details: dir.to_string_lossy().to_string(),
+            });
+        }
+        return Ok(dir.join(DB_FILE_NAME));
+    }
+
+    Ok(app_data_dir.join(DB_FILE_NAME))
 }
 
 /// Get app data directory
@@ -50,9 +94,15 @@ pub fn get_app_data_dir(app: &AppHandle) -> std::result::Result<PathBuf, DbInitE
         })
 }
 
-/// Create a new database connection with optimized settings
-pub fn create_connection(path: &PathBuf) -> Result<Connection> {
-    let conn = Connection::open(path)?;
+/// Create a new database connection with optimized settings. `key` is the
+/// passphrase of an encrypted (SQLCipher) database; a wrong one fails here.
+pub fn create_connection(path: &PathBuf, key: Option<&str>) -> Result<Connection> {
+    let mut conn = Connection::open(path)?;
+    // Must come before anything reads the file
+    if let Some(key) = key {
+        conn.pragma_update(None, "key", key)?;
+    }
+    conn.profile(Some(metrics::profile_query));
 
     // Critical PRAGMA settings for performance and FTS triggers
     conn.execute_batch(
@@ -68,11 +118,89 @@ pub fn create_connection(path: &PathBuf) -> Result<Connection> {
         ",
     )?;
 
+    // Per connection, so every reopened writer (unlock, rekey, move) applies it again
+    if let Err(e) = apply_secure_delete(&conn) {
+        warn!("Failed to apply secure deletion: {}", e);
+    }
+
     Ok(conn)
 }
 
-/// Create a connection for development/testing with in-memory database
-#[allow(dead_code)]
+/// Turn SQLite secure deletion on or off for a connection (deleted content
+/// is overwritten with zeros). Turning it on also moves the database to
+/// incremental auto-vacuum, which takes a one-off VACUUM that rewrites the
+/// file without any previously freed pages.
+pub fn set_secure_delete(conn: &Connection, enabled: bool) -> Result<()> {
+    conn.execute_batch(if enabled { "PRAGMA secure_delete = ON;" } else { "PRAGMA secure_delete = OFF;" })?;
+    if !enabled {
+        return Ok(());
+    }
+
+    let auto_vacuum: i64 = conn.query_row("PRAGMA auto_vacuum", [], |row| row.get(0))?;
+    if auto_vacuum != 2 {
+        conn.execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM;")?;
+    }
+    scrub(conn)
+}
+
+/// Apply the `secure_delete` setting stored in the database behind `conn`
+/// (off for a fresh database, which has no settings yet)
+pub fn apply_secure_delete(conn: &Connection) -> Result<()> {
+    let enabled = queries::get_setting(conn, "secure_delete").ok().flatten();
+    set_secure_delete(conn, enabled.as_deref() == Some("true"))
+}
+
+/// Release freed pages and checkpoint the WAL, so deleted content lingers in neither
+fn scrub(conn: &Connection) -> Result<()> {
+    conn.execute_batch("PRAGMA incremental_vacuum; PRAGMA wal_checkpoint(TRUNCATE);")
+}
+
+/// Call after deleting notes or attachments. With secure deletion on, scrubs
+/// the freed space (failures are logged, the deletion itself already happened).
+pub fn scrub_after_delete(conn: &Connection) {
+    let result = conn
+        .query_row("PRAGMA secure_delete", [], |row| row.get::<_, i64>(0))
+        .and_then(|enabled| if enabled != 0 { scrub(conn) } else { Ok(()) });
+    if let Err(e) = result {
+        warn!("Failed to scrub deleted content: {}", e);
+    }
+}
+
+/// Path of a file SQLite keeps next to the database, e.g. `flashnotes.db-wal`
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Rename a closed database together with its `-wal` and `-shm` files. A WAL
+/// left at `to` by an older file is removed so it can't be replayed into this one.
+pub fn rename_database(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::rename(from, to)?;
+    for suffix in ["-wal", "-shm"] {
+        let (source, target) = (sidecar(from, suffix), sidecar(to, suffix));
+        if source.exists() {
+            fs::rename(&source, &target)?;
+        } else if let Err(e) = fs::remove_file(&target) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Delete a closed database and its `-wal` and `-shm` files
+pub fn remove_database(path: &Path) -> std::io::Result<()> {
+    fs::remove_file(path)?;
+    for suffix in ["-wal", "-shm"] {
+        let _ = fs::remove_file(sidecar(path, suffix));
+    }
+    Ok(())
+}
+
+/// Create a connection for development/testing with in-memory database, also
+/// the stand-in writer until the database is opened
 pub fn create_memory_connection() -> Result<Connection> {
     let conn = Connection::open_in_memory()?;
 
@@ -87,10 +215,18 @@ pub fn create_memory_connection() -> Result<Connection> {
     Ok(conn)
 }
 
-/// Create a connection pool for read operations
-pub fn create_reader_pool(path: &PathBuf) -> std::result::Result<Pool<SqliteConnectionManager>, DbInitError> {
+/// Create a connection pool for read operations (`key` as for `create_connection`)
+pub fn create_reader_pool(
+    path: &PathBuf,
+    key: Option<&str>,
+) -> std::result::Result<Pool<SqliteConnectionManager>, DbInitError> {
+    let key = key.map(str::to_string);
     let manager = SqliteConnectionManager::file(path)
-        .with_init(|conn| {
+        .with_init(move |conn| {
+            if let Some(key) = &key {
+                conn.pragma_update(None, "key", key)?;
+            }
+            conn.profile(Some(metrics::profile_query));
             conn.execute_batch(
                 "
                 PRAGMA trusted_schema = ON;
@@ -119,3 +255,16 @@ pub fn create_reader_pool(path: &PathBuf) -> std::result::Result<Pool<SqliteConn
             }
         })
 }
+
+/// Stand-in pool until startup opens the database (or while an encrypted
+/// one is locked); nothing reads from it because commands wait until then
+pub fn create_placeholder_pool() -> std::result::Result<Pool<SqliteConnectionManager>, DbInitError> {
+    Pool::builder()
+        .max_size(1)
+        .min_idle(Some(0))
+        .build(SqliteConnectionManager::memory())
+        .map_err(|e| DbInitError {
+            message: "Failed to create database connection pool".to_string(),
+            details: e.to_string(),
+        })
+}
@@ -0,0 +1,102 @@
+use rusqlite::{Connection, OptionalExtension, Result, params};
+
+/// Buffer whose embedding may be out of date: none stored, from another
+/// model, or older than the buffer's last edit
+pub struct EmbeddingCandidate {
+    pub buffer_id: String,
+    pub content: String,
+    /// Hash of the content the stored vector was made from
+    pub embedded_hash: Option<String>,
+}
+
+/// Active buffers that may need (re-)embedding with `model`
+pub fn get_candidates(conn: &Connection, model: &str) -> Result<Vec<EmbeddingCandidate>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT b.id, b.content, e.content_hash
+        FROM buffers b
+        LEFT JOIN embeddings e ON e.buffer_id = b.id
+        WHERE b.is_archived = 0 AND b.deleted_at IS NULL
+          AND (e.buffer_id IS NULL OR e.model != ? OR e.embedded_at < b.updated_at)
+        ORDER BY b.updated_at DESC
+        ",
+    )?;
+
+    let rows = stmt.query_map(params![model], |row| {
+        Ok(EmbeddingCandidate {
+            buffer_id: row.get(0)?,
+            content: row.get(1)?,
+            embedded_hash: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Model of a buffer's stored vector, if it has one
+pub fn get_model(conn: &Connection, buffer_id: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT model FROM embeddings WHERE buffer_id = ?",
+        params![buffer_id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Stored vectors made with `model`, for active buffers
+pub fn get_vectors(conn: &Connection, model: &str) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT e.buffer_id, e.vector
+        FROM embeddings e
+        JOIN buffers b ON b.id = e.buffer_id
+        WHERE b.is_archived = 0 AND b.deleted_at IS NULL AND e.model = ?
+        ",
+    )?;
+
+    let rows = stmt.query_map(params![model], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+/// Store a buffer's vector, replacing any previous one
+pub fn upsert_embedding(
+    conn: &Connection,
+    buffer_id: &str,
+    content_hash: &str,
+    model: &str,
+    vector: &[u8],
+    timestamp: i64,
+) -> Result<()> {
+    conn.execute(
+        "
+        INSERT INTO embeddings (buffer_id, content_hash, model, vector, embedded_at)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT(buffer_id) DO UPDATE SET
+            content_hash = excluded.content_hash,
+            model = excluded.model,
+            vector = excluded.vector,
+            embedded_at = excluded.embedded_at
+        ",
+        params![buffer_id, content_hash, model, vector, timestamp],
+    )?;
+    Ok(())
+}
+
+/// Mark a buffer's vector current without re-embedding (content unchanged)
+pub fn touch_embedding(conn: &Connection, buffer_id: &str, timestamp: i64) -> Result<()> {
+    conn.execute(
+        "UPDATE embeddings SET embedded_at = ? WHERE buffer_id = ?",
+        params![timestamp, buffer_id],
+    )?;
+    Ok(())
+}
+
+/// Drop a buffer's vector (e.g. it was emptied)
+pub fn delete_embedding(conn: &Connection, buffer_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM embeddings WHERE buffer_id = ?", params![buffer_id])?;
+    Ok(())
+}
+
+/// Drop every vector, forcing a full rebuild
+pub fn clear_embeddings(conn: &Connection) -> Result<usize> {
+    conn.execute("DELETE FROM embeddings", [])
+}
@@ -0,0 +1,77 @@
+use rusqlite::{Connection, ErrorCode, Result, params};
+
+/// Rows re-indexed per batch during a rebuild (progress is reported per batch)
+const REBUILD_BATCH_SIZE: i64 = 500;
+
+/// Rebuild the `buffers_fts` index from the `buffers` table.
+/// Equivalent to FTS5's `rebuild` command, but done in batches so progress can be
+/// reported as `(indexed, total)`. Runs in one transaction: readers keep seeing
+/// the old index until the rebuild commits.
+pub fn rebuild_index(conn: &mut Connection, mut progress: impl FnMut(usize, usize)) -> Result<usize> {
+    let tx = conn.transaction()?;
+
+    let total: i64 = tx.query_row("SELECT COUNT(*) FROM buffers", [], |row| row.get(0))?;
+    let total = total as usize;
+    progress(0, total);
+
+    tx.execute("INSERT INTO buffers_fts(buffers_fts) VALUES('delete-all')", [])?;
+
+    let mut indexed = 0;
+    let mut last_rowid: i64 = 0;
+    loop {
+        let max_rowid: Option<i64> = tx.query_row(
+            "
+            SELECT MAX(rowid) FROM (
+                SELECT rowid FROM buffers WHERE rowid > ? ORDER BY rowid LIMIT ?
+            )
+            ",
+            params![last_rowid, REBUILD_BATCH_SIZE],
+            |row| row.get(0),
+        )?;
+        let Some(max_rowid) = max_rowid else {
+            break;
+        };
+
+        indexed += tx.execute(
+            "
+            INSERT INTO buffers_fts(rowid, content)
+            SELECT rowid, content FROM buffers WHERE rowid > ? AND rowid <= ?
+            ",
+            params![last_rowid, max_rowid],
+        )?;
+        last_rowid = max_rowid;
+        progress(indexed, total);
+    }
+
+    // Attachment text and tag names are small enough to rebuild in one step
+    tx.execute("INSERT INTO attachments_fts(attachments_fts) VALUES('rebuild')", [])?;
+    tx.execute("INSERT INTO tags_fts(tags_fts) VALUES('rebuild')", [])?;
+
+    tx.commit()?;
+    Ok(indexed)
+}
+
+/// Check the search index against the buffers table. Returns false when
+/// the index is damaged or out of sync (rebuild it with `rebuild_index`).
+pub fn verify(conn: &Connection) -> Result<bool> {
+    match conn.execute("INSERT INTO buffers_fts(buffers_fts) VALUES('integrity-check')", []) {
+        Ok(_) => Ok(true),
+        Err(e) if matches!(e.sqlite_error_code(), Some(ErrorCode::DatabaseCorrupt)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Incrementally merge index segments, doing at most `pages` pages of work
+pub fn merge(conn: &Connection, pages: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO buffers_fts(buffers_fts, rank) VALUES('merge', ?)",
+        params![pages],
+    )?;
+    Ok(())
+}
+
+/// Merge all index segments into one (slower, best search performance)
+pub fn optimize(conn: &Connection) -> Result<()> {
+    conn.execute("INSERT INTO buffers_fts(buffers_fts) VALUES('optimize')", [])?;
+    Ok(())
+}
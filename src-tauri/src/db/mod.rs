@@ -1,4 +1,16 @@
+pub mod activity;
+pub mod attachments;
 pub mod backup;
+pub mod cards;
+pub mod cloud_sync;
 pub mod connection;
+pub mod embeddings;
+pub mod fts;
 pub mod schema;
 pub mod queries;
+pub mod recovery;
+pub mod revisions;
+pub mod scripts;
+pub mod sync;
+pub mod tags;
+pub mod webhooks;
@@ -1,15 +1,27 @@
-use rusqlite::{Connection, Result, params};
+use super::{attachments, cards, embeddings, revisions, sync, tags};
+use crate::metrics::CommandStats;
+use crate::tasks;
+use rusqlite::{Connection, OptionalExtension, Result, params};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use tracing::warn;
 
 /// Summary of a buffer for sidebar display
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BufferSummary {
     pub id: String,
     pub title: String,
     pub preview: String,
     pub updated_at: i64,
     pub is_pinned: bool,
+    pub word_count: i64,
+    /// Unchecked and total Markdown checkboxes
+    pub tasks_open: i64,
+    pub tasks_total: i64,
+    pub has_title_override: bool,
+    /// Content is encrypted (see `note_lock`): the title is "Untitled" unless
+    /// renamed and the preview is empty
+    pub is_locked: bool,
 }
 
 /// Search result with highlighted snippet
@@ -18,6 +30,10 @@ pub struct SearchResult {
     pub id: String,
     pub snippet: String,
     pub updated_at: i64,
+    /// Set when the match is in one of the buffer's attachments
+    pub attachment_name: Option<String>,
+    /// Set when the match is one of the buffer's tags
+    pub tag: Option<String>,
 }
 
 /// Full buffer content
@@ -30,29 +46,218 @@ pub struct Buffer {
     pub accessed_at: i64,
     pub is_archived: bool,
     pub is_pinned: bool,
+    pub archived_at: Option<i64>,
+    pub title_override: Option<String>,
+    pub word_count: i64,
+    pub slug: String,
+    /// How the note was created, e.g. "manual", "clipboard", "import:notes.md"
+    pub source: String,
+    /// Content is encrypted and `content` is empty until the note is unlocked
+    #[serde(default)]
+    pub is_locked: bool,
 }
 
-/// Extract title and preview from content
+/// Maximum length of extracted title and preview
+pub const TITLE_PREVIEW_MAX_CHARS: usize = 100;
+
+/// Columns read into a full `Buffer`, in `row_to_buffer` order
+const BUFFER_COLUMNS: &str = "id, content, created_at, updated_at, accessed_at, is_archived, is_pinned, \
+                              archived_at, title_override, word_count, slug, source, is_locked";
+
+/// Columns read into a `BufferSummary`, in `row_to_summary` order
+/// (`title` and `preview` are stored on save, so summaries never read `content`)
+const SUMMARY_COLUMNS: &str =
+    "id, title, preview, updated_at, is_pinned, word_count, title_override, tasks_open, tasks_total, is_locked";
+
+/// Maximum length of a buffer slug
+const SLUG_MAX_CHARS: usize = 80;
+
+/// Strip markdown syntax from a single line: heading markers, blockquotes,
+/// list bullets and task boxes, emphasis, inline code, images and link syntax
+fn strip_markdown_line(line: &str) -> String {
+    let mut rest = line.trim();
+
+    // Block-level prefixes (may be stacked, e.g. "> - [ ] item")
+    loop {
+        let before = rest;
+        rest = rest.trim_start_matches('#').trim_start();
+        rest = rest.strip_prefix('>').unwrap_or(rest).trim_start();
+        for bullet in ["- ", "* ", "+ "] {
+            rest = rest.strip_prefix(bullet).unwrap_or(rest);
+        }
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 {
+            if let Some(after) = rest[digits..].strip_prefix(". ").or_else(|| rest[digits..].strip_prefix(") ")) {
+                rest = after;
+            }
+        }
+        for task in ["[ ] ", "[x] ", "[X] "] {
+            rest = rest.strip_prefix(task).unwrap_or(rest);
+        }
+        rest = rest.trim_start();
+        if rest == before {
+            break;
+        }
+    }
+
+    // Inline syntax: keep link/image text, drop URLs and emphasis markers
+    let mut out = String::with_capacity(rest.len());
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // Underscores inside words (snake_case) are not emphasis
+            '_' if out.ends_with(|p: char| p.is_alphanumeric())
+                && chars.peek().is_some_and(|n| n.is_alphanumeric()) => out.push(c),
+            '~' if chars.peek() == Some(&'~') => {
+                chars.next();
+            }
+            '*' | '_' | '`' => {}
+            '!' if chars.peek() == Some(&'[') => {
+                // Images carry no readable text: drop "![alt](url)" entirely
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                }
+                if chars.peek() == Some(&'(') {
+                    for c in chars.by_ref() {
+                        if c == ')' {
+                            break;
+                        }
+                    }
+                }
+            }
+            ']' if chars.peek() == Some(&'(') => {
+                // Skip the "(url)" part
+                for c in chars.by_ref() {
+                    if c == ')' {
+                        break;
+                    }
+                }
+            }
+            '[' | ']' => {}
+            _ => out.push(c),
+        }
+    }
+
+    out.trim().to_string()
+}
+
+/// Lines of readable text: skips front matter, fenced code blocks,
+/// horizontal rules and lines that are nothing but markdown syntax
+fn text_lines(content: &str) -> impl Iterator<Item = String> + '_ {
+    let mut lines = content.lines().peekable();
+
+    // Front matter only counts at the very start of the note
+    if lines.peek().is_some_and(|l| l.trim() == "---") {
+        lines.next();
+        let mut front_matter = lines.clone();
+        if front_matter.any(|l| matches!(l.trim(), "---" | "...")) {
+            lines = front_matter;
+        } else {
+            // Unterminated: it was a horizontal rule, not front matter
+            lines = content.lines().peekable();
+            lines.next();
+        }
+    }
+
+    let mut fence: Option<&str> = None;
+    lines.filter_map(move |line| {
+        let trimmed = line.trim();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            return None;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            return None;
+        }
+        if trimmed.len() >= 3 && trimmed.chars().all(|c| matches!(c, '-' | '*' | '_' | ' ')) {
+            return None;
+        }
+
+        let text = strip_markdown_line(line);
+        (!text.is_empty()).then_some(text)
+    })
+}
+
+/// Extract title and preview from content, ignoring markdown syntax
 pub fn extract_title_preview(content: &str) -> (String, String) {
-    let lines: Vec<&str> = content.lines().collect();
+    let mut lines = text_lines(content);
+    let truncate = |s: String| s.chars().take(TITLE_PREVIEW_MAX_CHARS).collect::<String>();
 
     let title = lines
-        .iter()
-        .find(|line| !line.trim().is_empty())
-        .map(|s| s.trim().chars().take(100).collect())
+        .next()
+        .map(truncate)
         .unwrap_or_else(|| "Untitled".to_string());
-
-    let preview = lines
-        .iter()
-        .skip_while(|line| line.trim().is_empty())
-        .skip(1) // Skip title line
-        .find(|line| !line.trim().is_empty())
-        .map(|s| s.trim().chars().take(100).collect())
-        .unwrap_or_default();
+    let preview = lines.next().map(truncate).unwrap_or_default();
 
     (title, preview)
 }
 
+/// URL/file-safe slug for a title: lowercase alphanumeric runs joined by
+/// hyphens (e.g. "Meeting Notes: Q3!" -> "meeting-notes-q3")
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug: String = slug.chars().take(SLUG_MAX_CHARS).collect();
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "untitled".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Count whitespace-separated words in content
+pub fn count_words(content: &str) -> i64 {
+    content.split_whitespace().count() as i64
+}
+
+/// `#hashtags` in content, lowercased and deduplicated in order of appearance.
+/// A tag starts after whitespace (or at the start of a line) and runs over
+/// letters, digits, `-`, `_` and `/`; it needs at least one letter, so
+/// headings and `#1` don't count. Fenced code is skipped.
+pub fn extract_hashtags(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        for word in line.split_whitespace() {
+            let Some(rest) = word.strip_prefix('#') else {
+                continue;
+            };
+            let tag: String = rest
+                .chars()
+                .take_while(|&c| c.is_alphanumeric() || c == '-' || c == '_' || c == '/')
+                .flat_map(char::to_lowercase)
+                .collect();
+            let tag = tag.trim_end_matches(['-', '/']);
+            if tag.chars().any(char::is_alphabetic) && !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+    tags
+}
+
 /// Sanitize and validate search query for FTS5
 /// Returns None if the query contains suspicious characters
 pub fn sanitize_search_query(query: &str) -> Option<String> {
@@ -81,41 +286,146 @@ pub fn sanitize_search_query(query: &str) -> Option<String> {
     Some(trimmed.to_string())
 }
 
+/// Build a sidebar summary from a row of `SUMMARY_COLUMNS`
+fn row_to_summary(row: &rusqlite::Row) -> Result<BufferSummary> {
+    let id: String = row.get(0)?;
+    let extracted_title: String = row.get(1)?;
+    let preview: String = row.get(2)?;
+    let updated_at: i64 = row.get(3)?;
+    let is_pinned: i64 = row.get(4)?;
+    let word_count: i64 = row.get(5)?;
+    let title_override: Option<String> = row.get(6)?;
+    let tasks_open: i64 = row.get(7)?;
+    let tasks_total: i64 = row.get(8)?;
+    let is_locked: i64 = row.get(9)?;
+
+    Ok(BufferSummary {
+        id,
+        title: title_override.clone().unwrap_or(extracted_title),
+        preview,
+        updated_at,
+        is_pinned: is_pinned != 0,
+        word_count,
+        tasks_open,
+        tasks_total,
+        has_title_override: title_override.is_some(),
+        is_locked: is_locked != 0,
+    })
+}
+
 /// Get sidebar buffers (non-archived, sorted by pinned then sort_order then accessed_at)
 pub fn get_sidebar_buffers(conn: &Connection, limit: usize, offset: usize) -> Result<Vec<BufferSummary>> {
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare(&format!(
         "
-        SELECT id, content, updated_at, is_pinned
+        SELECT {}
         FROM buffers
-        WHERE is_archived = 0
+        WHERE is_archived = 0 AND deleted_at IS NULL
         ORDER BY is_pinned DESC, sort_order ASC, accessed_at DESC
         LIMIT ? OFFSET ?
+        ",
+        SUMMARY_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map([limit as i64, offset as i64], row_to_summary)?;
+
+    rows.collect()
+}
+
+/// Number of buffers in the sidebar, or of those carrying the tag `tag` (normalized)
+pub fn count_sidebar_buffers(conn: &Connection, tag: Option<&str>) -> Result<i64> {
+    match tag {
+        Some(tag) => conn.query_row(
+            "
+            SELECT COUNT(*) FROM buffers
+            WHERE is_archived = 0 AND deleted_at IS NULL
+            AND id IN (
+                SELECT bt.buffer_id FROM buffer_tags bt
+                JOIN tags t ON t.id = bt.tag_id
+                WHERE t.name = ?
+            )
+            ",
+            [tag],
+            |row| row.get(0),
+        ),
+        None => conn.query_row(
+            "SELECT COUNT(*) FROM buffers WHERE is_archived = 0 AND deleted_at IS NULL",
+            [],
+            |row| row.get(0),
+        ),
+    }
+}
+
+/// Sidebar buffers carrying the tag `tag` (normalized), in sidebar order
+pub fn get_sidebar_buffers_by_tag(
+    conn: &Connection,
+    tag: &str,
+    limit: usize,
+    offset: usize,
+) -> Result<Vec<BufferSummary>> {
+    let mut stmt = conn.prepare(&format!(
         "
-    )?;
+        SELECT {}
+        FROM buffers
+        WHERE is_archived = 0 AND deleted_at IS NULL
+        AND id IN (
+            SELECT bt.buffer_id FROM buffer_tags bt
+            JOIN tags t ON t.id = bt.tag_id
+            WHERE t.name = ?
+        )
+        ORDER BY is_pinned DESC, sort_order ASC, accessed_at DESC
+        LIMIT ? OFFSET ?
+        ",
+        SUMMARY_COLUMNS
+    ))?;
 
-    let rows = stmt.query_map([limit as i64, offset as i64], |row| {
-        let id: String = row.get(0)?;
-        let content: String = row.get(1)?;
-        let updated_at: i64 = row.get(2)?;
-        let is_pinned: i64 = row.get(3)?;
+    let rows = stmt.query_map(params![tag, limit as i64, offset as i64], row_to_summary)?;
 
-        let (title, preview) = extract_title_preview(&content);
+    rows.collect()
+}
 
-        Ok(BufferSummary {
-            id,
-            title,
-            preview,
-            updated_at,
-            is_pinned: is_pinned != 0,
-        })
-    })?;
+/// Ids of non-archived buffers, most recently accessed first
+pub fn get_recent_buffer_ids(conn: &Connection, limit: usize) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT id FROM buffers
+        WHERE is_archived = 0 AND deleted_at IS NULL
+        ORDER BY accessed_at DESC
+        LIMIT ?
+        "
+    )?;
 
+    let rows = stmt.query_map([limit as i64], |row| row.get(0))?;
     rows.collect()
 }
 
-/// Search buffers using FTS5
+/// Summaries for up to `limit` of the given ids in the same order, skipping
+/// ids that are archived or no longer exist
+pub fn get_buffer_summaries(conn: &Connection, ids: &[String], limit: usize) -> Result<Vec<BufferSummary>> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "
+        SELECT {}
+        FROM buffers
+        WHERE id = ? AND is_archived = 0 AND deleted_at IS NULL
+        ",
+        SUMMARY_COLUMNS
+    ))?;
+
+    let mut summaries = Vec::with_capacity(limit.min(ids.len()));
+    for id in ids {
+        if summaries.len() == limit {
+            break;
+        }
+        if let Some(summary) = stmt.query_row([id], row_to_summary).optional()? {
+            summaries.push(summary);
+        }
+    }
+    Ok(summaries)
+}
+
+/// Search buffers using FTS5, optionally only those from `source`. A source
+/// kind such as "import" also matches detailed sources like "import:notes.md".
 /// Note: query should already be sanitized via sanitize_search_query
-pub fn search_buffers(conn: &Connection, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+pub fn search_buffers(conn: &Connection, query: &str, source: Option<&str>, limit: usize) -> Result<Vec<SearchResult>> {
     if query.trim().is_empty() {
         return Ok(Vec::new());
     }
@@ -131,58 +441,242 @@ pub fn search_buffers(conn: &Connection, query: &str, limit: usize) -> Result<Ve
 
     let mut stmt = conn.prepare(
         "
-        SELECT b.id, highlight(buffers_fts, 0, '<mark>', '</mark>') as snippet, b.updated_at
+        SELECT b.id, highlight(buffers_fts, 0, '<mark>', '</mark>') as snippet, b.updated_at, buffers_fts.rank
         FROM buffers_fts
         JOIN buffers b ON buffers_fts.rowid = b.rowid
-        WHERE buffers_fts MATCH ?
-        AND b.is_archived = 0
+        WHERE buffers_fts MATCH ?1
+        AND b.is_archived = 0 AND b.deleted_at IS NULL
+        AND (?2 IS NULL OR b.source = ?2 OR substr(b.source, 1, length(?2) + 1) = ?2 || ':')
         ORDER BY rank
-        LIMIT ?
+        LIMIT ?3
         "
     )?;
 
-    let rows = stmt.query_map(params![safe_query, limit as i64], |row| {
-        Ok(SearchResult {
-            id: row.get(0)?,
-            snippet: row.get(1)?,
-            updated_at: row.get(2)?,
-        })
+    let rows = stmt.query_map(params![safe_query, source, limit as i64], |row| {
+        Ok((
+            SearchResult {
+                id: row.get(0)?,
+                snippet: row.get(1)?,
+                updated_at: row.get(2)?,
+                attachment_name: None,
+                tag: None,
+            },
+            row.get::<_, f64>(3)?,
+        ))
     })?;
+    let mut ranked = rows.collect::<Result<Vec<_>>>()?;
 
-    rows.collect()
+    // Attachment text matches, tagged with their owning buffer
+    for m in attachments::search_attachments(conn, &safe_query, source, limit)? {
+        ranked.push((
+            SearchResult {
+                id: m.buffer_id,
+                snippet: m.snippet,
+                updated_at: m.updated_at,
+                attachment_name: Some(m.file_name),
+                tag: None,
+            },
+            m.rank,
+        ));
+    }
+
+    // Tag name matches, shown as the tag itself
+    for m in tags::search_tags(conn, &safe_query, source, limit)? {
+        ranked.push((
+            SearchResult {
+                id: m.buffer_id,
+                snippet: format!("#<mark>{}</mark>", m.tag),
+                updated_at: m.updated_at,
+                attachment_name: None,
+                tag: Some(m.tag),
+            },
+            m.rank,
+        ));
+    }
+
+    // FTS5 rank: lower is a better match
+    ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+    ranked.truncate(limit);
+
+    Ok(ranked.into_iter().map(|(result, _)| result).collect())
 }
 
 /// Get full buffer content by ID
 pub fn get_buffer_content(conn: &Connection, id: &str) -> Result<Option<Buffer>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM buffers WHERE id = ?", BUFFER_COLUMNS))?;
+
+    let result = stmt.query_row([id], row_to_buffer);
+
+    match result {
+        Ok(buffer) => Ok(Some(buffer)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Content of a buffer that may be shown outside the app (None if it doesn't
+/// exist, is in the trash or is locked)
+pub fn get_shareable_content(conn: &Connection, id: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT content FROM buffers WHERE id = ? AND deleted_at IS NULL AND is_locked = 0",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Build a full buffer from a row of `BUFFER_COLUMNS`
+fn row_to_buffer(row: &rusqlite::Row) -> Result<Buffer> {
+    Ok(Buffer {
+        id: row.get(0)?,
+        content: row.get(1)?,
+        created_at: row.get(2)?,
+        updated_at: row.get(3)?,
+        accessed_at: row.get(4)?,
+        is_archived: row.get::<_, i64>(5)? != 0,
+        is_pinned: row.get::<_, i64>(6)? != 0,
+        archived_at: row.get(7)?,
+        title_override: row.get(8)?,
+        word_count: row.get(9)?,
+        slug: row.get(10)?,
+        source: row.get(11)?,
+        is_locked: row.get::<_, i64>(12)? != 0,
+    })
+}
+
+/// Ids and content of non-archived buffers mentioning a board property
+/// (`#property/` or `property::`), in sidebar order
+pub fn get_board_candidates(conn: &Connection, property: &str) -> Result<Vec<(String, String)>> {
+    let property = property.to_lowercase();
     let mut stmt = conn.prepare(
         "
-        SELECT id, content, created_at, updated_at, accessed_at, is_archived, is_pinned
+        SELECT id, content
         FROM buffers
-        WHERE id = ?
+        WHERE is_archived = 0 AND deleted_at IS NULL
+        AND (instr(lower(content), ?) > 0 OR instr(lower(content), ?) > 0)
+        ORDER BY is_pinned DESC, sort_order ASC, accessed_at DESC
         "
     )?;
 
-    let result = stmt.query_row([id], |row| {
-        Ok(Buffer {
-            id: row.get(0)?,
-            content: row.get(1)?,
-            created_at: row.get(2)?,
-            updated_at: row.get(3)?,
-            accessed_at: row.get(4)?,
-            is_archived: row.get::<_, i64>(5)? != 0,
-            is_pinned: row.get::<_, i64>(6)? != 0,
-        })
-    });
+    let rows = stmt.query_map(
+        params![format!("#{}/", property), format!("{}::", property)],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    rows.collect()
+}
 
-    match result {
-        Ok(buffer) => Ok(Some(buffer)),
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-        Err(e) => Err(e),
+/// Non-archived buffers that may contain due dates (content mentions a
+/// `tasks::DUE_MARKERS` marker)
+pub fn get_buffers_with_due_dates(conn: &Connection) -> Result<Vec<Buffer>> {
+    let mut stmt = conn.prepare(&format!(
+        "
+        SELECT {}
+        FROM buffers
+        WHERE is_archived = 0 AND deleted_at IS NULL AND (instr(content, ?) > 0 OR instr(content, ?) > 0)
+        ",
+        BUFFER_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map(tasks::DUE_MARKERS, row_to_buffer)?;
+    rows.collect()
+}
+
+/// Get active buffers updated within `[since, until)`, oldest first
+pub fn get_buffers_updated_between(conn: &Connection, since: i64, until: i64) -> Result<Vec<Buffer>> {
+    let mut stmt = conn.prepare(&format!(
+        "
+        SELECT {}
+        FROM buffers
+        WHERE is_archived = 0 AND deleted_at IS NULL AND updated_at >= ? AND updated_at < ?
+        ORDER BY updated_at ASC
+        ",
+        BUFFER_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map(params![since, until], row_to_buffer)?;
+    rows.collect()
+}
+
+/// Get every buffer outside the trash, archived included, oldest first (for full exports)
+pub fn get_all_buffers(conn: &Connection) -> Result<Vec<Buffer>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM buffers WHERE deleted_at IS NULL ORDER BY created_at ASC",
+        BUFFER_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map([], row_to_buffer)?;
+    rows.collect()
+}
+
+/// Insert a buffer with its original id, timestamps and flags, or overwrite
+/// the existing buffer with that id (keeping its position and access time, and
+/// taking it out of the trash). A locked buffer is left as it is.
+/// Word count, slug and checklist counts are recomputed from the content.
+pub fn upsert_imported_buffer(conn: &Connection, buffer: &Buffer) -> Result<()> {
+    let min_order: i64 = conn
+        .query_row(
+            "SELECT COALESCE(MIN(sort_order), 0) - 1 FROM buffers WHERE is_archived = 0",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(-1);
+    let (extracted_title, preview) = extract_title_preview(&buffer.content);
+    let title = buffer.title_override.clone().unwrap_or_else(|| extracted_title.clone());
+    let (tasks_open, tasks_total) = tasks::count(&buffer.content);
+
+    let rows_affected = conn.execute(
+        "
+        INSERT INTO buffers (id, content, created_at, updated_at, accessed_at, is_archived, is_pinned,
+                             archived_at, title_override, word_count, slug, source, sort_order,
+                             tasks_open, tasks_total, title, preview)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
+        ON CONFLICT(id) DO UPDATE SET
+            content = excluded.content,
+            created_at = excluded.created_at,
+            updated_at = excluded.updated_at,
+            is_archived = excluded.is_archived,
+            is_pinned = excluded.is_pinned,
+            archived_at = excluded.archived_at,
+            title_override = excluded.title_override,
+            word_count = excluded.word_count,
+            slug = excluded.slug,
+            source = excluded.source,
+            tasks_open = excluded.tasks_open,
+            tasks_total = excluded.tasks_total,
+            title = excluded.title,
+            preview = excluded.preview,
+            deleted_at = NULL
+        WHERE is_locked = 0
+        ",
+        params![
+            buffer.id,
+            buffer.content,
+            buffer.created_at,
+            buffer.updated_at,
+            buffer.accessed_at,
+            buffer.is_archived as i64,
+            buffer.is_pinned as i64,
+            buffer.archived_at,
+            buffer.title_override,
+            count_words(&buffer.content),
+            slugify(&title),
+            buffer.source,
+            min_order,
+            tasks_open,
+            tasks_total,
+            extracted_title,
+            preview,
+        ],
+    )?;
+    if rows_affected > 0 {
+        cards::sync_buffer_cards(conn, &buffer.id, &buffer.content, buffer.updated_at)?;
+        tags::sync_hashtags(conn, &buffer.id, &buffer.content, buffer.updated_at)?;
     }
+    Ok(())
 }
 
 /// Create a new buffer with sort_order = min(existing) - 1 to place at top
-pub fn create_buffer(conn: &Connection, id: &str, content: &str, timestamp: i64) -> Result<()> {
+pub fn create_buffer(conn: &Connection, id: &str, content: &str, source: &str, timestamp: i64) -> Result<()> {
     // Get the minimum sort_order to place new buffer at top
     let min_order: i64 = conn
         .query_row(
@@ -192,29 +686,182 @@ pub fn create_buffer(conn: &Connection, id: &str, content: &str, timestamp: i64)
         )
         .unwrap_or(-1);
 
+    let (tasks_open, tasks_total) = tasks::count(content);
+    let (title, preview) = extract_title_preview(content);
+
     conn.execute(
         "
-        INSERT INTO buffers (id, content, created_at, updated_at, accessed_at, sort_order)
-        VALUES (?, ?, ?, ?, ?, ?)
+        INSERT INTO buffers (id, content, created_at, updated_at, accessed_at, sort_order, word_count, slug, source,
+                             tasks_open, tasks_total, title, preview)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ",
-        params![id, content, timestamp, timestamp, timestamp, min_order],
+        params![
+            id,
+            content,
+            timestamp,
+            timestamp,
+            timestamp,
+            min_order,
+            count_words(content),
+            slugify(&title),
+            source,
+            tasks_open,
+            tasks_total,
+            title,
+            preview,
+        ],
     )?;
+    cards::sync_buffer_cards(conn, id, content, timestamp)?;
+    tags::sync_hashtags(conn, id, content, timestamp)?;
     Ok(())
 }
 
-/// Update buffer content
+/// Update buffer content (and the flashcards and #hashtags parsed from it),
+/// keeping the previous content as a revision when one is due. Locked buffers
+/// are not updated.
 pub fn update_buffer_content(conn: &Connection, id: &str, content: &str, timestamp: i64) -> Result<bool> {
+    revisions::snapshot(conn, id, content, timestamp, false)?;
+    let (title, preview) = extract_title_preview(content);
+    let slug = slugify(&get_title_override(conn, id)?.unwrap_or_else(|| title.clone()));
+    let (tasks_open, tasks_total) = tasks::count(content);
     let rows_affected = conn.execute(
         "
         UPDATE buffers
-        SET content = ?, updated_at = ?
-        WHERE id = ?
+        SET content = ?, updated_at = ?, word_count = ?, slug = ?, tasks_open = ?, tasks_total = ?, title = ?,
+            preview = ?
+        WHERE id = ? AND is_locked = 0
         ",
-        params![content, timestamp, id],
+        params![content, timestamp, count_words(content), slug, tasks_open, tasks_total, title, preview, id],
+    )?;
+    if rows_affected > 0 {
+        cards::sync_buffer_cards(conn, id, content, timestamp)?;
+        tags::sync_hashtags(conn, id, content, timestamp)?;
+    }
+    Ok(rows_affected > 0)
+}
+
+/// Set or clear (None) a buffer's custom title. `effective_title` is the
+/// title the buffer ends up with (used for its slug). Returns false if the
+/// buffer doesn't exist.
+pub fn rename_buffer(conn: &Connection, id: &str, title: Option<&str>, effective_title: &str) -> Result<bool> {
+    let rows_affected = conn.execute(
+        "UPDATE buffers SET title_override = ?, slug = ? WHERE id = ?",
+        params![title, slugify(effective_title), id],
+    )?;
+    Ok(rows_affected > 0)
+}
+
+/// Encrypted content of a locked buffer (None if it doesn't exist or isn't locked)
+pub fn get_locked_content(conn: &Connection, id: &str) -> Result<Option<Vec<u8>>> {
+    conn.query_row(
+        "SELECT locked_content FROM buffers WHERE id = ? AND is_locked = 1",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Ids and encrypted content of all locked buffers
+pub fn get_locked_buffers(conn: &Connection) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut stmt = conn.prepare("SELECT id, locked_content FROM buffers WHERE is_locked = 1")?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
+
+/// Replace a locked buffer's encrypted content, e.g. re-sealed under a new
+/// passphrase. Returns false if the buffer doesn't exist or isn't locked.
+pub fn set_locked_content(conn: &Connection, id: &str, sealed: &[u8]) -> Result<bool> {
+    let rows_affected = conn.execute(
+        "UPDATE buffers SET locked_content = ? WHERE id = ? AND is_locked = 1",
+        params![sealed, id],
     )?;
     Ok(rows_affected > 0)
 }
 
+/// Lock a buffer: store its encrypted content and clear everything readable
+/// derived from it (content, slug, counts, cards, hashtags, revisions, embedding).
+/// The slug follows the custom title, if any. Returns false if the buffer
+/// doesn't exist or is already locked.
+pub fn lock_buffer(conn: &Connection, id: &str, sealed: &[u8], timestamp: i64) -> Result<bool> {
+    let title = get_title_override(conn, id)?.unwrap_or_default();
+    let (extracted_title, preview) = extract_title_preview("");
+    let rows_affected = conn.execute(
+        "
+        UPDATE buffers
+        SET is_locked = 1, locked_content = ?, content = '', word_count = 0, slug = ?, tasks_open = 0,
+            tasks_total = 0, title = ?, preview = ?
+        WHERE id = ? AND is_locked = 0
+        ",
+        params![sealed, slugify(&title), extracted_title, preview, id],
+    )?;
+    if rows_affected > 0 {
+        cards::sync_buffer_cards(conn, id, "", timestamp)?;
+        tags::sync_hashtags(conn, id, "", timestamp)?;
+        revisions::delete_history(conn, id)?;
+        embeddings::delete_embedding(conn, id)?;
+        sync::clear_base(conn, id)?;
+    }
+    Ok(rows_affected > 0)
+}
+
+/// Unlock a buffer, putting its decrypted content back (the edit time is kept).
+/// Returns false if the buffer doesn't exist or isn't locked.
+pub fn unlock_buffer(conn: &Connection, id: &str, content: &str, timestamp: i64) -> Result<bool> {
+    let (title, preview) = extract_title_preview(content);
+    let slug = slugify(&get_title_override(conn, id)?.unwrap_or_else(|| title.clone()));
+    let (tasks_open, tasks_total) = tasks::count(content);
+    let rows_affected = conn.execute(
+        "
+        UPDATE buffers
+        SET is_locked = 0, locked_content = NULL, content = ?, word_count = ?, slug = ?, tasks_open = ?,
+            tasks_total = ?, title = ?, preview = ?
+        WHERE id = ? AND is_locked = 1
+        ",
+        params![content, count_words(content), slug, tasks_open, tasks_total, title, preview, id],
+    )?;
+    if rows_affected > 0 {
+        cards::sync_buffer_cards(conn, id, content, timestamp)?;
+        tags::sync_hashtags(conn, id, content, timestamp)?;
+    }
+    Ok(rows_affected > 0)
+}
+
+/// Get a buffer's slug
+pub fn get_slug(conn: &Connection, id: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT slug FROM buffers WHERE id = ?",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Ids of non-archived buffers with the given slug, other than `exclude_id`
+pub fn find_buffers_by_slug(conn: &Connection, slug: &str, exclude_id: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id FROM buffers WHERE slug = ? AND id != ? AND is_archived = 0 AND deleted_at IS NULL"
+    )?;
+    let rows = stmt.query_map(params![slug, exclude_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Get a buffer's custom title, if one is set
+pub fn get_title_override(conn: &Connection, id: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT title_override FROM buffers WHERE id = ?",
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+    .map(Option::flatten)
+}
+
+/// Title/preview for a buffer's content, honoring its custom title
+pub fn buffer_title_preview(conn: &Connection, id: &str, content: &str) -> Result<(String, String)> {
+    let (title, preview) = extract_title_preview(content);
+    Ok((get_title_override(conn, id)?.unwrap_or(title), preview))
+}
+
 /// Update buffer accessed timestamp (for "recently used" sorting)
 pub fn touch_buffer(conn: &Connection, id: &str, timestamp: i64) -> Result<bool> {
     let rows_affected = conn.execute(
@@ -233,7 +880,7 @@ pub fn get_next_buffer_id(conn: &Connection, current_id: &str) -> Result<Option<
     // Get first non-archived buffer that isn't the one being deleted
     let mut stmt = conn.prepare(
         "SELECT id FROM buffers
-         WHERE is_archived = 0 AND id != ?
+         WHERE is_archived = 0 AND deleted_at IS NULL AND id != ?
          ORDER BY is_pinned DESC, sort_order ASC, accessed_at DESC
          LIMIT 1"
     )?;
@@ -245,6 +892,72 @@ pub fn get_next_buffer_id(conn: &Connection, current_id: &str) -> Result<Option<
     }
 }
 
+/// Ordering used when stepping between buffers
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    /// Sidebar order: pinned first, then manual order, then recently used
+    #[default]
+    Sidebar,
+    /// Most recently accessed first
+    Recent,
+}
+
+impl SortMode {
+    fn order_by(self) -> &'static str {
+        match self {
+            SortMode::Sidebar => "is_pinned DESC, sort_order ASC, accessed_at DESC",
+            SortMode::Recent => "accessed_at DESC",
+        }
+    }
+}
+
+/// Direction to step in a buffer ordering
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Next,
+    Previous,
+}
+
+/// Get the buffer after/before `current_id` among non-archived buffers,
+/// wrapping around at either end. If `current_id` isn't in the list (e.g. it
+/// was archived), starts from the first or last buffer. None if there is no
+/// other buffer to move to.
+pub fn get_adjacent_buffer_id(
+    conn: &Connection,
+    current_id: &str,
+    direction: Direction,
+    sort_mode: SortMode,
+) -> Result<Option<String>> {
+    let step: i64 = match direction {
+        Direction::Next => 1,
+        Direction::Previous => -1,
+    };
+
+    let sql = format!(
+        "
+        WITH ordered AS (
+            SELECT id,
+                   ROW_NUMBER() OVER (ORDER BY {}) AS pos,
+                   COUNT(*) OVER () AS total
+            FROM buffers
+            WHERE is_archived = 0 AND deleted_at IS NULL
+        )
+        SELECT COALESCE(
+            (SELECT o.id FROM ordered o, ordered cur
+             WHERE cur.id = ?1 AND o.pos = (cur.pos - 1 + ?2 + cur.total) % cur.total + 1),
+            (SELECT id FROM ordered
+             WHERE pos = CASE WHEN ?2 > 0 THEN 1 ELSE total END)
+        )
+        ",
+        sort_mode.order_by()
+    );
+
+    let id: Option<String> = conn.query_row(&sql, params![current_id, step], |row| row.get(0))?;
+    Ok(id.filter(|id| id != current_id))
+}
+
 /// Permanently delete a buffer
 pub fn delete_buffer(conn: &Connection, id: &str) -> Result<bool> {
     let rows_affected = conn.execute(
@@ -268,6 +981,37 @@ pub fn toggle_pin(conn: &Connection, id: &str) -> Result<bool> {
     )
 }
 
+/// Set a buffer's pin state. Returns false if the buffer doesn't exist.
+pub fn set_pinned(conn: &Connection, id: &str, pinned: bool) -> Result<bool> {
+    let rows_affected = conn.execute(
+        "UPDATE buffers SET is_pinned = ? WHERE id = ?",
+        params![pinned as i64, id],
+    )?;
+    Ok(rows_affected > 0)
+}
+
+/// Current sort_order of the given buffers (missing ids are skipped)
+pub fn get_sort_orders(conn: &Connection, ids: &[String]) -> Result<Vec<(String, i64)>> {
+    let mut stmt = conn.prepare_cached("SELECT sort_order FROM buffers WHERE id = ?")?;
+    let mut orders = Vec::with_capacity(ids.len());
+    for id in ids {
+        let order = stmt.query_row(params![id], |row| row.get::<_, Option<i64>>(0)).optional()?;
+        if let Some(order) = order {
+            orders.push((id.clone(), order.unwrap_or(0)));
+        }
+    }
+    Ok(orders)
+}
+
+/// Set each buffer's sort_order
+pub fn set_sort_orders(conn: &Connection, orders: &[(String, i64)]) -> Result<()> {
+    let mut stmt = conn.prepare_cached("UPDATE buffers SET sort_order = ? WHERE id = ?")?;
+    for (id, order) in orders {
+        stmt.execute(params![order, id])?;
+    }
+    Ok(())
+}
+
 /// Reorder buffers by setting sort_order based on provided ID list
 /// Wrapped in transaction for 10-50x performance improvement
 pub fn reorder_buffers(conn: &mut Connection, ids: &[String]) -> Result<()> {
@@ -281,13 +1025,216 @@ pub fn reorder_buffers(conn: &mut Connection, ids: &[String]) -> Result<()> {
     tx.commit()
 }
 
-/// Delete all empty buffers (content is empty or whitespace only)
-pub fn delete_empty_buffers(conn: &Connection) -> Result<usize> {
+/// Delete all empty buffers (content is empty or whitespace only) and return their ids.
+/// Pinned buffers, buffers with attachments and any ids in `keep_ids` are never removed.
+pub fn delete_empty_buffers(conn: &mut Connection, keep_ids: &[String]) -> Result<Vec<String>> {
+    let tx = conn.transaction()?;
+
+    let candidates: Vec<String> = {
+        let mut stmt = tx.prepare(
+            "
+            SELECT id FROM buffers
+            WHERE TRIM(content) = '' AND is_archived = 0 AND deleted_at IS NULL AND is_pinned = 0 AND is_locked = 0
+            AND NOT EXISTS (SELECT 1 FROM attachments WHERE attachments.buffer_id = buffers.id)
+            ",
+        )?;
+        let ids = stmt.query_map([], |row| row.get(0))?;
+        ids.collect::<Result<_>>()?
+    };
+
+    let mut deleted = Vec::new();
+    for id in candidates.into_iter().filter(|id| !keep_ids.contains(id)) {
+        if tx.execute("DELETE FROM buffers WHERE id = ?", params![id])? > 0 {
+            deleted.push(id);
+        }
+    }
+
+    tx.commit()?;
+    Ok(deleted)
+}
+
+/// Archive non-pinned buffers not updated or opened since `cutoff` and return their ids
+pub fn archive_stale_buffers(conn: &Connection, cutoff: i64, timestamp: i64) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "
+        UPDATE buffers
+        SET is_archived = 1, archived_at = ?
+        WHERE is_archived = 0 AND deleted_at IS NULL AND is_pinned = 0
+        AND MAX(updated_at, accessed_at) < ?
+        RETURNING id
+        ",
+    )?;
+    let rows = stmt.query_map(params![timestamp, cutoff], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Archive or restore a single buffer. Returns false if the buffer doesn't exist
+/// or is in the trash.
+pub fn set_archived(conn: &Connection, id: &str, archived: bool, timestamp: i64) -> Result<bool> {
     let rows_affected = conn.execute(
-        "DELETE FROM buffers WHERE TRIM(content) = '' AND is_archived = 0",
-        [],
+        "UPDATE buffers SET is_archived = ?, archived_at = ? WHERE id = ? AND deleted_at IS NULL",
+        params![archived as i64, archived.then_some(timestamp), id],
+    )?;
+    Ok(rows_affected > 0)
+}
+
+/// Get archived buffers, most recently archived first
+pub fn get_archived_buffers(conn: &Connection, limit: usize, offset: usize) -> Result<Vec<BufferSummary>> {
+    let mut stmt = conn.prepare(&format!(
+        "
+        SELECT {}
+        FROM buffers
+        WHERE is_archived = 1 AND deleted_at IS NULL
+        ORDER BY archived_at DESC, updated_at DESC
+        LIMIT ? OFFSET ?
+        ",
+        SUMMARY_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map([limit as i64, offset as i64], row_to_summary)?;
+
+    rows.collect()
+}
+
+/// Move a buffer to the trash. Returns false if it doesn't exist or is already trashed.
+pub fn trash_buffer(conn: &Connection, id: &str, timestamp: i64) -> Result<bool> {
+    let rows_affected = conn.execute(
+        "UPDATE buffers SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+        params![timestamp, id],
+    )?;
+    Ok(rows_affected > 0)
+}
+
+/// Take a buffer out of the trash, back to the sidebar or archive it was in.
+/// Returns false if it isn't in the trash.
+pub fn restore_buffer(conn: &Connection, id: &str) -> Result<bool> {
+    let rows_affected = conn.execute(
+        "UPDATE buffers SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
+        params![id],
+    )?;
+    Ok(rows_affected > 0)
+}
+
+/// Get trashed buffers, most recently deleted first
+pub fn get_trashed_buffers(conn: &Connection, limit: usize, offset: usize) -> Result<Vec<BufferSummary>> {
+    let mut stmt = conn.prepare(&format!(
+        "
+        SELECT {}
+        FROM buffers
+        WHERE deleted_at IS NOT NULL
+        ORDER BY deleted_at DESC
+        LIMIT ? OFFSET ?
+        ",
+        SUMMARY_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map([limit as i64, offset as i64], row_to_summary)?;
+
+    rows.collect()
+}
+
+/// Ids of buffers trashed before `cutoff` (all trashed buffers when None)
+pub fn get_trashed_ids(conn: &Connection, cutoff: Option<i64>) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM buffers WHERE deleted_at IS NOT NULL AND (?1 IS NULL OR deleted_at < ?1)"
     )?;
-    Ok(rows_affected)
+    let rows = stmt.query_map(params![cutoff], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Outcome of merging duplicate buffers into one
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DedupeReport {
+    pub keep_id: String,
+    pub removed_ids: Vec<String>,
+    pub missing_ids: Vec<String>,
+    pub pinned: bool,
+    pub dry_run: bool,
+}
+
+/// Merge `remove_ids` into `keep_id`: the kept buffer inherits any pin and
+/// the others' tags, the others go to the trash. Runs in one transaction;
+/// with `dry_run` nothing is written. Returns None if `keep_id` doesn't exist.
+pub fn dedupe_buffers(
+    conn: &mut Connection,
+    keep_id: &str,
+    remove_ids: &[String],
+    timestamp: i64,
+    dry_run: bool,
+) -> Result<Option<DedupeReport>> {
+    let tx = conn.transaction()?;
+
+    let keep_pinned = tx
+        .query_row(
+            "SELECT is_pinned FROM buffers WHERE id = ? AND deleted_at IS NULL",
+            params![keep_id],
+            |row| Ok(row.get::<_, i64>(0)? != 0),
+        )
+        .optional()?;
+    let Some(keep_pinned) = keep_pinned else {
+        return Ok(None);
+    };
+
+    let mut removed_ids = Vec::new();
+    let mut missing_ids = Vec::new();
+    let mut any_pinned = keep_pinned;
+    for id in remove_ids.iter().filter(|id| id.as_str() != keep_id) {
+        let pinned = tx
+            .query_row(
+                "SELECT is_pinned FROM buffers WHERE id = ? AND is_archived = 0 AND deleted_at IS NULL",
+                params![id],
+                |row| Ok(row.get::<_, i64>(0)? != 0),
+            )
+            .optional()?;
+        match pinned {
+            Some(pinned) => {
+                any_pinned |= pinned;
+                removed_ids.push(id.clone());
+            }
+            None => missing_ids.push(id.clone()),
+        }
+    }
+
+    if !dry_run {
+        if any_pinned && !keep_pinned {
+            tx.execute("UPDATE buffers SET is_pinned = 1 WHERE id = ?", params![keep_id])?;
+        }
+        for id in &removed_ids {
+            // Copied as hand-assigned tags: they aren't in the kept buffer's content
+            tx.execute(
+                "
+                INSERT OR IGNORE INTO buffer_tags (buffer_id, tag_id, manual, in_content)
+                SELECT ?, tag_id, 1, 0 FROM buffer_tags WHERE buffer_id = ?
+                ",
+                params![keep_id, id],
+            )?;
+            trash_buffer(&tx, id, timestamp)?;
+        }
+        tx.commit()?;
+    }
+
+    Ok(Some(DedupeReport {
+        keep_id: keep_id.to_string(),
+        removed_ids,
+        missing_ids,
+        pinned: any_pinned,
+        dry_run,
+    }))
+}
+
+/// Count (active, archived) buffers, not counting the trash
+pub fn get_buffer_counts(conn: &Connection) -> Result<(i64, i64)> {
+    conn.query_row(
+        "
+        SELECT
+            COALESCE(SUM(is_archived = 0), 0),
+            COALESCE(SUM(is_archived = 1), 0)
+        FROM buffers
+        WHERE deleted_at IS NULL
+        ",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
 }
 
 /// App settings
@@ -300,6 +1247,46 @@ pub struct AppSettings {
     pub sidebar_collapsed: bool,
     pub vim_mode: bool,
     pub always_on_top: bool,
+    /// Accelerator that shows/hides the window from anywhere (empty = off)
+    pub global_shortcut: String,
+    /// Accelerator that creates a note from the clipboard (empty = off)
+    pub clipboard_shortcut: String,
+    pub update_channel: String,
+    pub log_level: String,
+    pub usage_metrics_enabled: bool,
+    pub auto_lock_minutes: i32,
+    pub auto_lock_hide_window: bool,
+    pub empty_buffer_cleanup: String,
+    pub auto_archive_days: i32,
+    pub trash_retention_days: i32,
+    pub backup_interval_hours: i32,
+    pub backup_retention_count: i32,
+    pub backup_destination: String,
+    pub duplicate_titles: String,
+    pub save_hook_script: String,
+    pub lint_rules: String,
+    pub lint_todo_days: i32,
+    pub lint_daily: bool,
+    pub secure_delete: bool,
+    pub ai_provider: String,
+    pub ai_model: String,
+    pub ai_base_url: String,
+    pub transcription_provider: String,
+    pub transcription_url: String,
+    pub digest_enabled: bool,
+    pub digest_day: String,
+    pub digest_hour: i32,
+    pub digest_ai_summary: bool,
+    pub semantic_search: bool,
+    pub embedding_model: String,
+    pub locale: String,
+    pub inbox_watch_dir: String,
+    pub inbox_after_import: String,
+    pub sync_dir: String,
+    pub cloud_sync_enabled: bool,
+    pub api_enabled: bool,
+    pub api_port: i32,
+    pub mcp_enabled: bool,
 }
 
 impl Default for AppSettings {
@@ -312,6 +1299,44 @@ impl Default for AppSettings {
             sidebar_collapsed: false,
             vim_mode: false,
             always_on_top: false,
+            global_shortcut: "CommandOrControl+Shift+Space".to_string(),
+            clipboard_shortcut: "CommandOrControl+Alt+V".to_string(),
+            update_channel: "stable".to_string(),
+            log_level: "info".to_string(),
+            usage_metrics_enabled: false,
+            auto_lock_minutes: 0,
+            auto_lock_hide_window: false,
+            empty_buffer_cleanup: "on_blur".to_string(),
+            auto_archive_days: 0,
+            trash_retention_days: 30,
+            backup_interval_hours: 24,
+            backup_retention_count: 7,
+            backup_destination: String::new(),
+            duplicate_titles: "allow".to_string(),
+            save_hook_script: String::new(),
+            lint_rules: "empty_title,broken_wiki_link,unclosed_code_fence,stale_todo".to_string(),
+            lint_todo_days: 30,
+            lint_daily: false,
+            secure_delete: false,
+            ai_provider: "none".to_string(),
+            ai_model: String::new(),
+            ai_base_url: String::new(),
+            transcription_provider: "none".to_string(),
+            transcription_url: String::new(),
+            digest_enabled: false,
+            digest_day: "sunday".to_string(),
+            digest_hour: 18,
+            digest_ai_summary: false,
+            semantic_search: false,
+            embedding_model: String::new(),
+            locale: "system".to_string(),
+            inbox_watch_dir: String::new(),
+            inbox_after_import: "archive".to_string(),
+            sync_dir: String::new(),
+            cloud_sync_enabled: false,
+            api_enabled: false,
+            api_port: 52580,
+            mcp_enabled: false,
         }
     }
 }
@@ -349,6 +1374,84 @@ pub fn get_settings(conn: &Connection) -> Result<AppSettings> {
             "sidebar_collapsed" => settings.sidebar_collapsed = value == "true",
             "vim_mode" => settings.vim_mode = value == "true",
             "always_on_top" => settings.always_on_top = value == "true",
+            "global_shortcut" => settings.global_shortcut = value,
+            "clipboard_shortcut" => settings.clipboard_shortcut = value,
+            "update_channel" => settings.update_channel = value,
+            "log_level" => settings.log_level = value,
+            "usage_metrics_enabled" => settings.usage_metrics_enabled = value == "true",
+            "auto_lock_minutes" => {
+                settings.auto_lock_minutes = value.parse().unwrap_or_else(|_| {
+                    warn!("Failed to parse auto_lock_minutes setting '{}', using default 0", value);
+                    0
+                });
+            }
+            "auto_lock_hide_window" => settings.auto_lock_hide_window = value == "true",
+            "empty_buffer_cleanup" => settings.empty_buffer_cleanup = value,
+            "auto_archive_days" => {
+                settings.auto_archive_days = value.parse().unwrap_or_else(|_| {
+                    warn!("Failed to parse auto_archive_days setting '{}', using default 0", value);
+                    0
+                });
+            }
+            "trash_retention_days" => {
+                settings.trash_retention_days = value.parse().unwrap_or_else(|_| {
+                    warn!("Failed to parse trash_retention_days setting '{}', using default 30", value);
+                    30
+                });
+            }
+            "backup_interval_hours" => {
+                settings.backup_interval_hours = value.parse().unwrap_or_else(|_| {
+                    warn!("Failed to parse backup_interval_hours setting '{}', using default 24", value);
+                    24
+                });
+            }
+            "backup_retention_count" => {
+                settings.backup_retention_count = value.parse().unwrap_or_else(|_| {
+                    warn!("Failed to parse backup_retention_count setting '{}', using default 7", value);
+                    7
+                });
+            }
+            "backup_destination" => settings.backup_destination = value,
+            "duplicate_titles" => settings.duplicate_titles = value,
+            "save_hook_script" => settings.save_hook_script = value,
+            "lint_rules" => settings.lint_rules = value,
+            "lint_todo_days" => {
+                settings.lint_todo_days = value.parse().unwrap_or_else(|_| {
+                    warn!("Failed to parse lint_todo_days setting '{}', using default 30", value);
+                    30
+                });
+            }
+            "lint_daily" => settings.lint_daily = value == "true",
+            "secure_delete" => settings.secure_delete = value == "true",
+            "ai_provider" => settings.ai_provider = value,
+            "ai_model" => settings.ai_model = value,
+            "ai_base_url" => settings.ai_base_url = value,
+            "transcription_provider" => settings.transcription_provider = value,
+            "transcription_url" => settings.transcription_url = value,
+            "digest_enabled" => settings.digest_enabled = value == "true",
+            "digest_day" => settings.digest_day = value,
+            "digest_hour" => {
+                settings.digest_hour = value.parse().unwrap_or_else(|_| {
+                    warn!("Failed to parse digest_hour setting '{}', using default 18", value);
+                    18
+                });
+            }
+            "digest_ai_summary" => settings.digest_ai_summary = value == "true",
+            "semantic_search" => settings.semantic_search = value == "true",
+            "embedding_model" => settings.embedding_model = value,
+            "locale" => settings.locale = value,
+            "inbox_watch_dir" => settings.inbox_watch_dir = value,
+            "inbox_after_import" => settings.inbox_after_import = value,
+            "sync_dir" => settings.sync_dir = value,
+            "cloud_sync_enabled" => settings.cloud_sync_enabled = value == "true",
+            "api_enabled" => settings.api_enabled = value == "true",
+            "mcp_enabled" => settings.mcp_enabled = value == "true",
+            "api_port" => {
+                settings.api_port = value.parse().unwrap_or_else(|_| {
+                    warn!("Failed to parse api_port setting '{}', using default 52580", value);
+                    52580
+                });
+            }
             _ => {}
         }
     }
@@ -356,6 +1459,16 @@ pub fn get_settings(conn: &Connection) -> Result<AppSettings> {
     Ok(settings)
 }
 
+/// Read a single raw setting value (including internal keys not in `AppSettings`)
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?",
+        params![key],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
 /// Set a single setting
 pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
     conn.execute(
@@ -364,3 +1477,111 @@ pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
     )?;
     Ok(())
 }
+
+/// Persisted usage metrics for one command
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommandUsage {
+    pub command: String,
+    pub calls: i64,
+    pub errors: i64,
+    pub error_rate: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    pub last_called_at: i64,
+}
+
+/// Merge in-memory command stats into the usage_metrics table
+pub fn record_usage_metrics(conn: &mut Connection, stats: &HashMap<&'static str, CommandStats>) -> Result<()> {
+    let tx = conn.transaction()?;
+    for (command, s) in stats {
+        tx.execute(
+            "
+            INSERT INTO usage_metrics (command, calls, errors, total_us, max_us, last_called_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(command) DO UPDATE SET
+                calls = calls + excluded.calls,
+                errors = errors + excluded.errors,
+                total_us = total_us + excluded.total_us,
+                max_us = MAX(max_us, excluded.max_us),
+                last_called_at = excluded.last_called_at
+            ",
+            params![command, s.calls as i64, s.errors as i64, s.total_us as i64, s.max_us as i64, s.last_called_at],
+        )?;
+    }
+    tx.commit()
+}
+
+/// Get usage metrics for all commands, most called first
+pub fn get_usage_metrics(conn: &Connection) -> Result<Vec<CommandUsage>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT command, calls, errors, total_us, max_us, last_called_at
+        FROM usage_metrics
+        ORDER BY calls DESC
+        "
+    )?;
+
+    let rows = stmt.query_map([], |row| {
+        let calls: i64 = row.get(1)?;
+        let errors: i64 = row.get(2)?;
+        let total_us: i64 = row.get(3)?;
+        let max_us: i64 = row.get(4)?;
+
+        Ok(CommandUsage {
+            command: row.get(0)?,
+            calls,
+            errors,
+            error_rate: if calls > 0 { errors as f64 / calls as f64 } else { 0.0 },
+            avg_ms: if calls > 0 { total_us as f64 / calls as f64 / 1000.0 } else { 0.0 },
+            max_ms: max_us as f64 / 1000.0,
+            last_called_at: row.get(5)?,
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// Delete all stored usage metrics
+pub fn clear_usage_metrics(conn: &Connection) -> Result<usize> {
+    conn.execute("DELETE FROM usage_metrics", [])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn title_and_preview_skip_markdown_and_blank_lines() {
+        assert_eq!(
+            extract_title_preview("# Hello\n\n- [ ] Second line"),
+            ("Hello".to_string(), "Second line".to_string())
+        );
+        assert_eq!(extract_title_preview("  \n"), ("Untitled".to_string(), String::new()));
+    }
+
+    #[test]
+    fn slugify_joins_alphanumeric_runs() {
+        assert_eq!(slugify("Meeting Notes: Q3!"), "meeting-notes-q3");
+        assert_eq!(slugify("  Café -- Über  "), "café-über");
+        assert_eq!(slugify("!!!"), "untitled");
+        assert_eq!(slugify(""), "untitled");
+    }
+
+    #[test]
+    fn slugify_caps_length_without_a_trailing_hyphen() {
+        let title = format!("{} b", "a".repeat(SLUG_MAX_CHARS - 1));
+        assert_eq!(slugify(&title), "a".repeat(SLUG_MAX_CHARS - 1));
+    }
+
+    #[test]
+    fn extracts_hashtags_in_order_without_duplicates() {
+        let content = "Plan #Work and #work/q3, #work again\n# Heading #1 #a- foo#bar #todo.";
+        assert_eq!(extract_hashtags(content), ["work", "work/q3", "a", "todo"]);
+    }
+
+    #[test]
+    fn skips_hashtags_in_fenced_code() {
+        let content = "#before\n```\n#include <stdio.h>\n```\n~~~\n#inside\n~~~\n#after";
+        assert_eq!(extract_hashtags(content), ["before", "after"]);
+    }
+}
@@ -0,0 +1,112 @@
+// Startup corruption check. Before the connections are opened the database
+// gets a full integrity check; a corrupt file is renamed aside together with
+// its WAL and the newest backup that passes its own check is copied into
+// place. Without a usable backup the corrupt file is left where it is.
+
+use super::{backup, connection, schema};
+use rusqlite::{Connection, ErrorCode, OpenFlags};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, info, warn};
+
+/// What happened to a database found corrupt on launch
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryReport {
+    /// Why the database was considered corrupt
+    pub problem: String,
+    /// Where the corrupt database was moved; None when it was left in place
+    pub corrupt_path: Option<String>,
+    /// Backup the database was restored from; None when no backup was usable
+    pub restored_from: Option<String>,
+    /// When that backup was made (changes since then are only in the corrupt file)
+    pub backup_created_at: Option<i64>,
+}
+
+/// The problem with the database file, or None when it is healthy or does not exist yet
+fn find_problem(db_path: &Path) -> Option<String> {
+    if !db_path.exists() {
+        return None;
+    }
+
+    let result = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_WRITE)
+        .and_then(|conn| schema::check_integrity(&conn));
+    match result {
+        Ok(true) => None,
+        Ok(false) => Some("Database failed integrity check".to_string()),
+        Err(e) => match e.sqlite_error_code() {
+            Some(ErrorCode::DatabaseCorrupt) | Some(ErrorCode::NotADatabase) => Some(e.to_string()),
+            _ => {
+                // Locked or unreadable is not corruption; opening the connections reports it
+                warn!("Could not check database integrity: {}", e);
+                None
+            }
+        },
+    }
+}
+
+/// Move the database and its WAL to `flashnotes.corrupt_<timestamp>.db`
+fn move_aside(db_path: &Path) -> std::io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let corrupt_path = db_path.with_file_name(format!("flashnotes.corrupt_{}.db", timestamp));
+
+    // The WAL may hold the newest changes; it moves with the file it belongs to
+    connection::rename_database(db_path, &corrupt_path)?;
+    Ok(corrupt_path)
+}
+
+/// Check the database before it is opened and recover it from the newest
+/// valid backup when it is corrupt. Returns None when the database is fine.
+pub fn check_and_recover(db_path: &Path, app_data_dir: &PathBuf) -> Option<RecoveryReport> {
+    let problem = find_problem(db_path)?;
+    error!("Database is corrupt: {}", problem);
+
+    let mut report = RecoveryReport {
+        problem,
+        corrupt_path: None,
+        restored_from: None,
+        backup_created_at: None,
+    };
+
+    let Some(source) = backup::list_backups(app_data_dir).into_iter().find(|b| {
+        backup::validate_backup(Path::new(&b.path), None)
+            .map_err(|e| warn!("Skipping backup {}: {}", b.file_name, e))
+            .is_ok()
+    }) else {
+        error!("No valid backup to recover from; leaving the corrupt database in place");
+        return Some(report);
+    };
+
+    // Copy next to the database first so the final step is a rename
+    let staged = db_path.with_file_name("flashnotes.recovering.db");
+    if let Err(e) = fs::copy(&source.path, &staged) {
+        error!("Failed to copy backup {} for recovery: {}", source.file_name, e);
+        return Some(report);
+    }
+    let corrupt_path = match move_aside(db_path) {
+        Ok(path) => path,
+        Err(e) => {
+            error!("Failed to move corrupt database aside: {}", e);
+            let _ = fs::remove_file(&staged);
+            return Some(report);
+        }
+    };
+    report.corrupt_path = Some(corrupt_path.to_string_lossy().to_string());
+
+    if let Err(e) = fs::rename(&staged, db_path) {
+        error!("Failed to put backup in place: {}", e);
+        // Better the corrupt database than an empty one
+        if connection::rename_database(&corrupt_path, db_path).is_ok() {
+            report.corrupt_path = None;
+        }
+        return Some(report);
+    }
+    info!("Recovered database from backup {} (corrupt copy kept at {:?})", source.file_name, corrupt_path);
+    report.restored_from = Some(source.path);
+    report.backup_created_at = Some(source.created_at);
+    Some(report)
+}
@@ -0,0 +1,150 @@
+// Version history: before a buffer's content is overwritten, the old content
+// is kept as a revision, at most once per `SNAPSHOT_INTERVAL_SECS` so typing
+// doesn't create a revision per keystroke save. Older history is thinned out:
+// everything from the last day, one revision per day for a month, one per
+// week after that, and never more than `MAX_REVISIONS` per buffer.
+
+use super::queries;
+use rusqlite::{Connection, OptionalExtension, Result, params};
+use serde::Serialize;
+
+/// Minimum time between two revisions of the same buffer
+const SNAPSHOT_INTERVAL_SECS: i64 = 5 * 60;
+
+/// Upper bound on revisions kept per buffer
+const MAX_REVISIONS: usize = 100;
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+const WEEK_SECS: i64 = 7 * DAY_SECS;
+
+/// Revisions younger than this are all kept
+const KEEP_ALL_SECS: i64 = DAY_SECS;
+
+/// Revisions younger than this (and older than `KEEP_ALL_SECS`) are kept one per day
+const KEEP_DAILY_SECS: i64 = 30 * DAY_SECS;
+
+/// Characters read from a revision to build its title and preview
+const PREVIEW_SOURCE_CHARS: i64 = 500;
+
+/// A revision as listed in a buffer's history
+#[derive(Debug, Serialize)]
+pub struct RevisionSummary {
+    pub id: i64,
+    pub buffer_id: String,
+    pub title: String,
+    pub preview: String,
+    /// Content length in bytes
+    pub size: i64,
+    /// When the content was replaced, i.e. the latest time it was current
+    pub created_at: i64,
+}
+
+/// Keep the buffer's current content as a revision before it is replaced by
+/// `new_content`. Skipped when the content is unchanged or empty, and, unless
+/// `force` is set, when the buffer got a revision within the snapshot interval.
+pub fn snapshot(conn: &Connection, buffer_id: &str, new_content: &str, timestamp: i64, force: bool) -> Result<()> {
+    if !force {
+        let latest: Option<i64> = conn.query_row(
+            "SELECT MAX(created_at) FROM buffer_revisions WHERE buffer_id = ?",
+            params![buffer_id],
+            |row| row.get(0),
+        )?;
+        if matches!(latest, Some(latest) if timestamp - latest < SNAPSHOT_INTERVAL_SECS) {
+            return Ok(());
+        }
+    }
+
+    let current: Option<String> = conn
+        .query_row("SELECT content FROM buffers WHERE id = ?", params![buffer_id], |row| row.get(0))
+        .optional()?;
+    let Some(current) = current else {
+        return Ok(());
+    };
+    if current == new_content || current.trim().is_empty() {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO buffer_revisions (buffer_id, content, created_at) VALUES (?, ?, ?)",
+        params![buffer_id, current, timestamp],
+    )?;
+    compact(conn, buffer_id, timestamp)
+}
+
+/// Drop a buffer's whole history (e.g. it was locked)
+pub fn delete_history(conn: &Connection, buffer_id: &str) -> Result<usize> {
+    conn.execute("DELETE FROM buffer_revisions WHERE buffer_id = ?", params![buffer_id])
+}
+
+/// Thin out a buffer's history, keeping the newest revision in each bucket
+/// (every revision from the last day, then per day, then per week)
+fn compact(conn: &Connection, buffer_id: &str, now: i64) -> Result<()> {
+    let revisions: Vec<(i64, i64)> = {
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, created_at FROM buffer_revisions WHERE buffer_id = ? ORDER BY created_at DESC, id DESC",
+        )?;
+        let rows = stmt.query_map(params![buffer_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_>>()?
+    };
+
+    let mut kept = 0;
+    let mut last_bucket = None;
+    let mut stale = Vec::new();
+    for (id, created_at) in revisions {
+        let age = now - created_at;
+        let span = if age < KEEP_ALL_SECS {
+            1
+        } else if age < KEEP_DAILY_SECS {
+            DAY_SECS
+        } else {
+            WEEK_SECS
+        };
+        let bucket = Some((span, created_at.div_euclid(span)));
+        if kept < MAX_REVISIONS && (span == 1 || bucket != last_bucket) {
+            kept += 1;
+        } else {
+            stale.push(id);
+        }
+        last_bucket = bucket;
+    }
+
+    let mut stmt = conn.prepare_cached("DELETE FROM buffer_revisions WHERE id = ?")?;
+    for id in stale {
+        stmt.execute(params![id])?;
+    }
+    Ok(())
+}
+
+/// A buffer's revisions, newest first
+pub fn get_history(conn: &Connection, buffer_id: &str) -> Result<Vec<RevisionSummary>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT id, buffer_id, substr(content, 1, ?), length(CAST(content AS BLOB)), created_at
+        FROM buffer_revisions
+        WHERE buffer_id = ?
+        ORDER BY created_at DESC, id DESC
+        ",
+    )?;
+    let rows = stmt.query_map(params![PREVIEW_SOURCE_CHARS, buffer_id], |row| {
+        let (title, preview) = queries::extract_title_preview(&row.get::<_, String>(2)?);
+        Ok(RevisionSummary {
+            id: row.get(0)?,
+            buffer_id: row.get(1)?,
+            title,
+            preview,
+            size: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// The buffer id and content of a revision
+pub fn get_revision(conn: &Connection, id: i64) -> Result<Option<(String, String)>> {
+    conn.query_row(
+        "SELECT buffer_id, content FROM buffer_revisions WHERE id = ?",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+}
@@ -1,7 +1,116 @@
-use rusqlite::{Connection, Result};
+// Schema migrations, tracked in `PRAGMA user_version`. Migration N brings
+// the database to version N; each runs in its own transaction together with
+// the version bump, so a failed migration leaves the previous version intact.
+// Append new migrations to `MIGRATIONS`; never edit or reorder one that has
+// shipped.
 
-/// Initialize the database schema including FTS5 tables and triggers
-pub fn initialize_schema(conn: &Connection) -> Result<()> {
+use super::{backup, cards, queries, tags};
+use crate::tasks;
+use rusqlite::{Connection, Result, params};
+use std::path::PathBuf;
+use tracing::{error, info, warn};
+
+/// A schema migration, run inside a transaction
+type Migration = fn(&Connection) -> Result<()>;
+
+/// Ordered schema migrations; `MIGRATIONS[i]` brings the schema to version i + 1
+const MIGRATIONS: &[Migration] = &[
+    baseline,
+    add_usage_metrics,
+    add_archived_at,
+    add_word_count,
+    add_title_override,
+    add_slugs,
+    add_source,
+    add_task_counts,
+    add_trash,
+    add_attachments,
+    add_tags,
+    add_hashtags,
+    add_cards,
+    add_webhooks,
+    add_embeddings,
+    add_revisions,
+    add_sync_files,
+    add_activity_log,
+    add_undo_data,
+    add_scripts,
+    add_note_locks,
+    add_title_preview,
+    add_cloud_sync,
+    add_sync_base,
+];
+
+/// Schema version of the database (`PRAGMA user_version`, 0 for a new database)
+pub fn schema_version(conn: &Connection) -> Result<i64> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Number of migrations the database has not run yet
+fn pending_migrations(conn: &Connection) -> Result<usize> {
+    let version = usize::try_from(schema_version(conn)?).unwrap_or(0);
+    if version > MIGRATIONS.len() {
+        warn!("Database schema version {} is newer than this app ({})", version, MIGRATIONS.len());
+    }
+    Ok(MIGRATIONS.len().saturating_sub(version))
+}
+
+/// Run the pending migrations in order
+fn run_migrations(conn: &Connection) -> Result<()> {
+    let done = MIGRATIONS.len() - pending_migrations(conn)?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(done) {
+        let version = index as i64 + 1;
+        let tx = conn.unchecked_transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+        info!("Migrated database schema to version {}", version);
+    }
+    Ok(())
+}
+
+/// Bring the schema up to date. A database that already holds notes is
+/// backed up first, and the backup is restored if a migration fails.
+/// `key` is the passphrase of an encrypted database.
+pub fn migrate(conn: &mut Connection, app_data_dir: &PathBuf, key: Option<&str>) -> std::result::Result<(), String> {
+    let pending = pending_migrations(conn).map_err(|e| format!("Failed to read schema version: {}", e))?;
+    if pending == 0 {
+        return Ok(());
+    }
+
+    let has_data: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'buffers')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to inspect database: {}", e))?;
+    let backup_path = if has_data {
+        Some(backup::create_snapshot(conn, app_data_dir, "premigration")?)
+    } else {
+        None
+    };
+
+    let Err(e) = run_migrations(conn) else {
+        return Ok(());
+    };
+    error!("Schema migration failed: {}", e);
+    let Some(backup_path) = backup_path else {
+        return Err(format!("Failed to migrate database schema: {}", e));
+    };
+    backup::restore_backup(conn, &backup_path, key).map_err(|restore_error| {
+        format!(
+            "Failed to migrate database schema: {}; restoring the pre-migration backup failed too: {}",
+            e, restore_error
+        )
+    })?;
+    Err(format!("Failed to migrate database schema: {} (the pre-migration backup was restored)", e))
+}
+
+/// Version 1: the schema as it was before versioned migrations: notes, settings
+/// and the FTS5 index. Databases from that time may lack the `sort_order`
+/// column, so every step tolerates already being applied.
+fn baseline(conn: &Connection) -> Result<()> {
     // Create main buffers table
     conn.execute(
         "
@@ -104,8 +213,452 @@ pub fn initialize_schema(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+/// Version 2: local usage metrics (opt-in, see metrics.rs)
+fn add_usage_metrics(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE usage_metrics (
+            command TEXT PRIMARY KEY,
+            calls INTEGER NOT NULL DEFAULT 0,
+            errors INTEGER NOT NULL DEFAULT 0,
+            total_us INTEGER NOT NULL DEFAULT 0,
+            max_us INTEGER NOT NULL DEFAULT 0,
+            last_called_at INTEGER NOT NULL
+        );
+        ",
+    )
+}
+
+/// Version 3: when each archived buffer was archived
+fn add_archived_at(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE buffers ADD COLUMN archived_at INTEGER;")
+}
+
+/// Version 4: word counts for the sidebar, backfilled for existing buffers
+fn add_word_count(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE buffers ADD COLUMN word_count INTEGER NOT NULL DEFAULT 0;")?;
+    backfill_word_counts(conn)
+}
+
+/// Version 5: a custom title, independent of the content
+fn add_title_override(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE buffers ADD COLUMN title_override TEXT;")
+}
+
+/// Version 6: slugs of the effective titles, for wiki links and duplicate
+/// title checks, backfilled for existing buffers
+fn add_slugs(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE buffers ADD COLUMN slug TEXT NOT NULL DEFAULT '';
+        CREATE INDEX idx_buffers_slug ON buffers (slug);
+        ",
+    )?;
+    backfill_slugs(conn)
+}
+
+/// Version 7: where each note came from, e.g. "manual" or "import:notes.md"
+fn add_source(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE buffers ADD COLUMN source TEXT NOT NULL DEFAULT 'manual';")
+}
+
+/// Version 8: checklist counts, backfilled for existing buffers
+fn add_task_counts(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE buffers ADD COLUMN tasks_open INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE buffers ADD COLUMN tasks_total INTEGER NOT NULL DEFAULT 0;
+        ",
+    )?;
+    backfill_task_counts(conn)
+}
+
+/// Version 9: the trash. `deleted_at` is set while a buffer is in it; the
+/// index serves listing the trash and purging expired notes.
+fn add_trash(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE buffers ADD COLUMN deleted_at INTEGER;
+        CREATE INDEX idx_buffers_deleted ON buffers (deleted_at) WHERE deleted_at IS NOT NULL;
+        ",
+    )
+}
+
+/// Version 10: attachments, files stored under app_data_dir/attachments and
+/// owned by a buffer, with a companion FTS table so their text shows up in search
+fn add_attachments(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE attachments (
+            id TEXT PRIMARY KEY,
+            buffer_id TEXT NOT NULL REFERENCES buffers(id) ON DELETE CASCADE,
+            file_name TEXT NOT NULL,
+            stored_path TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            text_content TEXT NOT NULL DEFAULT '',
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE INDEX idx_attachments_buffer ON attachments (buffer_id);
+
+        CREATE VIRTUAL TABLE attachments_fts USING fts5(
+            file_name,
+            text_content,
+            content='attachments',
+            content_rowid='rowid'
+        );
+
+        CREATE TRIGGER attachments_ai AFTER INSERT ON attachments BEGIN
+            INSERT INTO attachments_fts(rowid, file_name, text_content)
+            VALUES (new.rowid, new.file_name, new.text_content);
+        END;
+
+        CREATE TRIGGER attachments_ad AFTER DELETE ON attachments BEGIN
+            INSERT INTO attachments_fts(attachments_fts, rowid, file_name, text_content)
+            VALUES('delete', old.rowid, old.file_name, old.text_content);
+        END;
+
+        CREATE TRIGGER attachments_au AFTER UPDATE ON attachments BEGIN
+            INSERT INTO attachments_fts(attachments_fts, rowid, file_name, text_content)
+            VALUES('delete', old.rowid, old.file_name, old.text_content);
+            INSERT INTO attachments_fts(rowid, file_name, text_content)
+            VALUES (new.rowid, new.file_name, new.text_content);
+        END;
+        ",
+    )
+}
+
+/// Version 11: tags assigned to notes, shared by name, with an FTS table over
+/// tag names so search finds notes by their tags
+fn add_tags(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE tags (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE TABLE buffer_tags (
+            buffer_id TEXT NOT NULL REFERENCES buffers(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            PRIMARY KEY (buffer_id, tag_id)
+        );
+
+        CREATE INDEX idx_buffer_tags_tag ON buffer_tags (tag_id);
+
+        CREATE VIRTUAL TABLE tags_fts USING fts5(
+            name,
+            content='tags',
+            content_rowid='id'
+        );
+
+        CREATE TRIGGER tags_ai AFTER INSERT ON tags BEGIN
+            INSERT INTO tags_fts(rowid, name) VALUES (new.id, new.name);
+        END;
+
+        CREATE TRIGGER tags_ad AFTER DELETE ON tags BEGIN
+            INSERT INTO tags_fts(tags_fts, rowid, name) VALUES('delete', old.id, old.name);
+        END;
+
+        CREATE TRIGGER tags_au AFTER UPDATE ON tags BEGIN
+            INSERT INTO tags_fts(tags_fts, rowid, name) VALUES('delete', old.id, old.name);
+            INSERT INTO tags_fts(rowid, name) VALUES (new.id, new.name);
+        END;
+        ",
+    )
+}
+
+/// Version 12: whether a tag was assigned by hand, written as a #hashtag in
+/// the content, or both; hashtags of existing buffers are backfilled
+fn add_hashtags(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE buffer_tags ADD COLUMN manual INTEGER NOT NULL DEFAULT 1;
+        ALTER TABLE buffer_tags ADD COLUMN in_content INTEGER NOT NULL DEFAULT 0;
+        ",
+    )?;
+    backfill_hashtags(conn)
+}
+
+/// Version 13: flashcards parsed from buffer content, with SM-2 scheduling
+/// data, backfilled for existing buffers
+fn add_cards(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE cards (
+            id INTEGER PRIMARY KEY,
+            buffer_id TEXT NOT NULL REFERENCES buffers(id) ON DELETE CASCADE,
+            question TEXT NOT NULL,
+            answer TEXT NOT NULL,
+            ease REAL NOT NULL,
+            interval_days INTEGER NOT NULL DEFAULT 0,
+            repetitions INTEGER NOT NULL DEFAULT 0,
+            due_at INTEGER NOT NULL,
+            last_reviewed_at INTEGER,
+            created_at INTEGER NOT NULL,
+            UNIQUE (buffer_id, question)
+        );
+
+        CREATE INDEX idx_cards_due ON cards (due_at);
+        ",
+    )?;
+    backfill_cards(conn)
+}
+
+/// Version 14: webhook endpoints notified of note events
+fn add_webhooks(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE webhooks (
+            id TEXT PRIMARY KEY,
+            url TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            events TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL
+        );
+        ",
+    )
+}
+
+/// Version 15: note embeddings for semantic search, one vector per buffer
+fn add_embeddings(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE embeddings (
+            buffer_id TEXT PRIMARY KEY REFERENCES buffers(id) ON DELETE CASCADE,
+            content_hash TEXT NOT NULL,
+            model TEXT NOT NULL,
+            vector BLOB NOT NULL,
+            embedded_at INTEGER NOT NULL
+        );
+        ",
+    )
+}
+
+/// Version 16: earlier versions of each buffer's content (see db/revisions.rs)
+fn add_revisions(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE buffer_revisions (
+            id INTEGER PRIMARY KEY,
+            buffer_id TEXT NOT NULL REFERENCES buffers(id) ON DELETE CASCADE,
+            content TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE INDEX idx_revisions_buffer ON buffer_revisions (buffer_id, created_at);
+        ",
+    )
+}
+
+/// Version 17: folder sync, which file mirrors each buffer and the state both
+/// sides had after the last sync. No foreign key: a mapping outlives its
+/// buffer so the sync engine can remove the file.
+fn add_sync_files(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE sync_files (
+            buffer_id TEXT PRIMARY KEY,
+            file_name TEXT NOT NULL UNIQUE,
+            content_hash TEXT NOT NULL,
+            file_modified INTEGER NOT NULL,
+            buffer_updated_at INTEGER NOT NULL
+        );
+        ",
+    )
+}
+
+/// Version 18: append-only history of note operations. No foreign key:
+/// entries outlive the buffers they describe.
+fn add_activity_log(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE activity_log (
+            id INTEGER PRIMARY KEY,
+            buffer_id TEXT,
+            operation TEXT NOT NULL,
+            origin TEXT NOT NULL,
+            detail TEXT,
+            created_at INTEGER NOT NULL
+        );
+
+        CREATE INDEX idx_activity_buffer ON activity_log (buffer_id, created_at);
+        ",
+    )
+}
+
+/// Version 19: undo/redo of structural operations. Entries written by one bulk
+/// operation share a `batch_id`; `data` holds the JSON needed to undo or redo it.
+fn add_undo_data(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE activity_log ADD COLUMN batch_id TEXT;
+        ALTER TABLE activity_log ADD COLUMN data TEXT;
+        ",
+    )
+}
+
+/// Version 20: user automation scripts
+fn add_scripts(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE scripts (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            source TEXT NOT NULL,
+            schedule_minutes INTEGER NOT NULL DEFAULT 0,
+            last_run_at INTEGER,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        ",
+    )
+}
+
+/// Version 21: per-note locks. A locked buffer keeps its encrypted content in
+/// `locked_content` and an empty `content` (see `note_lock`).
+fn add_note_locks(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE buffers ADD COLUMN is_locked INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE buffers ADD COLUMN locked_content BLOB;
+        ",
+    )
+}
+
+/// Version 22: the title and preview extracted from content, stored on save so
+/// sidebar summaries don't read whole notes. `title` ignores `title_override`.
+fn add_title_preview(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE buffers ADD COLUMN title TEXT NOT NULL DEFAULT '';
+        ALTER TABLE buffers ADD COLUMN preview TEXT NOT NULL DEFAULT '';
+        ",
+    )?;
+
+    let mut stmt = conn.prepare("SELECT id, content FROM buffers")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>>>()?;
+
+    for (id, content) in rows {
+        let (title, preview) = queries::extract_title_preview(&content);
+        conn.execute(
+            "UPDATE buffers SET title = ?, preview = ? WHERE id = ?",
+            params![title, preview, id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Version 23: iCloud sync state (see `sync::cloud`). `cloud_records` holds
+/// what each synced note and its record had in common after the last pass,
+/// `cloud_sync_state` the server change token to fetch changes from.
+fn add_cloud_sync(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE cloud_records (
+            buffer_id TEXT PRIMARY KEY,
+            content_hash TEXT NOT NULL,
+            buffer_updated_at INTEGER NOT NULL,
+            system_fields BLOB
+        );
+
+        CREATE TABLE cloud_sync_state (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            change_token BLOB
+        );
+        ",
+    )
+}
+
+/// Version 24: the content a note and its sync file last had in common, the
+/// base for merging edits made to both. NULL for mappings made before.
+fn add_sync_base(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE sync_files ADD COLUMN base_content TEXT;")
+}
+
+/// Compute slug for all existing buffers
+fn backfill_slugs(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, content, title_override FROM buffers")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    for (id, content, title_override) in rows {
+        let title = title_override.unwrap_or_else(|| queries::extract_title_preview(&content).0);
+        conn.execute(
+            "UPDATE buffers SET slug = ? WHERE id = ?",
+            params![queries::slugify(&title), id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Parse flashcards out of all existing buffers
+fn backfill_cards(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, content, updated_at FROM buffers")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?)))?
+        .collect::<Result<Vec<_>>>()?;
+
+    for (id, content, updated_at) in rows {
+        cards::sync_buffer_cards(conn, &id, &content, updated_at)?;
+    }
+    Ok(())
+}
+
+/// Index the #hashtags of all existing buffers
+fn backfill_hashtags(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, content, updated_at FROM buffers")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?)))?
+        .collect::<Result<Vec<_>>>()?;
+
+    for (id, content, updated_at) in rows {
+        tags::sync_hashtags(conn, &id, &content, updated_at)?;
+    }
+    Ok(())
+}
+
+/// Compute word_count for all existing buffers
+fn backfill_word_counts(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, content FROM buffers")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>>>()?;
+
+    for (id, content) in rows {
+        conn.execute(
+            "UPDATE buffers SET word_count = ? WHERE id = ?",
+            params![queries::count_words(&content), id],
+        )?;
+    }
+    Ok(())
+}
+
+/// Compute tasks_open/tasks_total for all existing buffers
+fn backfill_task_counts(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT id, content FROM buffers")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>>>()?;
+
+    for (id, content) in rows {
+        let (open, total) = tasks::count(&content);
+        conn.execute(
+            "UPDATE buffers SET tasks_open = ?, tasks_total = ? WHERE id = ?",
+            params![open, total, id],
+        )?;
+    }
+    Ok(())
+}
+
 /// Run database integrity check
-#[allow(dead_code)]
 pub fn check_integrity(conn: &Connection) -> Result<bool> {
     let result: String = conn.query_row("PRAGMA integrity_check;", [], |row| row.get(0))?;
     Ok(result == "ok")
@@ -117,3 +670,80 @@ pub fn vacuum(conn: &Connection) -> Result<()> {
     conn.execute("VACUUM;", [])?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table_exists(conn: &Connection, name: &str) -> bool {
+        conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?)",
+            [name],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn migrates_a_new_database_to_the_latest_version() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn, &PathBuf::new(), None).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), MIGRATIONS.len() as i64);
+        for table in ["buffers", "sync_files", "cloud_records", "cloud_sync_state"] {
+            assert!(table_exists(&conn, table), "missing table {}", table);
+        }
+
+        // Nothing left to run the second time
+        migrate(&mut conn, &PathBuf::new(), None).unwrap();
+        assert_eq!(pending_migrations(&conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn baseline_tolerates_a_pre_versioning_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        baseline(&conn).unwrap();
+        // Unversioned databases from before migrations already have the baseline
+        baseline(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), MIGRATIONS.len() as i64);
+    }
+
+    #[test]
+    fn migrations_backfill_existing_notes() {
+        let content = "# Hello **world**\n\n- [ ] Second line #todo";
+        let conn = Connection::open_in_memory().unwrap();
+        baseline(&conn).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        conn.execute(
+            "INSERT INTO buffers (id, content, created_at, updated_at, accessed_at) VALUES (?, ?, 1, 1, 1)",
+            params!["note", content],
+        )
+        .unwrap();
+
+        run_migrations(&conn).unwrap();
+        let row: (String, String, String, i64, i64) = conn
+            .query_row(
+                "SELECT title, preview, slug, word_count, tasks_open FROM buffers WHERE id = 'note'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )
+            .unwrap();
+        let (title, preview) = queries::extract_title_preview(content);
+        assert_eq!(row, (title, preview, "hello-world".to_string(), queries::count_words(content), 1));
+        let tag: String = conn
+            .query_row("SELECT t.name FROM tags t JOIN buffer_tags bt ON bt.tag_id = t.id", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tag, "todo");
+        assert_eq!(schema_version(&conn).unwrap(), MIGRATIONS.len() as i64);
+    }
+
+    #[test]
+    fn leaves_a_newer_schema_alone() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let newer = MIGRATIONS.len() as i64 + 1;
+        conn.pragma_update(None, "user_version", newer).unwrap();
+        migrate(&mut conn, &PathBuf::new(), None).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), newer);
+        assert!(!table_exists(&conn, "buffers"));
+    }
+}
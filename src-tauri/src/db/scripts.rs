@@ -0,0 +1,110 @@
+use rusqlite::{Connection, OptionalExtension, Result, params};
+use serde::{Deserialize, Serialize};
+
+/// Stored user automation script
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Script {
+    pub id: String,
+    pub name: String,
+    pub source: String,
+    /// Run automatically every this many minutes (0 = on demand only)
+    pub schedule_minutes: i64,
+    pub last_run_at: Option<i64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const SCRIPT_COLUMNS: &str = "id, name, source, schedule_minutes, last_run_at, created_at, updated_at";
+
+fn row_to_script(row: &rusqlite::Row) -> Result<Script> {
+    Ok(Script {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        source: row.get(2)?,
+        schedule_minutes: row.get(3)?,
+        last_run_at: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+/// Insert a script, or update the one with the same id
+pub fn upsert_script(conn: &Connection, script: &Script) -> Result<()> {
+    conn.execute(
+        "
+        INSERT INTO scripts (id, name, source, schedule_minutes, last_run_at, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            source = excluded.source,
+            schedule_minutes = excluded.schedule_minutes,
+            updated_at = excluded.updated_at
+        ",
+        params![
+            script.id,
+            script.name,
+            script.source,
+            script.schedule_minutes,
+            script.last_run_at,
+            script.created_at,
+            script.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// All scripts, by name
+pub fn list_scripts(conn: &Connection) -> Result<Vec<Script>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM scripts ORDER BY name COLLATE NOCASE", SCRIPT_COLUMNS))?;
+    let scripts = stmt.query_map([], row_to_script)?.collect::<Result<Vec<_>>>()?;
+    Ok(scripts)
+}
+
+pub fn get_script(conn: &Connection, id: &str) -> Result<Option<Script>> {
+    conn.query_row(
+        &format!("SELECT {} FROM scripts WHERE id = ?", SCRIPT_COLUMNS),
+        params![id],
+        row_to_script,
+    )
+    .optional()
+}
+
+/// Id of the script called `name`, if any
+pub fn find_script_by_name(conn: &Connection, name: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT id FROM scripts WHERE name = ? COLLATE NOCASE",
+        params![name],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Scheduled scripts whose interval has elapsed at `now`
+pub fn get_due_scripts(conn: &Connection, now: i64) -> Result<Vec<Script>> {
+    let mut stmt = conn.prepare(&format!(
+        "
+        SELECT {} FROM scripts
+        WHERE schedule_minutes > 0
+          AND (last_run_at IS NULL OR last_run_at + schedule_minutes * 60 <= ?)
+        ORDER BY name COLLATE NOCASE
+        ",
+        SCRIPT_COLUMNS
+    ))?;
+    let scripts = stmt.query_map(params![now], row_to_script)?.collect::<Result<Vec<_>>>()?;
+    Ok(scripts)
+}
+
+/// Record that a script ran
+pub fn mark_script_run(conn: &Connection, id: &str, timestamp: i64) -> Result<()> {
+    conn.execute(
+        "UPDATE scripts SET last_run_at = ? WHERE id = ?",
+        params![timestamp, id],
+    )?;
+    Ok(())
+}
+
+/// Delete a script. Returns false if it doesn't exist.
+pub fn delete_script(conn: &Connection, id: &str) -> Result<bool> {
+    let rows = conn.execute("DELETE FROM scripts WHERE id = ?", params![id])?;
+    Ok(rows > 0)
+}
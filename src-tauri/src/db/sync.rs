@@ -0,0 +1,79 @@
+use rusqlite::{Connection, Result, params};
+
+/// A buffer mirrored to a file, with the state both sides had when they were
+/// last in sync
+#[derive(Debug, Clone)]
+pub struct SyncedFile {
+    pub buffer_id: String,
+    pub file_name: String,
+    pub content_hash: String,
+    /// File modification time (Unix seconds) right after the last sync
+    pub file_modified: i64,
+    /// Buffer `updated_at` at the last sync
+    pub buffer_updated_at: i64,
+    /// Content both sides had at the last sync (None for mappings recorded
+    /// before it was kept)
+    pub base_content: Option<String>,
+}
+
+/// Every buffer-to-file mapping
+pub fn get_synced_files(conn: &Connection) -> Result<Vec<SyncedFile>> {
+    let mut stmt = conn.prepare(
+        "SELECT buffer_id, file_name, content_hash, file_modified, buffer_updated_at, base_content FROM sync_files",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(SyncedFile {
+            buffer_id: row.get(0)?,
+            file_name: row.get(1)?,
+            content_hash: row.get(2)?,
+            file_modified: row.get(3)?,
+            buffer_updated_at: row.get(4)?,
+            base_content: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Record that a buffer and its file are in sync
+pub fn upsert_synced_file(conn: &Connection, file: &SyncedFile) -> Result<()> {
+    conn.execute(
+        "
+        INSERT INTO sync_files (buffer_id, file_name, content_hash, file_modified, buffer_updated_at, base_content)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(buffer_id) DO UPDATE SET
+            file_name = excluded.file_name,
+            content_hash = excluded.content_hash,
+            file_modified = excluded.file_modified,
+            buffer_updated_at = excluded.buffer_updated_at,
+            base_content = excluded.base_content
+        ",
+        params![
+            file.buffer_id,
+            file.file_name,
+            file.content_hash,
+            file.file_modified,
+            file.buffer_updated_at,
+            file.base_content,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Forget a buffer's file
+pub fn delete_synced_file(conn: &Connection, buffer_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM sync_files WHERE buffer_id = ?", params![buffer_id])?;
+    Ok(())
+}
+
+/// Drop the plaintext sync base of a buffer (e.g. it was locked); a conflict
+/// then falls back to keeping the newer side
+pub fn clear_base(conn: &Connection, buffer_id: &str) -> Result<()> {
+    conn.execute("UPDATE sync_files SET base_content = NULL WHERE buffer_id = ?", params![buffer_id])?;
+    Ok(())
+}
+
+/// Forget all mappings, e.g. when the sync folder changes
+pub fn clear(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM sync_files", [])?;
+    Ok(())
+}
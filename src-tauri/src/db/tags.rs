@@ -0,0 +1,287 @@
+use super::queries;
+use rusqlite::{Connection, Result, params};
+use serde::Serialize;
+use serde_json::json;
+
+/// Most co-occurring tag pairs returned with the stats
+const MAX_PAIRS: usize = 200;
+
+/// Longest allowed tag name, in characters
+pub const MAX_TAG_LEN: usize = 64;
+
+/// A tag with the number of active notes carrying it
+#[derive(Debug, Serialize)]
+pub struct TagSummary {
+    pub name: String,
+    pub note_count: i64,
+}
+
+/// A note whose tag matched a search
+#[derive(Debug)]
+pub struct TagMatch {
+    pub buffer_id: String,
+    pub tag: String,
+    pub updated_at: i64,
+    pub rank: f64,
+}
+
+/// Usage of one #hashtag across active notes
+#[derive(Debug, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub note_count: i64,
+    /// Latest `updated_at` of a note using the tag
+    pub last_used: i64,
+}
+
+/// Two tags appearing in the same notes. `a` sorts before `b`.
+#[derive(Debug, Serialize)]
+pub struct TagPair {
+    pub a: String,
+    pub b: String,
+    pub note_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagStats {
+    pub tags: Vec<TagCount>,
+    pub pairs: Vec<TagPair>,
+}
+
+/// (buffer, tag, updated_at) rows for every #hashtag in active notes,
+/// as a JSON array SQLite can read with `json_each`
+fn note_tags_json(conn: &Connection) -> Result<String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, content, updated_at FROM buffers WHERE is_archived = 0 AND deleted_at IS NULL"
+    )?;
+    let mut rows = stmt.query([])?;
+
+    let mut note_tags = Vec::new();
+    while let Some(row) = rows.next()? {
+        let id: String = row.get(0)?;
+        let content: String = row.get(1)?;
+        let updated_at: i64 = row.get(2)?;
+        for tag in queries::extract_hashtags(&content) {
+            note_tags.push(json!([id, tag, updated_at]));
+        }
+    }
+    Ok(serde_json::Value::Array(note_tags).to_string())
+}
+
+/// Per-tag note counts and last use, plus the most frequent co-occurring pairs
+pub fn get_tag_stats(conn: &Connection) -> Result<TagStats> {
+    let note_tags = note_tags_json(conn)?;
+    let cte = "
+        WITH note_tags AS (
+            SELECT json_extract(value, '$[0]') AS buffer_id,
+                   json_extract(value, '$[1]') AS tag,
+                   json_extract(value, '$[2]') AS updated_at
+            FROM json_each(?1)
+        )
+    ";
+
+    let mut stmt = conn.prepare(&format!(
+        "{}
+        SELECT tag, COUNT(*), MAX(updated_at)
+        FROM note_tags
+        GROUP BY tag
+        ORDER BY COUNT(*) DESC, tag ASC",
+        cte
+    ))?;
+    let tags = stmt
+        .query_map(params![note_tags], |row| {
+            Ok(TagCount {
+                tag: row.get(0)?,
+                note_count: row.get(1)?,
+                last_used: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "{}
+        SELECT a.tag, b.tag, COUNT(*)
+        FROM note_tags a
+        JOIN note_tags b ON b.buffer_id = a.buffer_id AND a.tag < b.tag
+        GROUP BY a.tag, b.tag
+        ORDER BY COUNT(*) DESC, a.tag ASC, b.tag ASC
+        LIMIT ?2",
+        cte
+    ))?;
+    let pairs = stmt
+        .query_map(params![note_tags, MAX_PAIRS as i64], |row| {
+            Ok(TagPair {
+                a: row.get(0)?,
+                b: row.get(1)?,
+                note_count: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(TagStats { tags, pairs })
+}
+
+/// Normalize a tag name the way #hashtags are ("#Work " -> "work"). None if
+/// it is empty, too long, has characters a hashtag can't contain, or no letter.
+pub fn normalize_name(name: &str) -> Option<String> {
+    let name = name.trim().trim_start_matches('#').to_lowercase();
+    let valid = !name.is_empty()
+        && name.chars().count() <= MAX_TAG_LEN
+        && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '/')
+        && name.chars().any(char::is_alphabetic);
+    valid.then_some(name)
+}
+
+/// Tag a buffer (`name` must be normalized), creating the tag if needed
+pub fn add_tag(conn: &Connection, buffer_id: &str, name: &str, timestamp: i64) -> Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO tags (name, created_at) VALUES (?, ?)",
+        params![name, timestamp],
+    )?;
+    conn.execute(
+        "
+        INSERT INTO buffer_tags (buffer_id, tag_id, manual, in_content)
+        SELECT ?, id, 1, 0 FROM tags WHERE name = ?
+        ON CONFLICT(buffer_id, tag_id) DO UPDATE SET manual = 1
+        ",
+        params![buffer_id, name],
+    )?;
+    Ok(())
+}
+
+/// Drop buffer tags that are neither assigned by hand nor in the content,
+/// then tags no note uses anymore
+fn remove_unused(conn: &Connection, buffer_id: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM buffer_tags WHERE buffer_id = ? AND manual = 0 AND in_content = 0",
+        params![buffer_id],
+    )?;
+    conn.execute(
+        "DELETE FROM tags WHERE NOT EXISTS (SELECT 1 FROM buffer_tags WHERE tag_id = tags.id)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Remove a hand-assigned tag from a buffer. A tag also written as a
+/// #hashtag in the content stays until it is removed from the text.
+/// Returns false if the buffer didn't have the tag assigned.
+pub fn remove_tag(conn: &Connection, buffer_id: &str, name: &str) -> Result<bool> {
+    let removed = conn.execute(
+        "
+        UPDATE buffer_tags SET manual = 0
+        WHERE buffer_id = ? AND manual = 1 AND tag_id = (SELECT id FROM tags WHERE name = ?)
+        ",
+        params![buffer_id, name],
+    )? > 0;
+    remove_unused(conn, buffer_id)?;
+    Ok(removed)
+}
+
+/// Bring a buffer's #hashtag index in line with its content
+pub fn sync_hashtags(conn: &Connection, buffer_id: &str, content: &str, timestamp: i64) -> Result<()> {
+    conn.execute("UPDATE buffer_tags SET in_content = 0 WHERE buffer_id = ?", params![buffer_id])?;
+    for name in queries::extract_hashtags(content).iter().filter_map(|tag| normalize_name(tag)) {
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (name, created_at) VALUES (?, ?)",
+            params![name, timestamp],
+        )?;
+        conn.execute(
+            "
+            INSERT INTO buffer_tags (buffer_id, tag_id, manual, in_content)
+            SELECT ?, id, 0, 1 FROM tags WHERE name = ?
+            ON CONFLICT(buffer_id, tag_id) DO UPDATE SET in_content = 1
+            ",
+            params![buffer_id, name],
+        )?;
+    }
+    remove_unused(conn, buffer_id)
+}
+
+/// #hashtags used in active notes with their note counts and last use,
+/// most used first
+pub fn get_all_hashtags(conn: &Connection) -> Result<Vec<TagCount>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT t.name, COUNT(*), MAX(b.updated_at)
+        FROM buffer_tags bt
+        JOIN tags t ON t.id = bt.tag_id
+        JOIN buffers b ON b.id = bt.buffer_id
+        WHERE bt.in_content = 1 AND b.is_archived = 0 AND b.deleted_at IS NULL
+        GROUP BY t.id
+        ORDER BY COUNT(*) DESC, t.name ASC
+        ",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(TagCount {
+            tag: row.get(0)?,
+            note_count: row.get(1)?,
+            last_used: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// A buffer's tags, alphabetically
+pub fn get_buffer_tags(conn: &Connection, buffer_id: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare_cached(
+        "
+        SELECT t.name FROM tags t
+        JOIN buffer_tags bt ON bt.tag_id = t.id
+        WHERE bt.buffer_id = ?
+        ORDER BY t.name ASC
+        ",
+    )?;
+    let rows = stmt.query_map(params![buffer_id], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Every tag with its count of active (not archived or trashed) notes,
+/// most used first
+pub fn list_tags(conn: &Connection) -> Result<Vec<TagSummary>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT t.name, COUNT(b.id)
+        FROM tags t
+        LEFT JOIN buffer_tags bt ON bt.tag_id = t.id
+        LEFT JOIN buffers b ON b.id = bt.buffer_id AND b.is_archived = 0 AND b.deleted_at IS NULL
+        GROUP BY t.id
+        ORDER BY COUNT(b.id) DESC, t.name ASC
+        ",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(TagSummary {
+            name: row.get(0)?,
+            note_count: row.get(1)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Active notes carrying a tag whose name matches `query` (already in FTS5
+/// syntax; `source` filters as in `queries::search_buffers`)
+pub fn search_tags(conn: &Connection, query: &str, source: Option<&str>, limit: usize) -> Result<Vec<TagMatch>> {
+    let mut stmt = conn.prepare(
+        "
+        SELECT bt.buffer_id, t.name, b.updated_at, tags_fts.rank
+        FROM tags_fts
+        JOIN tags t ON tags_fts.rowid = t.id
+        JOIN buffer_tags bt ON bt.tag_id = t.id
+        JOIN buffers b ON b.id = bt.buffer_id
+        WHERE tags_fts MATCH ?1
+        AND b.is_archived = 0 AND b.deleted_at IS NULL
+        AND (?2 IS NULL OR b.source = ?2 OR substr(b.source, 1, length(?2) + 1) = ?2 || ':')
+        ORDER BY rank
+        LIMIT ?3
+        ",
+    )?;
+    let rows = stmt.query_map(params![query, source, limit as i64], |row| {
+        Ok(TagMatch {
+            buffer_id: row.get(0)?,
+            tag: row.get(1)?,
+            updated_at: row.get(2)?,
+            rank: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
@@ -0,0 +1,110 @@
+use rusqlite::{Connection, Result, params};
+use serde::{Deserialize, Serialize};
+
+/// Note events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Created,
+    Updated,
+    Deleted,
+    Archived,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Updated => "updated",
+            Self::Deleted => "deleted",
+            Self::Archived => "archived",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "created" => Some(Self::Created),
+            "updated" => Some(Self::Updated),
+            "deleted" => Some(Self::Deleted),
+            "archived" => Some(Self::Archived),
+            _ => None,
+        }
+    }
+}
+
+/// Configured webhook endpoint. The secret signs payloads and is only
+/// returned when the webhook is created.
+#[derive(Debug, Clone, Serialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    pub enabled: bool,
+    pub created_at: i64,
+}
+
+fn row_to_webhook(row: &rusqlite::Row) -> Result<Webhook> {
+    let events: String = row.get(3)?;
+    Ok(Webhook {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        secret: row.get(2)?,
+        events: events.split(',').filter_map(WebhookEvent::parse).collect(),
+        enabled: row.get::<_, i64>(4)? != 0,
+        created_at: row.get(5)?,
+    })
+}
+
+/// Insert a webhook
+pub fn insert_webhook(conn: &Connection, webhook: &Webhook) -> Result<()> {
+    let events: Vec<&str> = webhook.events.iter().map(|e| e.as_str()).collect();
+    conn.execute(
+        "
+        INSERT INTO webhooks (id, url, secret, events, enabled, created_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ",
+        params![
+            webhook.id,
+            webhook.url,
+            webhook.secret,
+            events.join(","),
+            webhook.enabled as i64,
+            webhook.created_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// All webhooks (secrets included), oldest first
+pub fn list_webhooks(conn: &Connection) -> Result<Vec<Webhook>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, url, secret, events, enabled, created_at FROM webhooks ORDER BY created_at, id",
+    )?;
+    let webhooks = stmt.query_map([], row_to_webhook)?.collect::<Result<Vec<_>>>()?;
+    Ok(webhooks)
+}
+
+/// Enabled webhooks subscribed to `event`
+pub fn webhooks_for_event(conn: &Connection, event: WebhookEvent) -> Result<Vec<Webhook>> {
+    Ok(list_webhooks(conn)?
+        .into_iter()
+        .filter(|w| w.enabled && w.events.contains(&event))
+        .collect())
+}
+
+/// Enable or disable a webhook. Returns false if it doesn't exist.
+pub fn set_webhook_enabled(conn: &Connection, id: &str, enabled: bool) -> Result<bool> {
+    let rows = conn.execute(
+        "UPDATE webhooks SET enabled = ? WHERE id = ?",
+        params![enabled as i64, id],
+    )?;
+    Ok(rows > 0)
+}
+
+/// Delete a webhook. Returns false if it doesn't exist.
+pub fn delete_webhook(conn: &Connection, id: &str) -> Result<bool> {
+    let rows = conn.execute("DELETE FROM webhooks WHERE id = ?", params![id])?;
+    Ok(rows > 0)
+}
@@ -0,0 +1,42 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+/// Per-key debouncing for background work triggered by frequent events
+/// (e.g. autosaves). Each event bumps the key's generation; after its delay
+/// the work only runs if no newer event arrived in the meantime.
+pub struct Debouncer {
+    generations: Mutex<HashMap<String, u64>>,
+}
+
+impl Debouncer {
+    pub fn new() -> Self {
+        Self {
+            generations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record an event for `key`, returning its generation
+    pub fn bump(&self, key: &str) -> u64 {
+        let mut generations = self.generations.lock();
+        let generation = generations.entry(key.to_string()).or_insert(0);
+        *generation += 1;
+        *generation
+    }
+
+    /// Whether `generation` is still the latest event for `key`.
+    /// If so the key is cleared, so the work runs exactly once.
+    pub fn take_if_latest(&self, key: &str, generation: u64) -> bool {
+        let mut generations = self.generations.lock();
+        if generations.get(key) != Some(&generation) {
+            return false;
+        }
+        generations.remove(key);
+        true
+    }
+}
+
+impl Default for Debouncer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,166 @@
+// flashnotes:// links: `flashnotes://note/<id>` opens a note and
+// `flashnotes://new?content=...` creates one. Links arrive through the
+// deep-link plugin (macOS and mobile, plus the launch arguments elsewhere)
+// and, on Windows and Linux, as arguments of a second instance that the
+// single-instance callback forwards. Both end in `OPEN_BUFFER_EVENT`; a link
+// that launched the app is also kept until the frontend asks for it, since
+// it arrives before the window listens.
+
+use crate::commands::buffer::insert_buffer;
+use crate::db::activity::Origin;
+use crate::db::queries;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::AppState;
+use crate::tray;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use tauri::{AppHandle, Manager, Url};
+use tracing::{info, warn};
+
+/// URL scheme registered for the app (see `plugins.deep-link` in tauri.conf.json)
+pub const SCHEME: &str = "flashnotes";
+
+/// What a link asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Link {
+    /// `flashnotes://note/<id>`
+    Note(String),
+    /// `flashnotes://new?content=...` (content optional)
+    New(String),
+}
+
+impl Link {
+    pub fn parse(url: &Url) -> AppResult<Self> {
+        if url.scheme() != SCHEME {
+            return Err(AppError::Validation(format!("Not a {} link: {}", SCHEME, url)));
+        }
+        match url.host_str() {
+            Some("note") => {
+                let id = url.path().trim_matches('/');
+                if id.is_empty() || id.contains('/') {
+                    return Err(AppError::Validation(format!("Invalid note link: {}", url)));
+                }
+                Ok(Self::Note(id.to_string()))
+            }
+            Some("new") => {
+                let content = url
+                    .query_pairs()
+                    .find(|(key, _)| key == "content")
+                    .map(|(_, value)| value.into_owned())
+                    .unwrap_or_default();
+                Ok(Self::New(content))
+            }
+            _ => Err(AppError::Validation(format!("Unknown link: {}", url))),
+        }
+    }
+}
+
+/// Note opened by the link the app was launched with, until the frontend
+/// takes it; links after that go straight to the event
+#[derive(Default)]
+pub struct PendingLink {
+    id: Mutex<Option<String>>,
+    taken: AtomicBool,
+}
+
+impl PendingLink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember the note while the frontend has not asked yet
+    fn offer(&self, id: &str) {
+        if !self.taken.load(Ordering::SeqCst) {
+            *self.id.lock() = Some(id.to_string());
+        }
+    }
+
+    /// The pending note, if any; later links are no longer kept
+    pub fn take(&self) -> Option<String> {
+        self.taken.store(true, Ordering::SeqCst);
+        self.id.lock().take()
+    }
+}
+
+/// Open or create the note a link points to
+fn follow(app: &AppHandle, link: Link) -> AppResult<()> {
+    let state = app.state::<AppState>();
+    state.ready.wait()?;
+    let id = match link {
+        Link::Note(id) => {
+            let exists = state
+                .read(|conn| queries::get_buffer_content(conn, &id))
+                .db_context("Failed to get buffer")?
+                .is_some();
+            if !exists {
+                return Err(AppError::not_found("Buffer", id));
+            }
+            id
+        }
+        Link::New(content) => insert_buffer(app, &state, &content, "deep_link", Origin::DeepLink)?.id,
+    };
+    state.deep_link.offer(&id);
+    tray::open_buffer(app, id);
+    Ok(())
+}
+
+/// Handle `flashnotes://` URLs; anything else (e.g. other arguments) is ignored.
+/// Runs off the calling thread since startup may still be opening the database.
+pub fn handle_urls(app: &AppHandle, urls: Vec<Url>) {
+    let links: Vec<Link> = urls
+        .iter()
+        .filter(|url| url.scheme() == SCHEME)
+        .filter_map(|url| Link::parse(url).map_err(|e| warn!("Ignoring link: {}", e)).ok())
+        .collect();
+    if links.is_empty() {
+        return;
+    }
+
+    let app = app.clone();
+    let spawned = thread::Builder::new().name("deep-link".to_string()).spawn(move || {
+        for link in links {
+            info!("Following link: {:?}", link);
+            if let Err(e) = follow(&app, link) {
+                warn!("Failed to follow link: {}", e);
+            }
+        }
+    });
+    if let Err(e) = spawned {
+        warn!("Failed to handle link: {}", e);
+    }
+}
+
+/// Handle the links among a second instance's command line arguments
+#[cfg(desktop)]
+pub fn handle_args(app: &AppHandle, args: &[String]) {
+    let prefix = format!("{}://", SCHEME);
+    let urls = args
+        .iter()
+        .filter(|arg| arg.starts_with(&prefix))
+        .filter_map(|arg| Url::parse(arg).ok())
+        .collect();
+    handle_urls(app, urls);
+}
+
+/// Listen for links and handle the one the app was launched with
+pub fn install(app: &AppHandle) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| handle_urls(&handle, event.urls()));
+
+    // Unpackaged builds on Windows and Linux have no registration from an installer
+    #[cfg(any(windows, target_os = "linux"))]
+    {
+        if let Err(e) = app.deep_link().register_all() {
+            warn!("Failed to register {}:// links: {}", SCHEME, e);
+        }
+        // macOS and mobile deliver launch links through `on_open_url` as well
+        match app.deep_link().get_current() {
+            Ok(Some(urls)) => handle_urls(app, urls),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to read launch link: {}", e),
+        }
+    }
+}
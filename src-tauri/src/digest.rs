@@ -0,0 +1,144 @@
+// Weekly "Week in review" note: what was created and edited, the most used
+// tags and the tasks ticked off, optionally with an AI summary
+
+use crate::ai::{self, Feature};
+use crate::db::queries::{self, AppSettings, Buffer};
+use crate::tasks;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc, Weekday};
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Source of generated digest notes (digests leave themselves out)
+pub const DIGEST_SOURCE: &str = "digest";
+
+const WEEK_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Tags listed under "Top tags"
+const TOP_TAGS: usize = 10;
+
+/// Notes and tasks listed before the rest are summed up as "and N more"
+const MAX_LISTED: usize = 20;
+
+/// The latest scheduled digest time (`day` at `hour`:00 local) at or before `now`
+pub fn last_scheduled(now: DateTime<Local>, day: Weekday, hour: u32) -> Option<DateTime<Local>> {
+    let today = now.date_naive();
+    let days_back = (7 + today.weekday().num_days_from_monday() - day.num_days_from_monday()) % 7;
+    let at = |date: NaiveDate| date.and_hms_opt(hour, 0, 0)?.and_local_timezone(Local).earliest();
+
+    let date = today - Duration::days(i64::from(days_back));
+    let scheduled = at(date)?;
+    if scheduled.timestamp() <= now.timestamp() {
+        Some(scheduled)
+    } else {
+        at(date - Duration::days(7))
+    }
+}
+
+/// Start of the week a digest scheduled at `end` covers
+pub fn week_start(end: i64) -> i64 {
+    end - WEEK_SECS
+}
+
+fn title(buffer: &Buffer) -> String {
+    buffer
+        .title_override
+        .clone()
+        .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0)
+}
+
+/// Bullet list of at most `MAX_LISTED` items
+fn push_list(out: &mut String, items: &[String]) {
+    for item in items.iter().take(MAX_LISTED) {
+        out.push_str(&format!("- {}\n", item));
+    }
+    if items.len() > MAX_LISTED {
+        out.push_str(&format!("- …and {} more\n", items.len() - MAX_LISTED));
+    }
+}
+
+/// AI summary of the week's notes, when enabled and a provider is configured
+fn ai_summary(settings: &AppSettings, buffers: &[&Buffer]) -> Option<String> {
+    if !settings.digest_ai_summary {
+        return None;
+    }
+    let notes: Vec<String> = buffers.iter().map(|b| b.content.trim().to_string()).collect();
+    let result = ai::provider(settings).and_then(|provider| {
+        let prompt = Feature::Summarize.prompt(&notes.join("\n\n---\n\n"))?;
+        ai::complete(provider.as_ref(), &prompt, &mut |_| {})
+    });
+    match result {
+        Ok(summary) if !summary.is_empty() => Some(summary),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("Digest AI summary failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Markdown for the digest of notes updated in `[start, end)`. Completed
+/// tasks are the ticked checkboxes in notes edited that week.
+pub fn render(settings: &AppSettings, buffers: &[Buffer], start: i64, end: i64) -> String {
+    let buffers: Vec<&Buffer> = buffers.iter().filter(|b| b.source != DIGEST_SOURCE).collect();
+    let local_date = |ts: i64| DateTime::<Utc>::from_timestamp(ts, 0).map(|d| d.with_timezone(&Local).date_naive());
+
+    let mut out = String::from("# Week in review");
+    if let (Some(first), Some(last)) = (local_date(start), local_date(end - 1)) {
+        out.push_str(&format!(": {} – {}", first.format("%b %-d"), last.format("%b %-d, %Y")));
+    }
+    out.push_str("\n\n");
+
+    let created = buffers.iter().filter(|b| b.created_at >= start).count();
+    out.push_str(&format!(
+        "{} note{} created, {} edited.\n\n",
+        created,
+        if created == 1 { "" } else { "s" },
+        buffers.len() - created
+    ));
+
+    if let Some(summary) = ai_summary(settings, &buffers) {
+        out.push_str(&format!("## Summary\n\n{}\n\n", summary));
+    }
+
+    let mut tag_counts: HashMap<String, usize> = HashMap::new();
+    for buffer in &buffers {
+        for tag in queries::extract_hashtags(&buffer.content) {
+            *tag_counts.entry(tag).or_default() += 1;
+        }
+    }
+    if !tag_counts.is_empty() {
+        let mut tags: Vec<(String, usize)> = tag_counts.into_iter().collect();
+        tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let top: Vec<String> = tags
+            .iter()
+            .take(TOP_TAGS)
+            .map(|(tag, count)| format!("#{} ({})", tag, count))
+            .collect();
+        out.push_str(&format!("## Top tags\n\n{}\n\n", top.join(", ")));
+    }
+
+    let done: Vec<String> = buffers
+        .iter()
+        .flat_map(|b| {
+            let title = title(b);
+            tasks::parse_tasks(&b.content)
+                .into_iter()
+                .filter(|t| t.done && !t.text.is_empty())
+                .map(move |t| format!("{} — [[{}]]", t.text, title))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    if !done.is_empty() {
+        out.push_str(&format!("## Completed tasks ({})\n\n", done.len()));
+        push_list(&mut out, &done);
+        out.push('\n');
+    }
+
+    if !buffers.is_empty() {
+        out.push_str("## Notes\n\n");
+        let notes: Vec<String> = buffers.iter().rev().map(|b| format!("[[{}]]", title(b))).collect();
+        push_list(&mut out, &notes);
+    }
+
+    out.trim_end().to_string() + "\n"
+}
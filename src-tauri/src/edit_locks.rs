@@ -0,0 +1,149 @@
+// Advisory edit locks: the window that opens a buffer for editing holds its
+// lock and refreshes it with heartbeats; other windows opening the same
+// buffer are told it's being edited elsewhere and can show it read-only.
+// Nothing is enforced on save; a lock without a recent heartbeat (e.g. a
+// crashed webview) is treated as free.
+
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use crate::state::AppState;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::warn;
+
+/// Event emitted when a buffer's lock holder changes
+pub const EDIT_LOCK_CHANGED_EVENT: &str = "edit-lock-changed";
+
+/// A lock without a heartbeat for this long can be taken over.
+/// Windows should heartbeat well within it (e.g. every 10 seconds).
+pub const LOCK_TIMEOUT_SECS: i64 = 30;
+
+/// Result of trying to take a buffer's edit lock
+#[derive(Debug, Clone, Serialize)]
+pub struct EditLockStatus {
+    pub buffer_id: String,
+    /// False when another window is editing; open the buffer read-only
+    pub acquired: bool,
+    /// Label of the window holding the lock, if any
+    pub holder: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct EditLockChanged {
+    buffer_id: String,
+    holder: Option<String>,
+}
+
+struct Lock {
+    window: String,
+    heartbeat_at: i64,
+}
+
+/// Edit locks by buffer id
+pub struct EditLocks {
+    locks: Mutex<HashMap<String, Lock>>,
+}
+
+impl EditLocks {
+    pub fn new() -> Self {
+        Self {
+            locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take the lock for `window`, or refresh it if the window already holds
+    /// it. Returns the status and whether the holder changed.
+    fn acquire(&self, buffer_id: &str, window: &str, now: i64) -> (EditLockStatus, bool) {
+        let mut locks = self.locks.lock();
+        let held_elsewhere = locks
+            .get(buffer_id)
+            .filter(|lock| lock.window != window && now - lock.heartbeat_at < LOCK_TIMEOUT_SECS)
+            .map(|lock| lock.window.clone());
+
+        if let Some(holder) = held_elsewhere {
+            let status = EditLockStatus { buffer_id: buffer_id.to_string(), acquired: false, holder: Some(holder) };
+            return (status, false);
+        }
+
+        let previous = locks.insert(
+            buffer_id.to_string(),
+            Lock {
+                window: window.to_string(),
+                heartbeat_at: now,
+            },
+        );
+        let changed = !matches!(previous, Some(lock) if lock.window == window);
+        let status = EditLockStatus {
+            buffer_id: buffer_id.to_string(),
+            acquired: true,
+            holder: Some(window.to_string()),
+        };
+        (status, changed)
+    }
+
+    /// Drop the lock if `window` holds it. Returns whether it did.
+    fn release(&self, buffer_id: &str, window: &str) -> bool {
+        let mut locks = self.locks.lock();
+        if locks.get(buffer_id).is_some_and(|lock| lock.window == window) {
+            locks.remove(buffer_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Drop every lock held by `window`, returning the buffer ids
+    fn release_window(&self, window: &str) -> Vec<String> {
+        let mut locks = self.locks.lock();
+        let held: Vec<String> = locks
+            .iter()
+            .filter(|(_, lock)| lock.window == window)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &held {
+            locks.remove(id);
+        }
+        held
+    }
+}
+
+impl Default for EditLocks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn emit_changed(app: &AppHandle, buffer_id: &str, holder: Option<String>) {
+    let payload = EditLockChanged { buffer_id: buffer_id.to_string(), holder };
+    if let Err(e) = app.emit(EDIT_LOCK_CHANGED_EVENT, payload) {
+        warn!("Failed to emit {} event: {}", EDIT_LOCK_CHANGED_EVENT, e);
+    }
+}
+
+/// Take or refresh `window`'s lock on a buffer, telling other windows when
+/// the holder changes
+pub fn acquire(app: &AppHandle, buffer_id: &str, window: &str) -> EditLockStatus {
+    let state = app.state::<AppState>();
+    let (status, changed) = state.edit_locks.acquire(buffer_id, window, Utc::now().timestamp());
+    if changed {
+        emit_changed(app, buffer_id, status.holder.clone());
+    }
+    status
+}
+
+/// Release `window`'s lock on a buffer (no-op if it doesn't hold it)
+pub fn release(app: &AppHandle, buffer_id: &str, window: &str) {
+    let state = app.state::<AppState>();
+    if state.edit_locks.release(buffer_id, window) {
+        emit_changed(app, buffer_id, None);
+    }
+}
+
+/// Release everything a closed window was editing
+pub fn release_window(app: &AppHandle, window: &str) {
+    let state = app.state::<AppState>();
+    for buffer_id in state.edit_locks.release_window(window) {
+        emit_changed(app, &buffer_id, None);
+    }
+}
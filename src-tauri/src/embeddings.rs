@@ -0,0 +1,207 @@
+// Embedding index for semantic search. Vectors are kept per buffer with a
+// hash of the content they were made from; only notes whose content changed
+// are re-embedded, in small throttled batches.
+
+use crate::ai::{self, Embedder};
+use crate::db::embeddings as db_embeddings;
+use crate::db::queries;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::jobs::CancelToken;
+use crate::progress;
+use crate::state::AppState;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// Notes sent to the provider per request
+const BATCH_SIZE: usize = 16;
+
+/// Pause between batches so background indexing stays out of the way
+const BATCH_DELAY: Duration = Duration::from_millis(500);
+
+/// Note text beyond this many characters isn't embedded (stays under
+/// provider input limits; the start of a note carries most of its meaning)
+const MAX_EMBED_CHARS: usize = 8_000;
+
+/// Guards against overlapping embedding runs
+pub struct EmbeddingIndexer {
+    running: AtomicBool,
+}
+
+impl EmbeddingIndexer {
+    pub fn new() -> Self {
+        Self {
+            running: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Default for EmbeddingIndexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Releases the running flag when a run ends, however it ends
+struct RunGuard<'a>(&'a AtomicBool);
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Hex SHA-256 of a note's content
+pub fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Unit-length copy of a vector, so similarity is a plain dot product
+pub fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|v| v / norm).collect()
+}
+
+/// Little-endian f32 bytes for storage
+pub fn encode(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Vector from stored bytes
+pub fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// The `limit` stored vectors most similar to `query` (cosine similarity,
+/// best first). Vectors of a different length are skipped.
+pub fn nearest(vectors: &[(String, Vec<u8>)], query: &[f32], limit: usize) -> Vec<(String, f32)> {
+    let query = normalize(query);
+    let mut scored: Vec<(String, f32)> = vectors
+        .iter()
+        .filter_map(|(id, bytes)| {
+            let vector = decode(bytes);
+            (vector.len() == query.len()).then(|| (id.clone(), vector.iter().zip(&query).map(|(a, b)| a * b).sum()))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(limit);
+    scored
+}
+
+/// Embed a search query
+pub fn embed_query(embedder: &dyn Embedder, query: &str) -> AppResult<Vec<f32>> {
+    embedder
+        .embed(&ai::agent(), &[query.to_string()])?
+        .pop()
+        .ok_or_else(|| AppError::Internal("No embedding returned for the query".to_string()))
+}
+
+/// Text sent for embedding
+fn embed_input(content: &str) -> String {
+    match content.char_indices().nth(MAX_EMBED_CHARS) {
+        Some((end, _)) => content[..end].to_string(),
+        None => content.to_string(),
+    }
+}
+
+/// Bring the index up to date: embed new and changed notes, skipping notes
+/// whose content is unchanged. With `full`, every vector is dropped first.
+/// Returns how many notes were embedded. Stops between batches when `cancel` is set.
+pub fn refresh(app: &AppHandle, embedder: &dyn Embedder, full: bool, cancel: &CancelToken) -> AppResult<usize> {
+    let state = app.state::<AppState>();
+    if state.embeddings.running.swap(true, Ordering::SeqCst) {
+        return Err(AppError::Conflict("The embedding index is already being updated".to_string()));
+    }
+    let _guard = RunGuard(&state.embeddings.running);
+
+    if full {
+        let conn = state.writer.lock();
+        let cleared = db_embeddings::clear_embeddings(&conn).db_context("Failed to clear embeddings")?;
+        info!("Cleared {} embeddings for a full rebuild", cleared);
+    }
+
+    let candidates = state
+        .read(|conn| db_embeddings::get_candidates(conn, embedder.model()))
+        .db_context("Failed to find stale embeddings")?;
+
+    // Unchanged or empty notes only need their bookkeeping updated
+    let now = Utc::now().timestamp();
+    let mut stale = Vec::new();
+    {
+        let conn = state.writer.lock();
+        for candidate in candidates {
+            let hash = content_hash(&candidate.content);
+            if candidate.content.trim().is_empty() {
+                db_embeddings::delete_embedding(&conn, &candidate.buffer_id)
+                    .db_context("Failed to update embeddings")?;
+            } else if candidate.embedded_hash.as_deref() == Some(hash.as_str()) {
+                db_embeddings::touch_embedding(&conn, &candidate.buffer_id, now)
+                    .db_context("Failed to update embeddings")?;
+            } else {
+                stale.push((candidate, hash));
+            }
+        }
+    }
+    if stale.is_empty() {
+        return Ok(0);
+    }
+
+    let total = stale.len();
+    let agent = ai::agent();
+    progress::run(app, "embeddings", |progress| {
+        let mut done = 0;
+        progress.update("embedding", done, total);
+        for batch in stale.chunks(BATCH_SIZE) {
+            if done > 0 {
+                thread::sleep(BATCH_DELAY);
+            }
+            cancel.check()?;
+            let inputs: Vec<String> = batch.iter().map(|(c, _)| embed_input(&c.content)).collect();
+            let vectors = embedder.embed(&agent, &inputs)?;
+
+            let now = Utc::now().timestamp();
+            let conn = state.writer.lock();
+            for ((candidate, hash), vector) in batch.iter().zip(vectors) {
+                let bytes = encode(&normalize(&vector));
+                // The note may have been deleted while its batch was in flight
+                if let Err(e) =
+                    db_embeddings::upsert_embedding(&conn, &candidate.buffer_id, hash, embedder.model(), &bytes, now)
+                {
+                    warn!("Failed to store embedding for {}: {}", candidate.buffer_id, e);
+                }
+            }
+            drop(conn);
+
+            done += batch.len();
+            progress.update("embedding", done, total);
+        }
+        Ok(())
+    })?;
+
+    info!("Embedded {} notes with {}", total, embedder.model());
+    Ok(total)
+}
+
+/// Re-embed changed notes when semantic search is on (maintenance job)
+pub fn run_background_refresh(app: &AppHandle, cancel: &CancelToken) {
+    let state = app.state::<AppState>();
+    let settings = state.read(queries::get_settings).unwrap_or_default();
+    if !settings.semantic_search {
+        return;
+    }
+
+    match ai::embedder(&settings).and_then(|embedder| refresh(app, embedder.as_ref(), false, cancel)) {
+        Ok(_) | Err(AppError::Conflict(_)) | Err(AppError::Cancelled(_)) => {}
+        Err(e) => warn!("Embedding refresh failed: {}", e),
+    }
+}
@@ -0,0 +1,405 @@
+// Opt-in database encryption with SQLCipher (builds with the `sqlcipher`
+// feature). An encrypted database is opened locked: the connections are
+// in-memory stand-ins and commands fail with `DatabaseLocked` until
+// `unlock` gets the passphrase and startup continues. The idle auto-lock
+// closes it the same way again (`relock`). Encrypting and
+// changing the passphrase both export the database into a new file with
+// `sqlcipher_export`, then swap that file in for the old one. Rotating the
+// key (`rotate_key`) also re-seals the locked notes and can be resumed.
+
+use crate::biometric;
+use crate::db::{backup, connection, queries};
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::note_lock;
+use crate::relocate;
+use crate::startup;
+use crate::state::AppState;
+use parking_lot::Mutex;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, ErrorCode};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+/// First bytes of every unencrypted SQLite database
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Shortest passphrase accepted when encrypting
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// How long a swap waits for reads still running on the old file
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Re-encrypted copy of the database, next to it until it is swapped in
+const STAGED_NAME: &str = "flashnotes.rekey.db";
+
+/// The replaced database file, kept until its successor has been opened
+const ASIDE_NAME: &str = "flashnotes.prerekey.db";
+
+/// Progress of an unfinished key rotation, in the app data directory
+const ROTATION_FILE: &str = "key_rotation.json";
+
+/// Text sealed with the old passphrase in the rotation progress
+const ROTATION_CHECK: &str = "flashnotes key rotation";
+
+/// Passphrase of the open database (None when it is not encrypted), needed
+/// to open further connections
+#[derive(Default)]
+pub struct DatabaseKey(Mutex<Option<String>>);
+
+impl DatabaseKey {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().clone()
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.0.lock().is_some()
+    }
+
+    fn set(&self, key: Option<String>) {
+        *self.0.lock() = key;
+    }
+}
+
+/// Whether SQLCipher support is compiled in
+fn available() -> AppResult<()> {
+    if cfg!(feature = "sqlcipher") {
+        Ok(())
+    } else {
+        Err(AppError::Unsupported("Database encryption"))
+    }
+}
+
+/// Whether the database file is encrypted: it exists but lacks the SQLite header.
+/// Always false without SQLCipher, so such a file is treated as corrupt instead.
+pub fn is_encrypted(path: &Path) -> bool {
+    if !cfg!(feature = "sqlcipher") {
+        return false;
+    }
+    let mut header = [0u8; 16];
+    match File::open(path).and_then(|mut file| file.read_exact(&mut header)) {
+        Ok(()) => &header != SQLITE_HEADER,
+        // Missing or shorter than a header: a new database
+        Err(_) => false,
+    }
+}
+
+/// Passphrases for the database and for locked notes need `MIN_PASSPHRASE_LEN` characters
+pub fn validate_passphrase(passphrase: &str) -> AppResult<()> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::Validation(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Open the writer and reader pool on `path`, turning a wrong passphrase into a validation error
+fn open(path: &PathBuf, key: Option<&str>) -> AppResult<(rusqlite::Connection, Pool<SqliteConnectionManager>)> {
+    let writer = connection::create_connection(path, key).map_err(|e| match e.sqlite_error_code() {
+        Some(ErrorCode::NotADatabase) => AppError::Validation("Wrong passphrase".to_string()),
+        _ => AppError::Database {
+            message: "Failed to open database".to_string(),
+            source: e,
+        },
+    })?;
+    let pool = connection::create_reader_pool(path, key).map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok((writer, pool))
+}
+
+/// Wait (briefly) for reads still using connections of a pool being retired
+pub fn drain(pool: Pool<SqliteConnectionManager>) {
+    let start = Instant::now();
+    while start.elapsed() < DRAIN_TIMEOUT {
+        let pool_state = pool.state();
+        if pool_state.idle_connections == pool_state.connections {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    warn!("Reads on the old database did not finish within {:?}", DRAIN_TIMEOUT);
+}
+
+/// Open `path` with `key` as the app's connections
+fn reopen(state: &AppState, writer: &mut rusqlite::Connection, path: &PathBuf, key: Option<&str>) -> AppResult<()> {
+    let (new_writer, new_pool) = open(path, key)?;
+    *writer = new_writer;
+    state.replace_reader_pool(new_pool);
+    Ok(())
+}
+
+/// Export the database into a copy encrypted with `new_key` and swap it in.
+/// The copy must pass an integrity check before the swap, and the old file is
+/// kept aside until the copy has been opened in its place.
+fn rekey(state: &AppState, new_key: &str) -> AppResult<()> {
+    let path = relocate::current_path(state)?;
+    let old_key = state.db_key.get();
+    let staged = path.with_file_name(STAGED_NAME);
+    let aside = path.with_file_name(ASIDE_NAME);
+    let _ = fs::remove_file(&staged);
+    let _ = connection::remove_database(&aside);
+
+    let mut writer = state.writer.lock();
+    let exported = writer
+        .execute("ATTACH DATABASE ?1 AS rekeyed KEY ?2", params![staged.to_string_lossy().to_string(), new_key])
+        .and_then(|_| writer.query_row("SELECT sqlcipher_export('rekeyed')", [], |_| Ok(())))
+        .and_then(|()| writer.execute("DETACH DATABASE rekeyed", []).map(|_| ()));
+    if let Err(e) = exported {
+        let _ = writer.execute("DETACH DATABASE rekeyed", []);
+        let _ = fs::remove_file(&staged);
+        return Err(e).db_context("Failed to encrypt database");
+    }
+    if let Err(e) = backup::validate_backup(&staged, Some(new_key)) {
+        let _ = fs::remove_file(&staged);
+        return Err(AppError::Internal(format!("Encrypted copy of the database is unusable: {}", e)));
+    }
+
+    // Every connection to the old file is closed before it is replaced
+    let placeholder = connection::create_placeholder_pool().map_err(|e| AppError::Internal(e.to_string()))?;
+    drain(state.replace_reader_pool(placeholder));
+    *writer = connection::create_memory_connection().db_context("Failed to close database")?;
+
+    if let Err(e) = connection::rename_database(&path, &aside) {
+        reopen(state, &mut writer, &path, old_key.as_deref())?;
+        return Err(AppError::io("Failed to replace database", e));
+    }
+    if let Err(e) = fs::rename(&staged, &path) {
+        let _ = connection::rename_database(&aside, &path);
+        reopen(state, &mut writer, &path, old_key.as_deref())?;
+        return Err(AppError::io("Failed to replace database", e));
+    }
+    if let Err(e) = reopen(state, &mut writer, &path, Some(new_key)) {
+        // Back to the old file; the copy stays staged for a look at what went wrong
+        let _ = connection::rename_database(&path, &staged);
+        connection::rename_database(&aside, &path).map_err(|e| AppError::io("Failed to restore database", e))?;
+        reopen(state, &mut writer, &path, old_key.as_deref())?;
+        return Err(e);
+    }
+    state.db_key.set(Some(new_key.to_string()));
+    drop(writer);
+
+    if let Err(e) = connection::remove_database(&aside) {
+        warn!("Failed to remove the old database file {:?}: {}", aside, e);
+    }
+    Ok(())
+}
+
+/// Put the old database file back when the app quit halfway through a swap
+/// (before the re-encrypted copy took its place). Runs before the database is opened.
+pub fn recover_interrupted_rekey(db_path: &Path) {
+    let aside = db_path.with_file_name(ASIDE_NAME);
+    if db_path.exists() || !aside.exists() {
+        return;
+    }
+    match connection::rename_database(&aside, db_path) {
+        Ok(()) => warn!("Restored the database from an interrupted re-encryption"),
+        Err(e) => warn!("Failed to restore the database from {:?}: {}", aside, e),
+    }
+}
+
+/// Encrypt the unencrypted database with a passphrase
+pub fn enable(state: &AppState, passphrase: &str) -> AppResult<()> {
+    available()?;
+    if state.db_key.is_set() {
+        return Err(AppError::Conflict("The database is already encrypted".to_string()));
+    }
+    validate_passphrase(passphrase)?;
+    rekey(state, passphrase)?;
+    info!("Database encrypted");
+    Ok(())
+}
+
+/// Re-encrypt the database with a new passphrase
+pub fn change_passphrase(state: &AppState, current: &str, new: &str) -> AppResult<()> {
+    available()?;
+    match state.db_key.get() {
+        None => return Err(AppError::Validation("The database is not encrypted".to_string())),
+        Some(key) if key != current => return Err(AppError::Validation("Wrong passphrase".to_string())),
+        Some(_) => {}
+    }
+    validate_passphrase(new)?;
+    rekey(state, new)?;
+    info!("Database passphrase changed");
+    if let Err(e) = biometric::update_passphrase(None, new) {
+        warn!("Failed to update the Touch ID passphrase: {}", e);
+    }
+    Ok(())
+}
+
+/// Open the locked database with its passphrase and finish starting up (or,
+/// after an auto-lock, pick up where the app left off)
+pub fn unlock(app: &AppHandle, passphrase: &str) -> AppResult<()> {
+    let state = app.state::<AppState>();
+    if !state.ready.is_locked() {
+        return Ok(());
+    }
+
+    let path = connection::get_db_path(app).map_err(|e| AppError::Internal(e.to_string()))?;
+    let (writer, pool) = open(&path, Some(passphrase))?;
+    *state.writer.lock() = writer;
+    state.replace_reader_pool(pool);
+    state.db_key.set(Some(passphrase.to_string()));
+    state.idle.unlock();
+
+    info!("Database unlocked");
+    if state.ready.set_unlocked() {
+        startup::spawn(app.clone());
+    }
+    Ok(())
+}
+
+/// Close the encrypted database and forget its passphrase (on auto-lock), so
+/// nothing can be read until `unlock` gets the passphrase again. Does nothing
+/// for an unencrypted database.
+pub fn relock(state: &AppState) -> AppResult<()> {
+    if !state.db_key.is_set() {
+        return Ok(());
+    }
+    // New commands fail from here on; running ones finish before the swap
+    state.ready.set_locked();
+
+    let mut writer = state.writer.lock();
+    let placeholder = connection::create_placeholder_pool().map_err(|e| AppError::Internal(e.to_string()))?;
+    drain(state.replace_reader_pool(placeholder));
+    *writer = connection::create_memory_connection().db_context("Failed to close database")?;
+    state.db_key.set(None);
+
+    info!("Database locked");
+    Ok(())
+}
+
+/// What a key rotation changed
+#[derive(Debug, Serialize)]
+pub struct RotationReport {
+    /// Whether the database was re-encrypted
+    pub database: bool,
+    /// Locked notes re-sealed with the new passphrase
+    pub notes: usize,
+    /// Snapshot taken before anything changed, still under the old passphrase
+    pub backup_path: String,
+}
+
+/// Rotation progress kept on disk, so a run that was interrupted continues
+/// instead of starting over (and taking another snapshot)
+#[derive(Serialize, Deserialize)]
+struct RotationProgress {
+    backup_path: PathBuf,
+    /// `ROTATION_CHECK` sealed with the old passphrase, checked before resuming
+    check: Vec<u8>,
+    notes: Vec<String>,
+    notes_done: bool,
+}
+
+fn read_progress(path: &Path) -> Option<RotationProgress> {
+    let text = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text)
+        .map_err(|e| warn!("Ignoring unreadable key rotation progress: {}", e))
+        .ok()
+}
+
+fn write_progress(path: &Path, progress: &RotationProgress) -> AppResult<()> {
+    let text = serde_json::to_string(progress).map_err(|e| AppError::Internal(e.to_string()))?;
+    fs::write(path, text).map_err(|e| AppError::io("Failed to save key rotation progress", e))
+}
+
+/// Replace passphrase `old` with `new` everywhere it is used: the database
+/// (when it is encrypted with `old`) and every locked note sealed with it.
+/// A snapshot is taken first, the notes are re-sealed in one transaction and
+/// the database is re-encrypted last. Calling it again with the same
+/// passphrases after an interruption finishes the rotation.
+pub fn rotate_key(state: &AppState, old: &str, new: &str) -> AppResult<RotationReport> {
+    validate_passphrase(new)?;
+    if old == new {
+        return Err(AppError::Validation("The new passphrase must differ from the old one".to_string()));
+    }
+    let progress_path = state.app_data_dir.join(ROTATION_FILE);
+    let resumed = read_progress(&progress_path);
+    // Only the passphrase the rotation started with may finish it
+    if let Some(progress) = &resumed {
+        if !note_lock::opens(&progress.check, old)? {
+            return Err(AppError::Validation("Wrong passphrase".to_string()));
+        }
+    }
+    let db_key = state.db_key.get();
+    let rotate_database = db_key.as_deref() == Some(old);
+    // An interrupted run may have re-encrypted the database already
+    let database_rotated = rotate_database || (resumed.is_some() && db_key.as_deref() == Some(new));
+    if rotate_database {
+        available()?;
+    }
+
+    let mut conn = state.writer.lock();
+    let mut resealed = Vec::new();
+    if !resumed.as_ref().is_some_and(|p| p.notes_done) {
+        for (id, sealed) in queries::get_locked_buffers(&conn).db_context("Failed to get locked notes")? {
+            if let Some(sealed) = note_lock::reseal(&sealed, old, new)? {
+                resealed.push((id, sealed));
+            }
+        }
+    }
+
+    let mut progress = match resumed {
+        Some(progress) => {
+            info!("Resuming key rotation (snapshot {:?})", progress.backup_path);
+            progress
+        }
+        None => {
+            if !rotate_database && resealed.is_empty() {
+                return Err(AppError::Validation("Wrong passphrase".to_string()));
+            }
+            let backup_path =
+                backup::create_snapshot(&conn, &state.app_data_dir, "prerotation").map_err(AppError::Internal)?;
+            let progress = RotationProgress {
+                backup_path,
+                check: note_lock::seal(ROTATION_CHECK, old)?,
+                notes: Vec::new(),
+                notes_done: false,
+            };
+            write_progress(&progress_path, &progress)?;
+            progress
+        }
+    };
+
+    if !progress.notes_done {
+        let tx = conn.transaction().db_context("Failed to start re-sealing notes")?;
+        for (id, sealed) in &resealed {
+            queries::set_locked_content(&tx, id, sealed).db_context("Failed to re-seal note")?;
+        }
+        tx.commit().db_context("Failed to commit re-sealed notes")?;
+        progress.notes.extend(resealed.into_iter().map(|(id, _)| id));
+        progress.notes_done = true;
+        write_progress(&progress_path, &progress)?;
+    }
+    drop(conn);
+
+    if rotate_database {
+        rekey(state, new)?;
+    }
+
+    for id in progress.notes.iter().map(|id| Some(id.as_str())).chain(database_rotated.then_some(None)) {
+        if let Err(e) = biometric::update_passphrase(id, new) {
+            warn!("Failed to update the Touch ID passphrase of {}: {}", id.unwrap_or("the database"), e);
+        }
+    }
+    if let Err(e) = fs::remove_file(&progress_path) {
+        warn!("Failed to remove key rotation progress: {}", e);
+    }
+
+    info!("Rotated key: database {}, {} locked notes", database_rotated, progress.notes.len());
+    Ok(RotationReport {
+        database: database_rotated,
+        notes: progress.notes.len(),
+        backup_path: progress.backup_path.to_string_lossy().into_owned(),
+    })
+}
@@ -0,0 +1,61 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Incognito scratch buffer, held only in memory. Never written to SQLite or
+/// the crash-recovery journal, so it's absent from search, backups and exports
+/// and gone when the app quits.
+#[derive(Debug, Clone, Serialize)]
+pub struct EphemeralBuffer {
+    pub id: String,
+    pub content: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// In-memory store of ephemeral buffers
+pub struct EphemeralStore {
+    buffers: Mutex<HashMap<String, EphemeralBuffer>>,
+}
+
+impl EphemeralStore {
+    pub fn new() -> Self {
+        Self {
+            buffers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn insert(&self, buffer: EphemeralBuffer) {
+        self.buffers.lock().insert(buffer.id.clone(), buffer);
+    }
+
+    pub fn get(&self, id: &str) -> Option<EphemeralBuffer> {
+        self.buffers.lock().get(id).cloned()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.buffers.lock().contains_key(id)
+    }
+
+    /// Replace a buffer's content. Returns false if it doesn't exist.
+    pub fn update(&self, id: &str, content: String, timestamp: i64) -> bool {
+        match self.buffers.lock().get_mut(id) {
+            Some(buffer) => {
+                buffer.content = content;
+                buffer.updated_at = timestamp;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&self, id: &str) -> Option<EphemeralBuffer> {
+        self.buffers.lock().remove(id)
+    }
+}
+
+impl Default for EphemeralStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,143 @@
+use crate::i18n;
+use rusqlite::ErrorCode;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// Result type returned by all commands
+pub type AppResult<T> = Result<T, AppError>;
+
+/// Application error returned to the frontend.
+/// Serialized as `{ code, message, context }` so the UI can branch on `code`
+/// instead of parsing message strings.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("{message}: {source}")]
+    Database {
+        message: String,
+        #[source]
+        source: rusqlite::Error,
+    },
+
+    #[error("{kind} not found: {id}")]
+    NotFound { kind: &'static str, id: String },
+
+    #[error("{0}")]
+    Validation(String),
+
+    #[error("{0}")]
+    Conflict(String),
+
+    #[error("{message}: {source}")]
+    Io {
+        message: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Feature missing on the current platform or build (mobile, no SQLCipher)
+    #[error("{0} is not available on this platform")]
+    Unsupported(&'static str),
+
+    /// The database is encrypted and has not been unlocked yet (`unlock_database`),
+    /// or the app is locked (`unlock_app`)
+    #[error("The database is locked")]
+    DatabaseLocked,
+
+    /// A background job was stopped via `cancel_job`
+    #[error("The {0} job was cancelled")]
+    Cancelled(&'static str),
+
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl AppError {
+    /// Shorthand for a missing entity
+    pub fn not_found(kind: &'static str, id: impl Into<String>) -> Self {
+        Self::NotFound { kind, id: id.into() }
+    }
+
+    /// Wrap an IO error with a description of what was being attempted
+    pub fn io(message: impl Into<String>, source: std::io::Error) -> Self {
+        Self::Io { message: message.into(), source }
+    }
+
+    /// Stable machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Database { source, .. } => match source {
+                rusqlite::Error::QueryReturnedNoRows => "not_found",
+                _ => match source.sqlite_error_code() {
+                    Some(ErrorCode::DatabaseBusy) | Some(ErrorCode::DatabaseLocked) => "database_locked",
+                    Some(ErrorCode::DatabaseCorrupt) | Some(ErrorCode::NotADatabase) => "database_corrupt",
+                    Some(ErrorCode::ConstraintViolation) => "conflict",
+                    _ => "database",
+                },
+            },
+            Self::NotFound { .. } => "not_found",
+            Self::Validation(_) => "validation",
+            Self::Conflict(_) => "conflict",
+            Self::Io { .. } => "io",
+            Self::Unsupported(_) => "unsupported",
+            Self::DatabaseLocked => "database_encrypted",
+            Self::Cancelled(_) => "cancelled",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    /// User-facing summary without low-level details, in the active locale
+    pub fn message(&self) -> String {
+        match self {
+            Self::Database { message, .. } | Self::Io { message, .. } => i18n::translate_message(message),
+            Self::NotFound { kind, id } => format!("{}: {}", i18n::t_with("{} not found", kind), id),
+            Self::Unsupported(feature) => i18n::t_with("{} is not available on this platform", feature),
+            Self::DatabaseLocked => i18n::t("The database is locked").to_string(),
+            Self::Cancelled(kind) => i18n::t_with("The {} job was cancelled", kind),
+            Self::Validation(message) | Self::Conflict(message) | Self::Internal(message) => {
+                i18n::translate_message(message)
+            }
+        }
+    }
+
+    /// Low-level details (underlying error, offending id), if any
+    pub fn context(&self) -> Option<String> {
+        match self {
+            Self::Database { source, .. } => Some(source.to_string()),
+            Self::Io { source, .. } => Some(source.to_string()),
+            Self::NotFound { id, .. } => Some(id.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("AppError", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.message())?;
+        s.serialize_field("context", &self.context())?;
+        s.end()
+    }
+}
+
+/// Helpers that still report failures as strings (backup, export, logging)
+/// surface as internal errors
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::Internal(message)
+    }
+}
+
+/// Attach a user-facing message to database errors
+pub trait DbResultExt<T> {
+    fn db_context(self, message: &str) -> AppResult<T>;
+}
+
+impl<T> DbResultExt<T> for Result<T, rusqlite::Error> {
+    fn db_context(self, message: &str) -> AppResult<T> {
+        self.map_err(|source| AppError::Database {
+            message: message.to_string(),
+            source,
+        })
+    }
+}
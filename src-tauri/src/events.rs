@@ -0,0 +1,87 @@
+// Buffer change events, emitted by every command that changes buffers so
+// other windows, the tray menu and integrations can stay in sync without
+// polling. They go to all windows and to Rust listeners (`app.listen`).
+// Content saves additionally emit `live_merge::BUFFERS_CHANGED_EVENT`,
+// which tells windows to reload an open buffer.
+
+use crate::db::queries::BufferSummary;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+
+/// Payload: the new buffer's `BufferSummary`
+pub const BUFFER_CREATED_EVENT: &str = "buffer-created";
+
+/// Payload: `BufferUpdated`
+pub const BUFFER_UPDATED_EVENT: &str = "buffer-updated";
+
+/// Payload: `BufferDeleted`
+pub const BUFFER_DELETED_EVENT: &str = "buffer-deleted";
+
+/// Payload: `BuffersReordered`
+pub const BUFFERS_REORDERED_EVENT: &str = "buffers-reordered";
+
+/// Every event above, for listeners that only need to know something changed
+pub const BUFFER_EVENTS: &[&str] = &[
+    BUFFER_CREATED_EVENT,
+    BUFFER_UPDATED_EVENT,
+    BUFFER_DELETED_EVENT,
+    BUFFERS_REORDERED_EVENT,
+];
+
+/// What changed about an updated buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Content,
+    Title,
+    Pin,
+    Archive,
+    Unarchive,
+    /// Taken out of the trash
+    Restore,
+    Lock,
+    Unlock,
+    /// Undo or redo of an earlier change
+    Undo,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferUpdated {
+    pub id: String,
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferDeleted {
+    pub id: String,
+    /// Moved to the trash (restorable) rather than deleted permanently
+    pub trashed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuffersReordered {
+    pub ids: Vec<String>,
+}
+
+fn emit<T: Serialize + Clone>(app: &AppHandle, event: &str, payload: T) {
+    if let Err(e) = app.emit(event, payload) {
+        warn!("Failed to emit {} event: {}", event, e);
+    }
+}
+
+pub fn created(app: &AppHandle, summary: &BufferSummary) {
+    emit(app, BUFFER_CREATED_EVENT, summary.clone());
+}
+
+pub fn updated(app: &AppHandle, id: &str, change: Change) {
+    emit(app, BUFFER_UPDATED_EVENT, BufferUpdated { id: id.to_string(), change });
+}
+
+pub fn deleted(app: &AppHandle, id: &str, trashed: bool) {
+    emit(app, BUFFER_DELETED_EVENT, BufferDeleted { id: id.to_string(), trashed });
+}
+
+pub fn reordered(app: &AppHandle, ids: &[String]) {
+    emit(app, BUFFERS_REORDERED_EVENT, BuffersReordered { ids: ids.to_vec() });
+}
@@ -0,0 +1,31 @@
+// Zip output for exports that produce a set of files
+
+use crate::error::{AppError, AppResult};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Write `files` (name, contents) into a new zip archive at `path`,
+/// replacing any existing file
+pub fn write_zip(path: &Path, files: &[(String, String)]) -> AppResult<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| AppError::io("Failed to create export directory", e))?;
+    }
+
+    let file = File::create(path).map_err(|e| AppError::io("Failed to create zip file", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for (name, contents) in files {
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| AppError::Internal(format!("Failed to write zip entry {}: {}", name, e)))?;
+        zip.write_all(contents.as_bytes())
+            .map_err(|e| AppError::io("Failed to write zip file", e))?;
+    }
+
+    zip.finish()
+        .map_err(|e| AppError::Internal(format!("Failed to finish zip file: {}", e)))?;
+    Ok(())
+}
@@ -0,0 +1,162 @@
+// Blog export: notes as Hugo or Jekyll posts, re-exportable in place
+
+use crate::db::queries::{self, Buffer};
+use crate::error::{AppError, AppResult};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File in the export root mapping buffer ids to the post files written for them
+pub const MAPPING_FILE: &str = ".flashnotes-blog.json";
+
+/// Static site generator layout to write posts for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlogFlavor {
+    /// `content/posts/<slug>.md`
+    Hugo,
+    /// `_posts/<date>-<slug>.md`
+    Jekyll,
+}
+
+impl BlogFlavor {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "hugo" => Some(Self::Hugo),
+            "jekyll" => Some(Self::Jekyll),
+            _ => None,
+        }
+    }
+
+    /// Posts directory, relative to the export root
+    fn content_dir(&self) -> &'static str {
+        match self {
+            Self::Hugo => "content/posts",
+            Self::Jekyll => "_posts",
+        }
+    }
+
+    /// File name for a new post
+    fn file_name(&self, slug: &str, created_at: i64) -> String {
+        match self {
+            Self::Hugo => format!("{}.md", slug),
+            Self::Jekyll => format!("{}-{}.md", format_timestamp(created_at, "%Y-%m-%d"), slug),
+        }
+    }
+}
+
+/// Posts written by a blog export
+#[derive(Debug, Default, Serialize)]
+pub struct BlogExportReport {
+    /// New post files
+    pub created: usize,
+    /// Posts rewritten in place from an earlier export
+    pub updated: usize,
+}
+
+fn format_timestamp(timestamp: i64, format: &str) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .map(|dt| dt.format(format).to_string())
+        .unwrap_or_default()
+}
+
+/// JSON strings and arrays are valid YAML flow scalars/sequences
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// Post front matter: title, dates and tags (from the note's #hashtags)
+fn render_front_matter(flavor: BlogFlavor, buffer: &Buffer, title: &str) -> String {
+    let tags = queries::extract_hashtags(&buffer.content);
+    let tags = serde_json::to_string(&tags).unwrap_or_else(|_| "[]".to_string());
+
+    let mut out = String::from("---\n");
+    if flavor == BlogFlavor::Jekyll {
+        out.push_str("layout: post\n");
+    }
+    out.push_str(&format!("title: {}\n", yaml_string(title)));
+    out.push_str(&format!("date: {}\n", format_timestamp(buffer.created_at, "%Y-%m-%dT%H:%M:%SZ")));
+    let updated_key = match flavor {
+        BlogFlavor::Hugo => "lastmod",
+        BlogFlavor::Jekyll => "last_modified_at",
+    };
+    out.push_str(&format!("{}: {}\n", updated_key, format_timestamp(buffer.updated_at, "%Y-%m-%dT%H:%M:%SZ")));
+    out.push_str(&format!("tags: {}\n", tags));
+    out.push_str(&format!("flashnotes_id: {}\n", buffer.id));
+    out.push_str("---\n");
+    out
+}
+
+/// Content without a leading `# Title` heading, which the front matter title replaces
+fn post_body<'a>(content: &'a str, title: &str) -> &'a str {
+    let trimmed = content.trim_start();
+    let (first, rest) = trimmed.split_once('\n').unwrap_or((trimmed, ""));
+    match first.strip_prefix("# ") {
+        Some(heading) if heading.trim() == title => rest.trim_start_matches(['\r', '\n']),
+        _ => content,
+    }
+}
+
+fn read_mapping(root: &Path) -> BTreeMap<String, String> {
+    fs::read_to_string(root.join(MAPPING_FILE))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Write `buffers` as posts under `root`. Notes exported before (per the
+/// mapping file) are rewritten at their existing path, so re-exporting
+/// updates posts instead of duplicating them and keeps their URLs stable.
+pub fn write_posts(root: &Path, flavor: BlogFlavor, buffers: &[Buffer]) -> AppResult<BlogExportReport> {
+    let content_dir = root.join(flavor.content_dir());
+    fs::create_dir_all(&content_dir).map_err(|e| AppError::io("Failed to create export directory", e))?;
+
+    let mut mapping = read_mapping(root);
+    let mut used: HashSet<String> = mapping.values().map(|p| p.to_lowercase()).collect();
+    let mut report = BlogExportReport::default();
+
+    for buffer in buffers {
+        let title = buffer
+            .title_override
+            .clone()
+            .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+
+        let existing = mapping.get(&buffer.id).filter(|p| root.join(p).is_file()).cloned();
+        let relative = match existing {
+            Some(path) => {
+                report.updated += 1;
+                path
+            }
+            None => {
+                let slug = if buffer.slug.is_empty() { queries::slugify(&title) } else { buffer.slug.clone() };
+                let mut name = flavor.file_name(&slug, buffer.created_at);
+                let mut n = 2;
+                // Don't overwrite other notes' posts or files we didn't write
+                let path = loop {
+                    let candidate = format!("{}/{}", flavor.content_dir(), name);
+                    if !root.join(&candidate).exists() && used.insert(candidate.to_lowercase()) {
+                        break candidate;
+                    }
+                    name = flavor.file_name(&format!("{}-{}", slug, n), buffer.created_at);
+                    n += 1;
+                };
+                report.created += 1;
+                path
+            }
+        };
+
+        let mut data = render_front_matter(flavor, buffer, &title);
+        data.push_str(post_body(&buffer.content, &title));
+        let path: PathBuf = root.join(&relative);
+        fs::write(&path, data).map_err(|e| AppError::io(format!("Failed to write {}", path.display()), e))?;
+        mapping.insert(buffer.id.clone(), relative);
+    }
+
+    let mapping_json = serde_json::to_string_pretty(&mapping)
+        .map_err(|e| AppError::Internal(format!("Failed to encode blog mapping: {}", e)))?;
+    fs::write(root.join(MAPPING_FILE), mapping_json)
+        .map_err(|e| AppError::io("Failed to write blog mapping file", e))?;
+
+    Ok(report)
+}
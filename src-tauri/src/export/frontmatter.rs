@@ -0,0 +1,101 @@
+// Flashnotes front matter: the metadata needed to re-import an exported note losslessly
+
+use crate::db::queries::Buffer;
+use chrono::{DateTime, FixedOffset, Utc};
+
+/// Metadata read from a note's front matter
+#[derive(Debug, Default)]
+pub struct FrontMatter {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub created_at: Option<i64>,
+    pub updated_at: Option<i64>,
+    pub pinned: bool,
+    pub archived: bool,
+    pub source: Option<String>,
+}
+
+/// Format a Unix timestamp as an RFC 3339 UTC date
+pub fn format_timestamp(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// Parse an RFC 3339 date or a plain Unix timestamp
+fn parse_timestamp(value: &str) -> Option<i64> {
+    value
+        .parse::<i64>()
+        .ok()
+        .or_else(|| DateTime::<FixedOffset>::parse_from_rfc3339(value).ok().map(|dt| dt.timestamp()))
+}
+
+/// Parse a scalar value, unquoting double-quoted strings
+fn parse_string(value: &str) -> String {
+    if value.starts_with('"') {
+        if let Ok(s) = serde_json::from_str::<String>(value) {
+            return s;
+        }
+    }
+    value.trim_matches('\'').to_string()
+}
+
+/// Render a buffer's front matter block (including the closing delimiter and newline)
+pub fn render(buffer: &Buffer, title: &str) -> String {
+    // JSON strings are valid YAML double-quoted scalars
+    let quoted_title = serde_json::to_string(title).unwrap_or_else(|_| "\"\"".to_string());
+
+    let mut out = String::from("---\n");
+    out.push_str(&format!("id: {}\n", buffer.id));
+    out.push_str(&format!("title: {}\n", quoted_title));
+    out.push_str(&format!("created: {}\n", format_timestamp(buffer.created_at)));
+    out.push_str(&format!("updated: {}\n", format_timestamp(buffer.updated_at)));
+    out.push_str(&format!("pinned: {}\n", buffer.is_pinned));
+    out.push_str(&format!("archived: {}\n", buffer.is_archived));
+    out.push_str(&format!(
+        "source: {}\n",
+        serde_json::to_string(&buffer.source).unwrap_or_else(|_| "\"\"".to_string())
+    ));
+    out.push_str("---\n");
+    out
+}
+
+/// Split a leading flashnotes front matter block from `content`.
+/// Only blocks carrying an `id` are treated as ours; anything else (including
+/// a note's own YAML header) is left in the body untouched.
+pub fn parse(content: &str) -> Option<(FrontMatter, &str)> {
+    let rest = content.strip_prefix("---\n").or_else(|| content.strip_prefix("---\r\n"))?;
+
+    let mut meta = FrontMatter::default();
+    let mut offset = content.len() - rest.len();
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        let line = line.trim();
+        if line == "---" {
+            return meta.id.is_some().then(|| (meta, &content[offset..]));
+        }
+
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "id" => {
+                let id = parse_string(value);
+                if !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                    meta.id = Some(id);
+                }
+            }
+            "title" => meta.title = Some(parse_string(value)),
+            "created" => meta.created_at = parse_timestamp(value),
+            "updated" => meta.updated_at = parse_timestamp(value),
+            "pinned" => meta.pinned = value == "true",
+            "archived" => meta.archived = value == "true",
+            "source" => meta.source = Some(parse_string(value)).filter(|s| !s.is_empty()),
+            _ => {}
+        }
+    }
+
+    // Unterminated block: not front matter
+    None
+}
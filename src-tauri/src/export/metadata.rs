@@ -0,0 +1,50 @@
+// Note metadata as CSV, one row per note, for analysis in spreadsheets.
+// Note bodies are never included.
+
+use super::frontmatter::format_timestamp;
+use crate::db::queries::{self, Buffer};
+
+const HEADER: &str = "id,title,created_at,updated_at,word_count,tags,pinned,archived";
+
+/// Quote a CSV field when needed. Fields a spreadsheet would evaluate as a
+/// formula get a leading apostrophe.
+fn field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// CSV with a header row and a row per buffer. Tags are the note's
+/// #hashtags, space-separated; dates are RFC 3339 UTC.
+pub fn render(buffers: &[Buffer]) -> String {
+    let mut out = String::from(HEADER);
+    out.push_str("\r\n");
+
+    for buffer in buffers {
+        let title = buffer
+            .title_override
+            .clone()
+            .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+        let tags = queries::extract_hashtags(&buffer.content).join(" ");
+        let row = [
+            field(&buffer.id),
+            field(&title),
+            format_timestamp(buffer.created_at),
+            format_timestamp(buffer.updated_at),
+            buffer.word_count.to_string(),
+            field(&tags),
+            buffer.is_pinned.to_string(),
+            buffer.is_archived.to_string(),
+        ];
+        out.push_str(&row.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
@@ -0,0 +1,88 @@
+pub mod archive;
+pub mod blog;
+pub mod frontmatter;
+pub mod metadata;
+pub mod obsidian;
+pub mod pdf;
+pub mod site;
+
+use crate::error::{AppError, AppResult};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Maximum length of a generated file name (without extension)
+const MAX_FILE_STEM_LEN: usize = 60;
+
+/// Output format for exported notes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Text,
+    Pdf,
+}
+
+impl ExportFormat {
+    /// Parse a format name as sent by the frontend ("md", "txt", "pdf", ...)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "txt" | "text" | "plaintext" => Some(Self::Text),
+            "pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+
+    /// File extension for this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Text => "txt",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+/// Turn a note title into a safe file name stem
+pub fn file_stem(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' { c } else { ' ' })
+        .collect();
+
+    let stem = cleaned
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_FILE_STEM_LEN)
+        .collect::<String>()
+        .trim()
+        .to_string();
+
+    if stem.is_empty() {
+        "Untitled".to_string()
+    } else {
+        stem
+    }
+}
+
+/// Render note content into the bytes of the requested format
+pub fn render(content: &str, title: &str, format: ExportFormat) -> Vec<u8> {
+    match format {
+        ExportFormat::Markdown | ExportFormat::Text => content.as_bytes().to_vec(),
+        ExportFormat::Pdf => pdf::render(content, title),
+    }
+}
+
+/// Write a rendered note into `dir` as `<name>.<ext>` (name is usually the
+/// buffer's slug), returning the path of the new file
+pub fn write_to_dir(dir: &Path, name: &str, content: &str, title: &str, format: ExportFormat) -> AppResult<PathBuf> {
+    fs::create_dir_all(dir)
+        .map_err(|e| AppError::io("Failed to create export directory", e))?;
+
+    let path = dir.join(format!("{}.{}", file_stem(name), format.extension()));
+    fs::write(&path, render(content, title, format))
+        .map_err(|e| AppError::io("Failed to write export file", e))?;
+
+    Ok(path)
+}
@@ -0,0 +1,191 @@
+// Obsidian vault export: one Markdown file per note, named by title so
+// Obsidian resolves `[[wiki links]]`, with attachments in an assets folder
+
+use crate::db::attachments::Attachment;
+use crate::db::queries::{self, Buffer};
+use crate::error::{AppError, AppResult};
+use crate::export;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+/// Attachment folder, relative to the vault root
+const ASSETS_DIR: &str = "assets";
+
+/// Folder for archived notes, relative to the vault root
+const ARCHIVE_DIR: &str = "Archive";
+
+/// What a vault export wrote
+#[derive(Debug, Default, Serialize)]
+pub struct VaultExportReport {
+    pub notes: usize,
+    pub attachments: usize,
+    /// Attachments whose stored file was missing
+    pub missing_attachments: usize,
+}
+
+/// Pick a name not used yet in `used` (case-insensitive): "name", "name 2", ...
+fn unique_name(used: &mut HashSet<String>, stem: &str, extension: &str) -> String {
+    let mut name = format!("{}{}", stem, extension);
+    let mut n = 2;
+    while !used.insert(name.to_lowercase()) {
+        name = format!("{} {}{}", stem, n, extension);
+        n += 1;
+    }
+    name
+}
+
+/// Point `[[wiki links]]` outside code fences at the exported file names.
+/// Obsidian resolves links by file name while flashnotes resolves them by
+/// slug, so `[[meeting notes]]` becomes `[[Meeting Notes|meeting notes]]` when
+/// that is the target's file. Aliases and `#heading` parts are kept;
+/// unresolved links are left as written.
+fn rewrite_wiki_links(content: &str, files_by_slug: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut in_fence = false;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if in_fence {
+            out.push_str(line);
+        } else {
+            rewrite_line(line, files_by_slug, &mut out);
+        }
+    }
+    out
+}
+
+fn rewrite_line(line: &str, files_by_slug: &HashMap<String, String>, out: &mut String) {
+    let mut rest = line;
+    while let Some(start) = rest.find("[[") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("]]") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+
+        let inner = &after[..end];
+        let (link, alias) = match inner.split_once('|') {
+            Some((link, alias)) => (link, Some(alias)),
+            None => (inner, None),
+        };
+        let (target, heading) = match link.split_once('#') {
+            Some((target, heading)) => (target, Some(heading)),
+            None => (link, None),
+        };
+
+        match files_by_slug.get(&queries::slugify(target.trim())) {
+            Some(file) => {
+                out.push_str("[[");
+                out.push_str(file);
+                if let Some(heading) = heading {
+                    out.push('#');
+                    out.push_str(heading);
+                }
+                // Keep the text as written when it differs from the file name
+                match alias {
+                    Some(alias) => {
+                        out.push('|');
+                        out.push_str(alias);
+                    }
+                    None if target.trim() != file => {
+                        out.push('|');
+                        out.push_str(target.trim());
+                    }
+                    None => {}
+                }
+                out.push_str("]]");
+            }
+            None => {
+                out.push_str("[[");
+                out.push_str(inner);
+                out.push_str("]]");
+            }
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+}
+
+/// Write every buffer into `dir` as an Obsidian vault. Notes are named by
+/// title (archived ones go in `Archive/`), wiki links and #hashtags stay as
+/// they are, and attachments are copied into `assets/` and embedded at the
+/// end of their note.
+pub fn write_vault(dir: &Path, buffers: &[Buffer], attachments: &[Attachment]) -> AppResult<VaultExportReport> {
+    fs::create_dir_all(dir).map_err(|e| AppError::io("Failed to create export directory", e))?;
+    if buffers.iter().any(|b| b.is_archived) {
+        fs::create_dir_all(dir.join(ARCHIVE_DIR))
+            .map_err(|e| AppError::io("Failed to create export directory", e))?;
+    }
+
+    // Names are unique across the vault so links never become ambiguous
+    let mut used = HashSet::new();
+    let mut files_by_slug: HashMap<String, String> = HashMap::new();
+    let mut notes = Vec::with_capacity(buffers.len());
+    for buffer in buffers {
+        let title = buffer
+            .title_override
+            .clone()
+            .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+        let name = unique_name(&mut used, &export::file_stem(&title), "");
+        notes.push((buffer, name));
+    }
+    // Active notes win link targets over archived ones with the same slug
+    for archived in [false, true] {
+        for (buffer, name) in notes.iter().filter(|(b, _)| b.is_archived == archived) {
+            files_by_slug.entry(buffer.slug.clone()).or_insert_with(|| name.clone());
+        }
+    }
+
+    let mut report = VaultExportReport::default();
+    let mut embeds: HashMap<&str, Vec<String>> = HashMap::new();
+    if !attachments.is_empty() {
+        let assets = dir.join(ASSETS_DIR);
+        fs::create_dir_all(&assets).map_err(|e| AppError::io("Failed to create assets directory", e))?;
+
+        let mut used_assets = HashSet::new();
+        for attachment in attachments {
+            let path = Path::new(&attachment.file_name);
+            let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            let extension = path
+                .extension()
+                .map(|e| format!(".{}", e.to_string_lossy()))
+                .unwrap_or_default();
+            let name = unique_name(&mut used_assets, &export::file_stem(&stem), &extension);
+
+            if let Err(e) = fs::copy(&attachment.stored_path, assets.join(&name)) {
+                warn!("Failed to copy attachment {:?}: {}", attachment.stored_path, e);
+                report.missing_attachments += 1;
+                continue;
+            }
+            report.attachments += 1;
+            embeds
+                .entry(attachment.buffer_id.as_str())
+                .or_default()
+                .push(format!("![[{}/{}]]", ASSETS_DIR, name));
+        }
+    }
+
+    for (buffer, name) in &notes {
+        let mut data = rewrite_wiki_links(&buffer.content, &files_by_slug);
+        if let Some(embeds) = embeds.get(buffer.id.as_str()) {
+            if !data.is_empty() && !data.ends_with('\n') {
+                data.push('\n');
+            }
+            data.push('\n');
+            data.push_str(&embeds.join("\n"));
+            data.push('\n');
+        }
+
+        let folder = if buffer.is_archived { dir.join(ARCHIVE_DIR) } else { dir.to_path_buf() };
+        let path = folder.join(format!("{}.md", name));
+        fs::write(&path, data).map_err(|e| AppError::io(format!("Failed to write {}", path.display()), e))?;
+        report.notes += 1;
+    }
+
+    Ok(report)
+}
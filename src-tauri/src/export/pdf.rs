@@ -0,0 +1,132 @@
+// Minimal plain-text PDF writer (monospace, US Letter, no external deps)
+
+const PAGE_WIDTH: f64 = 612.0;
+const PAGE_HEIGHT: f64 = 792.0;
+const MARGIN: f64 = 54.0;
+const FONT_SIZE: f64 = 10.0;
+const LINE_HEIGHT: f64 = 12.0;
+/// Courier glyphs are 0.6em wide
+const CHAR_WIDTH: f64 = FONT_SIZE * 0.6;
+
+/// Render text content as a paginated PDF document
+pub fn render(content: &str, title: &str) -> Vec<u8> {
+    let chars_per_line = ((PAGE_WIDTH - 2.0 * MARGIN) / CHAR_WIDTH) as usize;
+    let lines_per_page = ((PAGE_HEIGHT - 2.0 * MARGIN) / LINE_HEIGHT) as usize;
+
+    let lines = wrap_lines(content, chars_per_line);
+    let pages: Vec<&[String]> = if lines.is_empty() {
+        vec![&[]]
+    } else {
+        lines.chunks(lines_per_page).collect()
+    };
+
+    // Object layout: 1 catalog, 2 page tree, 3 font, 4 info, then (page, contents) pairs
+    let first_page_obj = 5;
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+
+    let kids = (0..pages.len())
+        .map(|i| format!("{} 0 R", first_page_obj + i * 2))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, pages.len()).into_bytes());
+    objects.push(b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec());
+
+    let mut info = b"<< /Producer (Flashnotes) /Title ".to_vec();
+    info.extend(pdf_string(title));
+    info.extend(b" >>");
+    objects.push(info);
+
+    for (i, page_lines) in pages.iter().enumerate() {
+        let contents_obj = first_page_obj + i * 2 + 1;
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH, PAGE_HEIGHT, contents_obj
+            )
+            .into_bytes(),
+        );
+
+        let mut stream = format!(
+            "BT\n/F1 {} Tf\n{} TL\n{} {} Td\n",
+            FONT_SIZE,
+            LINE_HEIGHT,
+            MARGIN,
+            PAGE_HEIGHT - MARGIN - FONT_SIZE
+        )
+        .into_bytes();
+        for line in page_lines.iter() {
+            stream.extend(pdf_string(line));
+            stream.extend(b" Tj T*\n");
+        }
+        stream.extend(b"ET");
+
+        let mut obj = format!("<< /Length {} >>\nstream\n", stream.len()).into_bytes();
+        obj.extend(stream);
+        obj.extend(b"\nendstream");
+        objects.push(obj);
+    }
+
+    // Serialize with cross-reference table
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, body) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+        out.extend(body);
+        out.extend(b"\nendobj\n");
+    }
+
+    let xref_offset = out.len();
+    out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        out.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+    }
+    out.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info 4 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        )
+        .into_bytes(),
+    );
+
+    out
+}
+
+/// Hard-wrap content to a fixed column width, expanding tabs
+fn wrap_lines(content: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for raw in content.lines() {
+        let expanded = raw.replace('\t', "    ");
+        let chars: Vec<char> = expanded.chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+            continue;
+        }
+        for chunk in chars.chunks(width) {
+            lines.push(chunk.iter().collect());
+        }
+    }
+    lines
+}
+
+/// Encode text as a PDF literal string in WinAnsi (Latin-1 subset)
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for ch in text.chars() {
+        match ch {
+            '(' | ')' | '\\' => {
+                out.push(b'\\');
+                out.push(ch as u8);
+            }
+            c if (' '..='~').contains(&c) => out.push(c as u8),
+            c if ('\u{a0}'..='\u{ff}').contains(&c) => out.push(c as u32 as u8),
+            _ => out.push(b'?'),
+        }
+    }
+    out.push(b')');
+    out
+}
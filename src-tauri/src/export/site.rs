@@ -0,0 +1,225 @@
+// Static HTML site export: an index page, one page per note and a stylesheet
+
+use crate::db::queries::{self, Buffer};
+use crate::error::{AppError, AppResult};
+use chrono::{DateTime, Utc};
+use pulldown_cmark::{html, Event, Options, Parser};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+const STYLESHEET: &str = "style.css";
+
+/// Theme matching the app's Nord palette, with a light variant
+const CSS: &str = r#":root {
+  --bg: #2E3440;
+  --bg-raised: #3B4252;
+  --text: #ECEFF4;
+  --muted: #D8DEE9;
+  --accent: #88C0D0;
+  --border: #4C566A;
+}
+@media (prefers-color-scheme: light) {
+  :root {
+    --bg: #ECEFF4;
+    --bg-raised: #E5E9F0;
+    --text: #2E3440;
+    --muted: #4C566A;
+    --accent: #5E81AC;
+    --border: #D8DEE9;
+  }
+}
+* { box-sizing: border-box; }
+body {
+  margin: 0;
+  background: var(--bg);
+  color: var(--text);
+  font: 16px/1.6 -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif;
+}
+main { max-width: 46rem; margin: 0 auto; padding: 2rem 1.25rem 4rem; }
+nav { margin-bottom: 2rem; font-size: 0.9rem; }
+a { color: var(--accent); }
+h1, h2, h3, h4 { line-height: 1.25; }
+code, pre { font-family: "SF Mono", Menlo, Consolas, monospace; font-size: 0.9em; }
+code { background: var(--bg-raised); padding: 0.1em 0.3em; border-radius: 3px; }
+pre { background: var(--bg-raised); padding: 1rem; border-radius: 6px; overflow-x: auto; }
+pre code { background: none; padding: 0; }
+blockquote { margin: 0; padding-left: 1rem; border-left: 3px solid var(--border); color: var(--muted); }
+table { border-collapse: collapse; }
+th, td { border: 1px solid var(--border); padding: 0.3rem 0.6rem; }
+img { max-width: 100%; }
+ul.notes { list-style: none; padding: 0; }
+ul.notes li { display: flex; justify-content: space-between; gap: 1rem; padding: 0.4rem 0; border-bottom: 1px solid var(--border); }
+time { color: var(--muted); font-size: 0.85rem; white-space: nowrap; }
+"#;
+
+/// A note to publish, with its title and page file name resolved
+struct Page<'a> {
+    buffer: &'a Buffer,
+    title: String,
+    file: String,
+}
+
+/// Escape text for use in HTML content and attribute values
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_date(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Rewrite `[[wiki links]]` outside code fences into Markdown links to the
+/// target's page. Links to notes that aren't part of the site become plain text.
+fn resolve_wiki_links(content: &str, pages_by_slug: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut in_fence = false;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if in_fence {
+            out.push_str(line);
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("]]") else {
+                break;
+            };
+            out.push_str(&rest[..start]);
+
+            let inner = &after[..end];
+            let (target, alias) = match inner.split_once('|') {
+                Some((target, alias)) => (target, alias.trim()),
+                None => (inner, inner.trim()),
+            };
+            let target = target.split('#').next().unwrap_or("").trim();
+            match pages_by_slug.get(&queries::slugify(target)) {
+                Some(file) => {
+                    let text = alias.replace('[', "\\[").replace(']', "\\]");
+                    out.push_str(&format!("[{}]({})", text, file));
+                }
+                None => out.push_str(alias),
+            }
+            rest = &after[end + 2..];
+        }
+        out.push_str(rest);
+    }
+
+    out
+}
+
+/// Render note Markdown to HTML. Raw HTML in notes is escaped, not passed through.
+fn render_markdown(content: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(content, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        event => event,
+    });
+
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+fn page_html(title: &str, nav: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<link rel=\"stylesheet\" href=\"{css}\">\n</head>\n\
+         <body>\n<main>\n{nav}{body}</main>\n</body>\n</html>\n",
+        title = escape_html(title),
+        css = STYLESHEET,
+        nav = nav,
+        body = body,
+    )
+}
+
+/// Render one note as a self-contained page (stylesheet inlined, wiki links
+/// as plain text), for serving outside an exported site
+pub fn standalone_page(title: &str, content: &str) -> String {
+    let body = render_markdown(&resolve_wiki_links(content, &HashMap::new()));
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>\n{css}</style>\n</head>\n\
+         <body>\n<main>\n{body}</main>\n</body>\n</html>\n",
+        title = escape_html(title),
+        css = CSS,
+        body = body,
+    )
+}
+
+fn write_file(path: &Path, data: &str) -> AppResult<()> {
+    fs::write(path, data).map_err(|e| AppError::io(format!("Failed to write {}", path.display()), e))
+}
+
+/// Write `buffers` as a static site into `dir`: `index.html` listing the notes
+/// (pinned first, then most recently updated), one page per note named by
+/// slug, and a stylesheet. Wiki links between exported notes become page links.
+/// Returns the number of note pages written.
+pub fn write_site(dir: &Path, site_title: &str, buffers: &[Buffer]) -> AppResult<usize> {
+    fs::create_dir_all(dir).map_err(|e| AppError::io("Failed to create export directory", e))?;
+
+    let mut sorted: Vec<&Buffer> = buffers.iter().collect();
+    sorted.sort_by(|a, b| b.is_pinned.cmp(&a.is_pinned).then(b.updated_at.cmp(&a.updated_at)));
+
+    // Slugs aren't unique; later notes with the same slug get "-2", "-3", ...
+    let mut used: HashMap<String, usize> = HashMap::new();
+    let mut pages_by_slug: HashMap<String, String> = HashMap::new();
+    let mut pages = Vec::with_capacity(sorted.len());
+    for buffer in sorted {
+        let title = buffer
+            .title_override
+            .clone()
+            .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+        let slug = if buffer.slug.is_empty() { queries::slugify(&title) } else { buffer.slug.clone() };
+        let count = used.entry(slug.clone()).or_insert(0);
+        *count += 1;
+        let file = match *count {
+            1 => format!("{}.html", slug),
+            n => format!("{}-{}.html", slug, n),
+        };
+        pages_by_slug.entry(slug).or_insert_with(|| file.clone());
+        pages.push(Page { buffer, title, file });
+    }
+
+    let nav = format!("<nav><a href=\"index.html\">&larr; {}</a></nav>\n", escape_html(site_title));
+    for page in &pages {
+        let body = render_markdown(&resolve_wiki_links(&page.buffer.content, &pages_by_slug));
+        write_file(&dir.join(&page.file), &page_html(&page.title, &nav, &body))?;
+    }
+
+    let mut index = format!("<h1>{}</h1>\n<ul class=\"notes\">\n", escape_html(site_title));
+    for page in &pages {
+        index.push_str(&format!(
+            "<li><a href=\"{}\">{}</a><time>{}</time></li>\n",
+            escape_html(&page.file),
+            escape_html(&page.title),
+            format_date(page.buffer.updated_at),
+        ));
+    }
+    index.push_str("</ul>\n");
+    write_file(&dir.join("index.html"), &page_html(site_title, "", &index))?;
+    write_file(&dir.join(STYLESHEET), CSS)?;
+
+    Ok(pages.len())
+}
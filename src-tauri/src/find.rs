@@ -0,0 +1,219 @@
+use crate::error::{AppError, AppResult};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Default cap on returned matches so a one-character query in a huge note stays cheap
+const DEFAULT_MAX_MATCHES: usize = 10_000;
+
+/// Compiled regex size limit for user-supplied patterns
+const REGEX_SIZE_LIMIT: usize = 1024 * 1024;
+
+/// Options for finding text within a buffer
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FindOptions {
+    /// Treat the query as a regular expression instead of literal text
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub max_matches: Option<usize>,
+}
+
+/// A single match. Line is 0-based; column and length are in UTF-16 code
+/// units so they map directly onto JavaScript string indices.
+#[derive(Debug, Serialize)]
+pub struct FindMatch {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+/// All matches of a query, possibly truncated at `max_matches`
+#[derive(Debug, Serialize)]
+pub struct FindResult {
+    pub matches: Vec<FindMatch>,
+    pub truncated: bool,
+}
+
+/// Build the matcher for a query
+fn build_pattern(query: &str, options: &FindOptions) -> AppResult<Regex> {
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let pattern = if options.whole_word {
+        format!(r"\b(?:{})\b", pattern)
+    } else {
+        pattern
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| AppError::Validation(format!("Invalid search pattern: {}", e)))
+}
+
+/// Find all occurrences of `query` in `content`, line by line
+pub fn find_matches(content: &str, query: &str, options: &FindOptions) -> AppResult<FindResult> {
+    if query.is_empty() {
+        return Ok(FindResult { matches: Vec::new(), truncated: false });
+    }
+
+    let pattern = build_pattern(query, options)?;
+    let max_matches = options.max_matches.unwrap_or(DEFAULT_MAX_MATCHES);
+
+    let mut matches = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        // Track the UTF-16 column incrementally so long lines stay linear
+        let mut byte_pos = 0;
+        let mut column = 0;
+        for m in pattern.find_iter(line) {
+            // Empty regex matches (e.g. `^`) would highlight nothing
+            if m.start() == m.end() {
+                continue;
+            }
+            if matches.len() == max_matches {
+                return Ok(FindResult { matches, truncated: true });
+            }
+            column += line[byte_pos..m.start()].encode_utf16().count();
+            byte_pos = m.start();
+            matches.push(FindMatch {
+                line: line_index,
+                column,
+                length: m.as_str().encode_utf16().count(),
+            });
+        }
+    }
+
+    Ok(FindResult { matches, truncated: false })
+}
+
+/// Maximum changed lines previewed per buffer
+const MAX_PREVIEW_LINES: usize = 20;
+
+/// One changed line in a replacement preview (line is 0-based)
+#[derive(Debug, Serialize)]
+pub struct LineChange {
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// Result of replacing a pattern in one buffer's content
+#[derive(Debug)]
+pub struct Replacement {
+    pub content: String,
+    pub count: usize,
+    /// Lines containing a match, with the replacement applied to that line.
+    /// Patterns spanning lines are counted but not previewed.
+    pub preview: Vec<LineChange>,
+}
+
+/// Compile a case-sensitive regex for batch replacement
+/// (inline flags such as `(?i)` still apply)
+pub fn compile_regex(pattern: &str) -> AppResult<Regex> {
+    build_pattern(
+        pattern,
+        &FindOptions {
+            regex: true,
+            case_sensitive: true,
+            ..Default::default()
+        },
+    )
+}
+
+/// Replace every match of `pattern` in `content`. `replacement` may refer to
+/// capture groups (`$1`, `${name}`). Returns None when nothing changes.
+pub fn replace_all(content: &str, pattern: &Regex, replacement: &str) -> Option<Replacement> {
+    let count = pattern.find_iter(content).count();
+    if count == 0 {
+        return None;
+    }
+
+    let replaced = pattern.replace_all(content, replacement);
+    if replaced == content {
+        return None;
+    }
+
+    let preview = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| pattern.is_match(line))
+        .map(|(line, before)| LineChange {
+            line,
+            before: before.to_string(),
+            after: pattern.replace_all(before, replacement).into_owned(),
+        })
+        .filter(|change| change.before != change.after)
+        .take(MAX_PREVIEW_LINES)
+        .collect();
+
+    Some(Replacement {
+        content: replaced.into_owned(),
+        count,
+        preview,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_are_utf16_offsets() {
+        let result = find_matches("é😀 foo\nFOO", "foo", &FindOptions::default()).unwrap();
+        let found: Vec<_> = result.matches.iter().map(|m| (m.line, m.column, m.length)).collect();
+        assert_eq!(found, [(0, 4, 3), (1, 0, 3)]);
+        assert!(!result.truncated);
+    }
+
+    #[test]
+    fn whole_word_and_case_options() {
+        let options = FindOptions {
+            case_sensitive: true,
+            whole_word: true,
+            ..Default::default()
+        };
+        let result = find_matches("cat Cat concat cat", "cat", &options).unwrap();
+        let columns: Vec<_> = result.matches.iter().map(|m| m.column).collect();
+        assert_eq!(columns, [0, 15]);
+    }
+
+    #[test]
+    fn truncates_at_max_matches() {
+        let options = FindOptions {
+            max_matches: Some(2),
+            ..Default::default()
+        };
+        let result = find_matches("a a a", "a", &options).unwrap();
+        assert_eq!(result.matches.len(), 2);
+        assert!(result.truncated);
+    }
+
+    #[test]
+    fn compile_regex_is_case_sensitive_unless_flagged() {
+        assert!(!compile_regex("Foo").unwrap().is_match("foo"));
+        assert!(compile_regex("(?i)foo").unwrap().is_match("FOO"));
+        assert!(matches!(compile_regex("("), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn replace_all_expands_groups_and_previews_lines() {
+        let pattern = compile_regex(r"foo (\d+)").unwrap();
+        let replaced = replace_all("foo 1\nbar\nfoo 22", &pattern, "n=$1").unwrap();
+        assert_eq!(replaced.content, "n=1\nbar\nn=22");
+        assert_eq!(replaced.count, 2);
+        let preview: Vec<_> =
+            replaced.preview.iter().map(|c| (c.line, c.before.as_str(), c.after.as_str())).collect();
+        assert_eq!(preview, [(0, "foo 1", "n=1"), (2, "foo 22", "n=22")]);
+    }
+
+    #[test]
+    fn replace_all_is_none_without_a_change() {
+        let pattern = compile_regex("a").unwrap();
+        assert!(replace_all("bcd", &pattern, "x").is_none());
+        assert!(replace_all("abc", &pattern, "a").is_none());
+    }
+}
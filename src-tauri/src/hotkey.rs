@@ -0,0 +1,185 @@
+// System-wide shortcuts, each configured by a setting holding an accelerator
+// like "CommandOrControl+Shift+Space" (empty turns it off): one shows or
+// hides the main window, the other captures the clipboard into a new note.
+// Registered once settings have loaded at startup.
+
+use crate::commands::buffer::insert_buffer;
+use crate::db::activity::Origin;
+use crate::db::queries::{self, AppSettings};
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::AppState;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tracing::{info, warn};
+
+/// Emitted with the new note's summary after the clipboard shortcut created it;
+/// the frontend selects it and focuses the editor
+pub const CLIPBOARD_NOTE_EVENT: &str = "clipboard-note-created";
+
+/// What a global shortcut does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Show and focus the main window, or hide it when it already has focus
+    ToggleWindow,
+    /// Create a note from the clipboard text and open it
+    CaptureClipboard,
+}
+
+impl Action {
+    const ALL: [Action; 2] = [Action::ToggleWindow, Action::CaptureClipboard];
+
+    /// Setting holding the action's accelerator
+    pub fn setting(self) -> &'static str {
+        match self {
+            Self::ToggleWindow => "global_shortcut",
+            Self::CaptureClipboard => "clipboard_shortcut",
+        }
+    }
+
+    pub fn from_setting(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.setting() == key)
+    }
+
+    fn accelerator(self, settings: &AppSettings) -> &str {
+        match self {
+            Self::ToggleWindow => &settings.global_shortcut,
+            Self::CaptureClipboard => &settings.clipboard_shortcut,
+        }
+    }
+
+    fn run(self, app: &AppHandle) {
+        match self {
+            Self::ToggleWindow => toggle_main_window(app),
+            Self::CaptureClipboard => capture_clipboard(app),
+        }
+    }
+}
+
+/// Parse an accelerator; None for an empty one (shortcut off)
+fn parse(accelerator: &str) -> AppResult<Option<Shortcut>> {
+    let accelerator = accelerator.trim();
+    if accelerator.is_empty() {
+        return Ok(None);
+    }
+    accelerator
+        .parse::<Shortcut>()
+        .map(Some)
+        .map_err(|e| AppError::Validation(format!("Invalid shortcut '{}': {}", accelerator, e)))
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+pub fn toggle_main_window(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    if window.is_visible().unwrap_or(false) && window.is_focused().unwrap_or(false) {
+        let _ = window.hide();
+    } else {
+        show_main_window(app);
+    }
+}
+
+fn capture_clipboard(app: &AppHandle) {
+    let text = match app.clipboard().read_text() {
+        Ok(text) if !text.trim().is_empty() => text,
+        Ok(_) => {
+            info!("Clipboard has no text; no note created");
+            return;
+        }
+        Err(e) => {
+            warn!("Failed to read clipboard: {}", e);
+            return;
+        }
+    };
+
+    let state = app.state::<AppState>();
+    match insert_buffer(app, &state, &text, "clipboard", Origin::Ui) {
+        Ok(summary) => {
+            show_main_window(app);
+            if let Err(e) = app.emit(CLIPBOARD_NOTE_EVENT, &summary) {
+                warn!("Failed to emit {} event: {}", CLIPBOARD_NOTE_EVENT, e);
+            }
+        }
+        Err(e) => warn!("Failed to create note from clipboard: {}", e),
+    }
+}
+
+/// Register `accelerator` for `action`; empty does nothing. Fails with a
+/// conflict when the OS won't register it, e.g. because another app uses it.
+fn register(app: &AppHandle, action: Action, accelerator: &str) -> AppResult<()> {
+    let Some(shortcut) = parse(accelerator)? else {
+        return Ok(());
+    };
+    app.global_shortcut()
+        .on_shortcut(shortcut, move |app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                action.run(app);
+            }
+        })
+        .map_err(|e| {
+            AppError::Conflict(format!("Shortcut '{}' is not available (it may be in use): {}", accelerator.trim(), e))
+        })
+}
+
+fn unregister(app: &AppHandle, accelerator: &str) {
+    if let Ok(Some(shortcut)) = parse(accelerator) {
+        if let Err(e) = app.global_shortcut().unregister(shortcut) {
+            warn!("Failed to unregister global shortcut {}: {}", accelerator, e);
+        }
+    }
+}
+
+/// Register every configured shortcut (at startup)
+pub fn register_from_settings(app: &AppHandle, settings: &AppSettings) {
+    if let Err(e) = app.global_shortcut().unregister_all() {
+        warn!("Failed to unregister global shortcuts: {}", e);
+    }
+    for action in Action::ALL {
+        let accelerator = action.accelerator(settings);
+        match register(app, action, accelerator) {
+            Ok(()) if accelerator.trim().is_empty() => {}
+            Ok(()) => info!("Registered global shortcut {} ({:?})", accelerator, action),
+            Err(e) => warn!("{}", e),
+        }
+    }
+}
+
+/// Switch an action to a new shortcut and save it. If it can't be
+/// registered, the previous shortcut stays active and the setting is unchanged.
+pub fn set(app: &AppHandle, action: Action, accelerator: &str) -> AppResult<()> {
+    let accelerator = accelerator.trim();
+    parse(accelerator)?;
+    let state = app.state::<AppState>();
+    let settings = state.read(queries::get_settings).db_context("Failed to get settings")?;
+    let previous = action.accelerator(&settings).to_string();
+    if previous.eq_ignore_ascii_case(accelerator) {
+        return Ok(());
+    }
+    let taken = Action::ALL
+        .into_iter()
+        .any(|other| other != action && other.accelerator(&settings).trim().eq_ignore_ascii_case(accelerator));
+    if !accelerator.is_empty() && taken {
+        return Err(AppError::Conflict(format!("Shortcut '{}' is already used by another action", accelerator)));
+    }
+
+    unregister(app, &previous);
+    if let Err(e) = register(app, action, accelerator) {
+        if let Err(restore_error) = register(app, action, &previous) {
+            warn!("Failed to restore global shortcut {}: {}", previous, restore_error);
+        }
+        return Err(e);
+    }
+
+    let conn = state.writer.lock();
+    queries::set_setting(&conn, action.setting(), accelerator).db_context("Failed to save setting")?;
+    info!("Global shortcut for {:?} set to '{}'", action, accelerator);
+    Ok(())
+}
@@ -0,0 +1,308 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Value of the `locale` setting that follows the OS language
+pub const SYSTEM_LOCALE: &str = "system";
+
+/// Supported values for the `locale` setting besides `system`
+pub const LOCALES: &[&str] = &["en", "de", "es", "fr"];
+
+/// Language used for native menus and error messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    De,
+    Es,
+    Fr,
+}
+
+/// Active locale, shared by menus and error serialization
+static CURRENT: AtomicU8 = AtomicU8::new(Locale::En as u8);
+
+impl Locale {
+    /// Parse a language tag such as `de`, `de-AT` or `fr_CA.UTF-8`
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_', '.']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Self::En),
+            "de" => Some(Self::De),
+            "es" => Some(Self::Es),
+            "fr" => Some(Self::Fr),
+            _ => None,
+        }
+    }
+
+    /// OS language, falling back to English when it isn't supported
+    pub fn system() -> Self {
+        sys_locale::get_locale()
+            .and_then(|tag| Self::parse(&tag))
+            .unwrap_or(Self::En)
+    }
+
+    /// Resolve a `locale` setting value
+    pub fn from_setting(value: &str) -> Self {
+        if value == SYSTEM_LOCALE {
+            Self::system()
+        } else {
+            Self::parse(value).unwrap_or(Self::En)
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::De,
+            2 => Self::Es,
+            3 => Self::Fr,
+            _ => Self::En,
+        }
+    }
+}
+
+/// Whether a `locale` setting value is valid
+pub fn is_valid_setting(value: &str) -> bool {
+    value == SYSTEM_LOCALE || LOCALES.contains(&value)
+}
+
+/// Switch the active locale
+pub fn set_locale(locale: Locale) {
+    CURRENT.store(locale as u8, Ordering::Relaxed);
+}
+
+/// The active locale
+pub fn current() -> Locale {
+    Locale::from_u8(CURRENT.load(Ordering::Relaxed))
+}
+
+/// Translate an English string into the active locale.
+/// Strings without a translation are returned unchanged.
+pub fn t(text: &str) -> &str {
+    lookup(current(), text).unwrap_or(text)
+}
+
+/// Translate a template containing one `{}` placeholder, translating the
+/// argument too (e.g. the entity name in "{} not found")
+pub fn t_with(template: &str, arg: &str) -> String {
+    t(template).replacen("{}", t(arg), 1)
+}
+
+/// Translate a user-facing message. Messages of the form
+/// "Invalid lint rules: foo" translate the part before the colon and keep
+/// the value as-is.
+pub fn translate_message(message: &str) -> String {
+    if let Some(translated) = lookup(current(), message) {
+        return translated.to_string();
+    }
+    match message.split_once(": ") {
+        Some((head, value)) => match lookup(current(), head) {
+            Some(translated) => format!("{}: {}", translated, value),
+            None => message.to_string(),
+        },
+        None => message.to_string(),
+    }
+}
+
+fn lookup(locale: Locale, text: &str) -> Option<&'static str> {
+    match locale {
+        Locale::En => None,
+        Locale::De => de(text),
+        Locale::Es => es(text),
+        Locale::Fr => fr(text),
+    }
+}
+
+fn de(text: &str) -> Option<&'static str> {
+    Some(match text {
+        // Menus
+        "About Flashnotes" => "Über Flashnotes",
+        "Hide Flashnotes" => "Flashnotes ausblenden",
+        "Hide Others" => "Andere ausblenden",
+        "Show All" => "Alle einblenden",
+        "Quit Flashnotes" => "Flashnotes beenden",
+        "Edit" => "Bearbeiten",
+        "Undo" => "Widerrufen",
+        "Redo" => "Wiederholen",
+        "Cut" => "Ausschneiden",
+        "Copy" => "Kopieren",
+        "Paste" => "Einsetzen",
+        "Select All" => "Alles auswählen",
+        "Window" => "Fenster",
+        "Minimize" => "Im Dock ablegen",
+        "Zoom" => "Zoomen",
+        "Stay on Top" => "Immer im Vordergrund",
+        "Close" => "Schließen",
+        "Help" => "Hilfe",
+        "GitHub Repository" => "GitHub-Repository",
+        "Database Error" => "Datenbankfehler",
+
+        // Error templates and entity names
+        "{} not found" => "{} nicht gefunden",
+        "{} is not available on this platform" => "{} ist auf dieser Plattform nicht verfügbar",
+        "The {} job was cancelled" => "Der Vorgang „{}“ wurde abgebrochen",
+        "Buffer" => "Notiz",
+        "Ephemeral buffer" => "Temporäre Notiz",
+        "Attachment" => "Anhang",
+        "Card" => "Karteikarte",
+        "Script" => "Skript",
+        "Webhook" => "Webhook",
+        "Journal entry" => "Journaleintrag",
+        "Job" => "Vorgang",
+        "Share link" => "Freigabelink",
+
+        // Errors
+        "Failed to get buffer" => "Notiz konnte nicht geladen werden",
+        "Failed to get buffers" => "Notizen konnten nicht geladen werden",
+        "Failed to create buffer" => "Notiz konnte nicht erstellt werden",
+        "Failed to save buffer" => "Notiz konnte nicht gespeichert werden",
+        "Failed to update buffer" => "Notiz konnte nicht aktualisiert werden",
+        "Failed to rename buffer" => "Notiz konnte nicht umbenannt werden",
+        "Failed to delete buffer" => "Notiz konnte nicht gelöscht werden",
+        "Failed to search buffers" => "Suche fehlgeschlagen",
+        "Failed to get sidebar data" => "Seitenleiste konnte nicht geladen werden",
+        "Failed to get settings" => "Einstellungen konnten nicht geladen werden",
+        "Failed to save setting" => "Einstellung konnte nicht gespeichert werden",
+        "Failed to reorder buffers" => "Reihenfolge konnte nicht geändert werden",
+        "Failed to toggle pin" => "Anheften konnte nicht geändert werden",
+        "Failed to import buffer" => "Notiz konnte nicht importiert werden",
+        "Failed to rebuild search index" => "Suchindex konnte nicht neu aufgebaut werden",
+        "Failed to undo" => "Rückgängig machen fehlgeschlagen",
+        "Failed to redo" => "Wiederholen fehlgeschlagen",
+        "No notes to export" => "Keine Notizen zum Exportieren",
+        "No update available" => "Kein Update verfügbar",
+        "Script name is required" => "Ein Skriptname ist erforderlich",
+        "Invalid note source" => "Ungültige Notizquelle",
+        "Invalid locale" => "Ungültige Sprache",
+        "URL must be http(s)" => "URL muss mit http(s) beginnen",
+        _ => return None,
+    })
+}
+
+fn es(text: &str) -> Option<&'static str> {
+    Some(match text {
+        // Menus
+        "About Flashnotes" => "Acerca de Flashnotes",
+        "Hide Flashnotes" => "Ocultar Flashnotes",
+        "Hide Others" => "Ocultar otros",
+        "Show All" => "Mostrar todo",
+        "Quit Flashnotes" => "Salir de Flashnotes",
+        "Edit" => "Edición",
+        "Undo" => "Deshacer",
+        "Redo" => "Rehacer",
+        "Cut" => "Cortar",
+        "Copy" => "Copiar",
+        "Paste" => "Pegar",
+        "Select All" => "Seleccionar todo",
+        "Window" => "Ventana",
+        "Minimize" => "Minimizar",
+        "Zoom" => "Zoom",
+        "Stay on Top" => "Mantener encima",
+        "Close" => "Cerrar",
+        "Help" => "Ayuda",
+        "GitHub Repository" => "Repositorio en GitHub",
+        "Database Error" => "Error de base de datos",
+
+        // Error templates and entity names
+        "{} not found" => "{}: no se encontró",
+        "{} is not available on this platform" => "{}: no disponible en esta plataforma",
+        "The {} job was cancelled" => "Se canceló la tarea «{}»",
+        "Buffer" => "Nota",
+        "Ephemeral buffer" => "Nota temporal",
+        "Attachment" => "Adjunto",
+        "Card" => "Tarjeta",
+        "Script" => "Script",
+        "Webhook" => "Webhook",
+        "Journal entry" => "Entrada del diario",
+        "Job" => "Tarea",
+        "Share link" => "Enlace compartido",
+
+        // Errors
+        "Failed to get buffer" => "No se pudo cargar la nota",
+        "Failed to get buffers" => "No se pudieron cargar las notas",
+        "Failed to create buffer" => "No se pudo crear la nota",
+        "Failed to save buffer" => "No se pudo guardar la nota",
+        "Failed to update buffer" => "No se pudo actualizar la nota",
+        "Failed to rename buffer" => "No se pudo renombrar la nota",
+        "Failed to delete buffer" => "No se pudo eliminar la nota",
+        "Failed to search buffers" => "La búsqueda falló",
+        "Failed to get sidebar data" => "No se pudo cargar la barra lateral",
+        "Failed to get settings" => "No se pudieron cargar los ajustes",
+        "Failed to save setting" => "No se pudo guardar el ajuste",
+        "Failed to reorder buffers" => "No se pudo cambiar el orden",
+        "Failed to toggle pin" => "No se pudo fijar la nota",
+        "Failed to import buffer" => "No se pudo importar la nota",
+        "Failed to rebuild search index" => "No se pudo reconstruir el índice de búsqueda",
+        "Failed to undo" => "No se pudo deshacer",
+        "Failed to redo" => "No se pudo rehacer",
+        "No notes to export" => "No hay notas para exportar",
+        "No update available" => "No hay actualizaciones disponibles",
+        "Script name is required" => "El nombre del script es obligatorio",
+        "Invalid note source" => "Origen de nota no válido",
+        "Invalid locale" => "Idioma no válido",
+        "URL must be http(s)" => "La URL debe ser http(s)",
+        _ => return None,
+    })
+}
+
+fn fr(text: &str) -> Option<&'static str> {
+    Some(match text {
+        // Menus
+        "About Flashnotes" => "À propos de Flashnotes",
+        "Hide Flashnotes" => "Masquer Flashnotes",
+        "Hide Others" => "Masquer les autres",
+        "Show All" => "Tout afficher",
+        "Quit Flashnotes" => "Quitter Flashnotes",
+        "Edit" => "Édition",
+        "Undo" => "Annuler",
+        "Redo" => "Rétablir",
+        "Cut" => "Couper",
+        "Copy" => "Copier",
+        "Paste" => "Coller",
+        "Select All" => "Tout sélectionner",
+        "Window" => "Fenêtre",
+        "Minimize" => "Placer dans le Dock",
+        "Zoom" => "Réduire/agrandir",
+        "Stay on Top" => "Toujours au premier plan",
+        "Close" => "Fermer",
+        "Help" => "Aide",
+        "GitHub Repository" => "Dépôt GitHub",
+        "Database Error" => "Erreur de base de données",
+
+        // Error templates and entity names
+        "{} not found" => "{} introuvable",
+        "{} is not available on this platform" => "{} n'est pas disponible sur cette plateforme",
+        "The {} job was cancelled" => "La tâche « {} » a été annulée",
+        "Buffer" => "Note",
+        "Ephemeral buffer" => "Note temporaire",
+        "Attachment" => "Pièce jointe",
+        "Card" => "Carte",
+        "Script" => "Script",
+        "Webhook" => "Webhook",
+        "Journal entry" => "Entrée du journal",
+        "Job" => "Tâche",
+        "Share link" => "Lien de partage",
+
+        // Errors
+        "Failed to get buffer" => "Impossible de charger la note",
+        "Failed to get buffers" => "Impossible de charger les notes",
+        "Failed to create buffer" => "Impossible de créer la note",
+        "Failed to save buffer" => "Impossible d'enregistrer la note",
+        "Failed to update buffer" => "Impossible de mettre à jour la note",
+        "Failed to rename buffer" => "Impossible de renommer la note",
+        "Failed to delete buffer" => "Impossible de supprimer la note",
+        "Failed to search buffers" => "La recherche a échoué",
+        "Failed to get sidebar data" => "Impossible de charger la barre latérale",
+        "Failed to get settings" => "Impossible de charger les réglages",
+        "Failed to save setting" => "Impossible d'enregistrer le réglage",
+        "Failed to reorder buffers" => "Impossible de modifier l'ordre",
+        "Failed to toggle pin" => "Impossible d'épingler la note",
+        "Failed to import buffer" => "Impossible d'importer la note",
+        "Failed to rebuild search index" => "Impossible de reconstruire l'index de recherche",
+        "Failed to undo" => "Impossible d'annuler",
+        "Failed to redo" => "Impossible de rétablir",
+        "No notes to export" => "Aucune note à exporter",
+        "No update available" => "Aucune mise à jour disponible",
+        "Script name is required" => "Le nom du script est obligatoire",
+        "Invalid note source" => "Source de note non valide",
+        "Invalid locale" => "Langue non valide",
+        "URL must be http(s)" => "L'URL doit commencer par http(s)",
+        _ => return None,
+    })
+}
@@ -0,0 +1,125 @@
+use crate::encryption;
+use crate::state::AppState;
+use chrono::Utc;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// How often the watcher checks for inactivity
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Event emitted to the frontend when the app locks itself
+pub const APP_LOCKED_EVENT: &str = "app-locked";
+
+/// Idle tracking for auto-lock.
+/// Any command invocation counts as activity; locking is disabled while
+/// `timeout_minutes` is 0.
+pub struct IdleLock {
+    last_activity: AtomicI64,
+    locked: AtomicBool,
+    timeout_minutes: AtomicU32,
+    hide_window: AtomicBool,
+}
+
+impl IdleLock {
+    pub fn new() -> Self {
+        Self {
+            last_activity: AtomicI64::new(Utc::now().timestamp()),
+            locked: AtomicBool::new(false),
+            timeout_minutes: AtomicU32::new(0),
+            hide_window: AtomicBool::new(false),
+        }
+    }
+
+    /// Apply the auto-lock settings
+    pub fn configure(&self, timeout_minutes: u32, hide_window: bool) {
+        self.timeout_minutes.store(timeout_minutes, Ordering::Relaxed);
+        self.hide_window.store(hide_window, Ordering::Relaxed);
+    }
+
+    /// Record user activity, postponing the next lock
+    pub fn touch(&self) {
+        self.last_activity.store(Utc::now().timestamp(), Ordering::Relaxed);
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::Relaxed)
+    }
+
+    /// Lock now. Returns false if already locked.
+    pub fn lock(&self) -> bool {
+        !self.locked.swap(true, Ordering::Relaxed)
+    }
+
+    pub fn unlock(&self) {
+        self.locked.store(false, Ordering::Relaxed);
+        self.touch();
+    }
+
+    /// Seconds since the last recorded activity
+    pub fn idle_secs(&self, now: i64) -> i64 {
+        now - self.last_activity.load(Ordering::Relaxed)
+    }
+
+    /// Whether the idle timeout has elapsed at `now`
+    fn is_idle(&self, now: i64) -> bool {
+        let minutes = self.timeout_minutes.load(Ordering::Relaxed);
+        minutes > 0 && self.idle_secs(now) >= i64::from(minutes) * 60
+    }
+}
+
+impl Default for IdleLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lock the app: flag the state, close an encrypted database, optionally
+/// hide the window, and notify the frontend
+pub fn lock_app(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if !state.idle.lock() {
+        return;
+    }
+    if let Err(e) = encryption::relock(&state) {
+        warn!("Failed to lock the database: {}", e);
+    }
+
+    #[cfg(desktop)]
+    if state.idle.hide_window.load(Ordering::Relaxed) {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.hide();
+        }
+    }
+
+    if let Err(e) = app.emit(APP_LOCKED_EVENT, ()) {
+        warn!("Failed to emit {} event: {}", APP_LOCKED_EVENT, e);
+    }
+
+    info!("App locked");
+}
+
+/// Spawn the background thread that locks the app after the configured idle time
+pub fn spawn_watcher(app: AppHandle) {
+    let spawned = thread::Builder::new()
+        .name("idle-lock".to_string())
+        .spawn(move || loop {
+            thread::sleep(CHECK_INTERVAL);
+
+            let idle = {
+                let state = app.state::<AppState>();
+                !state.idle.is_locked() && state.idle.is_idle(Utc::now().timestamp())
+            };
+
+            if idle {
+                info!("Idle timeout reached, locking");
+                lock_app(&app);
+            }
+        });
+
+    if let Err(e) = spawned {
+        warn!("Failed to start idle-lock watcher: {}", e);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::queries;
+use crate::error::{AppResult, DbResultExt};
+use crate::state::AppState;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Event emitted after shared items were turned into notes
+pub const INBOX_INGESTED_EVENT: &str = "inbox-ingested";
+
+/// Item written by a share extension as `<anything>.json`
+#[derive(Debug, Deserialize)]
+struct SharedItem {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub(crate) struct IngestedPayload {
+    pub ids: Vec<String>,
+}
+
+/// Staging directory that capture sources (e.g. the iOS share extension)
+/// drop items into. Files are `.json` (`{title?, text?, url?}`) or plain
+/// `.txt`/`.md`; each becomes a new note and is then deleted. Writers should
+/// create files under another extension and rename them into place when complete.
+pub fn inbox_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("inbox")
+}
+
+/// Turn a shared item into note content and its source
+/// ("web:<url>" for shared links, otherwise "share")
+fn item_to_content(item: SharedItem) -> (String, String) {
+    let source = match item.url.as_deref().map(str::trim) {
+        Some(url) if !url.is_empty() => format!("web:{}", url),
+        _ => "share".to_string(),
+    };
+
+    let parts: Vec<String> = [
+        item.title.map(|t| format!("# {}", t.trim())),
+        item.text.map(|t| t.trim().to_string()),
+        item.url.map(|u| u.trim().to_string()),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|p| !p.is_empty() && p != "#")
+    .collect();
+
+    (parts.join("\n\n"), source)
+}
+
+/// Read one inbox file into note content and source (None if unreadable or empty)
+fn read_item(path: &Path) -> Option<(String, String)> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| warn!("Failed to read inbox item {:?}: {}", path, e))
+        .ok()?;
+
+    let (content, source) = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => match serde_json::from_str::<SharedItem>(&data) {
+            Ok(item) => item_to_content(item),
+            Err(e) => {
+                warn!("Skipping malformed inbox item {:?}: {}", path, e);
+                return None;
+            }
+        },
+        Some("txt") | Some("md") => (data.trim().to_string(), "share".to_string()),
+        _ => return None,
+    };
+
+    (!content.is_empty()).then_some((content, source))
+}
+
+/// Create notes from every item in the inbox, oldest first.
+/// Returns the ids of the new buffers.
+pub fn ingest(state: &AppState) -> AppResult<Vec<String>> {
+    let dir = inbox_dir(&state.app_data_dir);
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    paths.sort_by_key(|p| p.metadata().and_then(|m| m.modified()).ok());
+
+    let mut ids = Vec::new();
+    for path in paths {
+        let Some((content, source)) = read_item(&path) else {
+            continue;
+        };
+
+        let id = Uuid::new_v4().to_string();
+        {
+            let conn = state.writer.lock();
+            queries::create_buffer(&conn, &id, &content, &source, Utc::now().timestamp())
+                .db_context("Failed to create buffer from shared item")?;
+            activity::record(&conn, Some(&id), Operation::Create, Origin::System, Some(&source));
+        }
+
+        // Only remove the file once its note exists
+        if let Err(e) = fs::remove_file(&path) {
+            warn!("Failed to remove ingested inbox item {:?}: {}", path, e);
+        }
+        ids.push(id);
+    }
+
+    if !ids.is_empty() {
+        info!("Ingested {} shared items from inbox", ids.len());
+    }
+    Ok(ids)
+}
+
+/// Ingest in the background and tell the frontend about new notes
+pub fn ingest_and_notify(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if state.ready.is_locked() {
+        return;
+    }
+    match ingest(&state) {
+        Ok(ids) if ids.is_empty() => {}
+        Ok(ids) => {
+            if let Err(e) = app.emit(INBOX_INGESTED_EVENT, IngestedPayload { ids }) {
+                warn!("Failed to emit {} event: {}", INBOX_INGESTED_EVENT, e);
+            }
+        }
+        Err(e) => warn!("Inbox ingestion failed: {}", e),
+    }
+}
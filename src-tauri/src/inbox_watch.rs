@@ -0,0 +1,312 @@
+// Opt-in capture folder: text and Markdown files dropped into a user-chosen
+// directory (by scripts, email rules, scanners) become new notes. Imported
+// files are moved to an "Imported" subfolder or deleted, per the
+// `inbox_after_import` setting. The folder is polled; a file is only picked
+// up once its size and modification time are unchanged between two polls,
+// so half-written files aren't imported.
+
+use crate::commands::buffer::validate_buffer_size;
+use crate::commands::import_export::IMPORTABLE_EXTENSIONS;
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::queries;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::inbox::{IngestedPayload, INBOX_INGESTED_EVENT};
+use crate::state::AppState;
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Setting holding the watched directory (empty = off)
+pub const WATCH_DIR_SETTING: &str = "inbox_watch_dir";
+
+/// How often the watched directory is scanned
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Subfolder that imported files are moved into with the "archive" policy
+const ARCHIVE_DIR: &str = "Imported";
+
+/// Number of per-file results kept for `get_inbox_watch_log`
+const MAX_LOG_ENTRIES: usize = 100;
+
+/// What happens to a file once its note exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AfterImport {
+    Archive,
+    Delete,
+}
+
+impl AfterImport {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "archive" => Some(Self::Archive),
+            "delete" => Some(Self::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of handling one file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchStatus {
+    Imported,
+    /// Empty file; archived or deleted without creating a note
+    Skipped,
+    /// Left in place; retried once the file changes
+    Failed,
+}
+
+/// Per-file result shown in the inbox log
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchResult {
+    pub file: String,
+    pub status: WatchStatus,
+    pub buffer_id: Option<String>,
+    pub message: Option<String>,
+    pub at: i64,
+}
+
+/// Size and modification time, compared between polls
+type Signature = (u64, Option<SystemTime>);
+
+/// Poll state and recent results of the folder watcher
+pub struct InboxWatcher {
+    /// Files seen on the previous poll, to detect when they stop changing
+    seen: Mutex<HashMap<PathBuf, Signature>>,
+    /// Files that failed to import, skipped until they change
+    failed: Mutex<HashMap<PathBuf, Signature>>,
+    log: Mutex<VecDeque<WatchResult>>,
+}
+
+impl InboxWatcher {
+    pub fn new() -> Self {
+        Self {
+            seen: Mutex::new(HashMap::new()),
+            failed: Mutex::new(HashMap::new()),
+            log: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Recent results, newest first
+    pub fn log(&self) -> Vec<WatchResult> {
+        self.log.lock().iter().rev().cloned().collect()
+    }
+
+    fn push(&self, result: WatchResult) {
+        let mut log = self.log.lock();
+        if log.len() == MAX_LOG_ENTRIES {
+            log.pop_front();
+        }
+        log.push_back(result);
+    }
+
+    /// Forget poll state, e.g. after the watched directory changes
+    pub fn reset(&self) {
+        self.seen.lock().clear();
+        self.failed.lock().clear();
+    }
+}
+
+impl Default for InboxWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check a directory chosen for watching
+pub fn validate_dir(value: &str) -> AppResult<()> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    let path = Path::new(value);
+    if !path.is_absolute() || !path.is_dir() {
+        return Err(AppError::Validation(format!("Inbox folder must be an existing directory: {}", value)));
+    }
+    Ok(())
+}
+
+fn signature(path: &Path) -> Option<Signature> {
+    let meta = fs::metadata(path).ok()?;
+    meta.is_file().then(|| (meta.len(), meta.modified().ok()))
+}
+
+fn is_candidate(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let extension = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+    !name.starts_with('.') && extension.is_some_and(|e| IMPORTABLE_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Files whose signature matches the previous poll and that haven't
+/// already failed in their current state
+fn stable_files(watcher: &InboxWatcher, dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let current: HashMap<PathBuf, Signature> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| is_candidate(p))
+        .filter_map(|p| signature(&p).map(|sig| (p, sig)))
+        .collect();
+
+    let mut failed = watcher.failed.lock();
+    failed.retain(|path, sig| current.get(path) == Some(sig));
+
+    let mut seen = watcher.seen.lock();
+    let mut stable: Vec<PathBuf> = current
+        .iter()
+        .filter(|(path, sig)| seen.get(*path) == Some(sig) && !failed.contains_key(*path))
+        .map(|(path, _)| path.clone())
+        .collect();
+    *seen = current;
+
+    stable.sort_by_key(|p| seen.get(p).and_then(|(_, modified)| *modified));
+    stable
+}
+
+/// Move an imported file into the archive subfolder, keeping its name
+/// unless a file with that name was archived before
+fn archive_file(dir: &Path, path: &Path) -> std::io::Result<()> {
+    let archive_dir = dir.join(ARCHIVE_DIR);
+    fs::create_dir_all(&archive_dir)?;
+
+    let name = path.file_name().unwrap_or_default();
+    let mut target = archive_dir.join(name);
+    if target.exists() {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = path.extension().unwrap_or_default().to_string_lossy();
+        target = archive_dir.join(format!("{}-{}.{}", stem, Utc::now().timestamp(), extension));
+    }
+    fs::rename(path, target)
+}
+
+fn dispose(dir: &Path, path: &Path, after: AfterImport) -> std::io::Result<()> {
+    match after {
+        AfterImport::Archive => archive_file(dir, path),
+        AfterImport::Delete => fs::remove_file(path),
+    }
+}
+
+/// Import one file. Returns the new buffer id, or None for an empty file.
+fn import_file(state: &AppState, path: &Path, file_name: &str) -> AppResult<Option<String>> {
+    let text = fs::read_to_string(path).map_err(|e| AppError::io("Failed to read file", e))?;
+    let content = text.trim();
+    if content.is_empty() {
+        return Ok(None);
+    }
+    validate_buffer_size(content)?;
+
+    let id = Uuid::new_v4().to_string();
+    let source = format!("import:{}", file_name);
+    let conn = state.writer.lock();
+    queries::create_buffer(&conn, &id, content, &source, Utc::now().timestamp())
+        .db_context("Failed to create buffer")?;
+    activity::record(&conn, Some(&id), Operation::Create, Origin::System, Some(&source));
+    Ok(Some(id))
+}
+
+/// Handle one stable file: import it, then archive or delete it. A file
+/// that can't be moved afterwards is remembered so it isn't imported twice.
+fn process(state: &AppState, dir: &Path, path: &Path, after: AfterImport) -> WatchResult {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let mut result = WatchResult {
+        file: file_name.clone(),
+        status: WatchStatus::Failed,
+        buffer_id: None,
+        message: None,
+        at: Utc::now().timestamp(),
+    };
+
+    match import_file(state, path, &file_name) {
+        Ok(Some(id)) => {
+            info!("Inbox folder: imported {} as {}", file_name, id);
+            result.status = WatchStatus::Imported;
+            result.buffer_id = Some(id);
+        }
+        Ok(None) => {
+            info!("Inbox folder: skipped empty file {}", file_name);
+            result.status = WatchStatus::Skipped;
+        }
+        Err(e) => {
+            warn!("Inbox folder: failed to import {}: {}", file_name, e);
+            result.message = Some(e.to_string());
+        }
+    }
+
+    let disposed = match result.status {
+        WatchStatus::Failed => Ok(()),
+        _ => dispose(dir, path, after),
+    };
+    if let Err(e) = disposed {
+        warn!("Inbox folder: failed to move {} after import: {}", file_name, e);
+        result.message = Some(format!("Failed to move the file after import: {}", e));
+    }
+
+    if result.status == WatchStatus::Failed || result.message.is_some() {
+        if let Some(sig) = signature(path) {
+            state.inbox_watch.failed.lock().insert(path.to_path_buf(), sig);
+        }
+    }
+    result
+}
+
+/// One poll of the watched directory
+fn poll(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let Ok(settings) = state.read(queries::get_settings) else {
+        return;
+    };
+    if settings.inbox_watch_dir.is_empty() {
+        return;
+    }
+
+    let dir = PathBuf::from(&settings.inbox_watch_dir);
+    if !dir.is_dir() {
+        debug!("Inbox folder {:?} is not available", dir);
+        return;
+    }
+    let after = AfterImport::parse(&settings.inbox_after_import).unwrap_or(AfterImport::Archive);
+
+    let mut ids = Vec::new();
+    for path in stable_files(&state.inbox_watch, &dir) {
+        let result = process(&state, &dir, &path, after);
+        if let Some(id) = &result.buffer_id {
+            ids.push(id.clone());
+        }
+        state.inbox_watch.push(result);
+    }
+
+    if !ids.is_empty() {
+        if let Err(e) = app.emit(INBOX_INGESTED_EVENT, IngestedPayload { ids }) {
+            warn!("Failed to emit {} event: {}", INBOX_INGESTED_EVENT, e);
+        }
+    }
+}
+
+/// Spawn the thread polling the inbox folder (idle while the setting is empty)
+pub fn spawn(app: AppHandle) {
+    let spawned = thread::Builder::new()
+        .name("inbox-watch".to_string())
+        .spawn(move || loop {
+            // Skipped while the encrypted database is locked
+            if !app.state::<AppState>().ready.is_locked() {
+                poll(&app);
+            }
+            thread::sleep(POLL_INTERVAL);
+        });
+
+    if let Err(e) = spawned {
+        warn!("Failed to start inbox folder watcher: {}", e);
+    }
+}
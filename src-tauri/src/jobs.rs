@@ -0,0 +1,148 @@
+// Background job registry. Long-running work (imports, embedding runs,
+// maintenance) is registered here while it runs so it can be listed and
+// cancelled. Cancellation is cooperative: jobs check their token between steps.
+
+use crate::error::{AppError, AppResult};
+use crate::state::AppState;
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+/// Set when a job is asked to stop
+#[derive(Clone)]
+pub struct CancelToken {
+    kind: &'static str,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Err(Cancelled) once cancellation was requested; call between steps
+    pub fn check(&self) -> AppResult<()> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            Err(AppError::Cancelled(self.kind))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A running job, as listed to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: String,
+    /// "import", "embeddings", "maintenance", ...
+    pub kind: &'static str,
+    pub started_at: i64,
+    /// Cancellation was requested; the job stops at its next check
+    pub cancelling: bool,
+}
+
+struct Job {
+    info: JobInfo,
+    token: CancelToken,
+}
+
+/// Registry of running jobs
+pub struct JobQueue {
+    jobs: Mutex<HashMap<String, Job>>,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self {
+            jobs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn register(&self, kind: &'static str) -> (String, CancelToken) {
+        let id = Uuid::new_v4().to_string();
+        let token = CancelToken::new(kind);
+        let info = JobInfo {
+            id: id.clone(),
+            kind,
+            started_at: Utc::now().timestamp(),
+            cancelling: false,
+        };
+        self.jobs.lock().insert(id.clone(), Job { info, token: token.clone() });
+        (id, token)
+    }
+
+    fn finish(&self, id: &str) {
+        self.jobs.lock().remove(id);
+    }
+
+    /// Running jobs, oldest first
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<JobInfo> = self.jobs.lock().values().map(|job| job.info.clone()).collect();
+        jobs.sort_by_key(|job| job.started_at);
+        jobs
+    }
+
+    /// Ask a job to stop. Returns false if no such job is running.
+    pub fn cancel(&self, id: &str) -> bool {
+        let mut jobs = self.jobs.lock();
+        let Some(job) = jobs.get_mut(id) else {
+            return false;
+        };
+        job.token.cancel();
+        job.info.cancelling = true;
+        true
+    }
+}
+
+/// Removes a job from the registry when it ends, even by panic
+struct Registration<'a> {
+    queue: &'a JobQueue,
+    id: String,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        self.queue.finish(&self.id);
+    }
+}
+
+/// Run a job on the current thread, registered for its duration
+pub fn run_blocking<T>(
+    app: &AppHandle,
+    kind: &'static str,
+    job: impl FnOnce(&CancelToken) -> AppResult<T>,
+) -> AppResult<T> {
+    let state = app.state::<AppState>();
+    let (id, token) = state.jobs.register(kind);
+    let _registration = Registration { queue: &state.jobs, id };
+    job(&token)
+}
+
+/// Run a job on the blocking thread pool, registered for its duration
+pub async fn run<T: Send + 'static>(
+    app: AppHandle,
+    kind: &'static str,
+    job: impl FnOnce(&AppHandle, &CancelToken) -> AppResult<T> + Send + 'static,
+) -> AppResult<T> {
+    tauri::async_runtime::spawn_blocking(move || run_blocking(&app, kind, |token| job(&app, token)))
+        .await
+        .map_err(|e| AppError::Internal(format!("Background {} job failed: {}", kind, e)))?
+}
@@ -0,0 +1,96 @@
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Unsaved content captured between debounced saves
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub buffer_id: String,
+    pub content: String,
+    pub journaled_at: i64,
+}
+
+/// Directory holding one journal file per buffer with unsaved edits
+pub fn journal_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("journal")
+}
+
+/// Buffer IDs are UUIDs; reject anything that could escape the journal directory
+fn entry_path(dir: &Path, buffer_id: &str) -> Option<PathBuf> {
+    let valid = !buffer_id.is_empty()
+        && buffer_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    valid.then(|| dir.join(format!("{}.json", buffer_id)))
+}
+
+/// Durably write the latest unsaved content for a buffer.
+/// Writes to a temp file, fsyncs, then renames so a crash never leaves a torn entry.
+pub fn write_entry(dir: &Path, entry: &JournalEntry) -> AppResult<()> {
+    let path = entry_path(dir, &entry.buffer_id)
+        .ok_or_else(|| AppError::Validation(format!("Invalid buffer id: {}", entry.buffer_id)))?;
+
+    fs::create_dir_all(dir)
+        .map_err(|e| AppError::io("Failed to create journal directory", e))?;
+
+    let data = serde_json::to_vec(entry)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize journal entry: {}", e)))?;
+
+    let tmp_path = path.with_extension("json.tmp");
+    let mut file = File::create(&tmp_path)
+        .map_err(|e| AppError::io("Failed to create journal file", e))?;
+    file.write_all(&data)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| AppError::io("Failed to write journal file", e))?;
+
+    fs::rename(&tmp_path, &path)
+        .map_err(|e| AppError::io("Failed to commit journal file", e))?;
+
+    Ok(())
+}
+
+/// Remove the journal entry for a buffer (after a successful save or discard)
+pub fn remove_entry(dir: &Path, buffer_id: &str) {
+    let Some(path) = entry_path(dir, buffer_id) else {
+        return;
+    };
+
+    if let Err(e) = fs::remove_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove journal entry {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Read a single buffer's journal entry, if present
+pub fn read_entry(dir: &Path, buffer_id: &str) -> Option<JournalEntry> {
+    let path = entry_path(dir, buffer_id)?;
+    let data = fs::read(&path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// Read all journal entries, skipping (and logging) unreadable files
+pub fn read_entries(dir: &Path) -> Vec<JournalEntry> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let parsed = fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|data| serde_json::from_slice::<JournalEntry>(&data).map_err(|e| e.to_string()));
+            match parsed {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Skipping unreadable journal entry {:?}: {}", path, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
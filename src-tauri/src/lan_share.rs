@@ -0,0 +1,291 @@
+// Temporary share links: a read-only rendering of a note served over plain
+// HTTP on the local network. Each link has an unguessable token and an expiry;
+// the server starts on the first share and only answers `GET /s/<token>`.
+
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::export::site;
+use crate::state::AppState;
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream, UdpSocket};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Shortest allowed link lifetime in seconds
+pub const MIN_TTL_SECS: u64 = 60;
+
+/// Longest allowed link lifetime in seconds (one day)
+pub const MAX_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Slow clients are dropped after this, so one can't stall the server
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bound on the request line plus headers
+const MAX_REQUEST_BYTES: u64 = 8 * 1024;
+
+/// Only inline styles; no scripts, images or external requests
+const CONTENT_SECURITY_POLICY: &str = "default-src 'none'; style-src 'unsafe-inline'";
+
+/// A share link handed back to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareLink {
+    pub token: String,
+    pub buffer_id: String,
+    /// Loopback URL, always reachable from this machine
+    pub url: String,
+    /// URL for other devices on the network, when a LAN address was found
+    pub lan_url: Option<String>,
+    pub expires_at: i64,
+}
+
+struct Share {
+    buffer_id: String,
+    expires_at: i64,
+}
+
+/// Active share tokens and the port of the running server, if started
+pub struct ShareServer {
+    port: Mutex<Option<u16>>,
+    shares: Mutex<HashMap<String, Share>>,
+}
+
+impl ShareServer {
+    pub fn new() -> Self {
+        Self {
+            port: Mutex::new(None),
+            shares: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Buffer shared under `token`, dropping expired links along the way
+    fn lookup(&self, token: &str) -> Option<String> {
+        let now = Utc::now().timestamp();
+        let mut shares = self.shares.lock();
+        shares.retain(|_, share| share.expires_at > now);
+        shares.get(token).map(|share| share.buffer_id.clone())
+    }
+
+    /// Revoke a link before it expires. Returns false if it didn't exist.
+    pub fn revoke(&self, token: &str) -> bool {
+        self.shares.lock().remove(token).is_some()
+    }
+}
+
+impl Default for ShareServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Create a link serving `buffer_id` for `ttl_secs` seconds
+pub fn share(app: &AppHandle, buffer_id: &str, ttl_secs: u64) -> AppResult<ShareLink> {
+    let port = ensure_running(app)?;
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let expires_at = Utc::now().timestamp() + ttl_secs as i64;
+
+    let state = app.state::<AppState>();
+    state.share_links.shares.lock().insert(
+        token.clone(),
+        Share {
+            buffer_id: buffer_id.to_string(),
+            expires_at,
+        },
+    );
+
+    info!("Sharing buffer {} on port {} for {}s", buffer_id, port, ttl_secs);
+    Ok(ShareLink {
+        url: format!("http://127.0.0.1:{}/s/{}", port, token),
+        lan_url: lan_address().map(|ip| format!("http://{}:{}/s/{}", ip, port, token)),
+        token,
+        buffer_id: buffer_id.to_string(),
+        expires_at,
+    })
+}
+
+/// Start the server on a random port unless it is already running
+fn ensure_running(app: &AppHandle) -> AppResult<u16> {
+    let state = app.state::<AppState>();
+    let mut port = state.share_links.port.lock();
+    if let Some(port) = *port {
+        return Ok(port);
+    }
+
+    let listener = TcpListener::bind(("0.0.0.0", 0))
+        .map_err(|e| AppError::io("Failed to start share server", e))?;
+    let bound = listener
+        .local_addr()
+        .map_err(|e| AppError::io("Failed to start share server", e))?
+        .port();
+
+    let app = app.clone();
+    thread::Builder::new()
+        .name("share-server".to_string())
+        .spawn(move || serve(&app, listener))
+        .map_err(|e| AppError::io("Failed to start share server", e))?;
+
+    info!("Share server listening on port {}", bound);
+    *port = Some(bound);
+    Ok(bound)
+}
+
+/// The address other devices on the network would use to reach this one.
+/// Connecting a UDP socket only picks a route; nothing is sent.
+fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).ok()?;
+    socket.connect(("192.0.2.1", 80)).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+fn serve(app: &AppHandle, listener: TcpListener) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle(app, stream) {
+                    debug!("Share request failed: {}", e);
+                }
+            }
+            Err(e) => warn!("Share server accept failed: {}", e),
+        }
+    }
+}
+
+fn handle(app: &AppHandle, mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+    // Request line, then skip headers up to the blank line
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+
+    let Some(request) = parse_request_line(&request_line) else {
+        return respond(&mut stream, "405 Method Not Allowed", "Method not allowed", false);
+    };
+    // Nothing is served while the app is locked, even for live links
+    let state = app.state::<AppState>();
+    if state.ready.is_locked() || state.idle.is_locked() {
+        return respond(&mut stream, "423 Locked", "Flashnotes is locked", request.head_only);
+    }
+    match request.token.and_then(|token| render(&state, token)) {
+        Some(page) => respond(&mut stream, "200 OK", &page, request.head_only),
+        None => respond(&mut stream, "404 Not Found", "This link has expired or does not exist", request.head_only),
+    }
+}
+
+/// What a request line asks for
+#[derive(Debug, PartialEq)]
+struct ShareRequest<'a> {
+    /// HEAD: headers without the page
+    head_only: bool,
+    /// Token of a `/s/<token>` path; None for any other path
+    token: Option<&'a str>,
+}
+
+/// Parse a request line; None for methods other than GET and HEAD
+fn parse_request_line(line: &str) -> Option<ShareRequest<'_>> {
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("");
+    if method != "GET" && method != "HEAD" {
+        return None;
+    }
+    Some(ShareRequest {
+        head_only: method == "HEAD",
+        token: path.strip_prefix("/s/"),
+    })
+}
+
+/// Current content of the note behind `token`, rendered as a page. Notes
+/// since trashed or locked are no longer served.
+fn render(state: &AppState, token: &str) -> Option<String> {
+    let buffer_id = state.share_links.lookup(token)?;
+    let content = state
+        .read(|conn| queries::get_shareable_content(conn, &buffer_id))
+        .ok()
+        .flatten()?;
+    let (title, _) = queries::extract_title_preview(&content);
+    Some(site::standalone_page(&title, &content))
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str, head_only: bool) -> io::Result<()> {
+    let content_type = if status.starts_with("200") { "text/html" } else { "text/plain" };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n\
+         Content-Type: {}; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         Cache-Control: no-store\r\n\
+         Referrer-Policy: no-referrer\r\n\
+         X-Robots-Tag: noindex\r\n\
+         Content-Security-Policy: {}\r\n\
+         Connection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len(),
+        CONTENT_SECURITY_POLICY,
+    )?;
+    if !head_only {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_share_paths() {
+        assert_eq!(
+            parse_request_line("GET /s/abc123 HTTP/1.1\r\n"),
+            Some(ShareRequest { head_only: false, token: Some("abc123") })
+        );
+        assert_eq!(
+            parse_request_line("HEAD /s/abc123 HTTP/1.1\r\n"),
+            Some(ShareRequest { head_only: true, token: Some("abc123") })
+        );
+        assert_eq!(
+            parse_request_line("GET /favicon.ico HTTP/1.1\r\n"),
+            Some(ShareRequest { head_only: false, token: None })
+        );
+    }
+
+    #[test]
+    fn refuses_other_methods() {
+        assert_eq!(parse_request_line("POST /s/abc123 HTTP/1.1\r\n"), None);
+        assert_eq!(parse_request_line(""), None);
+    }
+
+    #[test]
+    fn lookup_drops_expired_links() {
+        let server = ShareServer::new();
+        let now = Utc::now().timestamp();
+        for (token, expires_at) in [("live", now + 60), ("expired", now - 1)] {
+            server.shares.lock().insert(
+                token.to_string(),
+                Share {
+                    buffer_id: format!("buffer-{}", token),
+                    expires_at,
+                },
+            );
+        }
+
+        assert_eq!(server.lookup("live").as_deref(), Some("buffer-live"));
+        assert_eq!(server.lookup("expired"), None);
+        assert!(!server.revoke("expired"));
+        assert!(server.revoke("live"));
+        assert_eq!(server.lookup("live"), None);
+    }
+}
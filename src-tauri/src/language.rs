@@ -0,0 +1,274 @@
+// Programming language guessing for code snippets, so inserted code fences
+// get a language annotation. A weighted keyword heuristic: cheap, offline
+// and good enough to tell the common languages apart.
+
+use serde::Serialize;
+use std::cmp::Reverse;
+
+/// Only the start of a snippet is examined
+const MAX_SAMPLE_BYTES: usize = 16 * 1024;
+
+/// Below this score there isn't enough evidence to guess
+const MIN_SCORE: u32 = 4;
+
+/// Fence names for shebang interpreters
+const SHEBANGS: &[(&str, &str)] = &[
+    ("python", "python"),
+    ("node", "javascript"),
+    ("deno", "typescript"),
+    ("ruby", "ruby"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("bash", "bash"),
+    ("zsh", "bash"),
+    ("sh", "bash"),
+];
+
+/// Features per language (fence name): substrings and their weight. Strong,
+/// distinctive syntax weighs 3; common but shared constructs weigh 1.
+const FEATURES: &[(&str, &[(&str, u32)])] = &[
+    (
+        "rust",
+        &[
+            ("fn ", 2), ("let mut ", 3), ("impl ", 3), ("pub fn ", 3), ("::", 1), ("-> ", 1), ("&self", 3),
+            ("use std::", 3), ("println!(", 3), ("#[derive(", 3), ("Option<", 2), ("Result<", 2), ("match ", 1),
+            ("unwrap()", 2), ("&str", 3), ("Vec<", 2),
+        ],
+    ),
+    (
+        "python",
+        &[
+            ("def ", 3), ("import ", 1), ("from ", 1), ("self.", 1), ("elif ", 3), ("print(", 1), ("None", 1),
+            ("__init__", 3), ("):\n", 2), ("True", 1), ("False", 1), ("lambda ", 2), ("if __name__", 3),
+            (" in range(", 3), ("    return ", 1),
+        ],
+    ),
+    (
+        "javascript",
+        &[
+            ("function ", 2), ("const ", 1), ("let ", 1), ("=> ", 1), ("console.log(", 3), ("require(", 3),
+            ("module.exports", 3), ("document.", 3), ("===", 2), ("undefined", 2), ("async ", 1), ("await ", 1),
+            ("export default", 1), ("new Promise", 2),
+        ],
+    ),
+    (
+        "typescript",
+        &[
+            ("interface ", 2), (": string", 3), (": number", 3), (": boolean", 3), ("export type ", 3),
+            ("import type ", 3), ("as const", 2), ("<T>", 1), ("readonly ", 2), ("public ", 1), ("private ", 1),
+            ("=> ", 1), ("const ", 1), ("===", 1),
+        ],
+    ),
+    (
+        "go",
+        &[
+            ("func ", 3), ("package ", 2), (":= ", 3), ("fmt.", 3), ("err != nil", 3), ("go func", 3),
+            ("chan ", 2), ("defer ", 3), ("import (", 3), ("struct {", 1), ("[]string", 3),
+        ],
+    ),
+    (
+        "java",
+        &[
+            ("public class ", 3), ("public static void main", 3), ("System.out.println", 3), ("private ", 1),
+            ("public ", 1), ("import java.", 3), ("@Override", 3), ("new ", 1), ("String[]", 2), ("extends ", 1),
+            ("implements ", 2), ("final ", 1),
+        ],
+    ),
+    (
+        "c",
+        &[
+            ("#include <", 3), ("int main(", 3), ("printf(", 3), ("malloc(", 3), ("->", 1), ("NULL", 2),
+            ("sizeof(", 2), ("char *", 2), ("void ", 1), ("struct ", 1), ("#define ", 2),
+        ],
+    ),
+    (
+        "cpp",
+        &[
+            ("#include <", 2), ("std::", 3), ("cout <<", 3), ("template<", 3), ("template <", 3),
+            ("namespace ", 2), ("nullptr", 3), ("::", 1), ("public:", 3), ("private:", 3), ("auto ", 1),
+        ],
+    ),
+    (
+        "csharp",
+        &[
+            ("using System", 3), ("namespace ", 2), ("Console.WriteLine", 3), ("public class ", 2),
+            ("{ get; set; }", 3), ("var ", 1), ("async Task", 3), ("string ", 1), ("[HttpGet", 3),
+        ],
+    ),
+    (
+        "ruby",
+        &[
+            ("def ", 2), ("end\n", 2), ("puts ", 3), ("require '", 3), ("attr_accessor", 3), (".each do", 3),
+            ("do |", 3), ("elsif ", 3), ("nil", 2), ("@", 1),
+        ],
+    ),
+    (
+        "php",
+        &[("<?php", 5), ("$this->", 3), ("echo ", 1), ("function ", 1), ("=> ", 1), ("->", 1), ("$", 1)],
+    ),
+    (
+        "bash",
+        &[
+            ("echo ", 1), ("fi\n", 3), ("then\n", 2), ("done\n", 2), ("$(", 2), ("${", 2), ("esac", 3),
+            ("sudo ", 3), ("export ", 1), ("; then", 3), ("| grep ", 3), ("apt-get ", 3), ("npm ", 2),
+            ("cd ", 1), ("$ ", 1),
+        ],
+    ),
+    (
+        "sql",
+        &[
+            ("SELECT ", 3), (" FROM ", 2), (" WHERE ", 2), ("INSERT INTO", 3), ("CREATE TABLE", 3),
+            ("UPDATE ", 1), ("JOIN ", 2), ("GROUP BY", 3), ("ORDER BY", 3), ("PRIMARY KEY", 3), ("select ", 2),
+            (" from ", 1), (" where ", 1),
+        ],
+    ),
+    (
+        "html",
+        &[
+            ("<!DOCTYPE", 5), ("<html", 4), ("<div", 3), ("</div>", 3), ("<span", 2), ("<p>", 2), ("href=\"", 2),
+            ("class=\"", 2), ("<head>", 3), ("<body", 3),
+        ],
+    ),
+    (
+        "css",
+        &[
+            ("{\n", 1), ("color:", 2), ("margin:", 3), ("padding:", 3), ("display:", 3), ("font-size:", 3),
+            ("px;", 2), ("@media", 3), (":hover", 3), ("background", 1),
+        ],
+    ),
+    (
+        "yaml",
+        &[("---\n", 1), (": ", 1), ("- name:", 3), ("apiVersion:", 4), ("version:", 1), ("  - ", 1), ("steps:", 3)],
+    ),
+    (
+        "toml",
+        &[("[package]", 5), ("[dependencies]", 5), (" = \"", 2), ("[[", 1), ("= [", 1), ("= true", 1)],
+    ),
+    (
+        "swift",
+        &[
+            ("import SwiftUI", 5), ("import Foundation", 4), ("func ", 2), ("var body: some View", 5),
+            ("guard let ", 4), ("if let ", 3), ("let ", 1), ("-> ", 1),
+        ],
+    ),
+    (
+        "kotlin",
+        &[
+            ("fun ", 3), ("val ", 2), ("var ", 1), ("println(", 1), ("data class ", 4), ("?.", 1),
+            ("companion object", 5),
+        ],
+    ),
+];
+
+/// A language guess for a snippet
+#[derive(Debug, Serialize)]
+pub struct LanguageGuess {
+    /// Code fence name, e.g. "rust", "python", "bash"
+    pub language: String,
+    /// 0..=1: how far the best guess is ahead of the runner-up
+    pub confidence: f32,
+}
+
+fn sample(text: &str) -> &str {
+    if text.len() <= MAX_SAMPLE_BYTES {
+        return text;
+    }
+    let mut end = MAX_SAMPLE_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Language named by a `#!` line
+fn shebang_language(first_line: &str) -> Option<&'static str> {
+    let interpreter = first_line.strip_prefix("#!")?.split_whitespace().collect::<Vec<_>>();
+    // `#!/usr/bin/env python3` names the interpreter as an argument
+    let program = match interpreter.as_slice() {
+        [env, program, ..] if env.ends_with("/env") => *program,
+        [path, ..] => path.rsplit('/').next().unwrap_or(path),
+        [] => return None,
+    };
+    SHEBANGS
+        .iter()
+        .find(|(name, _)| program.starts_with(name))
+        .map(|(_, language)| *language)
+}
+
+/// Guess the language of a code snippet; None when there's too little to go on
+pub fn detect(text: &str) -> Option<LanguageGuess> {
+    let text = sample(text.trim_start());
+    if let Some(language) = text.lines().next().and_then(shebang_language) {
+        return Some(LanguageGuess {
+            language: language.to_string(),
+            confidence: 1.0,
+        });
+    }
+
+    let trimmed = text.trim();
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some(LanguageGuess {
+            language: "json".to_string(),
+            confidence: 1.0,
+        });
+    }
+
+    // A trailing newline lets line-end features match on the last line
+    let text = format!("{}\n", text);
+    let mut scores: Vec<(&str, u32)> = FEATURES
+        .iter()
+        .map(|(language, features)| {
+            let score = features
+                .iter()
+                .map(|(pattern, weight)| text.matches(pattern).count().min(3) as u32 * weight)
+                .sum();
+            (*language, score)
+        })
+        .collect();
+    scores.sort_by_key(|&(_, score)| Reverse(score));
+
+    let (language, best) = scores[0];
+    if best < MIN_SCORE {
+        return None;
+    }
+    let runner_up = scores.get(1).map_or(0, |s| s.1);
+    Some(LanguageGuess {
+        language: language.to_string(),
+        confidence: (best - runner_up) as f32 / best as f32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shebang_names_the_language() {
+        let guess = detect("#!/usr/bin/env python3\nprint('hi')").unwrap();
+        assert_eq!(guess.language, "python");
+        assert_eq!(guess.confidence, 1.0);
+        assert_eq!(detect("#!/bin/zsh\necho hi").unwrap().language, "bash");
+    }
+
+    #[test]
+    fn recognizes_json() {
+        assert_eq!(detect(r#"{"a": [1, 2]}"#).unwrap().language, "json");
+    }
+
+    #[test]
+    fn scores_distinctive_syntax() {
+        let rust = "use std::collections::HashMap;\n\n\
+                    pub fn main() {\n    let mut map: HashMap<&str, i32> = HashMap::new();\n    \
+                    println!(\"{:?}\", map);\n}";
+        assert_eq!(detect(rust).unwrap().language, "rust");
+        let sql = "SELECT id, name FROM users WHERE active = 1 ORDER BY name";
+        assert_eq!(detect(sql).unwrap().language, "sql");
+    }
+
+    #[test]
+    fn prose_has_no_guess() {
+        assert!(detect("Remember to buy milk and call the plumber.").is_none());
+    }
+}
@@ -1,50 +1,83 @@
+mod ai;
+mod api;
+mod attachments;
+mod backup_schedule;
+mod biometric;
+mod board;
+mod cards;
+#[cfg(desktop)]
+mod cli;
 mod commands;
 mod db;
+mod debounce;
+mod deep_link;
+mod digest;
+mod edit_locks;
+mod embeddings;
+mod encryption;
+mod ephemeral;
+mod error;
+mod events;
+mod export;
+mod find;
+#[cfg(desktop)]
+mod hotkey;
+mod i18n;
+mod idle;
+mod inbox;
+mod inbox_watch;
+mod jobs;
+mod journal;
+mod language;
+mod lan_share;
+mod live_merge;
+mod lint;
+mod logging;
+mod maintenance;
+mod menu;
+mod merge;
+mod metrics;
+mod mru;
+mod note_lock;
+mod note_window;
+mod paste;
+mod plugins;
+mod progress;
+mod qr;
+mod related;
+mod relocate;
+mod save_hook;
+mod scripting;
+mod search;
+mod secrets;
+mod startup;
 mod state;
+mod sync;
+mod tasks;
+mod trash;
+mod tray;
+mod undo;
+mod vault;
+mod webhooks;
 
 use state::AppState;
 use std::path::PathBuf;
 use tauri::Manager;
-use tauri::menu::{MenuBuilder, SubmenuBuilder, PredefinedMenuItem, MenuItem, AboutMetadata, CheckMenuItem};
 use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
-use tracing::{info, error, warn};
-use tracing_appender::rolling::{RollingFileAppender, Rotation};
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
-
-/// Initialize logging with file output and daily rotation
-fn init_logging(app_data_dir: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    let log_dir = app_data_dir.join("logs");
-    std::fs::create_dir_all(&log_dir)?;
-
-    let file_appender = RollingFileAppender::new(
-        Rotation::DAILY,
-        log_dir,
-        "flashnotes.log",
-    );
-
-    let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
-
-    // Keep the guard alive for the lifetime of the app
-    // by leaking it (acceptable for app-lifetime resources)
-    Box::leak(Box::new(_guard));
-
-    tracing_subscriber::registry()
-        .with(EnvFilter::new("flashnotes=info,warn"))
-        .with(fmt::layer().with_writer(non_blocking).with_ansi(false))
-        .with(fmt::layer().with_writer(std::io::stderr))
-        .init();
-
-    Ok(())
-}
+use tracing::{info, error};
 
 /// Database initialization result
 struct DbInit {
     writer: rusqlite::Connection,
     reader_pool: r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>,
     app_data_dir: PathBuf,
+    /// Encrypted and waiting for its passphrase
+    locked: bool,
 }
 
-/// Initialize database with proper error handling
+/// Set up stand-in connections so the window can show right away. The
+/// corruption check, opening the database file and schema setup are deferred
+/// to `startup::spawn`; an encrypted database waits for `unlock_database`.
 fn init_database(app: &tauri::AppHandle) -> Result<DbInit, String> {
     let app_data_dir = db::connection::get_app_data_dir(app)
         .map_err(|e| format!("{}", e))?;
@@ -53,36 +86,23 @@ fn init_database(app: &tauri::AppHandle) -> Result<DbInit, String> {
         .map_err(|e| format!("{}", e))?;
 
     info!("Database path: {:?}", db_path);
+    encryption::recover_interrupted_rekey(&db_path);
 
-    // Create writer connection
-    let writer = db::connection::create_connection(&db_path)
-        .map_err(|e| format!("Failed to create database connection: {}", e))?;
-
-    // Initialize schema
-    db::schema::initialize_schema(&writer)
-        .map_err(|e| format!("Failed to initialize database schema: {}", e))?;
-
-    // Create reader pool
-    let reader_pool = db::connection::create_reader_pool(&db_path)
-        .map_err(|e| format!("{}", e))?;
-
-    // Check if backup is needed (daily backup on startup)
-    if db::backup::needs_backup(&app_data_dir) {
-        match db::backup::create_backup(&writer, &app_data_dir) {
-            Ok(path) => info!("Daily backup created: {:?}", path),
-            Err(e) => warn!("Failed to create daily backup: {}", e),
-        }
+    let locked = encryption::is_encrypted(&db_path);
+    if locked {
+        info!("Database is encrypted");
     }
-
     Ok(DbInit {
-        writer,
-        reader_pool,
+        writer: db::connection::create_memory_connection()
+            .map_err(|e| format!("Failed to create database connection: {}", e))?,
+        reader_pool: db::connection::create_placeholder_pool().map_err(|e| format!("{}", e))?,
         app_data_dir,
+        locked,
     })
 }
 
 /// Show error dialog to user
-fn show_error_dialog(app: &tauri::AppHandle, title: &str, message: &str) {
+pub(crate) fn show_error_dialog(app: &tauri::AppHandle, title: &str, message: &str) {
     let dialog = app.dialog();
     dialog.message(message)
         .title(title)
@@ -92,23 +112,43 @@ fn show_error_dialog(app: &tauri::AppHandle, title: &str, message: &str) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    // `flashnotes new` / `flashnotes search` print their result and exit
+    #[cfg(desktop)]
+    if let Some(code) = cli::run_from_terminal() {
+        std::process::exit(code);
+    }
+
+    let builder = tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_deep_link::init());
+
+    // Desktop-only plugins: updates, window geometry, single instance
+    #[cfg(desktop)]
+    let builder = builder
+        .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_window_state::Builder::new().build())
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            // `flashnotes new` / `flashnotes search` from a terminal leave the window alone
+            if cli::handle_args(app, &args) {
+                return;
+            }
             // When second instance launches, focus the existing window
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.show();
                 let _ = window.set_focus();
             }
-        }))
+            // On Windows and Linux, opening a flashnotes:// link starts a second instance
+            deep_link::handle_args(app, &args);
+        }));
+
+    builder
         .setup(|app| {
             // Initialize logging first (best effort)
             if let Ok(app_data_dir) = app.path().app_data_dir() {
-                if let Err(e) = init_logging(&app_data_dir) {
-                    eprintln!("Warning: Failed to initialize logging: {}", e);
-                }
+                logging::init(&app_data_dir);
             }
 
             info!("Flashnotes starting up");
@@ -120,7 +160,7 @@ pub fn run() {
                     error!("Database initialization failed: {}", e);
                     show_error_dialog(
                         &app.handle(),
-                        "Database Error",
+                        i18n::t("Database Error"),
                         &format!(
                             "Failed to initialize the database.\n\n\
                             Error: {}\n\n\
@@ -136,85 +176,26 @@ pub fn run() {
                 }
             };
 
-            // Manage app state
-            app.manage(AppState::new(
+            // Manage app state; commands wait until startup marks it ready
+            let state = AppState::new(
                 db_init.writer,
                 db_init.reader_pool,
                 db_init.app_data_dir,
-            ));
-
-            // Build macOS menu bar
-            #[cfg(target_os = "macos")]
-            {
-                let about_metadata = AboutMetadata {
-                    website: Some("https://github.com/garyblankenship/flashnotes".into()),
-                    website_label: Some("GitHub".into()),
-                    ..Default::default()
-                };
-
-                let app_menu = SubmenuBuilder::new(app, "Flashnotes")
-                    .item(&PredefinedMenuItem::about(app, Some("About Flashnotes"), Some(about_metadata))?)
-                    .separator()
-                    .item(&PredefinedMenuItem::hide(app, Some("Hide Flashnotes"))?)
-                    .item(&PredefinedMenuItem::hide_others(app, Some("Hide Others"))?)
-                    .item(&PredefinedMenuItem::show_all(app, Some("Show All"))?)
-                    .separator()
-                    .item(&PredefinedMenuItem::quit(app, Some("Quit Flashnotes"))?)
-                    .build()?;
-
-
-                let edit_menu = SubmenuBuilder::new(app, "Edit")
-                    .item(&PredefinedMenuItem::undo(app, Some("Undo"))?)
-                    .item(&PredefinedMenuItem::redo(app, Some("Redo"))?)
-                    .separator()
-                    .item(&PredefinedMenuItem::cut(app, Some("Cut"))?)
-                    .item(&PredefinedMenuItem::copy(app, Some("Copy"))?)
-                    .item(&PredefinedMenuItem::paste(app, Some("Paste"))?)
-                    .item(&PredefinedMenuItem::select_all(app, Some("Select All"))?)
-                    .build()?;
-
-                // Load always_on_top setting
-                let always_on_top = {
-                    let state = app.state::<AppState>();
-                    let conn = state.writer.lock();
-                    db::queries::get_settings(&conn)
-                        .map(|s| s.always_on_top)
-                        .unwrap_or(false)
-                };
-
-                let stay_on_top_item = CheckMenuItem::with_id(
-                    app,
-                    "stay_on_top",
-                    "Stay on Top",
-                    true,
-                    always_on_top,
-                    Some("CmdOrCtrl+Shift+T"),
-                )?;
-
-                let window_menu = SubmenuBuilder::new(app, "Window")
-                    .item(&PredefinedMenuItem::minimize(app, Some("Minimize"))?)
-                    .item(&PredefinedMenuItem::maximize(app, Some("Zoom"))?)
-                    .separator()
-                    .item(&stay_on_top_item)
-                    .separator()
-                    .item(&PredefinedMenuItem::close_window(app, Some("Close"))?)
-                    .build()?;
-
-                let help_menu = SubmenuBuilder::new(app, "Help")
-                    .item(&MenuItem::with_id(app, "github", "GitHub Repository", true, None::<&str>)?)
-                    .build()?;
-
-                let menu = MenuBuilder::new(app)
-                    .item(&app_menu)
-                    .item(&edit_menu)
-                    .item(&window_menu)
-                    .item(&help_menu)
-                    .build()?;
-
-                app.set_menu(menu)?;
+            );
+            if db_init.locked {
+                state.ready.set_locked();
             }
+            app.manage(state);
+            deep_link::install(app.handle());
+            // This instance may be the relaunch of a terminal command (see `cli`)
+            #[cfg(desktop)]
+            cli::handle_args(app.handle(), &std::env::args().collect::<Vec<_>>());
+
+            // Build the native menu bar (relabelled once the locale setting loads)
+            menu::install(app.handle(), false)?;
 
             // Handle menu events
+            #[cfg(desktop)]
             app.on_menu_event(|app_handle, event| {
                 match event.id().0.as_str() {
                     "github" => {
@@ -246,41 +227,178 @@ pub fn run() {
                 }
             });
 
-            // Show the window after setup is complete
+            // Show the window right away (mobile windows are always visible)
+            #[cfg(desktop)]
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.show();
                 let _ = window.set_focus();
-
-                // Apply always_on_top setting from database
-                let always_on_top = {
-                    let state = app.state::<AppState>();
-                    let conn = state.writer.lock();
-                    db::queries::get_settings(&conn)
-                        .map(|s| s.always_on_top)
-                        .unwrap_or(false)
-                };
-                if always_on_top {
-                    let _ = window.set_always_on_top(true);
-                }
             }
 
+            // Schema, settings, index verification and backups finish in the background
+            startup::spawn(app.handle().clone());
+
             info!("Flashnotes startup complete");
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::create_buffer,
+            commands::create_ephemeral,
+            commands::get_ephemeral,
+            commands::update_ephemeral,
+            commands::discard_ephemeral,
+            commands::promote_to_buffer,
             commands::save_buffer,
             commands::get_buffer_content,
+            commands::get_buffer,
+            commands::mark_accessed,
             commands::get_sidebar_data,
+            commands::get_buffer_count,
             commands::search_buffers,
+            commands::hybrid_search,
+            commands::get_related,
+            commands::find_in_buffer,
+            commands::analyze_paste,
+            commands::detect_language,
+            commands::get_adjacent_buffer,
+            commands::get_mru_buffers,
+            commands::get_activity,
+            commands::undo_last_operation,
+            commands::redo,
             commands::delete_buffer,
             commands::toggle_pin,
+            commands::rename_buffer,
             commands::get_settings,
             commands::set_setting,
+            commands::get_database_path,
+            commands::set_database_path,
+            commands::get_inbox_watch_log,
+            commands::get_sync_status,
+            commands::sync_now,
+            commands::get_cloud_sync_status,
+            commands::cloud_sync_now,
+            commands::list_backups,
+            commands::backup_now,
+            commands::restore_backup,
+            commands::get_recovery_report,
+            commands::is_database_locked,
+            commands::unlock_database,
+            commands::enable_encryption,
+            commands::change_passphrase,
+            commands::rotate_encryption_key,
             commands::reorder_buffers,
             commands::cleanup_empty_buffers,
+            commands::get_archived_buffers,
+            commands::set_buffer_archived,
+            commands::archive_buffer,
+            commands::unarchive_buffer,
+            commands::trash_buffer,
+            commands::restore_buffer,
+            commands::get_trashed_buffers,
+            commands::empty_trash,
+            commands::dedupe_buffers,
+            commands::batch_transform,
+            commands::lint_buffers,
+            commands::get_broken_wikilinks,
+            commands::create_missing_target,
+            commands::get_tag_stats,
+            commands::add_tag,
+            commands::remove_tag,
+            commands::get_buffer_tags,
+            commands::list_tags,
+            commands::get_all_hashtags,
+            commands::get_tasks_due,
+            commands::get_board,
+            commands::ai_summarize,
+            commands::ai_suggest_tags,
+            commands::ai_suggest_title,
+            commands::translate_buffer,
+            commands::transcribe_attachment,
+            commands::move_card,
+            commands::add_attachment,
+            commands::list_attachments,
+            commands::delete_attachment,
+            commands::get_due_cards,
+            commands::grade_card,
             commands::toggle_always_on_top,
+            commands::set_global_shortcut,
+            commands::set_clipboard_shortcut,
+            commands::get_platform_info,
+            commands::share_buffer,
+            commands::share_temporarily,
+            commands::revoke_share_link,
+            commands::generate_note_qr,
+            commands::export_all_buffers,
+            commands::export_buffers,
+            commands::export_metadata_csv,
+            commands::export_site,
+            commands::export_blog,
+            commands::export_obsidian_vault,
+            commands::import_files,
+            commands::import_obsidian_vault,
+            commands::list_plugins,
+            commands::reload_plugins,
+            commands::add_webhook,
+            commands::list_webhooks,
+            commands::set_webhook_enabled,
+            commands::delete_webhook,
+            commands::set_secret,
+            commands::has_secret,
+            commands::save_script,
+            commands::list_scripts,
+            commands::delete_script,
+            commands::run_script,
+            commands::check_for_updates,
+            commands::install_update,
+            commands::get_recent_logs,
+            commands::get_usage_metrics,
+            commands::get_diagnostics,
+            commands::rebuild_search_index,
+            commands::reindex_embeddings,
+            commands::list_jobs,
+            commands::cancel_job,
+            commands::journal_edit,
+            commands::get_recoverable_edits,
+            commands::recover_edit,
+            commands::discard_edit,
+            commands::get_buffer_history,
+            commands::get_revision_content,
+            commands::restore_revision,
+            commands::lock_app,
+            commands::unlock_app,
+            commands::lock_buffer,
+            commands::unlock_buffer,
+            commands::take_launch_link,
+            commands::get_api_token,
+            commands::regenerate_api_token,
+            commands::get_biometric_status,
+            commands::enable_biometric_unlock,
+            commands::disable_biometric_unlock,
+            commands::unlock_database_with_biometrics,
+            commands::unlock_buffer_with_biometrics,
+            commands::is_app_locked,
+            commands::acquire_edit_lock,
+            commands::release_edit_lock,
+            commands::open_buffer_window,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running flashnotes");
+        .on_window_event(|window, event| {
+            match event {
+                tauri::WindowEvent::Focused(false) if window.label() == "main" => {
+                    maintenance::on_window_blur(window.app_handle())
+                }
+                // Pick up anything captured from other apps while we were in the background
+                tauri::WindowEvent::Focused(true) => inbox::ingest_and_notify(window.app_handle()),
+                tauri::WindowEvent::Destroyed => {
+                    edit_locks::release_window(window.app_handle(), window.label());
+                    window.state::<AppState>().note_windows.remove(window.label());
+                }
+                _ => {}
+            }
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building flashnotes")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                maintenance::on_exit(app_handle);
+            }
+        });
 }
@@ -0,0 +1,264 @@
+use crate::db::queries::{self, AppSettings, Buffer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Seconds per day, for TODO age
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// A check run over note content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintRule {
+    /// No custom title and no text to take one from
+    EmptyTitle,
+    /// `[[link]]` whose target matches no note
+    BrokenWikiLink,
+    /// Code fence opened but never closed
+    UnclosedCodeFence,
+    /// TODO in a note untouched for longer than the configured age
+    StaleTodo,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 4] = [
+        LintRule::EmptyTitle,
+        LintRule::BrokenWikiLink,
+        LintRule::UnclosedCodeFence,
+        LintRule::StaleTodo,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "empty_title" => Some(Self::EmptyTitle),
+            "broken_wiki_link" => Some(Self::BrokenWikiLink),
+            "unclosed_code_fence" => Some(Self::UnclosedCodeFence),
+            "stale_todo" => Some(Self::StaleTodo),
+            _ => None,
+        }
+    }
+
+    /// Parse the `lint_rules` setting: a comma-separated list of rule names
+    pub fn parse_list(value: &str) -> Option<Vec<Self>> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+}
+
+/// Which rules run, and their thresholds
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    pub rules: Vec<LintRule>,
+    pub todo_max_age_days: i64,
+}
+
+impl LintConfig {
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        Self {
+            rules: LintRule::parse_list(&settings.lint_rules).unwrap_or_else(|| LintRule::ALL.to_vec()),
+            todo_max_age_days: i64::from(settings.lint_todo_days.max(0)),
+        }
+    }
+}
+
+/// A `[[link]]` whose target matches no note. Line is 0-based.
+#[derive(Debug, Serialize)]
+pub struct BrokenLink {
+    pub buffer_id: String,
+    pub title: String,
+    pub line: usize,
+    pub target: String,
+}
+
+/// One problem found in a note. Line is 0-based, when the finding has one.
+#[derive(Debug, Serialize)]
+pub struct LintFinding {
+    pub buffer_id: String,
+    pub title: String,
+    pub rule: LintRule,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// Whether a line opens or closes a code fence; returns the fence marker
+fn fence_marker(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker)).map(|marker| {
+        let c = marker.chars().next().unwrap_or('`');
+        let len = trimmed.chars().take_while(|&ch| ch == c).count();
+        &trimmed[..len]
+    })
+}
+
+/// Lines outside fenced code blocks, plus the line of an unclosed fence (if any)
+fn prose_lines(content: &str) -> (Vec<(usize, &str)>, Option<usize>) {
+    let mut prose = Vec::new();
+    // (opening line, marker)
+    let mut open: Option<(usize, &str)> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        match (open, fence_marker(line)) {
+            (None, Some(marker)) => open = Some((index, marker)),
+            // A fence closes with the same character, at least as long, and nothing after it
+            (Some((_, opening)), Some(marker))
+                if marker.starts_with(&opening[..1])
+                    && marker.len() >= opening.len()
+                    && line.trim() == marker =>
+            {
+                open = None
+            }
+            (None, None) => prose.push((index, line)),
+            _ => {}
+        }
+    }
+
+    (prose, open.map(|(line, _)| line))
+}
+
+/// Targets of `[[wiki links]]` in a line (`[[target|alias]]` and
+/// `[[target#heading]]` resolve by target)
+pub fn wiki_link_targets(line: &str) -> Vec<&str> {
+    let mut targets = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("[[") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("]]") else {
+            break;
+        };
+        let inner = &after[..end];
+        let target = inner.split(['|', '#']).next().unwrap_or("").trim();
+        if !target.is_empty() {
+            targets.push(target);
+        }
+        rest = &after[end + 2..];
+    }
+    targets
+}
+
+/// Whether a line has a TODO marker (as a whole word)
+fn has_todo(line: &str) -> bool {
+    line.match_indices("TODO").any(|(i, _)| {
+        let before = line[..i].chars().next_back();
+        let after = line[i + 4..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// `[[link]]` targets in prose lines that match none of `slugs`, with their line
+fn broken_targets<'a>(prose: &[(usize, &'a str)], slugs: &HashSet<String>) -> Vec<(usize, &'a str)> {
+    prose
+        .iter()
+        .flat_map(|&(index, line)| wiki_link_targets(line).into_iter().map(move |target| (index, target)))
+        .filter(|(_, target)| !slugs.contains(&queries::slugify(target)))
+        .collect()
+}
+
+/// Lint one buffer. `slugs` holds the slugs of all linkable (non-archived) notes.
+fn lint_buffer(buffer: &Buffer, config: &LintConfig, slugs: &HashSet<String>, now: i64) -> Vec<LintFinding> {
+    let title = buffer
+        .title_override
+        .clone()
+        .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+    let mut findings = Vec::new();
+    let mut finding = |rule, line, message: String| {
+        findings.push(LintFinding {
+            buffer_id: buffer.id.clone(),
+            title: title.clone(),
+            rule,
+            line,
+            message,
+        })
+    };
+    let enabled = |rule| config.rules.contains(&rule);
+
+    if enabled(LintRule::EmptyTitle)
+        && buffer.title_override.is_none()
+        && title.trim_start_matches('#').trim().is_empty()
+    {
+        finding(LintRule::EmptyTitle, None, "Note has no title".to_string());
+    }
+
+    let (prose, unclosed_fence) = prose_lines(&buffer.content);
+
+    if enabled(LintRule::UnclosedCodeFence) {
+        if let Some(line) = unclosed_fence {
+            finding(LintRule::UnclosedCodeFence, Some(line), "Code fence is never closed".to_string());
+        }
+    }
+
+    if enabled(LintRule::BrokenWikiLink) {
+        for (index, target) in broken_targets(&prose, slugs) {
+            finding(LintRule::BrokenWikiLink, Some(index), format!("No note matches [[{}]]", target));
+        }
+    }
+
+    if enabled(LintRule::StaleTodo) {
+        let age_days = (now - buffer.updated_at) / DAY_SECS;
+        if age_days >= config.todo_max_age_days {
+            for &(index, line) in prose.iter().filter(|(_, line)| has_todo(line)) {
+                finding(
+                    LintRule::StaleTodo,
+                    Some(index),
+                    format!("TODO untouched for {} days: {}", age_days, line.trim()),
+                );
+            }
+        }
+    }
+
+    findings
+}
+
+/// Slugs of all linkable (non-archived) notes
+fn linkable_slugs(buffers: &[Buffer]) -> HashSet<String> {
+    buffers
+        .iter()
+        .filter(|b| !b.is_archived)
+        .map(|b| b.slug.clone())
+        .collect()
+}
+
+/// Every broken `[[link]]` in non-archived notes, whatever the lint settings
+pub fn broken_links(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<BrokenLink>> {
+    let buffers = queries::get_all_buffers(conn)?;
+    let slugs = linkable_slugs(&buffers);
+
+    let mut links = Vec::new();
+    for buffer in buffers.iter().filter(|b| !b.is_archived) {
+        let (prose, _) = prose_lines(&buffer.content);
+        let broken = broken_targets(&prose, &slugs);
+        if broken.is_empty() {
+            continue;
+        }
+        let title = buffer
+            .title_override
+            .clone()
+            .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+        links.extend(broken.into_iter().map(|(line, target)| BrokenLink {
+            buffer_id: buffer.id.clone(),
+            title: title.clone(),
+            line,
+            target: target.to_string(),
+        }));
+    }
+    Ok(links)
+}
+
+/// Lint the given buffers (all non-archived ones when `ids` is None) with
+/// the rules configured in settings
+pub fn run(conn: &rusqlite::Connection, ids: Option<&[String]>, now: i64) -> rusqlite::Result<Vec<LintFinding>> {
+    let config = LintConfig::from_settings(&queries::get_settings(conn)?);
+    let buffers = queries::get_all_buffers(conn)?;
+    let slugs = linkable_slugs(&buffers);
+
+    Ok(buffers
+        .iter()
+        .filter(|b| match ids {
+            Some(ids) => ids.contains(&b.id),
+            None => !b.is_archived,
+        })
+        .flat_map(|b| lint_buffer(b, &config, &slugs, now))
+        .collect())
+}
@@ -0,0 +1,74 @@
+// Concurrent edits of one buffer. A window saves together with the content
+// it started editing from (its base); if the stored content has moved on in
+// the meantime (another window or a sync wrote it), the save is merged
+// three-way instead of overwriting the other change, and every window is
+// told to reload so all views converge.
+
+use crate::merge::{self, MergeLabels, MergeResult};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+
+/// Event emitted after a buffer's stored content changed
+pub const BUFFERS_CHANGED_EVENT: &str = "buffers-changed";
+
+/// Conflict marker labels for a merged save
+const LABELS: MergeLabels<'static> = MergeLabels {
+    ours: "this edit",
+    theirs: "other edit",
+};
+
+#[derive(Clone, Serialize)]
+struct BuffersChanged {
+    ids: Vec<String>,
+    /// Label of the window that saved, so it can ignore its own change
+    window: Option<String>,
+}
+
+/// Merge a save of `incoming` (edited from `base`) with the `stored`
+/// content. None when the save can be stored as-is: no base was given, or
+/// nothing else changed the buffer since.
+pub fn reconcile(stored: &str, base: Option<&str>, incoming: &str) -> Option<MergeResult> {
+    let base = base?;
+    if stored == base || stored == incoming {
+        return None;
+    }
+    Some(merge::merge3(base, incoming, stored, LABELS))
+}
+
+/// Tell every window that buffers changed
+pub fn notify(app: &AppHandle, ids: Vec<String>, window: Option<&str>) {
+    let payload = BuffersChanged {
+        ids,
+        window: window.map(str::to_string),
+    };
+    if let Err(e) = app.emit(BUFFERS_CHANGED_EVENT, payload) {
+        warn!("Failed to emit {} event: {}", BUFFERS_CHANGED_EVENT, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_as_is_when_nothing_else_changed() {
+        assert!(reconcile("stored", None, "incoming").is_none());
+        assert!(reconcile("base", Some("base"), "incoming").is_none());
+        assert!(reconcile("same", Some("base"), "same").is_none());
+    }
+
+    #[test]
+    fn merges_with_a_concurrent_edit() {
+        let merged = reconcile("a\nb\nC", Some("a\nb\nc"), "A\nb\nc").unwrap();
+        assert_eq!(merged.text, "A\nb\nC");
+        assert_eq!(merged.conflicts, 0);
+    }
+
+    #[test]
+    fn labels_conflicts_from_the_saving_window() {
+        let merged = reconcile("a\nother\nc", Some("a\nb\nc"), "a\nmine\nc").unwrap();
+        assert_eq!(merged.text, "a\n<<<<<<< this edit\nmine\n=======\nother\n>>>>>>> other edit\nc");
+        assert_eq!(merged.conflicts, 1);
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::warn;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+
+/// Log file name prefix (rotated files get a date suffix)
+const LOG_FILE_PREFIX: &str = "flashnotes.log";
+
+/// Number of daily log files kept on disk
+const MAX_LOG_FILES: usize = 7;
+
+/// Supported values for the `log_level` setting
+pub const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+/// Default log level when the setting is missing or invalid
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+/// Handle for swapping the level filter at runtime
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Directory holding rotated log files
+pub fn log_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("logs")
+}
+
+/// Build the filter for a level: app logs at `level`, dependencies at warn
+fn build_filter(level: &str) -> EnvFilter {
+    EnvFilter::new(format!("flashnotes={},warn", level))
+}
+
+/// Initialize logging with daily-rotated file output plus stderr.
+/// Falls back to stderr-only logging if the log directory is unusable.
+pub fn init(app_data_dir: &Path) {
+    let (filter, handle) = reload::Layer::new(build_filter(DEFAULT_LOG_LEVEL));
+    let _ = FILTER_HANDLE.set(handle);
+
+    let file_appender = fs::create_dir_all(log_dir(app_data_dir))
+        .map_err(|e| e.to_string())
+        .and_then(|_| {
+            RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix(LOG_FILE_PREFIX)
+                .max_log_files(MAX_LOG_FILES)
+                .build(log_dir(app_data_dir))
+                .map_err(|e| e.to_string())
+        });
+
+    match file_appender {
+        Ok(appender) => {
+            let (non_blocking, guard) = tracing_appender::non_blocking(appender);
+
+            // Keep the guard alive for the lifetime of the app
+            // by leaking it (acceptable for app-lifetime resources)
+            Box::leak(Box::new(guard));
+
+            tracing_subscriber::registry()
+                .with(filter)
+                .with(fmt::layer().with_writer(non_blocking).with_ansi(false))
+                .with(fmt::layer().with_writer(std::io::stderr))
+                .init();
+        }
+        Err(e) => {
+            tracing_subscriber::registry()
+                .with(filter)
+                .with(fmt::layer().with_writer(std::io::stderr))
+                .init();
+
+            warn!("Failed to initialize file logging, using stderr only: {}", e);
+        }
+    }
+}
+
+/// Check whether a value is a supported log level
+pub fn is_valid_level(level: &str) -> bool {
+    LOG_LEVELS.contains(&level)
+}
+
+/// Change the active log level at runtime
+pub fn set_level(level: &str) -> Result<(), String> {
+    if !is_valid_level(level) {
+        return Err(format!(
+            "Invalid log level '{}'. Expected one of: {}",
+            level,
+            LOG_LEVELS.join(", ")
+        ));
+    }
+
+    let handle = FILTER_HANDLE
+        .get()
+        .ok_or_else(|| "Logging is not initialized".to_string())?;
+
+    handle
+        .reload(build_filter(level))
+        .map_err(|e| format!("Failed to change log level: {}", e))
+}
+
+/// Read the last `n` lines across the most recent log files
+pub fn read_recent_lines(app_data_dir: &Path, n: usize) -> Result<Vec<String>, String> {
+    let dir = log_dir(app_data_dir);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read log directory: {}", e))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(LOG_FILE_PREFIX))
+        })
+        .collect();
+
+    // Rotated files carry a YYYY-MM-DD suffix, so name order is date order
+    files.sort();
+
+    let mut lines: Vec<String> = Vec::new();
+    for path in files.iter().rev() {
+        if lines.len() >= n {
+            break;
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read log file {:?}: {}", path, e))?;
+
+        let needed = n - lines.len();
+        let file_lines: Vec<&str> = content.lines().collect();
+        let start = file_lines.len().saturating_sub(needed);
+
+        // Prepend older lines so the result stays in chronological order
+        let mut chunk: Vec<String> = file_lines[start..].iter().map(|l| l.to_string()).collect();
+        chunk.append(&mut lines);
+        lines = chunk;
+    }
+
+    Ok(lines)
+}
@@ -0,0 +1,398 @@
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::{connection, fts, queries};
+use crate::digest;
+use crate::embeddings;
+use crate::error::{AppResult, DbResultExt};
+use crate::jobs::{self, CancelToken};
+use crate::journal;
+use crate::lint;
+use crate::scripting;
+use crate::state::AppState;
+use chrono::{Local, Utc, Weekday};
+use serde::Serialize;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// How often the maintenance thread wakes up to run due jobs
+const TICK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Heavy jobs only run after this long without user activity
+const IDLE_THRESHOLD_SECS: i64 = 5 * 60;
+
+/// Minimum time between incremental FTS merges
+const FTS_MERGE_INTERVAL_SECS: i64 = 60 * 60;
+
+/// Pages of work per incremental FTS merge
+const FTS_MERGE_PAGES: i64 = 500;
+
+/// Daily jobs run at most once per this many seconds
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// Internal setting key recording the last daily empty-buffer cleanup
+const LAST_EMPTY_CLEANUP_KEY: &str = "last_empty_cleanup_at";
+
+/// Internal setting key recording the last auto-archive run
+const LAST_AUTO_ARCHIVE_KEY: &str = "last_auto_archive_at";
+
+/// Internal setting key recording the last background lint
+const LAST_LINT_KEY: &str = "last_lint_at";
+
+/// Internal setting key recording when the last weekly digest was created
+const LAST_DIGEST_KEY: &str = "last_digest_at";
+
+/// Internal setting key recording the last incremental FTS merge
+pub const LAST_FTS_MERGE_KEY: &str = "last_fts_merge_at";
+
+/// Internal setting key recording the last full FTS optimize
+pub const LAST_FTS_OPTIMIZE_KEY: &str = "last_fts_optimize_at";
+
+/// Event emitted after empty buffers were removed in the background
+pub const EMPTY_BUFFERS_CLEANED_EVENT: &str = "empty-buffers-cleaned";
+
+/// Event emitted after stale buffers were archived in the background
+pub const BUFFERS_ARCHIVED_EVENT: &str = "buffers-archived";
+
+/// Event emitted when the background lint found problems
+pub const LINT_FINDINGS_EVENT: &str = "lint-findings";
+
+/// Event emitted after the weekly digest note was created
+pub const DIGEST_CREATED_EVENT: &str = "digest-created";
+
+/// When empty buffers are cleaned up automatically
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupPolicy {
+    Never,
+    OnClose,
+    OnBlur,
+    Daily,
+}
+
+impl CleanupPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "never" => Some(Self::Never),
+            "on_close" => Some(Self::OnClose),
+            "on_blur" => Some(Self::OnBlur),
+            "daily" => Some(Self::Daily),
+            _ => None,
+        }
+    }
+
+    /// Read the configured policy, defaulting to on-blur (the historical behavior)
+    fn current(state: &AppState) -> Self {
+        state
+            .read(queries::get_settings)
+            .ok()
+            .and_then(|s| Self::parse(&s.empty_buffer_cleanup))
+            .unwrap_or(Self::OnBlur)
+    }
+}
+
+/// Payload for background job events
+#[derive(Clone, Serialize)]
+struct CountPayload {
+    count: usize,
+}
+
+/// Payload for events about a single buffer
+#[derive(Clone, Serialize)]
+struct IdPayload {
+    id: String,
+}
+
+/// When a job recorded under `key` last ran, if ever
+pub fn last_run_at(conn: &rusqlite::Connection, key: &str) -> Option<i64> {
+    queries::get_setting(conn, key)
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<i64>().ok())
+}
+
+/// Whether a job last recorded under `key` is due again after `interval_secs`
+fn is_job_due(state: &AppState, key: &str, now: i64, interval_secs: i64) -> bool {
+    now - state.read(|conn| last_run_at(conn, key)).unwrap_or(0) >= interval_secs
+}
+
+/// Whether a daily job last recorded under `key` is due again
+fn is_daily_job_due(state: &AppState, key: &str, now: i64) -> bool {
+    is_job_due(state, key, now, DAY_SECS)
+}
+
+/// Record that a job ran at `now`
+fn mark_job_run(state: &AppState, key: &str, now: i64) {
+    let conn = state.writer.lock();
+    if let Err(e) = queries::set_setting(&conn, key, &now.to_string()) {
+        warn!("Failed to record last run of {}: {}", key, e);
+    }
+}
+
+/// Emit a count event to the frontend
+fn emit_count(app: &AppHandle, event: &str, count: usize) {
+    if let Err(e) = app.emit(event, CountPayload { count }) {
+        warn!("Failed to emit {} event: {}", event, e);
+    }
+}
+
+/// Delete empty, unpinned buffers, sparing any with unsaved journaled edits
+/// or open in a note window
+pub fn cleanup_empty_buffers(state: &AppState, origin: Origin) -> AppResult<usize> {
+    let keep_ids: Vec<String> = journal::read_entries(&journal::journal_dir(&state.app_data_dir))
+        .into_iter()
+        .map(|e| e.buffer_id)
+        .chain(state.note_windows.buffer_ids())
+        .collect();
+
+    let mut conn = state.writer.lock();
+    let deleted = queries::delete_empty_buffers(&mut conn, &keep_ids)
+        .db_context("Failed to cleanup empty buffers")?;
+    if !deleted.is_empty() {
+        for id in &deleted {
+            activity::record(&conn, Some(id), Operation::Delete, origin, Some("empty"));
+        }
+        connection::scrub_after_delete(&conn);
+        info!("Cleaned up {} empty buffers", deleted.len());
+    }
+    Ok(deleted.len())
+}
+
+/// Run the cleanup in the background and tell the frontend to refresh
+fn run_background_cleanup(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    match cleanup_empty_buffers(&state, Origin::System) {
+        Ok(0) => {}
+        Ok(count) => emit_count(app, EMPTY_BUFFERS_CLEANED_EVENT, count),
+        Err(e) => warn!("Background empty-buffer cleanup failed: {}", e),
+    }
+}
+
+/// Main window lost focus
+pub fn on_window_blur(app: &AppHandle) {
+    if !app.state::<AppState>().ready.is_ready() {
+        return;
+    }
+    if CleanupPolicy::current(&app.state::<AppState>()) == CleanupPolicy::OnBlur {
+        run_background_cleanup(app);
+    }
+}
+
+/// App is about to exit
+pub fn on_exit(app: &AppHandle) {
+    if !app.state::<AppState>().ready.is_ready() {
+        return;
+    }
+    if CleanupPolicy::current(&app.state::<AppState>()) == CleanupPolicy::OnClose {
+        run_background_cleanup(app);
+    }
+}
+
+/// Daily cleanup, if the policy asks for it and a day has passed
+fn run_daily_cleanup(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let now = Utc::now().timestamp();
+    if CleanupPolicy::current(&state) != CleanupPolicy::Daily
+        || !is_daily_job_due(&state, LAST_EMPTY_CLEANUP_KEY, now)
+    {
+        return;
+    }
+
+    run_background_cleanup(app);
+    mark_job_run(&state, LAST_EMPTY_CLEANUP_KEY, now);
+}
+
+/// Archive non-pinned buffers untouched for `auto_archive_days` (0 = off)
+fn run_auto_archive(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let now = Utc::now().timestamp();
+
+    let days = state.read(queries::get_settings).map(|s| s.auto_archive_days).unwrap_or(0);
+    if days <= 0 || !is_daily_job_due(&state, LAST_AUTO_ARCHIVE_KEY, now) {
+        return;
+    }
+
+    let cutoff = now - i64::from(days) * DAY_SECS;
+    let result = {
+        let conn = state.writer.lock();
+        queries::archive_stale_buffers(&conn, cutoff, now).inspect(|ids| {
+            activity::record_batch(&conn, ids, Operation::Archive, Origin::System, Some("auto_archive"));
+        })
+    };
+
+    match result {
+        Ok(ids) if ids.is_empty() => {}
+        Ok(ids) => {
+            info!("Auto-archived {} buffers untouched for {} days", ids.len(), days);
+            emit_count(app, BUFFERS_ARCHIVED_EVENT, ids.len());
+        }
+        Err(e) => {
+            warn!("Auto-archive failed: {}", e);
+            return;
+        }
+    }
+
+    mark_job_run(&state, LAST_AUTO_ARCHIVE_KEY, now);
+}
+
+/// Lint all notes daily when `lint_daily` is on, reporting the finding count
+fn run_daily_lint(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let now = Utc::now().timestamp();
+
+    let enabled = state.read(queries::get_settings).map(|s| s.lint_daily).unwrap_or(false);
+    if !enabled || !is_daily_job_due(&state, LAST_LINT_KEY, now) {
+        return;
+    }
+
+    let result = match state.get_reader() {
+        Ok(conn) => lint::run(&conn, None, now),
+        Err(_) => {
+            let conn = state.writer.lock();
+            lint::run(&conn, None, now)
+        }
+    };
+
+    match result {
+        Ok(findings) => {
+            if !findings.is_empty() {
+                info!("Background lint found {} problems", findings.len());
+                emit_count(app, LINT_FINDINGS_EVENT, findings.len());
+            }
+        }
+        Err(e) => {
+            warn!("Background lint failed: {}", e);
+            return;
+        }
+    }
+
+    mark_job_run(&state, LAST_LINT_KEY, now);
+}
+
+/// Create the "Week in review" note once the configured day and hour have
+/// passed, catching up on the next run if the app was closed at the time
+fn run_weekly_digest(app: &AppHandle) {
+    let state = app.state::<AppState>();
+
+    let (settings, last_run) = state.read(|conn| {
+        let settings = queries::get_settings(conn).unwrap_or_default();
+        (settings, last_run_at(conn, LAST_DIGEST_KEY).unwrap_or(0))
+    });
+    if !settings.digest_enabled {
+        return;
+    }
+    let Ok(day) = settings.digest_day.parse::<Weekday>() else {
+        warn!("Invalid digest day '{}', skipping digest", settings.digest_day);
+        return;
+    };
+    let hour = settings.digest_hour.clamp(0, 23) as u32;
+    let Some(scheduled) = digest::last_scheduled(Local::now(), day, hour) else {
+        return;
+    };
+    let end = scheduled.timestamp();
+    if last_run >= end {
+        return;
+    }
+
+    let start = digest::week_start(end);
+    let result = state.read(|conn| queries::get_buffers_updated_between(conn, start, end));
+    let buffers = match result {
+        Ok(buffers) => buffers,
+        Err(e) => {
+            warn!("Weekly digest failed: {}", e);
+            return;
+        }
+    };
+
+    // Rendered outside the writer lock: the AI summary can take a while
+    let content = digest::render(&settings, &buffers, start, end);
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().timestamp();
+    let result = {
+        let conn = state.writer.lock();
+        queries::create_buffer(&conn, &id, &content, digest::DIGEST_SOURCE, now).inspect(|_| {
+            activity::record(&conn, Some(&id), Operation::Create, Origin::System, Some(digest::DIGEST_SOURCE));
+        })
+    };
+    if let Err(e) = result {
+        warn!("Failed to save weekly digest: {}", e);
+        return;
+    }
+
+    info!("Created weekly digest {}", id);
+    if let Err(e) = app.emit(DIGEST_CREATED_EVENT, IdPayload { id }) {
+        warn!("Failed to emit {} event: {}", DIGEST_CREATED_EVENT, e);
+    }
+    mark_job_run(&state, LAST_DIGEST_KEY, end);
+}
+
+/// Keep the FTS index compact while the user is idle: an incremental merge
+/// hourly and a full optimize daily
+fn run_fts_maintenance(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let now = Utc::now().timestamp();
+    if state.idle.idle_secs(now) < IDLE_THRESHOLD_SECS {
+        return;
+    }
+
+    if is_daily_job_due(&state, LAST_FTS_OPTIMIZE_KEY, now) {
+        let result = {
+            let conn = state.writer.lock();
+            fts::optimize(&conn)
+        };
+        match result {
+            Ok(()) => {
+                info!("Optimized search index");
+                mark_job_run(&state, LAST_FTS_OPTIMIZE_KEY, now);
+                mark_job_run(&state, LAST_FTS_MERGE_KEY, now);
+            }
+            Err(e) => warn!("Search index optimize failed: {}", e),
+        }
+    } else if is_job_due(&state, LAST_FTS_MERGE_KEY, now, FTS_MERGE_INTERVAL_SECS) {
+        let result = {
+            let conn = state.writer.lock();
+            fts::merge(&conn, FTS_MERGE_PAGES)
+        };
+        match result {
+            Ok(()) => mark_job_run(&state, LAST_FTS_MERGE_KEY, now),
+            Err(e) => warn!("Search index merge failed: {}", e),
+        }
+    }
+}
+
+/// Run all periodic jobs once, stopping early if cancelled
+fn run_due_jobs(app: &AppHandle, cancel: &CancelToken) -> AppResult<()> {
+    run_daily_cleanup(app);
+    run_auto_archive(app);
+    cancel.check()?;
+    run_daily_lint(app);
+    run_weekly_digest(app);
+    cancel.check()?;
+    run_fts_maintenance(app);
+    embeddings::run_background_refresh(app, cancel);
+    cancel.check()?;
+    scripting::run_scheduled(app);
+    Ok(())
+}
+
+/// Spawn the background maintenance thread.
+/// Jobs are checked once at startup, then every 15 minutes.
+pub fn spawn(app: AppHandle) {
+    let spawned = thread::Builder::new()
+        .name("maintenance".to_string())
+        .spawn(move || loop {
+            // Skipped while the encrypted database is locked
+            if app.state::<AppState>().ready.is_locked() {
+                thread::sleep(TICK_INTERVAL);
+                continue;
+            }
+            if let Err(e) = jobs::run_blocking(&app, "maintenance", |cancel| run_due_jobs(&app, cancel)) {
+                info!("Maintenance run stopped: {}", e);
+            }
+            thread::sleep(TICK_INTERVAL);
+        });
+
+    if let Err(e) = spawned {
+        warn!("Failed to start maintenance thread: {}", e);
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(target_os = "macos")]
+use crate::i18n::t;
+use tauri::AppHandle;
+#[cfg(target_os = "macos")]
+use tauri::menu::{AboutMetadata, CheckMenuItem, MenuBuilder, MenuItem, PredefinedMenuItem, SubmenuBuilder};
+
+/// Build the macOS menu bar in the active locale and install it, replacing
+/// any existing menu. Called again whenever the locale changes.
+#[cfg(target_os = "macos")]
+pub fn install(app: &AppHandle, always_on_top: bool) -> tauri::Result<()> {
+    let about_metadata = AboutMetadata {
+        website: Some("https://github.com/garyblankenship/flashnotes".into()),
+        website_label: Some("GitHub".into()),
+        ..Default::default()
+    };
+
+    let app_menu = SubmenuBuilder::new(app, "Flashnotes")
+        .item(&PredefinedMenuItem::about(app, Some(t("About Flashnotes")), Some(about_metadata))?)
+        .separator()
+        .item(&PredefinedMenuItem::hide(app, Some(t("Hide Flashnotes")))?)
+        .item(&PredefinedMenuItem::hide_others(app, Some(t("Hide Others")))?)
+        .item(&PredefinedMenuItem::show_all(app, Some(t("Show All")))?)
+        .separator()
+        .item(&PredefinedMenuItem::quit(app, Some(t("Quit Flashnotes")))?)
+        .build()?;
+
+    let edit_menu = SubmenuBuilder::new(app, t("Edit"))
+        .item(&PredefinedMenuItem::undo(app, Some(t("Undo")))?)
+        .item(&PredefinedMenuItem::redo(app, Some(t("Redo")))?)
+        .separator()
+        .item(&PredefinedMenuItem::cut(app, Some(t("Cut")))?)
+        .item(&PredefinedMenuItem::copy(app, Some(t("Copy")))?)
+        .item(&PredefinedMenuItem::paste(app, Some(t("Paste")))?)
+        .item(&PredefinedMenuItem::select_all(app, Some(t("Select All")))?)
+        .build()?;
+
+    let stay_on_top_item = CheckMenuItem::with_id(
+        app,
+        "stay_on_top",
+        t("Stay on Top"),
+        true,
+        always_on_top,
+        Some("CmdOrCtrl+Shift+T"),
+    )?;
+
+    let window_menu = SubmenuBuilder::new(app, t("Window"))
+        .item(&PredefinedMenuItem::minimize(app, Some(t("Minimize")))?)
+        .item(&PredefinedMenuItem::maximize(app, Some(t("Zoom")))?)
+        .separator()
+        .item(&stay_on_top_item)
+        .separator()
+        .item(&PredefinedMenuItem::close_window(app, Some(t("Close")))?)
+        .build()?;
+
+    let help_menu = SubmenuBuilder::new(app, t("Help"))
+        .item(&MenuItem::with_id(app, "github", t("GitHub Repository"), true, None::<&str>)?)
+        .build()?;
+
+    let menu = MenuBuilder::new(app)
+        .item(&app_menu)
+        .item(&edit_menu)
+        .item(&window_menu)
+        .item(&help_menu)
+        .build()?;
+
+    app.set_menu(menu)?;
+    Ok(())
+}
+
+/// Other platforms use the default window menus
+#[cfg(not(target_os = "macos"))]
+pub fn install(_app: &AppHandle, _always_on_top: bool) -> tauri::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,225 @@
+// Line-based three-way merge (diff3-style) for reconciling a buffer with an externally edited copy
+
+/// Outcome of a three-way merge
+#[derive(Debug)]
+pub struct MergeResult {
+    pub text: String,
+    /// Number of regions where both sides changed the same lines differently.
+    /// Each one is written with conflict markers.
+    pub conflicts: usize,
+}
+
+/// Labels written on conflict markers for each side
+#[derive(Debug, Clone, Copy)]
+pub struct MergeLabels<'a> {
+    pub ours: &'a str,
+    pub theirs: &'a str,
+}
+
+/// Pairs of (a index, b index) for lines in a longest common subsequence of
+/// `a` and `b`, in increasing order (Myers' O(ND) diff).
+fn matching_lines(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    // Common prefix/suffix are matches by definition; skip them for speed
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (inner_a, inner_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    pairs.extend(
+        myers(inner_a, inner_b)
+            .into_iter()
+            .map(|(x, y)| (x + prefix, y + prefix)),
+    );
+    pairs.extend((0..suffix).map(|i| (a.len() - suffix + i, b.len() - suffix + i)));
+    pairs
+}
+
+fn myers(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    if max == 0 {
+        return Vec::new();
+    }
+
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    // trace[d] holds v[-d..=d] as it was before step d
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let idx = |k: isize| (k + offset) as usize;
+
+    'search: for d in 0..=max {
+        trace.push(v[idx(-d)..=idx(d)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[idx(k - 1)] < v[idx(k + 1)]) {
+                v[idx(k + 1)]
+            } else {
+                v[idx(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Walk back through the snapshots collecting diagonal (matching) moves
+    let mut pairs = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, snapshot) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let get = |k: isize| snapshot[(k + d) as usize];
+        let k = x - y;
+        let (prev_x, prev_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let prev_k = if k == -d || (k != d && get(k - 1) < get(k + 1)) { k + 1 } else { k - 1 };
+            let prev_x = get(prev_k);
+            (prev_x, prev_x - prev_k)
+        };
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            pairs.push((x as usize, y as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    pairs.reverse();
+    pairs
+}
+
+/// For each line of `base`, the index of the matching line in `other`, if any
+fn base_matches(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base.len()];
+    for (i, j) in matching_lines(base, other) {
+        matches[i] = Some(j);
+    }
+    matches
+}
+
+/// Three-way merge of `ours` and `theirs`, both derived from `base`.
+/// Regions changed on only one side take that side; identical changes on
+/// both sides are taken once; differing changes to the same region become a
+/// conflict block:
+///
+/// ```text
+/// <<<<<<< ours
+/// ...
+/// =======
+/// ...
+/// >>>>>>> theirs
+/// ```
+pub fn merge3(base: &str, ours: &str, theirs: &str, labels: MergeLabels<'_>) -> MergeResult {
+    if ours == theirs || theirs == base {
+        return MergeResult { text: ours.to_string(), conflicts: 0 };
+    }
+    if ours == base {
+        return MergeResult { text: theirs.to_string(), conflicts: 0 };
+    }
+
+    let base_lines: Vec<&str> = base.lines().collect();
+    let our_lines: Vec<&str> = ours.lines().collect();
+    let their_lines: Vec<&str> = theirs.lines().collect();
+    let ours_at = base_matches(&base_lines, &our_lines);
+    let theirs_at = base_matches(&base_lines, &their_lines);
+
+    let mut out: Vec<&str> = Vec::new();
+    let mut conflicts = 0;
+    let our_marker = format!("<<<<<<< {}", labels.ours);
+    let their_marker = format!(">>>>>>> {}", labels.theirs);
+    let (mut o, mut a, mut b) = (0, 0, 0);
+
+    loop {
+        // Next base line kept by both sides (a stable sync point)
+        let sync = (o..base_lines.len()).find_map(|i| match (ours_at[i], theirs_at[i]) {
+            (Some(ai), Some(bi)) if ai >= a && bi >= b => Some((i, ai, bi)),
+            _ => None,
+        });
+        let (end_o, end_a, end_b) = sync.unwrap_or((base_lines.len(), our_lines.len(), their_lines.len()));
+
+        let base_chunk = &base_lines[o..end_o];
+        let our_chunk = &our_lines[a..end_a];
+        let their_chunk = &their_lines[b..end_b];
+        if our_chunk == base_chunk || our_chunk == their_chunk {
+            out.extend_from_slice(their_chunk);
+        } else if their_chunk == base_chunk {
+            out.extend_from_slice(our_chunk);
+        } else {
+            conflicts += 1;
+            out.push(&our_marker);
+            out.extend_from_slice(our_chunk);
+            out.push("=======");
+            out.extend_from_slice(their_chunk);
+            out.push(&their_marker);
+        }
+
+        match sync {
+            Some((i, ai, bi)) => {
+                out.push(base_lines[i]);
+                o = i + 1;
+                a = ai + 1;
+                b = bi + 1;
+            }
+            None => break,
+        }
+    }
+
+    let mut text = out.join("\n");
+    // Keep a trailing newline if either side ends with one
+    if ours.ends_with('\n') || theirs.ends_with('\n') {
+        text.push('\n');
+    }
+    MergeResult { text, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LABELS: MergeLabels<'static> = MergeLabels { ours: "ours", theirs: "theirs" };
+
+    #[test]
+    fn takes_changes_from_both_sides() {
+        let result = merge3("a\nb\nc\nd\ne", "a\nB\nc\nd\ne", "a\nb\nc\nD\ne", LABELS);
+        assert_eq!(result.text, "a\nB\nc\nD\ne");
+        assert_eq!(result.conflicts, 0);
+    }
+
+    #[test]
+    fn keeps_insertions_at_both_ends() {
+        let result = merge3("one\ntwo\n", "zero\none\ntwo\n", "one\ntwo\nthree\n", LABELS);
+        assert_eq!(result.text, "zero\none\ntwo\nthree\n");
+        assert_eq!(result.conflicts, 0);
+    }
+
+    #[test]
+    fn marks_conflicting_changes() {
+        let result = merge3("a\nb\nc", "a\nX\nc", "a\nY\nc", LABELS);
+        assert_eq!(result.text, "a\n<<<<<<< ours\nX\n=======\nY\n>>>>>>> theirs\nc");
+        assert_eq!(result.conflicts, 1);
+    }
+
+    #[test]
+    fn identical_changes_are_taken_once() {
+        let result = merge3("a\nb\nc", "a\nB\nc\nd", "a\nB\nc", LABELS);
+        assert_eq!(result.text, "a\nB\nc\nd");
+        assert_eq!(result.conflicts, 0);
+    }
+
+    #[test]
+    fn one_sided_edits_win_outright() {
+        assert_eq!(merge3("base", "base", "theirs", LABELS).text, "theirs");
+        assert_eq!(merge3("base", "ours", "base", LABELS).text, "ours");
+    }
+}
@@ -0,0 +1,240 @@
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Flush pending metrics after this many recorded calls
+const FLUSH_EVERY_CALLS: usize = 50;
+
+/// Flush pending metrics at least this often while commands are running
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Commands slower than this are logged
+const SLOW_COMMAND: Duration = Duration::from_millis(500);
+
+/// SQL statements slower than this are logged and kept for diagnostics
+const SLOW_QUERY: Duration = Duration::from_millis(100);
+
+/// Slow queries kept for diagnostics (most recent)
+const MAX_SLOW_QUERIES: usize = 50;
+
+/// Slow statements seen this session, newest last
+static SLOW_QUERIES: Mutex<VecDeque<SlowQuery>> = Mutex::new(VecDeque::new());
+
+/// Aggregated stats for one command since the last flush
+#[derive(Debug, Default, Clone)]
+pub struct CommandStats {
+    pub calls: u64,
+    pub errors: u64,
+    pub total_us: u64,
+    pub max_us: u64,
+    pub last_called_at: i64,
+}
+
+struct Pending {
+    stats: HashMap<&'static str, CommandStats>,
+    calls_since_flush: usize,
+    last_flush: Instant,
+}
+
+/// Local-only, opt-in usage metrics (command call counts, durations, errors).
+/// Nothing is recorded unless enabled, and nothing ever leaves the machine.
+pub struct UsageMetrics {
+    enabled: AtomicBool,
+    pending: Mutex<Pending>,
+}
+
+impl UsageMetrics {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: AtomicBool::new(enabled),
+            pending: Mutex::new(Pending {
+                stats: HashMap::new(),
+                calls_since_flush: 0,
+                last_flush: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Turn recording on or off; turning it off discards anything unflushed
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.take_pending();
+        }
+    }
+
+    /// Record one command invocation. Returns true when a flush is due.
+    pub fn record(&self, command: &'static str, elapsed: Duration, ok: bool, timestamp: i64) -> bool {
+        let elapsed_us = elapsed.as_micros() as u64;
+        let mut pending = self.pending.lock();
+
+        let stats = pending.stats.entry(command).or_default();
+        stats.calls += 1;
+        if !ok {
+            stats.errors += 1;
+        }
+        stats.total_us += elapsed_us;
+        stats.max_us = stats.max_us.max(elapsed_us);
+        stats.last_called_at = timestamp;
+
+        pending.calls_since_flush += 1;
+        pending.calls_since_flush >= FLUSH_EVERY_CALLS || pending.last_flush.elapsed() >= FLUSH_INTERVAL
+    }
+
+    /// Drain the stats recorded since the last flush
+    pub fn take_pending(&self) -> HashMap<&'static str, CommandStats> {
+        let mut pending = self.pending.lock();
+        pending.calls_since_flush = 0;
+        pending.last_flush = Instant::now();
+        std::mem::take(&mut pending.stats)
+    }
+}
+
+/// Timing of one command over this session
+#[derive(Debug, Serialize)]
+pub struct CommandTiming {
+    pub command: &'static str,
+    pub calls: u64,
+    pub errors: u64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    pub total_ms: f64,
+}
+
+/// Per-command execution times since launch. Always on and never
+/// persisted, unlike the opt-in `UsageMetrics`.
+pub struct CommandTimings {
+    stats: Mutex<HashMap<&'static str, CommandStats>>,
+}
+
+impl Default for CommandTimings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandTimings {
+    pub fn new() -> Self {
+        Self {
+            stats: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record one command invocation, logging it when slow
+    pub fn record(&self, command: &'static str, elapsed: Duration, ok: bool) {
+        if elapsed >= SLOW_COMMAND {
+            warn!("Slow command {} took {} ms", command, elapsed.as_millis());
+        }
+
+        let elapsed_us = elapsed.as_micros() as u64;
+        let mut stats = self.stats.lock();
+        let entry = stats.entry(command).or_default();
+        entry.calls += 1;
+        if !ok {
+            entry.errors += 1;
+        }
+        entry.total_us += elapsed_us;
+        entry.max_us = entry.max_us.max(elapsed_us);
+    }
+
+    /// Commands by total time spent, most expensive first
+    pub fn snapshot(&self) -> Vec<CommandTiming> {
+        let mut timings: Vec<CommandTiming> = self
+            .stats
+            .lock()
+            .iter()
+            .map(|(&command, stats)| CommandTiming {
+                command,
+                calls: stats.calls,
+                errors: stats.errors,
+                avg_ms: stats.total_us as f64 / stats.calls.max(1) as f64 / 1000.0,
+                max_ms: stats.max_us as f64 / 1000.0,
+                total_ms: stats.total_us as f64 / 1000.0,
+            })
+            .collect();
+        timings.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+        timings
+    }
+}
+
+/// A statement that exceeded the slow-query threshold
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowQuery {
+    /// Statement text with literals replaced by `?`
+    pub sql: String,
+    pub duration_ms: f64,
+    pub at: i64,
+}
+
+/// Replace string and numeric literals in SQL with `?` so logged
+/// statements never carry note content or ids
+pub fn redact_sql(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut prev_is_word = false;
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            // '' inside a literal is an escaped quote
+            loop {
+                match chars.next() {
+                    Some('\'') if chars.peek() == Some(&'\'') => {
+                        chars.next();
+                    }
+                    Some('\'') | None => break,
+                    Some(_) => {}
+                }
+            }
+            out.push('?');
+            prev_is_word = false;
+        } else if c.is_ascii_digit() && !prev_is_word {
+            while chars.peek().is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+                chars.next();
+            }
+            out.push('?');
+            prev_is_word = false;
+        } else if c.is_whitespace() {
+            if !out.ends_with(' ') {
+                out.push(' ');
+            }
+            prev_is_word = false;
+        } else {
+            out.push(c);
+            prev_is_word = c.is_alphanumeric() || c == '_' || c == '?';
+        }
+    }
+    out.trim().to_string()
+}
+
+/// SQLite profile callback: log and keep statements over the threshold.
+/// Installed on every connection with `Connection::profile`.
+pub fn profile_query(sql: &str, duration: Duration) {
+    if duration < SLOW_QUERY {
+        return;
+    }
+
+    let sql = redact_sql(sql);
+    warn!("Slow query ({} ms): {}", duration.as_millis(), sql);
+
+    let mut queries = SLOW_QUERIES.lock();
+    if queries.len() == MAX_SLOW_QUERIES {
+        queries.pop_front();
+    }
+    queries.push_back(SlowQuery {
+        sql,
+        duration_ms: duration.as_secs_f64() * 1000.0,
+        at: Utc::now().timestamp(),
+    });
+}
+
+/// Slow queries seen this session, newest first
+pub fn slow_queries() -> Vec<SlowQuery> {
+    SLOW_QUERIES.lock().iter().rev().cloned().collect()
+}
@@ -0,0 +1,45 @@
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+
+/// Maximum number of buffers remembered in the MRU stack
+pub const MRU_CAPACITY: usize = 100;
+
+/// Most-recently-used buffer stack for Ctrl+Tab-style switching.
+/// Fed by `mark_accessed`, so it reflects the order notes were actually
+/// opened rather than the sidebar sort. Seeded from `accessed_at` at startup.
+pub struct MruStack {
+    ids: Mutex<VecDeque<String>>,
+}
+
+impl MruStack {
+    pub fn new() -> Self {
+        Self {
+            ids: Mutex::new(VecDeque::with_capacity(MRU_CAPACITY)),
+        }
+    }
+
+    /// Replace the stack with ids ordered most recent first
+    pub fn seed(&self, ids: Vec<String>) {
+        let mut stack = self.ids.lock();
+        stack.clear();
+        stack.extend(ids.into_iter().take(MRU_CAPACITY));
+    }
+
+    /// Move a buffer to the top of the stack
+    pub fn push(&self, id: &str) {
+        let mut stack = self.ids.lock();
+        stack.retain(|existing| existing != id);
+        stack.push_front(id.to_string());
+        stack.truncate(MRU_CAPACITY);
+    }
+
+    /// Forget a buffer (e.g. after it was deleted)
+    pub fn remove(&self, id: &str) {
+        self.ids.lock().retain(|existing| existing != id);
+    }
+
+    /// All ids, most recent first
+    pub fn ids(&self) -> Vec<String> {
+        self.ids.lock().iter().cloned().collect()
+    }
+}
@@ -0,0 +1,193 @@
+// Per-note locks. A locked buffer's content is encrypted with AES-256-GCM
+// under a key derived from its passphrase with Argon2id and kept in
+// `locked_content`; the readable `content` is cleared, and with it the
+// search index, cards, hashtags, revisions and embedding of the note.
+// Sealed format: version byte, salt, nonce, then ciphertext with its tag.
+
+use crate::biometric;
+use crate::db::{connection, queries};
+use crate::encryption;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::events::{self, Change};
+use crate::journal;
+use crate::live_merge;
+use crate::state::AppState;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use chrono::Utc;
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+/// First byte of sealed content, bumped if the format ever changes
+const FORMAT_VERSION: u8 = 1;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+fn cipher(passphrase: &str, salt: &[u8]) -> AppResult<Aes256Gcm> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| AppError::Internal(format!("Failed to derive note key: {}", e)))?;
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+/// Encrypt note content with a passphrase
+pub(crate) fn seal(content: &str, passphrase: &str) -> AppResult<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(&nonce, content.as_bytes())
+        .map_err(|_| AppError::Internal("Failed to encrypt note".to_string()))?;
+
+    let mut sealed = Vec::with_capacity(1 + SALT_LEN + NONCE_LEN + ciphertext.len());
+    sealed.push(FORMAT_VERSION);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypt sealed note content; a wrong passphrase is a validation error
+fn open(sealed: &[u8], passphrase: &str) -> AppResult<String> {
+    let rest = match sealed.split_first() {
+        Some((&FORMAT_VERSION, rest)) if rest.len() >= SALT_LEN + NONCE_LEN => rest,
+        _ => return Err(AppError::Internal("Locked note has an unknown format".to_string())),
+    };
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let plain = cipher(passphrase, salt)?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| AppError::Validation("Wrong passphrase".to_string()))?;
+    String::from_utf8(plain).map_err(|_| AppError::Internal("Locked note is not valid text".to_string()))
+}
+
+/// Encrypted content of a locked buffer
+fn sealed_content(state: &AppState, id: &str) -> AppResult<Vec<u8>> {
+    let conn = state.writer.lock();
+    if let Some(sealed) = queries::get_locked_content(&conn, id).db_context("Failed to get locked note")? {
+        return Ok(sealed);
+    }
+    match queries::get_buffer_content(&conn, id).db_context("Failed to get buffer")? {
+        Some(_) => Err(AppError::Validation("Note is not locked".to_string())),
+        None => Err(AppError::not_found("Buffer", id.to_string())),
+    }
+}
+
+/// Seal content again under `new` if it opens with `old` (None if it doesn't)
+pub fn reseal(sealed: &[u8], old: &str, new: &str) -> AppResult<Option<Vec<u8>>> {
+    match open(sealed, old) {
+        Ok(content) => seal(&content, new).map(Some),
+        Err(AppError::Validation(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether sealed content opens with `passphrase`
+pub fn opens(sealed: &[u8], passphrase: &str) -> AppResult<bool> {
+    match open(sealed, passphrase) {
+        Ok(_) => Ok(true),
+        Err(AppError::Validation(_)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Check a locked buffer's passphrase without unlocking it
+pub fn verify(state: &AppState, id: &str, passphrase: &str) -> AppResult<()> {
+    open(&sealed_content(state, id)?, passphrase).map(|_| ())
+}
+
+/// Encrypt a buffer's content with a passphrase
+pub fn lock(app: &AppHandle, state: &AppState, id: &str, passphrase: &str) -> AppResult<()> {
+    encryption::validate_passphrase(passphrase)?;
+
+    let mut conn = state.writer.lock();
+    let buffer = queries::get_buffer_content(&conn, id)
+        .db_context("Failed to get buffer")?
+        .ok_or_else(|| AppError::not_found("Buffer", id.to_string()))?;
+    if buffer.is_locked {
+        return Err(AppError::Conflict("Note is already locked".to_string()));
+    }
+    let sealed = seal(&buffer.content, passphrase)?;
+
+    let tx = conn.transaction().db_context("Failed to start locking note")?;
+    queries::lock_buffer(&tx, id, &sealed, Utc::now().timestamp()).db_context("Failed to lock note")?;
+    tx.commit().db_context("Failed to commit note lock")?;
+    // The readable content is gone from the table; scrub it from free pages and the WAL too
+    connection::scrub_after_delete(&conn);
+    drop(conn);
+
+    journal::remove_entry(&journal::journal_dir(&state.app_data_dir), id);
+    events::updated(app, id, Change::Lock);
+    live_merge::notify(app, vec![id.to_string()], None);
+    info!("Locked buffer: {}", id);
+    Ok(())
+}
+
+/// Decrypt a locked buffer, remove the lock and return its content
+pub fn unlock(app: &AppHandle, state: &AppState, id: &str, passphrase: &str) -> AppResult<String> {
+    let content = open(&sealed_content(state, id)?, passphrase)?;
+
+    let mut conn = state.writer.lock();
+    let tx = conn.transaction().db_context("Failed to start unlocking note")?;
+    let unlocked =
+        queries::unlock_buffer(&tx, id, &content, Utc::now().timestamp()).db_context("Failed to unlock note")?;
+    tx.commit().db_context("Failed to commit note unlock")?;
+    drop(conn);
+    if !unlocked {
+        return Err(AppError::Conflict("Note was unlocked in the meantime".to_string()));
+    }
+
+    // The passphrase is of no use once the note is unlocked
+    if let Err(e) = biometric::forget(Some(id)) {
+        warn!("Failed to remove Touch ID passphrase of buffer {}: {}", id, e);
+    }
+    events::updated(app, id, Change::Unlock);
+    live_merge::notify(app, vec![id.to_string()], None);
+    info!("Unlocked buffer: {}", id);
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_round_trip() {
+        let sealed = seal("secret note ✓", "hunter2").unwrap();
+        assert_eq!(sealed[0], FORMAT_VERSION);
+        assert!(!sealed.windows(6).any(|w| w == b"secret"));
+        assert_eq!(open(&sealed, "hunter2").unwrap(), "secret note ✓");
+    }
+
+    #[test]
+    fn each_seal_uses_a_fresh_salt_and_nonce() {
+        assert_ne!(seal("same", "pass").unwrap(), seal("same", "pass").unwrap());
+    }
+
+    #[test]
+    fn wrong_passphrase_is_a_validation_error() {
+        let sealed = seal("content", "right").unwrap();
+        assert!(matches!(open(&sealed, "wrong"), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        assert!(matches!(open(&[], "pass"), Err(AppError::Internal(_))));
+        let mut sealed = seal("content", "pass").unwrap();
+        sealed[0] = FORMAT_VERSION + 1;
+        assert!(matches!(open(&sealed, "pass"), Err(AppError::Internal(_))));
+    }
+
+    #[test]
+    fn reseal_moves_content_to_the_new_passphrase() {
+        let sealed = seal("content", "old").unwrap();
+        let resealed = reseal(&sealed, "old", "new").unwrap().unwrap();
+        assert_eq!(open(&resealed, "new").unwrap(), "content");
+        assert!(reseal(&sealed, "wrong", "new").unwrap().is_none());
+    }
+}
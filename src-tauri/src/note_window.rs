@@ -0,0 +1,77 @@
+// Floating windows bound to one note (`open_buffer_window`). A window is
+// labelled `note-<buffer id>`, which is how its page knows what to show; it
+// saves like the main window (concurrent edits merge, see `live_merge`) and
+// follows the buffer change events (see `events`) to reload, retitle or close
+// itself. Open note windows are tracked so their buffers survive empty-note
+// cleanup while they are being written.
+
+use crate::error::AppResult;
+use crate::state::AppState;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+/// Label prefix of note windows, followed by the buffer id
+pub const LABEL_PREFIX: &str = "note-";
+
+/// Buffer shown by each open note window, by window label
+#[derive(Default)]
+pub struct NoteWindows(Mutex<HashMap<String, String>>);
+
+impl NoteWindows {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Buffers open in note windows
+    pub fn buffer_ids(&self) -> Vec<String> {
+        self.0.lock().values().cloned().collect()
+    }
+
+    /// Forget a closed window
+    pub fn remove(&self, label: &str) {
+        self.0.lock().remove(label);
+    }
+}
+
+#[cfg(desktop)]
+pub fn open(app: &tauri::AppHandle, state: &AppState, id: &str) -> AppResult<String> {
+    use crate::db::queries;
+    use crate::error::{AppError, DbResultExt};
+    use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+    use tracing::info;
+
+    let label = format!("{}{}", LABEL_PREFIX, id);
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(label);
+    }
+
+    let buffer = state
+        .read(|conn| queries::get_buffer_content(conn, id))
+        .db_context("Failed to get buffer")?
+        .ok_or_else(|| AppError::not_found("Buffer", id))?;
+    if buffer.is_locked {
+        return Err(AppError::Conflict("Note is locked".to_string()));
+    }
+    let title = buffer
+        .title_override
+        .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+
+    // Registered first so an empty note isn't cleaned up while the window loads
+    state.note_windows.0.lock().insert(label.clone(), id.to_string());
+    // The window-state plugin remembers each note window's size and position by label
+    let built = WebviewWindowBuilder::new(app, &label, WebviewUrl::App("index.html".into()))
+        .title(if title.is_empty() { "Untitled" } else { title.as_str() })
+        .inner_size(380.0, 420.0)
+        .min_inner_size(260.0, 180.0)
+        .always_on_top(true)
+        .build();
+    if let Err(e) = built {
+        state.note_windows.remove(&label);
+        return Err(AppError::Internal(format!("Failed to open note window: {}", e)));
+    }
+    info!("Opened note window for buffer {}", id);
+    Ok(label)
+}
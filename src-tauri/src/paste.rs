@@ -0,0 +1,263 @@
+// Pasted text classification: recognizes URLs, JSON, stack traces, CSV and
+// code, and suggests Markdown that presents them better
+
+use crate::language;
+use serde::Serialize;
+
+/// Larger pastes are left alone (classifying them isn't worth the wait)
+const MAX_ANALYZE_BYTES: usize = 512 * 1024;
+
+/// Keywords and line starts that mark a line as code
+const CODE_STARTS: &[&str] = &[
+    "fn ", "pub ", "let ", "const ", "var ", "function ", "def ", "class ", "import ", "from ", "use ", "return ",
+    "if (", "if ", "for (", "for ", "while ", "#include", "package ", "func ", "public ", "private ", "struct ",
+    "impl ", "async ", "export ", "SELECT ", "//", "/*", "* ", "#!",
+];
+
+/// Line starts that mark a stack trace frame or header
+const TRACE_MARKERS: &[&str] = &[
+    "Traceback (most recent call last):",
+    "File \"",
+    "at ",
+    "Exception in thread",
+    "Caused by:",
+    "thread '",
+    "stack backtrace:",
+    "goroutine ",
+    "panic:",
+];
+
+/// What a paste looks like
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteKind {
+    Url,
+    Json,
+    StackTrace,
+    Csv,
+    Code,
+    Text,
+}
+
+/// Suggested formatting for a paste; plain text has no suggestion
+#[derive(Debug, Serialize)]
+pub struct PasteSuggestion {
+    pub kind: PasteKind,
+    /// Short label for the action, e.g. "Paste as link"
+    pub action: Option<String>,
+    /// The text to insert instead of the raw paste
+    pub formatted: Option<String>,
+}
+
+impl PasteSuggestion {
+    fn plain() -> Self {
+        Self {
+            kind: PasteKind::Text,
+            action: None,
+            formatted: None,
+        }
+    }
+
+    fn new(kind: PasteKind, action: &str, formatted: String) -> Self {
+        Self {
+            kind,
+            action: Some(action.to_string()),
+            formatted: Some(formatted),
+        }
+    }
+}
+
+/// A fenced block that can't be closed early by backticks in the text
+pub fn fence(text: &str, language: &str) -> String {
+    let longest_run = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let marker = "`".repeat(longest_run.max(2) + 1);
+    format!("{}{}\n{}\n{}", marker, language, text.trim_end_matches(['\r', '\n']), marker)
+}
+
+/// `[example.com/page](https://example.com/page)` for a bare URL
+fn as_link(url: &str) -> String {
+    let label = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .trim_start_matches("www.")
+        .trim_end_matches('/');
+    let label = label.replace(['[', ']'], "");
+    format!("[{}](<{}>)", label, url)
+}
+
+fn is_url(text: &str) -> bool {
+    (text.starts_with("https://") || text.starts_with("http://"))
+        && text.len() > "https://".len()
+        && !text.contains(char::is_whitespace)
+}
+
+/// Fields of one CSV line, honouring double-quoted fields
+fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Rows of a paste that is a consistent table of at least two columns. Tab
+/// separated text needs two rows; comma and semicolon separated text needs
+/// three, so a couple of lines of prose aren't mistaken for a table.
+fn parse_csv(text: &str) -> Option<Vec<Vec<String>>> {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    [('\t', 2), (',', 3), (';', 3)].into_iter().find_map(|(delimiter, min_rows)| {
+        if lines.len() < min_rows {
+            return None;
+        }
+        let rows: Vec<Vec<String>> = lines.iter().map(|l| split_csv_line(l, delimiter)).collect();
+        let columns = rows[0].len();
+        (columns >= 2 && rows.iter().all(|r| r.len() == columns)).then_some(rows)
+    })
+}
+
+/// Markdown table from CSV rows, the first row as header
+fn as_table(rows: &[Vec<String>]) -> String {
+    let cell = |value: &str| value.trim().replace('|', "\\|");
+    let row = |fields: &[String]| format!("| {} |", fields.iter().map(|f| cell(f)).collect::<Vec<_>>().join(" | "));
+
+    let mut lines = vec![row(&rows[0]), format!("|{}", " --- |".repeat(rows[0].len()))];
+    lines.extend(rows[1..].iter().map(|r| row(r)));
+    lines.join("\n")
+}
+
+fn is_stack_trace(lines: &[&str]) -> bool {
+    let frames = lines
+        .iter()
+        .filter(|line| {
+            let trimmed = line.trim_start();
+            TRACE_MARKERS.iter().any(|m| trimmed.starts_with(m))
+        })
+        .count();
+    frames >= 2 && frames * 3 >= lines.len()
+}
+
+/// Whether a line reads like source code rather than prose
+fn is_code_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    CODE_STARTS.iter().any(|s| trimmed.starts_with(s))
+        || trimmed.ends_with(';')
+        || trimmed.ends_with('{')
+        || trimmed == "}"
+        || trimmed.ends_with("):")
+        || trimmed.contains("=>")
+        || trimmed.contains("->")
+        || trimmed.contains(" = ")
+}
+
+fn is_code(lines: &[&str]) -> bool {
+    let code_lines = lines.iter().filter(|l| is_code_line(l)).count();
+    let indented = lines.iter().filter(|l| l.starts_with("    ") || l.starts_with('\t')).count();
+    lines.len() >= 2 && code_lines * 2 >= lines.len() && (code_lines + indented) * 3 >= lines.len() * 2
+}
+
+/// Classify a paste and suggest how to format it
+pub fn analyze(text: &str) -> PasteSuggestion {
+    let trimmed = text.trim();
+    if trimmed.is_empty() || trimmed.len() > MAX_ANALYZE_BYTES {
+        return PasteSuggestion::plain();
+    }
+
+    if is_url(trimmed) {
+        return PasteSuggestion::new(PasteKind::Url, "Paste as link", as_link(trimmed));
+    }
+
+    if trimmed.starts_with(['{', '[']) {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(trimmed) {
+            if let Ok(pretty) = serde_json::to_string_pretty(&value) {
+                return PasteSuggestion::new(PasteKind::Json, "Paste as formatted JSON", fence(&pretty, "json"));
+            }
+        }
+    }
+
+    let lines: Vec<&str> = trimmed.lines().filter(|l| !l.trim().is_empty()).collect();
+    if is_stack_trace(&lines) {
+        return PasteSuggestion::new(PasteKind::StackTrace, "Paste as code block", fence(trimmed, "text"));
+    }
+    if is_code(&lines) {
+        let language = language::detect(trimmed).map(|g| g.language).unwrap_or_default();
+        let block = fence(text.trim_matches('\n'), &language);
+        return PasteSuggestion::new(PasteKind::Code, "Paste as code block", block);
+    }
+    if let Some(rows) = parse_csv(trimmed) {
+        return PasteSuggestion::new(PasteKind::Csv, "Paste as table", as_table(&rows));
+    }
+
+    PasteSuggestion::plain()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_becomes_a_link() {
+        let suggestion = analyze(" https://www.example.com/page/ ");
+        assert_eq!(suggestion.kind, PasteKind::Url);
+        assert_eq!(suggestion.formatted.unwrap(), "[example.com/page](<https://www.example.com/page/>)");
+    }
+
+    #[test]
+    fn json_is_pretty_printed() {
+        let suggestion = analyze(r#"{"a":1}"#);
+        assert_eq!(suggestion.kind, PasteKind::Json);
+        assert_eq!(suggestion.formatted.unwrap(), "```json\n{\n  \"a\": 1\n}\n```");
+    }
+
+    #[test]
+    fn stack_trace_is_fenced() {
+        let trace = "Traceback (most recent call last):\n  File \"app.py\", line 3, in <module>\nValueError: bad";
+        let suggestion = analyze(trace);
+        assert_eq!(suggestion.kind, PasteKind::StackTrace);
+        assert_eq!(suggestion.formatted.unwrap(), format!("```text\n{}\n```", trace));
+    }
+
+    #[test]
+    fn code_is_fenced_with_its_language() {
+        let suggestion = analyze("pub fn add(a: i32) -> i32 {\n    let mut b = a;\n    b\n}\n");
+        assert_eq!(suggestion.kind, PasteKind::Code);
+        assert!(suggestion.formatted.unwrap().starts_with("```rust\npub fn add"));
+    }
+
+    #[test]
+    fn csv_becomes_a_table() {
+        let suggestion = analyze("name,age\nann,3\n\"b|c\",4");
+        assert_eq!(suggestion.kind, PasteKind::Csv);
+        assert_eq!(
+            suggestion.formatted.unwrap(),
+            "| name | age |\n| --- | --- |\n| ann | 3 |\n| b\\|c | 4 |"
+        );
+    }
+
+    #[test]
+    fn prose_is_left_alone() {
+        let suggestion = analyze("Just a sentence, nothing more.\nAnd another one.");
+        assert_eq!(suggestion.kind, PasteKind::Text);
+        assert!(suggestion.formatted.is_none());
+    }
+
+    #[test]
+    fn fence_outgrows_backticks_in_the_text() {
+        assert_eq!(fence("a ``` b", ""), "````\na ``` b\n````");
+    }
+}
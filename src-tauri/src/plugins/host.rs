@@ -0,0 +1,168 @@
+// Host side of the plugin ABI: guest memory helpers and the capability-scoped host API
+
+use super::Capability;
+use crate::db::queries;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use tracing::{info, warn};
+use wasmi::{AsContext, AsContextMut, Caller, Extern, Linker, Memory, StoreLimits, TypedFunc};
+
+/// Module name plugins import host functions from
+pub const HOST_MODULE: &str = "flashnotes";
+
+/// Host call results: negative values are errors
+pub const ERR_DENIED: i32 = -1;
+pub const ERR_FAILED: i32 = -2;
+
+/// Largest string copied across the guest boundary (matches the buffer size limit)
+const MAX_TRANSFER_BYTES: usize = 10 * 1024 * 1024;
+
+/// Maximum buffer writes a plugin may request in one hook call
+const MAX_WRITES_PER_CALL: usize = 100;
+
+/// State available to host functions during one hook call
+pub struct HostState {
+    pub plugin: String,
+    pub capabilities: Vec<Capability>,
+    pub reader_pool: Pool<SqliteConnectionManager>,
+    /// Buffer writes requested during the call; the app applies them afterwards
+    pub writes: Vec<(String, String)>,
+    pub limits: StoreLimits,
+}
+
+impl HostState {
+    fn can(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
+/// Pack a guest (ptr, len) pair into one i64 (ptr in the high 32 bits)
+pub fn pack(ptr: i32, len: i32) -> i64 {
+    (((ptr as u32 as u64) << 32) | len as u32 as u64) as i64
+}
+
+/// Inverse of `pack`
+pub fn unpack(value: i64) -> (i32, i32) {
+    let value = value as u64;
+    ((value >> 32) as u32 as i32, value as u32 as i32)
+}
+
+/// Copy a UTF-8 string out of guest memory
+pub fn read_string(ctx: impl AsContext, memory: Memory, ptr: i32, len: i32) -> Option<String> {
+    let (ptr, len) = (usize::try_from(ptr).ok()?, usize::try_from(len).ok()?);
+    if len > MAX_TRANSFER_BYTES {
+        return None;
+    }
+    let mut bytes = vec![0; len];
+    memory.read(ctx, ptr, &mut bytes).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Copy bytes into memory obtained from the guest's `alloc` export.
+/// Returns the guest pointer.
+pub fn write_bytes(
+    mut ctx: impl AsContextMut,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    bytes: &[u8],
+) -> Option<i32> {
+    let len = i32::try_from(bytes.len()).ok()?;
+    let ptr = alloc.call(&mut ctx, len).ok()?;
+    memory.write(&mut ctx, usize::try_from(ptr).ok()?, bytes).ok()?;
+    Some(ptr)
+}
+
+/// The guest's `memory` and `alloc` exports, as seen from inside a host call
+fn guest_exports(caller: &Caller<'_, HostState>) -> Option<(Memory, TypedFunc<i32, i32>)> {
+    let memory = caller.get_export("memory").and_then(Extern::into_memory)?;
+    let alloc = caller
+        .get_export("alloc")
+        .and_then(Extern::into_func)?
+        .typed::<i32, i32>(caller)
+        .ok()?;
+    Some((memory, alloc))
+}
+
+/// Current content of a buffer, including writes made earlier in this call
+fn buffer_content(state: &HostState, id: &str) -> Option<String> {
+    if let Some((_, content)) = state.writes.iter().rev().find(|(write_id, _)| write_id == id) {
+        return Some(content.clone());
+    }
+
+    let conn = state.reader_pool.get().ok()?;
+    match queries::get_buffer_content(&conn, id) {
+        Ok(buffer) => buffer.map(|b| b.content),
+        Err(e) => {
+            warn!("Plugin {} failed to read buffer {}: {}", state.plugin, id, e);
+            None
+        }
+    }
+}
+
+/// Register the host API under `HOST_MODULE`:
+///
+/// - `log(ptr, len)`: write a message to the app log
+/// - `read_buffer(id_ptr, id_len) -> i64`: packed (ptr, len) of the content in
+///   guest memory; needs `read_buffers`
+/// - `write_buffer(id_ptr, id_len, ptr, len) -> i32`: replace a buffer's
+///   content once the hook returns; needs `write_buffers`
+pub fn link(linker: &mut Linker<HostState>) -> Result<(), wasmi::Error> {
+    linker.func_wrap(HOST_MODULE, "log", |caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+        let Some(memory) = caller.get_export("memory").and_then(Extern::into_memory) else {
+            return;
+        };
+        if let Some(message) = read_string(&caller, memory, ptr, len) {
+            info!("[plugin {}] {}", caller.data().plugin, message);
+        }
+    })?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "read_buffer",
+        |mut caller: Caller<'_, HostState>, id_ptr: i32, id_len: i32| -> i64 {
+            if !caller.data().can(Capability::ReadBuffers) {
+                return ERR_DENIED.into();
+            }
+            let Some((memory, alloc)) = guest_exports(&caller) else {
+                return ERR_FAILED.into();
+            };
+            let Some(content) = read_string(&caller, memory, id_ptr, id_len)
+                .and_then(|id| buffer_content(caller.data(), &id))
+            else {
+                return ERR_FAILED.into();
+            };
+
+            match write_bytes(&mut caller, memory, alloc, content.as_bytes()) {
+                Some(ptr) => pack(ptr, content.len() as i32),
+                None => ERR_FAILED.into(),
+            }
+        },
+    )?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "write_buffer",
+        |mut caller: Caller<'_, HostState>, id_ptr: i32, id_len: i32, ptr: i32, len: i32| -> i32 {
+            if !caller.data().can(Capability::WriteBuffers) {
+                return ERR_DENIED;
+            }
+            if caller.data().writes.len() >= MAX_WRITES_PER_CALL {
+                return ERR_FAILED;
+            }
+            let Some(memory) = caller.get_export("memory").and_then(Extern::into_memory) else {
+                return ERR_FAILED;
+            };
+            let (Some(id), Some(content)) = (
+                read_string(&caller, memory, id_ptr, id_len),
+                read_string(&caller, memory, ptr, len),
+            ) else {
+                return ERR_FAILED;
+            };
+
+            caller.data_mut().writes.push((id, content));
+            0
+        },
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,378 @@
+// WASM plugins: sandboxed modules that hook into buffer lifecycle events
+//
+// A plugin is `<plugins dir>/<name>.wasm`, optionally with a `<name>.json`
+// manifest granting capabilities (`{"capabilities": ["read_buffers"]}`).
+// Modules export `memory` and `alloc(len) -> ptr`, plus any of:
+//
+// - `on_create(ptr, len)` / `on_save(ptr, len)`: called with a JSON event
+//   `{"id": ..., "content": ...}` after a buffer is created/saved
+// - `on_search(ptr, len) -> i64`: transform a search query; returns a packed
+//   (ptr, len) of the new query, or 0 to leave it unchanged
+//
+// Each call gets a fresh instance with bounded fuel and memory, and can only
+// reach the app through the host API in `host`.
+
+pub mod host;
+
+use crate::commands::buffer::validate_buffer_size;
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::queries;
+use crate::error::{AppError, AppResult};
+use crate::state::AppState;
+use chrono::Utc;
+use host::HostState;
+use parking_lot::RwLock;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+use wasmi::{Config, Engine, Instance, Linker, Module, Store, StoreLimitsBuilder, WasmResults};
+
+/// Event emitted after plugins changed buffers
+pub const PLUGIN_BUFFERS_CHANGED_EVENT: &str = "plugin-buffers-changed";
+
+/// Largest accepted module file
+const MAX_MODULE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Largest linear memory a plugin instance may grow to
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Fuel (roughly, instructions) per hook call, so a runaway plugin can't hang the app
+const FUEL_PER_CALL: u64 = 100_000_000;
+
+/// What a plugin is allowed to do through the host API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    ReadBuffers,
+    WriteBuffers,
+}
+
+/// Lifecycle hooks a plugin can export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Hook {
+    Create,
+    Save,
+    Search,
+}
+
+impl Hook {
+    const ALL: [Hook; 3] = [Hook::Create, Hook::Save, Hook::Search];
+
+    fn export_name(self) -> &'static str {
+        match self {
+            Hook::Create => "on_create",
+            Hook::Save => "on_save",
+            Hook::Search => "on_search",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    capabilities: Vec<Capability>,
+}
+
+struct Plugin {
+    name: String,
+    module: Module,
+    capabilities: Vec<Capability>,
+    hooks: Vec<Hook>,
+}
+
+/// A loaded plugin as shown in settings
+#[derive(Debug, Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub capabilities: Vec<Capability>,
+    pub hooks: Vec<Hook>,
+}
+
+/// Event passed to `on_create`/`on_save`
+#[derive(Serialize)]
+struct BufferEvent<'a> {
+    id: &'a str,
+    content: &'a str,
+}
+
+#[derive(Clone, Serialize)]
+struct BuffersChangedPayload {
+    ids: Vec<String>,
+}
+
+/// Directory plugins are loaded from
+pub fn plugins_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("plugins")
+}
+
+fn plugin_error(name: &str, e: impl Display) -> AppError {
+    AppError::Internal(format!("Plugin {}: {}", name, e))
+}
+
+/// Loaded plugins and the engine that runs them
+pub struct PluginHost {
+    engine: Engine,
+    plugins: RwLock<Vec<Plugin>>,
+}
+
+impl PluginHost {
+    pub fn new() -> Self {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        Self {
+            engine: Engine::new(&config),
+            plugins: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// (Re)load every plugin in `dir`. Plugins that fail to load are skipped.
+    /// Returns the number loaded.
+    pub fn load(&self, dir: &Path) -> usize {
+        let mut plugins = Vec::new();
+        let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "wasm"))
+                .collect(),
+            // No plugins directory means no plugins
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+
+        for path in paths {
+            match self.load_plugin(&path) {
+                Ok(plugin) => {
+                    info!(
+                        "Loaded plugin {} (hooks: {:?}, capabilities: {:?})",
+                        plugin.name, plugin.hooks, plugin.capabilities
+                    );
+                    plugins.push(plugin);
+                }
+                Err(e) => warn!("Skipping plugin {:?}: {}", path, e),
+            }
+        }
+
+        let count = plugins.len();
+        *self.plugins.write() = plugins;
+        count
+    }
+
+    fn load_plugin(&self, path: &Path) -> AppResult<Plugin> {
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let size = fs::metadata(path).map_err(|e| AppError::io("Failed to read plugin", e))?.len();
+        if size > MAX_MODULE_BYTES {
+            return Err(AppError::Validation(format!("Plugin {} is too large", name)));
+        }
+        let bytes = fs::read(path).map_err(|e| AppError::io("Failed to read plugin", e))?;
+        let module = Module::new(&self.engine, &bytes[..]).map_err(|e| plugin_error(&name, e))?;
+
+        let manifest_path = path.with_extension("json");
+        let manifest: Manifest = if manifest_path.exists() {
+            let text = fs::read_to_string(&manifest_path)
+                .map_err(|e| AppError::io("Failed to read plugin manifest", e))?;
+            serde_json::from_str(&text)
+                .map_err(|e| AppError::Validation(format!("Invalid manifest for plugin {}: {}", name, e)))?
+        } else {
+            Manifest::default()
+        };
+
+        let exports: Vec<&str> = module.exports().map(|e| e.name()).collect();
+        let hooks = Hook::ALL
+            .into_iter()
+            .filter(|hook| exports.contains(&hook.export_name()))
+            .collect();
+
+        Ok(Plugin {
+            name,
+            module,
+            capabilities: manifest.capabilities,
+            hooks,
+        })
+    }
+
+    /// Loaded plugins, in load order
+    pub fn list(&self) -> Vec<PluginInfo> {
+        self.plugins
+            .read()
+            .iter()
+            .map(|p| PluginInfo {
+                name: p.name.clone(),
+                capabilities: p.capabilities.clone(),
+                hooks: p.hooks.clone(),
+            })
+            .collect()
+    }
+
+    fn has_hook(&self, hook: Hook) -> bool {
+        self.plugins.read().iter().any(|p| p.hooks.contains(&hook))
+    }
+
+    /// Fresh sandboxed instance of a plugin for a single call
+    fn instantiate(
+        &self,
+        plugin: &Plugin,
+        reader_pool: &Pool<SqliteConnectionManager>,
+    ) -> AppResult<(Store<HostState>, Instance)> {
+        let state = HostState {
+            plugin: plugin.name.clone(),
+            capabilities: plugin.capabilities.clone(),
+            reader_pool: reader_pool.clone(),
+            writes: Vec::new(),
+            limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).build(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.add_fuel(FUEL_PER_CALL).map_err(|e| plugin_error(&plugin.name, e))?;
+
+        let mut linker = Linker::new(&self.engine);
+        host::link(&mut linker).map_err(|e| plugin_error(&plugin.name, e))?;
+        let instance = linker
+            .instantiate(&mut store, &plugin.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| plugin_error(&plugin.name, e))?;
+        Ok((store, instance))
+    }
+
+    /// Copy `input` into a fresh instance and call `hook` with it.
+    /// Returns the instance (for reading results) along with the hook's return value.
+    fn call<R: WasmResults>(
+        &self,
+        plugin: &Plugin,
+        reader_pool: &Pool<SqliteConnectionManager>,
+        hook: Hook,
+        input: &str,
+    ) -> AppResult<(Store<HostState>, Instance, R)> {
+        let (mut store, instance) = self.instantiate(plugin, reader_pool)?;
+
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or_else(|| plugin_error(&plugin.name, "missing `memory` export"))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|e| plugin_error(&plugin.name, e))?;
+        let func = instance
+            .get_typed_func::<(i32, i32), R>(&store, hook.export_name())
+            .map_err(|e| plugin_error(&plugin.name, e))?;
+
+        let ptr = host::write_bytes(&mut store, memory, alloc, input.as_bytes())
+            .ok_or_else(|| plugin_error(&plugin.name, "failed to pass input"))?;
+        let result = func
+            .call(&mut store, (ptr, input.len() as i32))
+            .map_err(|e| plugin_error(&plugin.name, e))?;
+        Ok((store, instance, result))
+    }
+
+    /// Run a create/save hook in every plugin exporting it.
+    /// Returns the buffer writes the plugins requested, in order.
+    fn notify(
+        &self,
+        hook: Hook,
+        reader_pool: &Pool<SqliteConnectionManager>,
+        id: &str,
+        content: &str,
+    ) -> Vec<(String, String)> {
+        let event = match serde_json::to_string(&BufferEvent { id, content }) {
+            Ok(event) => event,
+            Err(e) => {
+                warn!("Failed to encode {} event: {}", hook.export_name(), e);
+                return Vec::new();
+            }
+        };
+        let mut writes = Vec::new();
+
+        for plugin in self.plugins.read().iter().filter(|p| p.hooks.contains(&hook)) {
+            match self.call::<()>(plugin, reader_pool, hook, &event) {
+                Ok((mut store, _, ())) => writes.append(&mut store.data_mut().writes),
+                Err(e) => warn!("{} hook failed: {}", hook.export_name(), e),
+            }
+        }
+
+        writes
+    }
+
+    /// Pass a search query through every plugin's `on_search`, in load order
+    pub fn transform_search(&self, reader_pool: &Pool<SqliteConnectionManager>, query: &str) -> String {
+        let mut query = query.to_string();
+        if !self.has_hook(Hook::Search) {
+            return query;
+        }
+
+        for plugin in self.plugins.read().iter().filter(|p| p.hooks.contains(&Hook::Search)) {
+            let transformed = self
+                .call::<i64>(plugin, reader_pool, Hook::Search, &query)
+                .and_then(|(store, instance, packed)| {
+                    if packed == 0 {
+                        return Ok(None);
+                    }
+                    let (ptr, len) = host::unpack(packed);
+                    instance
+                        .get_memory(&store, "memory")
+                        .and_then(|memory| host::read_string(&store, memory, ptr, len))
+                        .map(Some)
+                        .ok_or_else(|| plugin_error(&plugin.name, "invalid search query returned"))
+                });
+
+            match transformed {
+                Ok(Some(transformed)) => query = transformed,
+                Ok(None) => {}
+                Err(e) => warn!("on_search hook failed: {}", e),
+            }
+        }
+
+        query
+    }
+}
+
+/// Run a buffer lifecycle hook, apply any writes the plugins made and tell
+/// the frontend which buffers changed. Plugin failures are logged, never
+/// surfaced to the command that triggered the hook.
+pub fn run_buffer_hook(app: &AppHandle, state: &AppState, hook: Hook, id: &str, content: &str) {
+    if !state.plugins.has_hook(hook) {
+        return;
+    }
+
+    let writes = state.plugins.notify(hook, &state.reader_pool(), id, content);
+    if writes.is_empty() {
+        return;
+    }
+
+    let conn = state.writer.lock();
+    let timestamp = Utc::now().timestamp();
+    let mut ids: Vec<String> = Vec::new();
+    for (write_id, new_content) in writes {
+        if validate_buffer_size(&new_content).is_err() {
+            warn!("Plugin write to {} rejected: content too large", write_id);
+            continue;
+        }
+        match queries::update_buffer_content(&conn, &write_id, &new_content, timestamp) {
+            Ok(true) => {
+                activity::record(&conn, Some(&write_id), Operation::Save, Origin::Plugin, None);
+                if !ids.contains(&write_id) {
+                    ids.push(write_id);
+                }
+            }
+            Ok(false) => warn!("Plugin write to unknown buffer {}", write_id),
+            Err(e) => warn!("Failed to apply plugin write to {}: {}", write_id, e),
+        }
+    }
+    drop(conn);
+
+    if ids.is_empty() {
+        return;
+    }
+    info!("Plugins updated {} buffers", ids.len());
+    if let Err(e) = app.emit(PLUGIN_BUFFERS_CHANGED_EVENT, BuffersChangedPayload { ids }) {
+        warn!("Failed to emit {}: {}", PLUGIN_BUFFERS_CHANGED_EVENT, e);
+    }
+}
@@ -0,0 +1,90 @@
+// Progress reporting for long-running operations (imports, exports,
+// reindexing, backups) over one event channel the frontend can listen to
+
+use crate::error::AppResult;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Progress of a long-running task
+pub const TASK_PROGRESS_EVENT: &str = "task-progress";
+
+/// Phase of a finished task
+const PHASE_DONE: &str = "done";
+
+/// Phase of a task that stopped with an error (the message has the error)
+const PHASE_FAILED: &str = "failed";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskProgress {
+    pub task_id: String,
+    /// What is running: "import", "export", "search_index", "embeddings", "backup", ...
+    pub kind: &'static str,
+    /// Current step ("reading", "writing", ...), then "done" or "failed"
+    pub phase: &'static str,
+    pub percent: u8,
+    pub message: Option<String>,
+}
+
+/// Reports one task's progress. Updates are only emitted when the phase or
+/// whole percentage changes, so callers can report every item.
+pub struct Progress {
+    app: AppHandle,
+    task_id: String,
+    kind: &'static str,
+    last: Option<(&'static str, u8)>,
+}
+
+impl Progress {
+    pub fn new(app: &AppHandle, kind: &'static str) -> Self {
+        Self {
+            app: app.clone(),
+            task_id: Uuid::new_v4().to_string(),
+            kind,
+            last: None,
+        }
+    }
+
+    fn emit(&mut self, phase: &'static str, percent: u8, message: Option<String>) {
+        self.last = Some((phase, percent));
+        let payload = TaskProgress {
+            task_id: self.task_id.clone(),
+            kind: self.kind,
+            phase,
+            percent,
+            message,
+        };
+        if let Err(e) = self.app.emit(TASK_PROGRESS_EVENT, payload) {
+            warn!("Failed to emit {} event: {}", TASK_PROGRESS_EVENT, e);
+        }
+    }
+
+    /// `done` of `total` items finished in `phase`
+    pub fn update(&mut self, phase: &'static str, done: usize, total: usize) {
+        let percent = (done.min(total) * 100).checked_div(total).map_or(100, |p| p as u8);
+        if self.last != Some((phase, percent)) {
+            self.emit(phase, percent, None);
+        }
+    }
+
+    /// Enter `phase` with a message, e.g. for a step without item counts
+    pub fn step(&mut self, phase: &'static str, message: impl Into<String>) {
+        let percent = self.last.map_or(0, |(_, percent)| percent);
+        self.emit(phase, percent, Some(message.into()));
+    }
+}
+
+/// Run a task, reporting "done" or "failed" when it ends
+pub fn run<T>(app: &AppHandle, kind: &'static str, task: impl FnOnce(&mut Progress) -> AppResult<T>) -> AppResult<T> {
+    let mut progress = Progress::new(app, kind);
+    let result = task(&mut progress);
+    match &result {
+        Ok(_) => progress.emit(PHASE_DONE, 100, None),
+        Err(e) => {
+            let percent = progress.last.map_or(0, |(_, percent)| percent);
+            progress.emit(PHASE_FAILED, percent, Some(e.to_string()));
+        }
+    }
+    result
+}
@@ -0,0 +1,54 @@
+// QR codes for moving a note to a phone: short notes are encoded as text,
+// longer ones as a temporary share link (see `lan_share`).
+
+use crate::error::{AppError, AppResult};
+use crate::lan_share::ShareLink;
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+use serde::Serialize;
+
+/// Notes up to this many bytes are encoded directly. Denser codes exist
+/// (up to ~2.9 KB) but phone cameras struggle to read them.
+pub const MAX_INLINE_BYTES: usize = 1200;
+
+/// Lifetime of the share link used for notes too large to inline
+pub const LINK_TTL_SECS: u64 = 15 * 60;
+
+/// Smallest rendered size in pixels
+const MIN_SIZE: u32 = 256;
+
+/// What the QR code contains
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QrPayload {
+    Text,
+    Link,
+}
+
+/// A rendered QR code for a note
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteQr {
+    /// SVG markup of the code
+    pub svg: String,
+    pub payload: QrPayload,
+    /// The link encoded in the code when `payload` is `link`
+    pub share: Option<ShareLink>,
+}
+
+/// Whether a note is small enough to encode as text
+pub fn fits_inline(content: &str) -> bool {
+    content.len() <= MAX_INLINE_BYTES
+}
+
+/// Render `data` as an SVG QR code
+pub fn render_svg(data: &str) -> AppResult<String> {
+    let code = QrCode::with_error_correction_level(data, EcLevel::L)
+        .map_err(|e| AppError::Validation(format!("Failed to encode QR code: {}", e)))?;
+
+    Ok(code
+        .render::<svg::Color>()
+        .min_dimensions(MIN_SIZE, MIN_SIZE)
+        .dark_color(svg::Color("#000000"))
+        .light_color(svg::Color("#ffffff"))
+        .build())
+}
@@ -0,0 +1,129 @@
+// "See also" suggestions: notes scored by embedding similarity, shared
+// #tags and how close they are in the wiki link graph
+
+use crate::db::queries::{self, Buffer};
+use crate::lint;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Weight of each signal in the combined score (each signal is 0..=1)
+const SEMANTIC_WEIGHT: f64 = 0.5;
+const TAG_WEIGHT: f64 = 0.25;
+const LINK_WEIGHT: f64 = 0.25;
+
+/// Link proximity for a direct link (either direction) and for notes two
+/// links apart
+const DIRECT_LINK: f64 = 1.0;
+const TWO_HOPS: f64 = 0.5;
+
+/// A suggested note and why it was suggested
+#[derive(Debug, Serialize)]
+pub struct RelatedNote {
+    pub id: String,
+    pub title: String,
+    pub score: f64,
+    /// Cosine similarity, when both notes are embedded
+    pub semantic_score: Option<f32>,
+    pub shared_tags: Vec<String>,
+    /// 1 when the notes link to each other, 2 when a note sits between them
+    pub link_distance: Option<u8>,
+}
+
+/// Slugs of the notes a buffer links to, outside code fences
+fn link_slugs(content: &str) -> HashSet<String> {
+    let mut slugs = HashSet::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if !in_fence {
+            slugs.extend(lint::wiki_link_targets(line).into_iter().map(queries::slugify));
+        }
+    }
+    slugs
+}
+
+/// Up to `limit` notes related to `target`, best first. `vectors` holds the
+/// normalized embeddings of notes that have one. Notes with no signal at
+/// all are never suggested.
+pub fn rank(
+    target: &Buffer,
+    buffers: &[Buffer],
+    vectors: &HashMap<String, Vec<f32>>,
+    limit: usize,
+) -> Vec<RelatedNote> {
+    let ids_by_slug: HashMap<&str, &str> = buffers.iter().map(|b| (b.slug.as_str(), b.id.as_str())).collect();
+
+    // Undirected link graph between the given notes
+    let mut neighbors: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for buffer in buffers {
+        for slug in link_slugs(&buffer.content) {
+            if let Some(&linked) = ids_by_slug.get(slug.as_str()) {
+                if linked != buffer.id {
+                    neighbors.entry(buffer.id.as_str()).or_default().insert(linked);
+                    neighbors.entry(linked).or_default().insert(buffer.id.as_str());
+                }
+            }
+        }
+    }
+    let empty = HashSet::new();
+    let direct = neighbors.get(target.id.as_str()).unwrap_or(&empty);
+    let two_hops: HashSet<&str> = direct
+        .iter()
+        .flat_map(|id| neighbors.get(id).into_iter().flatten().copied())
+        .collect();
+
+    let target_tags: HashSet<String> = queries::extract_hashtags(&target.content).into_iter().collect();
+    let target_vector = vectors.get(&target.id);
+
+    let mut related: Vec<RelatedNote> = buffers
+        .iter()
+        .filter(|b| b.id != target.id)
+        .filter_map(|buffer| {
+            let semantic_score = target_vector
+                .zip(vectors.get(&buffer.id))
+                .filter(|(a, b)| a.len() == b.len())
+                .map(|(a, b)| a.iter().zip(b.iter()).map(|(x, y)| x * y).sum::<f32>());
+
+            let tags: HashSet<String> = queries::extract_hashtags(&buffer.content).into_iter().collect();
+            let mut shared_tags: Vec<String> = target_tags.intersection(&tags).cloned().collect();
+            shared_tags.sort();
+            let union = target_tags.union(&tags).count();
+            let tag_score = if union == 0 { 0.0 } else { shared_tags.len() as f64 / union as f64 };
+
+            let link_distance = if direct.contains(buffer.id.as_str()) {
+                Some(1)
+            } else if two_hops.contains(buffer.id.as_str()) {
+                Some(2)
+            } else {
+                None
+            };
+            let link_score = match link_distance {
+                Some(1) => DIRECT_LINK,
+                Some(_) => TWO_HOPS,
+                None => 0.0,
+            };
+
+            let semantic = f64::from(semantic_score.unwrap_or(0.0).max(0.0));
+            let score = SEMANTIC_WEIGHT * semantic + TAG_WEIGHT * tag_score + LINK_WEIGHT * link_score;
+            (score > 0.0).then(|| RelatedNote {
+                id: buffer.id.clone(),
+                title: buffer
+                    .title_override
+                    .clone()
+                    .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0),
+                score,
+                semantic_score,
+                shared_tags,
+                link_distance,
+            })
+        })
+        .collect();
+
+    related.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+    related.truncate(limit);
+    related
+}
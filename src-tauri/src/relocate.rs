@@ -0,0 +1,93 @@
+// Moving the database to a custom folder (e.g. a synced folder) and back.
+// The live database is copied with VACUUM INTO, the copy is checked, and
+// only then do the connections switch over; the old file is renamed along
+// with its WAL so it can't be mistaken for the live one. The chosen folder is
+// remembered in a file in the app data directory (see `connection::custom_db_dir`).
+
+use crate::db::{backup, connection};
+use crate::encryption;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::AppState;
+use chrono::Utc;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Path of the open database
+pub fn current_path(state: &AppState) -> AppResult<PathBuf> {
+    state
+        .writer
+        .lock()
+        .path()
+        .map(PathBuf::from)
+        .ok_or_else(|| AppError::Internal("Database has no file path".to_string()))
+}
+
+/// Move the database into `dir`, or back into the app data directory with None.
+/// Returns the new database path.
+pub fn move_database(state: &AppState, dir: Option<&Path>) -> AppResult<PathBuf> {
+    if let Some(dir) = dir {
+        if !dir.is_absolute() || !dir.is_dir() {
+            return Err(AppError::Validation(format!(
+                "Database folder must be an existing directory: {}",
+                dir.display()
+            )));
+        }
+    }
+    let target = dir.unwrap_or(&state.app_data_dir).join(connection::DB_FILE_NAME);
+
+    let mut writer = state.writer.lock();
+    let current = writer
+        .path()
+        .map(PathBuf::from)
+        .ok_or_else(|| AppError::Internal("Database has no file path".to_string()))?;
+    let same_file = match (fs::canonicalize(&current), target.parent().map(fs::canonicalize)) {
+        (Ok(current), Some(Ok(target_dir))) => current == target_dir.join(connection::DB_FILE_NAME),
+        _ => current == target,
+    };
+    if same_file {
+        return Ok(current);
+    }
+    if target.exists() {
+        return Err(AppError::Conflict(format!("A database already exists at {}", target.display())));
+    }
+
+    // Copy through SQLite so the copy is consistent, WAL contents included
+    let target_str = target.to_string_lossy().replace('\'', "''");
+    writer
+        .execute(&format!("VACUUM INTO '{}'", target_str), [])
+        .db_context("Failed to copy database")?;
+    // The copy of an encrypted database keeps its key
+    let key = state.db_key.get();
+    let switched = backup::validate_backup(&target, key.as_deref())
+        .map_err(AppError::Internal)
+        .and_then(|()| {
+            let new_writer =
+                connection::create_connection(&target, key.as_deref()).db_context("Failed to open moved database")?;
+            let new_pool = connection::create_reader_pool(&target, key.as_deref())
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+            connection::set_custom_db_dir(&state.app_data_dir, dir)
+                .map_err(|e| AppError::io("Failed to save database location", e))?;
+            Ok((new_writer, new_pool))
+        });
+    let (new_writer, new_pool) = match switched {
+        Ok(connections) => connections,
+        Err(e) => {
+            let _ = fs::remove_file(&target);
+            return Err(e);
+        }
+    };
+
+    // Dropping the old connections closes the old file, once running reads finish
+    *writer = new_writer;
+    encryption::drain(state.replace_reader_pool(new_pool));
+    drop(writer);
+    info!("Moved database from {:?} to {:?}", current, target);
+
+    let moved = current.with_file_name(format!("flashnotes.moved_{}.db", Utc::now().timestamp()));
+    match connection::rename_database(&current, &moved) {
+        Ok(()) => info!("Kept previous database file as {:?}", moved),
+        Err(e) => warn!("Failed to rename previous database file {:?}: {}", current, e),
+    }
+    Ok(target)
+}
@@ -0,0 +1,184 @@
+use crate::db::queries;
+use crate::debounce::Debouncer;
+use crate::state::AppState;
+use parking_lot::Mutex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tracing::{debug, info, warn};
+
+/// Setting key holding the script path (empty disables the hook)
+pub const SAVE_HOOK_SETTING: &str = "save_hook_script";
+
+/// Quiet period after the last save of a buffer before the script runs
+const DEBOUNCE: Duration = Duration::from_secs(3);
+
+/// Scripts running longer than this are killed
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often a running script is polled for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Opt-in user script run after saves.
+/// Saves to the same buffer within `DEBOUNCE` collapse into one run. The
+/// script gets the buffer exported to a temp file plus metadata in
+/// `FLASHNOTES_*` environment variables.
+pub struct SaveHook {
+    script: Mutex<Option<PathBuf>>,
+    pending: Debouncer,
+}
+
+impl SaveHook {
+    pub fn new() -> Self {
+        Self {
+            script: Mutex::new(None),
+            pending: Debouncer::new(),
+        }
+    }
+
+    /// Apply the `save_hook_script` setting
+    pub fn configure(&self, script: &str) {
+        let script = script.trim();
+        *self.script.lock() = (!script.is_empty()).then(|| PathBuf::from(script));
+    }
+
+    fn script(&self) -> Option<PathBuf> {
+        self.script.lock().clone()
+    }
+}
+
+impl Default for SaveHook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check a script path before it is saved as the hook
+pub fn validate_script(value: &str) -> Result<(), String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(());
+    }
+
+    let path = Path::new(value);
+    if !path.is_absolute() {
+        return Err(format!("Save hook script must be an absolute path: {}", value));
+    }
+    if !path.is_file() {
+        return Err(format!("Save hook script not found: {}", value));
+    }
+    Ok(())
+}
+
+/// Schedule the save hook for a buffer that was just saved (no-op when disabled)
+pub fn schedule(app: &AppHandle, id: &str) {
+    let state = app.state::<AppState>();
+    if state.save_hook.script().is_none() {
+        return;
+    }
+
+    let generation = state.save_hook.pending.bump(id);
+
+    let app = app.clone();
+    let id = id.to_string();
+    let spawned = thread::Builder::new().name("save-hook".to_string()).spawn(move || {
+        thread::sleep(DEBOUNCE);
+
+        // A newer save reschedules the hook; only the last one runs
+        let state = app.state::<AppState>();
+        if !state.save_hook.pending.take_if_latest(&id, generation) {
+            return;
+        }
+
+        if let Some(script) = state.save_hook.script() {
+            run(&state, &script, &id);
+        }
+    });
+
+    if let Err(e) = spawned {
+        warn!("Failed to schedule save hook: {}", e);
+    }
+}
+
+/// Export the buffer to a temp file and run the script on it
+fn run(state: &AppState, script: &Path, id: &str) {
+    let buffer = match state.get_reader() {
+        Ok(conn) => queries::get_buffer_content(&conn, id),
+        Err(_) => {
+            let conn = state.writer.lock();
+            queries::get_buffer_content(&conn, id)
+        }
+    };
+    let buffer = match buffer {
+        Ok(Some(buffer)) => buffer,
+        // Deleted since it was saved
+        Ok(None) => return,
+        Err(e) => {
+            warn!("Save hook: failed to load buffer {}: {}", id, e);
+            return;
+        }
+    };
+
+    let file = std::env::temp_dir().join(format!("flashnotes-{}.md", id));
+    if let Err(e) = fs::write(&file, &buffer.content) {
+        warn!("Save hook: failed to write {:?}: {}", file, e);
+        return;
+    }
+
+    let title = buffer
+        .title_override
+        .clone()
+        .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+    let child = Command::new(script)
+        .env("FLASHNOTES_EVENT", "save")
+        .env("FLASHNOTES_BUFFER_ID", &buffer.id)
+        .env("FLASHNOTES_BUFFER_FILE", &file)
+        .env("FLASHNOTES_TITLE", &title)
+        .env("FLASHNOTES_SLUG", &buffer.slug)
+        .env("FLASHNOTES_SOURCE", &buffer.source)
+        .env("FLASHNOTES_CREATED_AT", buffer.created_at.to_string())
+        .env("FLASHNOTES_UPDATED_AT", buffer.updated_at.to_string())
+        .env("FLASHNOTES_PINNED", buffer.is_pinned.to_string())
+        .env("FLASHNOTES_WORD_COUNT", buffer.word_count.to_string())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    match child {
+        Ok(mut child) => {
+            let started = Instant::now();
+            loop {
+                match child.try_wait() {
+                    Ok(Some(status)) if status.success() => {
+                        info!("Ran save hook for {}", id);
+                        break;
+                    }
+                    Ok(Some(status)) => {
+                        warn!("Save hook exited with {} for {}", status, id);
+                        break;
+                    }
+                    Ok(None) if started.elapsed() >= SCRIPT_TIMEOUT => {
+                        warn!("Save hook timed out after {:?}, killing it", SCRIPT_TIMEOUT);
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        break;
+                    }
+                    Ok(None) => thread::sleep(POLL_INTERVAL),
+                    Err(e) => {
+                        warn!("Save hook: failed to wait for script: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+        Err(e) => warn!("Save hook: failed to run {:?}: {}", script, e),
+    }
+
+    if let Err(e) = fs::remove_file(&file) {
+        debug!("Save hook: failed to remove {:?}: {}", file, e);
+    }
+}
@@ -0,0 +1,319 @@
+// User automation scripts (Rhai) with a sandboxed API over buffers
+//
+// Scripts can read notes and queue changes; nothing is written until the
+// script finishes, then all changes are applied in one transaction (or none
+// are, if any fails). Available functions:
+//
+// - `notes()`: every note as a map (id, title, content, slug, source,
+//   created_at, updated_at, pinned, archived)
+// - `note(id)`: one note, or `()` if it doesn't exist
+// - `search(query)`: full-text search hits (id, snippet, updated_at)
+// - `create_note(content)`: returns the new note's id
+// - `set_content(id, content)`, `archive(id)`, `unarchive(id)`
+// - `now()`: current Unix time; `days(n)`: n days in seconds
+//
+// `print` output is collected and returned with the run report.
+
+use crate::commands::buffer::validate_buffer_size;
+use crate::db::activity::{self, NewEntry, Operation, Origin};
+use crate::db::queries::{self, Buffer};
+use crate::db::scripts::{self as db_scripts, Script};
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::AppState;
+use chrono::Utc;
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Event emitted after a script changed buffers
+pub const SCRIPT_BUFFERS_CHANGED_EVENT: &str = "script-buffers-changed";
+
+/// Operation budget per run, so a runaway loop can't hang the app
+const MAX_OPERATIONS: u64 = 50_000_000;
+
+/// Maximum changes a single run may queue
+const MAX_CHANGES: usize = 10_000;
+
+/// Maximum `print` lines kept per run
+const MAX_OUTPUT_LINES: usize = 1_000;
+
+/// Maximum search hits returned to a script
+const SEARCH_LIMIT: usize = 100;
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+type RhaiResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// A change queued by a script
+enum Change {
+    Create { id: String, content: String },
+    SetContent { id: String, content: String },
+    SetArchived { id: String, archived: bool },
+}
+
+struct ScriptContext {
+    reader_pool: Pool<SqliteConnectionManager>,
+    changes: Vec<Change>,
+    output: Vec<String>,
+}
+
+impl ScriptContext {
+    fn reader(&self) -> RhaiResult<PooledConnection<SqliteConnectionManager>> {
+        self.reader_pool.get().map_err(|e| e.to_string().into())
+    }
+
+    fn queue(&mut self, change: Change) -> RhaiResult<()> {
+        if self.changes.len() >= MAX_CHANGES {
+            return Err(format!("Too many changes (limit {})", MAX_CHANGES).into());
+        }
+        self.changes.push(change);
+        Ok(())
+    }
+}
+
+/// Outcome of a script run
+#[derive(Debug, Serialize)]
+pub struct ScriptReport {
+    /// Lines written with `print`
+    pub output: Vec<String>,
+    /// The script's final value, if it produced one
+    pub result: Option<String>,
+    /// Buffers created or changed, in order
+    pub changed_ids: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct BuffersChangedPayload {
+    ids: Vec<String>,
+}
+
+fn note_to_map(buffer: Buffer) -> Map {
+    let title = buffer
+        .title_override
+        .clone()
+        .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+
+    let mut map = Map::new();
+    map.insert("id".into(), buffer.id.into());
+    map.insert("title".into(), title.into());
+    map.insert("content".into(), buffer.content.into());
+    map.insert("slug".into(), buffer.slug.into());
+    map.insert("source".into(), buffer.source.into());
+    map.insert("created_at".into(), buffer.created_at.into());
+    map.insert("updated_at".into(), buffer.updated_at.into());
+    map.insert("pinned".into(), buffer.is_pinned.into());
+    map.insert("archived".into(), buffer.is_archived.into());
+    map
+}
+
+/// Sandboxed engine with the buffer API bound to `ctx`
+fn build_engine(ctx: &Rc<RefCell<ScriptContext>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(64);
+    engine.set_max_expr_depths(64, 64);
+    engine.set_max_string_size(10 * 1024 * 1024);
+    engine.set_max_array_size(100_000);
+    engine.set_max_map_size(10_000);
+
+    let c = ctx.clone();
+    engine.on_print(move |line| {
+        let mut ctx = c.borrow_mut();
+        if ctx.output.len() < MAX_OUTPUT_LINES {
+            ctx.output.push(line.to_string());
+        }
+    });
+
+    let c = ctx.clone();
+    engine.register_fn("notes", move || -> RhaiResult<Array> {
+        let conn = c.borrow().reader()?;
+        let buffers = queries::get_all_buffers(&conn).map_err(|e| e.to_string())?;
+        Ok(buffers.into_iter().map(|b| Dynamic::from_map(note_to_map(b))).collect())
+    });
+
+    let c = ctx.clone();
+    engine.register_fn("note", move |id: &str| -> RhaiResult<Dynamic> {
+        let conn = c.borrow().reader()?;
+        let buffer = queries::get_buffer_content(&conn, id).map_err(|e| e.to_string())?;
+        Ok(buffer.map_or(Dynamic::UNIT, |b| Dynamic::from_map(note_to_map(b))))
+    });
+
+    let c = ctx.clone();
+    engine.register_fn("search", move |query: &str| -> RhaiResult<Array> {
+        let Some(query) = queries::sanitize_search_query(query) else {
+            return Ok(Array::new());
+        };
+        let conn = c.borrow().reader()?;
+        let results = queries::search_buffers(&conn, &query, None, SEARCH_LIMIT).map_err(|e| e.to_string())?;
+        Ok(results
+            .into_iter()
+            .map(|r| {
+                let mut map = Map::new();
+                map.insert("id".into(), r.id.into());
+                map.insert("snippet".into(), r.snippet.into());
+                map.insert("updated_at".into(), r.updated_at.into());
+                Dynamic::from_map(map)
+            })
+            .collect())
+    });
+
+    let c = ctx.clone();
+    engine.register_fn("create_note", move |content: &str| -> RhaiResult<String> {
+        let id = Uuid::new_v4().to_string();
+        c.borrow_mut().queue(Change::Create {
+            id: id.clone(),
+            content: content.to_string(),
+        })?;
+        Ok(id)
+    });
+
+    let c = ctx.clone();
+    engine.register_fn("set_content", move |id: &str, content: &str| -> RhaiResult<()> {
+        c.borrow_mut().queue(Change::SetContent {
+            id: id.to_string(),
+            content: content.to_string(),
+        })
+    });
+
+    let c = ctx.clone();
+    engine.register_fn("archive", move |id: &str| -> RhaiResult<()> {
+        c.borrow_mut().queue(Change::SetArchived { id: id.to_string(), archived: true })
+    });
+
+    let c = ctx.clone();
+    engine.register_fn("unarchive", move |id: &str| -> RhaiResult<()> {
+        c.borrow_mut().queue(Change::SetArchived { id: id.to_string(), archived: false })
+    });
+
+    engine.register_fn("now", || Utc::now().timestamp());
+    engine.register_fn("days", |n: i64| n * DAY_SECS);
+
+    engine
+}
+
+/// Check that a script parses
+pub fn validate_source(source: &str) -> AppResult<()> {
+    Engine::new()
+        .compile(source)
+        .map(|_| ())
+        .map_err(|e| AppError::Validation(format!("Script error: {}", e)))
+}
+
+/// Apply queued changes in one transaction. Returns the ids touched.
+fn apply_changes(state: &AppState, script: &Script, changes: &[Change]) -> AppResult<Vec<String>> {
+    let mut ids: Vec<String> = Vec::new();
+    if changes.is_empty() {
+        return Ok(ids);
+    }
+
+    let source = format!("script:{}", script.name);
+    let batch_id = activity::new_batch_id();
+    let now = Utc::now().timestamp();
+    let mut conn = state.writer.lock();
+    let tx = conn.transaction().db_context("Failed to start script changes")?;
+    for change in changes {
+        let (id, operation) = match change {
+            Change::Create { id, content } => {
+                validate_buffer_size(content)?;
+                queries::create_buffer(&tx, id, content, &source, now).db_context("Failed to create buffer")?;
+                (id, Operation::Create)
+            }
+            Change::SetContent { id, content } => {
+                validate_buffer_size(content)?;
+                if !queries::update_buffer_content(&tx, id, content, now).db_context("Failed to save buffer")? {
+                    return Err(AppError::not_found("Buffer", id.clone()));
+                }
+                (id, Operation::Save)
+            }
+            Change::SetArchived { id, archived } => {
+                if !queries::set_archived(&tx, id, *archived, now).db_context("Failed to update buffer")? {
+                    return Err(AppError::not_found("Buffer", id.clone()));
+                }
+                (id, if *archived { Operation::Archive } else { Operation::Unarchive })
+            }
+        };
+        activity::log(
+            &tx,
+            &NewEntry {
+                buffer_id: Some(id),
+                operation,
+                origin: Origin::Script,
+                detail: Some(&source),
+                batch_id: Some(&batch_id),
+                data: None,
+            },
+        );
+        if !ids.contains(id) {
+            ids.push(id.clone());
+        }
+    }
+    tx.commit().db_context("Failed to commit script changes")?;
+    Ok(ids)
+}
+
+/// Run a script and apply its changes, then tell the frontend what changed
+pub fn run(app: &AppHandle, state: &AppState, script: &Script) -> AppResult<ScriptReport> {
+    let ctx = Rc::new(RefCell::new(ScriptContext {
+        reader_pool: state.reader_pool(),
+        changes: Vec::new(),
+        output: Vec::new(),
+    }));
+    let engine = build_engine(&ctx);
+    let result = engine.eval::<Dynamic>(&script.source);
+    // Drop the engine's clones of the context so it can be unwrapped
+    drop(engine);
+
+    {
+        let conn = state.writer.lock();
+        if let Err(e) = db_scripts::mark_script_run(&conn, &script.id, Utc::now().timestamp()) {
+            warn!("Failed to record run of script {}: {}", script.name, e);
+        }
+    }
+
+    let value = result.map_err(|e| AppError::Validation(format!("Script {} failed: {}", script.name, e)))?;
+    let ScriptContext { changes, output, .. } = Rc::try_unwrap(ctx)
+        .map_err(|_| AppError::Internal("Script context still in use".to_string()))?
+        .into_inner();
+
+    let changed_ids = apply_changes(state, script, &changes)?;
+    if !changed_ids.is_empty() {
+        info!("Script {} changed {} buffers", script.name, changed_ids.len());
+        let payload = BuffersChangedPayload { ids: changed_ids.clone() };
+        if let Err(e) = app.emit(SCRIPT_BUFFERS_CHANGED_EVENT, payload) {
+            warn!("Failed to emit {}: {}", SCRIPT_BUFFERS_CHANGED_EVENT, e);
+        }
+    }
+
+    Ok(ScriptReport {
+        output,
+        result: (!value.is_unit()).then(|| value.to_string()),
+        changed_ids,
+    })
+}
+
+/// Run scheduled scripts whose interval has elapsed
+pub fn run_scheduled(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let due = {
+        let conn = state.writer.lock();
+        db_scripts::get_due_scripts(&conn, Utc::now().timestamp())
+    };
+
+    match due {
+        Ok(scripts) => {
+            for script in scripts {
+                match run(app, &state, &script) {
+                    Ok(report) => info!("Scheduled script {} ran ({} changes)", script.name, report.changed_ids.len()),
+                    Err(e) => warn!("Scheduled script {} failed: {}", script.name, e),
+                }
+            }
+        }
+        Err(e) => warn!("Failed to load scheduled scripts: {}", e),
+    }
+}
@@ -0,0 +1,42 @@
+// Hybrid search: full-text and semantic result lists merged with reciprocal
+// rank fusion, so a note ranks well if either search finds it and best when
+// both do
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// RRF damping constant; 60 is the value from the original paper
+const RRF_K: f64 = 60.0;
+
+/// A fused search hit with the evidence from each source
+#[derive(Debug, Serialize)]
+pub struct HybridResult {
+    pub id: String,
+    pub title: String,
+    /// Highlighted keyword snippet, or the note preview for semantic-only hits
+    pub snippet: String,
+    pub updated_at: i64,
+    /// Set when the keyword match is in one of the buffer's attachments
+    pub attachment_name: Option<String>,
+    /// 1-based position in the full-text results
+    pub keyword_rank: Option<usize>,
+    /// Cosine similarity to the query
+    pub semantic_score: Option<f32>,
+    /// Fused score, higher is better
+    pub score: f64,
+}
+
+/// Fused ranking of two ranked lists of distinct ids (best first). An id's
+/// score is the sum of `1 / (k + rank)` over the lists it appears in.
+pub fn fuse(keyword_ids: &[String], semantic_ids: &[String]) -> Vec<(String, f64)> {
+    let mut scores: HashMap<&str, f64> = HashMap::new();
+    for list in [keyword_ids, semantic_ids] {
+        for (i, id) in list.iter().enumerate() {
+            *scores.entry(id.as_str()).or_default() += 1.0 / (RRF_K + (i + 1) as f64);
+        }
+    }
+
+    let mut fused: Vec<(String, f64)> = scores.into_iter().map(|(id, score)| (id.to_string(), score)).collect();
+    fused.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    fused
+}
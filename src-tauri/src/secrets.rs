@@ -0,0 +1,76 @@
+// Credentials for integrations (AI provider API keys, sync endpoints, webhook
+// signing secrets), kept in the OS keychain rather than the settings table
+
+use crate::error::{AppError, AppResult};
+
+/// Keychain service every secret is filed under
+#[cfg(desktop)]
+const SERVICE: &str = "com.flashnotes.notes";
+
+/// Longest accepted secret name
+const MAX_NAME_LEN: usize = 64;
+
+/// Secret names are short identifiers like `openai.api_key` or `sync.token`
+pub fn validate_name(name: &str) -> AppResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::Validation(format!(
+            "Invalid secret name '{}': use up to {} lowercase letters, digits, '.', '_' or '-'",
+            name, MAX_NAME_LEN
+        )))
+    }
+}
+
+#[cfg(desktop)]
+fn entry(name: &str) -> AppResult<keyring::Entry> {
+    keyring::Entry::new(SERVICE, name).map_err(|e| AppError::Internal(format!("Keychain unavailable: {}", e)))
+}
+
+/// Store a secret, replacing any previous value
+#[cfg(desktop)]
+pub fn set(name: &str, value: &str) -> AppResult<()> {
+    entry(name)?
+        .set_password(value)
+        .map_err(|e| AppError::Internal(format!("Failed to store secret '{}': {}", name, e)))
+}
+
+/// Read a secret, if one is stored
+#[cfg(desktop)]
+pub fn get(name: &str) -> AppResult<Option<String>> {
+    match entry(name)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(AppError::Internal(format!("Failed to read secret '{}': {}", name, e))),
+    }
+}
+
+/// Remove a secret; removing one that isn't stored is fine
+#[cfg(desktop)]
+pub fn delete(name: &str) -> AppResult<()> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(AppError::Internal(format!("Failed to remove secret '{}': {}", name, e))),
+    }
+}
+
+/// Mobile builds have no keychain integration yet
+#[cfg(mobile)]
+pub fn set(_name: &str, _value: &str) -> AppResult<()> {
+    Err(AppError::Unsupported("Secret storage is not available on mobile"))
+}
+
+#[cfg(mobile)]
+pub fn get(_name: &str) -> AppResult<Option<String>> {
+    Ok(None)
+}
+
+#[cfg(mobile)]
+pub fn delete(_name: &str) -> AppResult<()> {
+    Ok(())
+}
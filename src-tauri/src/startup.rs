@@ -0,0 +1,254 @@
+// Deferred startup. The window is shown right away; the corruption check,
+// opening the database, schema checks, settings, search index verification
+// and the daily backup run on a background thread. Database commands wait
+// on `ReadyGate` (on the blocking pool) until the schema is in place.
+
+use crate::db::{self, fts, recovery};
+use crate::error::{AppError, AppResult};
+use crate::i18n::{self, Locale};
+use crate::progress;
+use crate::state::AppState;
+use crate::{api, backup_schedule, idle, inbox, inbox_watch, logging, maintenance, menu, mru, plugins, sync, trash};
+#[cfg(desktop)]
+use crate::{hotkey, tray};
+use parking_lot::{Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info, warn};
+
+/// Emitted once the database is ready for commands
+pub const DB_READY_EVENT: &str = "db-ready";
+
+/// Emitted after launch when a corrupt database was found (payload: `RecoveryReport`)
+pub const DATABASE_RECOVERED_EVENT: &str = "database-recovered";
+
+enum Readiness {
+    Pending,
+    /// Encrypted database waiting for its passphrase
+    Locked,
+    Ready,
+    Failed(String),
+}
+
+/// Blocks commands until deferred database initialization finishes
+pub struct ReadyGate {
+    state: Mutex<Readiness>,
+    changed: Condvar,
+    /// Startup has run to the end (an unlock after auto-lock doesn't rerun it)
+    started: AtomicBool,
+}
+
+impl Default for ReadyGate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReadyGate {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(Readiness::Pending),
+            changed: Condvar::new(),
+            started: AtomicBool::new(false),
+        }
+    }
+
+    /// Wait until the database is ready; errors if initialization failed
+    pub fn wait(&self) -> AppResult<()> {
+        let mut state = self.state.lock();
+        loop {
+            match &*state {
+                Readiness::Ready => return Ok(()),
+                Readiness::Locked => return Err(AppError::DatabaseLocked),
+                Readiness::Failed(e) => return Err(AppError::Internal(format!("Database unavailable: {}", e))),
+                Readiness::Pending => self.changed.wait(&mut state),
+            }
+        }
+    }
+
+    /// Like `wait`, but without blocking: fine while startup is still
+    /// running, for commands that don't touch the database
+    pub fn check(&self) -> AppResult<()> {
+        match &*self.state.lock() {
+            Readiness::Ready | Readiness::Pending => Ok(()),
+            Readiness::Locked => Err(AppError::DatabaseLocked),
+            Readiness::Failed(e) => Err(AppError::Internal(format!("Database unavailable: {}", e))),
+        }
+    }
+
+    /// Keep commands out until the encrypted database is unlocked
+    pub fn set_locked(&self) {
+        self.set(Readiness::Locked);
+    }
+
+    /// Unlocked: commands wait again until startup finishes, or go ahead
+    /// right away if it already has. Returns true when startup still has to run.
+    pub fn set_unlocked(&self) -> bool {
+        if self.started.load(Ordering::Acquire) {
+            self.set(Readiness::Ready);
+            false
+        } else {
+            self.set(Readiness::Pending);
+            true
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        matches!(*self.state.lock(), Readiness::Locked)
+    }
+
+    pub fn is_ready(&self) -> bool {
+        matches!(*self.state.lock(), Readiness::Ready)
+    }
+
+    fn set(&self, readiness: Readiness) {
+        *self.state.lock() = readiness;
+        self.changed.notify_all();
+    }
+}
+
+/// Check the unencrypted database for corruption (recovering it from a
+/// backup) and open it in place of the stand-in connections. An encrypted
+/// database was already opened by `encryption::unlock`.
+fn open_database(app: &AppHandle, state: &AppState) -> Result<(), String> {
+    if state.db_key.is_set() {
+        return Ok(());
+    }
+    let db_path = db::connection::get_db_path(app).map_err(|e| e.to_string())?;
+    *state.recovery.lock() = recovery::check_and_recover(&db_path, &state.app_data_dir);
+
+    let writer = db::connection::create_connection(&db_path, None)
+        .map_err(|e| format!("Failed to create database connection: {}", e))?;
+    let reader_pool = db::connection::create_reader_pool(&db_path, None).map_err(|e| e.to_string())?;
+    *state.writer.lock() = writer;
+    state.replace_reader_pool(reader_pool);
+    Ok(())
+}
+
+/// Create or migrate the schema and apply settings that affect the backend
+pub(crate) fn init_schema(state: &AppState) -> Result<(), String> {
+    let mut conn = state.writer.lock();
+    db::schema::migrate(&mut conn, &state.app_data_dir, state.db_key.get().as_deref())?;
+
+    let settings = db::queries::get_settings(&conn).unwrap_or_default();
+    let recent_ids = db::queries::get_recent_buffer_ids(&conn, mru::MRU_CAPACITY).unwrap_or_default();
+    i18n::set_locale(Locale::from_setting(&settings.locale));
+    if let Err(e) = logging::set_level(&settings.log_level) {
+        warn!("{}", e);
+    }
+
+    state.metrics.set_enabled(settings.usage_metrics_enabled);
+    state.idle.configure(settings.auto_lock_minutes.max(0) as u32, settings.auto_lock_hide_window);
+    state.mru.seed(recent_ids);
+    state.save_hook.configure(&settings.save_hook_script);
+    state.webhooks.refresh(&conn);
+    Ok(())
+}
+
+/// Rebuild the menu in the configured locale, restore the window's
+/// always-on-top state and register the global shortcut
+#[cfg(desktop)]
+fn apply_window_settings(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let settings = state.read(db::queries::get_settings).unwrap_or_default();
+
+    if let Err(e) = menu::install(app, settings.always_on_top) {
+        warn!("Failed to rebuild menu: {}", e);
+    }
+    hotkey::register_from_settings(app, &settings);
+    tray::install(app);
+    if settings.always_on_top {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.set_always_on_top(true);
+        }
+    }
+}
+
+/// Check the full-text index and rebuild it if it is damaged
+fn verify_search_index(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let healthy = {
+        let conn = state.writer.lock();
+        fts::verify(&conn)
+    };
+    match healthy {
+        Ok(true) => {}
+        Ok(false) => {
+            warn!("Search index failed verification, rebuilding");
+            let result = progress::run(app, "search_index", |progress| {
+                let mut conn = state.writer.lock();
+                fts::rebuild_index(&mut conn, |indexed, total| progress.update("indexing", indexed, total))
+                    .map_err(|e| AppError::Internal(format!("Failed to rebuild search index: {}", e)))
+            });
+            match result {
+                Ok(indexed) => info!("Rebuilt search index ({} buffers)", indexed),
+                Err(e) => warn!("{}", e),
+            }
+        }
+        Err(e) => warn!("Search index verification failed: {}", e),
+    }
+}
+
+fn run(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    // Started again by `unlock_database` once it has the passphrase
+    if state.ready.is_locked() {
+        info!("Database is encrypted, waiting for it to be unlocked");
+        return;
+    }
+    if let Err(e) = open_database(app, &state).and_then(|()| init_schema(&state)) {
+        error!("Database initialization failed: {}", e);
+        state.ready.set(Readiness::Failed(e.clone()));
+        crate::show_error_dialog(
+            app,
+            i18n::t("Database Error"),
+            &format!(
+                "Failed to initialize the database.\n\n\
+                Error: {}\n\n\
+                The database file may be corrupted. The application will now exit.",
+                e
+            ),
+        );
+        app.exit(1);
+        return;
+    }
+
+    state.ready.set(Readiness::Ready);
+    state.ready.started.store(true, Ordering::Release);
+    info!("Database ready");
+    if let Err(e) = app.emit(DB_READY_EVENT, ()) {
+        warn!("Failed to emit {} event: {}", DB_READY_EVENT, e);
+    }
+    if let Some(report) = state.recovery.lock().clone() {
+        if let Err(e) = app.emit(DATABASE_RECOVERED_EVENT, report) {
+            warn!("Failed to emit {} event: {}", DATABASE_RECOVERED_EVENT, e);
+        }
+    }
+
+    #[cfg(desktop)]
+    apply_window_settings(app);
+    state.plugins.load(&plugins::plugins_dir(&state.app_data_dir));
+
+    verify_search_index(app);
+    trash::purge_expired(&state);
+
+    backup_schedule::spawn(app.clone());
+    idle::spawn_watcher(app.clone());
+    maintenance::spawn(app.clone());
+    inbox::ingest_and_notify(app);
+    inbox_watch::spawn(app.clone());
+    sync::spawn(app.clone());
+    api::start(app);
+}
+
+/// Finish initialization in the background
+pub fn spawn(app: AppHandle) {
+    let spawned = thread::Builder::new()
+        .name("startup".to_string())
+        .spawn(move || run(&app));
+
+    if let Err(e) = spawned {
+        error!("Failed to start initialization thread: {}", e);
+    }
+}
@@ -1,8 +1,32 @@
-use parking_lot::Mutex;
+use crate::api::ApiServer;
+use crate::db::queries;
+use crate::db::recovery::RecoveryReport;
+use crate::deep_link::PendingLink;
+use crate::edit_locks::EditLocks;
+use crate::embeddings::EmbeddingIndexer;
+use crate::encryption::DatabaseKey;
+use crate::ephemeral::EphemeralStore;
+use crate::error::{AppError, AppResult};
+use crate::idle::IdleLock;
+use crate::inbox_watch::InboxWatcher;
+use crate::jobs::JobQueue;
+use crate::lan_share::ShareServer;
+use crate::metrics::{CommandTimings, UsageMetrics};
+use crate::mru::MruStack;
+use crate::note_window::NoteWindows;
+use crate::plugins::PluginHost;
+use crate::save_hook::SaveHook;
+use crate::startup::ReadyGate;
+use crate::sync::{CloudSync, FolderSync};
+use crate::webhooks::WebhookDispatcher;
+use chrono::Utc;
+use parking_lot::{Mutex, RwLock};
 use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::Connection;
 use std::path::PathBuf;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
 use tracing::{debug, warn};
 
 /// Connection pool error type
@@ -14,11 +38,52 @@ pub type PoolError = r2d2::Error;
 pub struct AppState {
     /// Dedicated writer connection - all writes go through here
     pub writer: Mutex<Connection>,
-    /// Connection pool for read operations
-    pub reader_pool: Pool<SqliteConnectionManager>,
-    /// App data directory for backups (available for future use)
-    #[allow(dead_code)]
+    /// Connection pool for read operations; replaced when the database moves
+    reader_pool: RwLock<Pool<SqliteConnectionManager>>,
+    /// App data directory for backups
     pub app_data_dir: PathBuf,
+    /// Opt-in local usage metrics
+    pub metrics: UsageMetrics,
+    /// Per-command execution times for this session (diagnostics)
+    pub timings: CommandTimings,
+    /// Idle tracking for auto-lock
+    pub idle: IdleLock,
+    /// Recently opened buffers for Ctrl+Tab switching
+    pub mru: MruStack,
+    /// Loaded WASM plugins
+    pub plugins: PluginHost,
+    /// Opt-in user script run after saves
+    pub save_hook: SaveHook,
+    /// Outgoing webhook events
+    pub webhooks: WebhookDispatcher,
+    /// Incognito buffers that only live in memory
+    pub ephemeral: EphemeralStore,
+    /// Semantic search index maintenance
+    pub embeddings: EmbeddingIndexer,
+    /// Running background jobs, for listing and cancellation
+    pub jobs: JobQueue,
+    /// Opens once the schema is initialized (see `startup`)
+    pub ready: ReadyGate,
+    /// Temporary share links served on the local network
+    pub share_links: ShareServer,
+    /// Poll state and results of the inbox folder watcher
+    pub inbox_watch: InboxWatcher,
+    /// Advisory per-buffer edit locks held by windows
+    pub edit_locks: EditLocks,
+    /// Two-way sync with a folder of Markdown files
+    pub folder_sync: FolderSync,
+    /// Sync with the user's iCloud (macOS and iOS)
+    pub cloud_sync: CloudSync,
+    /// Set when the database was found corrupt on launch (by startup)
+    pub recovery: Mutex<Option<RecoveryReport>>,
+    /// Passphrase of an encrypted database
+    pub db_key: DatabaseKey,
+    /// Note opened by the link the app was launched with
+    pub deep_link: PendingLink,
+    /// Local HTTP API for integrations
+    pub api: ApiServer,
+    /// Buffers open in floating note windows
+    pub note_windows: NoteWindows,
 }
 
 impl AppState {
@@ -26,15 +91,47 @@ impl AppState {
     pub fn new(writer: Connection, reader_pool: Pool<SqliteConnectionManager>, app_data_dir: PathBuf) -> Self {
         Self {
             writer: Mutex::new(writer),
-            reader_pool,
+            reader_pool: RwLock::new(reader_pool),
             app_data_dir,
+            metrics: UsageMetrics::new(false),
+            timings: CommandTimings::new(),
+            idle: IdleLock::new(),
+            mru: MruStack::new(),
+            plugins: PluginHost::new(),
+            save_hook: SaveHook::new(),
+            webhooks: WebhookDispatcher::new(),
+            ephemeral: EphemeralStore::new(),
+            embeddings: EmbeddingIndexer::new(),
+            jobs: JobQueue::new(),
+            ready: ReadyGate::new(),
+            share_links: ShareServer::new(),
+            inbox_watch: InboxWatcher::new(),
+            edit_locks: EditLocks::new(),
+            folder_sync: FolderSync::new(),
+            cloud_sync: CloudSync::new(),
+            recovery: Mutex::new(None),
+            db_key: DatabaseKey::new(),
+            deep_link: PendingLink::new(),
+            api: ApiServer::new(),
+            note_windows: NoteWindows::new(),
         }
     }
 
+    /// The current reader pool (cheap to clone)
+    pub fn reader_pool(&self) -> Pool<SqliteConnectionManager> {
+        self.reader_pool.read().clone()
+    }
+
+    /// Swap in a pool opened on a new database file and return the old one.
+    /// Connections already handed out by the old pool stay usable until they are returned.
+    pub fn replace_reader_pool(&self, pool: Pool<SqliteConnectionManager>) -> Pool<SqliteConnectionManager> {
+        std::mem::replace(&mut *self.reader_pool.write(), pool)
+    }
+
     /// Get a read-only connection from the pool
     /// Falls back to writer if pool is exhausted
     pub fn get_reader(&self) -> Result<PooledConnection<SqliteConnectionManager>, PoolError> {
-        match self.reader_pool.get() {
+        match self.reader_pool().get() {
             Ok(conn) => {
                 debug!("Acquired reader connection from pool");
                 Ok(conn)
@@ -45,5 +142,82 @@ impl AppState {
             }
         }
     }
+
+    /// Run a read on a pooled reader connection, falling back to the writer
+    /// when the pool is exhausted. Keeps reads from queueing behind saves.
+    pub fn read<T>(&self, f: impl FnOnce(&Connection) -> T) -> T {
+        match self.get_reader() {
+            Ok(conn) => f(&conn),
+            Err(_) => f(&self.writer.lock()),
+        }
+    }
+
+    /// Run a command body, timing it for diagnostics and recording usage
+    /// metrics when they are enabled. Fails while the app is locked
+    /// (`unlock_app`), but doesn't wait for startup: sync commands run on the
+    /// main thread, so database work goes through `track_blocking`, which does.
+    /// Every command also counts as activity for the idle auto-lock.
+    pub fn track<T>(&self, command: &'static str, f: impl FnOnce() -> AppResult<T>) -> AppResult<T> {
+        self.ready.check()?;
+        if self.idle.is_locked() {
+            return Err(AppError::DatabaseLocked);
+        }
+        self.idle.touch();
+
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed();
+        self.timings.record(command, elapsed, result.is_ok());
+
+        // Checked after the call: the command itself may have just turned metrics off
+        if self.metrics.is_enabled()
+            && self.metrics.record(command, elapsed, result.is_ok(), Utc::now().timestamp())
+        {
+            self.flush_metrics();
+        }
+
+        result
+    }
+
+    /// Persist pending usage metrics (best effort)
+    pub fn flush_metrics(&self) {
+        let pending = self.metrics.take_pending();
+        if pending.is_empty() {
+            return;
+        }
+
+        let mut conn = self.writer.lock();
+        if let Err(e) = queries::record_usage_metrics(&mut conn, &pending) {
+            warn!("Failed to persist usage metrics: {}", e);
+        }
+    }
 }
 
+/// Run a command body like `AppState::track`, on the blocking thread pool,
+/// once the database is ready. Sync commands run on the main thread, where a
+/// slow query (or waiting for the writer or for startup) would freeze the UI;
+/// database-bound commands are async and hand their work to this instead.
+pub async fn track_blocking<T, F>(app: AppHandle, command: &'static str, f: F) -> AppResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&AppHandle, &AppState) -> AppResult<T> + Send + 'static,
+{
+    run_blocking(command, move || {
+        let state = app.state::<AppState>();
+        state.ready.wait()?;
+        state.track(command, || f(&app, &state))
+    })
+    .await
+}
+
+/// Run a command body on the blocking thread pool without tracking it, for
+/// commands that must work while tracked ones fail (locking and unlocking)
+pub async fn run_blocking<T, F>(command: &'static str, f: F) -> AppResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> AppResult<T> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Internal(format!("Command {} failed: {}", command, e)))?
+}
@@ -0,0 +1,406 @@
+// One sync pass between the notes and a cloud database holding one record
+// per active note, named after its buffer id (see `cloudkit` for iCloud).
+// Remote changes are fetched incrementally from a server change token; each
+// side's changes are found by comparing it with the state recorded in
+// `cloud_records` after the previous pass, and when both sides changed the
+// newer one wins. Locked notes never leave the device.
+
+use super::SyncReport;
+use crate::commands::buffer::validate_buffer_size;
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::cloud_sync::{self as db_cloud, CloudRecord};
+use crate::db::queries::{self, Buffer};
+use crate::db::revisions;
+use crate::embeddings::content_hash;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::state::AppState;
+use chrono::Utc;
+use std::collections::HashMap;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Source and activity detail of notes created from cloud records
+const SOURCE: &str = "sync:icloud";
+
+/// A note as stored in the cloud
+#[derive(Debug, Clone)]
+pub struct CloudNote {
+    /// Record name: the buffer id
+    pub id: String,
+    pub content: String,
+    /// When the note was last edited (Unix seconds)
+    pub modified_at: i64,
+    /// The record's server state; saving with stale fields reports a conflict
+    pub system_fields: Option<Vec<u8>>,
+}
+
+/// Remote changes since a change token
+#[derive(Debug, Default)]
+pub struct CloudChanges {
+    pub changed: Vec<CloudNote>,
+    /// Names of deleted records
+    pub deleted: Vec<String>,
+    /// Token to fetch the next changes from
+    pub token: Option<Vec<u8>>,
+}
+
+/// Result of saving a record (only CloudKit saves records so far)
+#[derive(Debug)]
+#[cfg_attr(not(any(target_os = "macos", target_os = "ios")), allow(dead_code))]
+pub enum SaveOutcome {
+    /// Saved; carries the record's new server state
+    Saved(Option<Vec<u8>>),
+    /// The record changed on the server since the `system_fields` it was saved with
+    Conflict(CloudNote),
+}
+
+/// A database of note records
+pub trait CloudDatabase {
+    /// Changes since `token`, or every record when None
+    fn fetch_changes(&self, token: Option<&[u8]>) -> AppResult<CloudChanges>;
+    /// Create or update a record
+    fn save(&self, note: &CloudNote) -> AppResult<SaveOutcome>;
+    /// Delete a record; one that is already gone is not an error
+    fn delete(&self, id: &str) -> AppResult<()>;
+}
+
+fn record(state: &AppState, record: &CloudRecord) -> AppResult<()> {
+    db_cloud::upsert_cloud_record(&state.writer.lock(), record).db_context("Failed to record sync state")
+}
+
+fn forget(state: &AppState, buffer_id: &str) -> AppResult<()> {
+    db_cloud::delete_cloud_record(&state.writer.lock(), buffer_id).db_context("Failed to record sync state")
+}
+
+/// Notes that are synced: not archived, trashed or locked
+fn active_buffers(buffers: Vec<Buffer>) -> HashMap<String, Buffer> {
+    buffers
+        .into_iter()
+        .filter(|b| !b.is_archived && !b.is_locked)
+        .map(|b| (b.id.clone(), b))
+        .collect()
+}
+
+/// Whether a note was edited since the last sync
+fn changed_locally(buffer: &Buffer, synced: &CloudRecord) -> bool {
+    buffer.updated_at != synced.buffer_updated_at && content_hash(&buffer.content) != synced.content_hash
+}
+
+/// Replace a note's content with its record's. Skipped (returns false) when
+/// the note was saved or locked since `buffer` was read; the next pass compares
+/// again. On a conflict the note's own edits are kept as a revision.
+fn pull(state: &AppState, buffer: &Buffer, note: &CloudNote, conflict: bool) -> AppResult<bool> {
+    validate_buffer_size(&note.content)?;
+    let conn = state.writer.lock();
+    let current = queries::get_buffer_content(&conn, &buffer.id).db_context("Failed to get buffer")?;
+    if current.is_none_or(|c| c.updated_at != buffer.updated_at || c.is_locked) {
+        return Ok(false);
+    }
+
+    let timestamp = Utc::now().timestamp();
+    if conflict {
+        revisions::snapshot(&conn, &buffer.id, &note.content, timestamp, true)
+            .db_context("Failed to save revision")?;
+    }
+    if !queries::update_buffer_content(&conn, &buffer.id, &note.content, timestamp)
+        .db_context("Failed to save buffer")?
+    {
+        return Ok(false);
+    }
+    activity::record(&conn, Some(&buffer.id), Operation::Save, Origin::Sync, Some(SOURCE));
+    db_cloud::upsert_cloud_record(
+        &conn,
+        &CloudRecord {
+            buffer_id: buffer.id.clone(),
+            content_hash: content_hash(&note.content),
+            buffer_updated_at: timestamp,
+            system_fields: note.system_fields.clone(),
+        },
+    )
+    .db_context("Failed to record sync state")?;
+    Ok(true)
+}
+
+/// Create a note from a record. It keeps the record's name unless a local
+/// note has it: an archived, trashed or locked note claims a record it has
+/// never synced with (removing it later in the pass), and one that went away
+/// after syncing leaves the edited record to a copy, pushed as a new record.
+/// Empty records are left alone.
+fn import(state: &AppState, note: &CloudNote, synced: bool) -> AppResult<Option<String>> {
+    let conn = state.writer.lock();
+    let taken = queries::get_buffer_content(&conn, &note.id).db_context("Failed to get buffer")?.is_some();
+    if taken && !synced {
+        db_cloud::upsert_cloud_record(
+            &conn,
+            &CloudRecord {
+                buffer_id: note.id.clone(),
+                content_hash: content_hash(&note.content),
+                buffer_updated_at: 0,
+                system_fields: note.system_fields.clone(),
+            },
+        )
+        .db_context("Failed to record sync state")?;
+        return Ok(None);
+    }
+    if note.content.trim().is_empty() {
+        return Ok(None);
+    }
+    validate_buffer_size(&note.content)?;
+
+    let id = if taken { Uuid::new_v4().to_string() } else { note.id.clone() };
+    let timestamp = Utc::now().timestamp();
+    queries::create_buffer(&conn, &id, &note.content, SOURCE, timestamp).db_context("Failed to create buffer")?;
+    activity::record(&conn, Some(&id), Operation::Create, Origin::Sync, Some(SOURCE));
+    if !taken {
+        db_cloud::upsert_cloud_record(
+            &conn,
+            &CloudRecord {
+                buffer_id: id.clone(),
+                content_hash: content_hash(&note.content),
+                buffer_updated_at: timestamp,
+                system_fields: note.system_fields.clone(),
+            },
+        )
+        .db_context("Failed to record sync state")?;
+    }
+    Ok(Some(id))
+}
+
+/// Bring a note in line with its changed record
+fn apply_changed(
+    state: &AppState,
+    note: &CloudNote,
+    buffer: Option<&Buffer>,
+    synced: Option<&CloudRecord>,
+    report: &mut SyncReport,
+) -> AppResult<()> {
+    let remote_hash = content_hash(&note.content);
+    let remote_changed = synced.is_none_or(|s| s.content_hash != remote_hash);
+
+    let Some(buffer) = buffer else {
+        if remote_changed {
+            // New, or edited after its note went away here: keep it as a note
+            if let Some(id) = import(state, note, synced.is_some())? {
+                report.imported += 1;
+                report.changed_ids.push(id);
+            }
+        }
+        return Ok(());
+    };
+
+    if content_hash(&buffer.content) == remote_hash {
+        return record(
+            state,
+            &CloudRecord {
+                buffer_id: buffer.id.clone(),
+                content_hash: remote_hash,
+                buffer_updated_at: buffer.updated_at,
+                system_fields: note.system_fields.clone(),
+            },
+        );
+    }
+
+    let local_changed = synced.is_none_or(|s| changed_locally(buffer, s));
+    let conflict = remote_changed && local_changed;
+    if conflict {
+        report.conflicts += 1;
+    }
+    if remote_changed && (!local_changed || note.modified_at > buffer.updated_at) {
+        if pull(state, buffer, note, conflict)? {
+            report.pulled += 1;
+            report.changed_ids.push(buffer.id.clone());
+        }
+        return Ok(());
+    }
+
+    // The note wins: keep the record's new server state so the push overwrites it
+    record(
+        state,
+        &CloudRecord {
+            buffer_id: buffer.id.clone(),
+            content_hash: remote_hash,
+            buffer_updated_at: synced.map_or(0, |s| s.buffer_updated_at),
+            system_fields: note.system_fields.clone(),
+        },
+    )
+}
+
+/// Trash a note whose record was deleted, unless it was edited since
+fn apply_deleted(
+    state: &AppState,
+    buffer: Option<&Buffer>,
+    synced: &CloudRecord,
+    report: &mut SyncReport,
+) -> AppResult<()> {
+    match buffer {
+        // Edited since: forgetting the record pushes the note as a new one
+        Some(buffer) if changed_locally(buffer, synced) => forget(state, &buffer.id),
+        Some(buffer) => {
+            let conn = state.writer.lock();
+            queries::trash_buffer(&conn, &buffer.id, Utc::now().timestamp()).db_context("Failed to trash buffer")?;
+            activity::record(&conn, Some(&buffer.id), Operation::Trash, Origin::Sync, Some(SOURCE));
+            db_cloud::delete_cloud_record(&conn, &buffer.id).db_context("Failed to record sync state")?;
+            report.trashed += 1;
+            report.changed_ids.push(buffer.id.clone());
+            Ok(())
+        }
+        None => forget(state, &synced.buffer_id),
+    }
+}
+
+/// Save a note to its record, settling a conflict with the server's version:
+/// the newer side wins, and a note that wins is saved again over the server state
+fn push(
+    state: &AppState,
+    db: &dyn CloudDatabase,
+    buffer: &Buffer,
+    synced: Option<&CloudRecord>,
+    report: &mut SyncReport,
+) -> AppResult<()> {
+    let mut note = CloudNote {
+        id: buffer.id.clone(),
+        content: buffer.content.clone(),
+        modified_at: buffer.updated_at,
+        system_fields: synced.and_then(|s| s.system_fields.clone()),
+    };
+    let mut retried = false;
+    loop {
+        match db.save(&note)? {
+            SaveOutcome::Saved(system_fields) => {
+                if synced.is_some() {
+                    report.pushed += 1;
+                } else {
+                    report.exported += 1;
+                }
+                return record(
+                    state,
+                    &CloudRecord {
+                        buffer_id: buffer.id.clone(),
+                        content_hash: content_hash(&buffer.content),
+                        buffer_updated_at: buffer.updated_at,
+                        system_fields,
+                    },
+                );
+            }
+            SaveOutcome::Conflict(server) if !retried => {
+                report.conflicts += 1;
+                if content_hash(&server.content) == content_hash(&buffer.content) {
+                    return record(
+                        state,
+                        &CloudRecord {
+                            buffer_id: buffer.id.clone(),
+                            content_hash: content_hash(&server.content),
+                            buffer_updated_at: buffer.updated_at,
+                            system_fields: server.system_fields,
+                        },
+                    );
+                }
+                if server.modified_at > buffer.updated_at {
+                    if pull(state, buffer, &server, true)? {
+                        report.pulled += 1;
+                        report.changed_ids.push(buffer.id.clone());
+                    }
+                    return Ok(());
+                }
+                note.system_fields = server.system_fields;
+                retried = true;
+            }
+            SaveOutcome::Conflict(_) => {
+                return Err(AppError::Conflict("The record changed again while saving".to_string()));
+            }
+        }
+    }
+}
+
+/// Run one sync pass between active (non-archived, unlocked) notes and `db`
+pub fn run(state: &AppState, db: &dyn CloudDatabase) -> AppResult<SyncReport> {
+    let (token, buffers, synced) = state
+        .read(|conn| {
+            Ok((
+                db_cloud::get_change_token(conn)?,
+                queries::get_all_buffers(conn)?,
+                db_cloud::get_cloud_records(conn)?,
+            ))
+        })
+        .db_context("Failed to read sync state")?;
+    let buffers = active_buffers(buffers);
+    let synced: HashMap<String, CloudRecord> = synced.into_iter().map(|s| (s.buffer_id.clone(), s)).collect();
+
+    let changes = db.fetch_changes(token.as_deref())?;
+    let mut report = SyncReport::default();
+    for note in &changes.changed {
+        if let Err(e) = apply_changed(state, note, buffers.get(&note.id), synced.get(&note.id), &mut report) {
+            warn!("iCloud sync failed for {}: {}", note.id, e);
+            report.errors.push(format!("{}: {}", note.id, e));
+        }
+    }
+    for id in &changes.deleted {
+        let Some(entry) = synced.get(id) else {
+            continue;
+        };
+        if let Err(e) = apply_deleted(state, buffers.get(id), entry, &mut report) {
+            warn!("iCloud sync failed for {}: {}", id, e);
+            report.errors.push(format!("{}: {}", id, e));
+        }
+    }
+    // Changes that failed are fetched again next pass
+    if report.errors.is_empty() {
+        db_cloud::set_change_token(&state.writer.lock(), changes.token.as_deref())
+            .db_context("Failed to record sync state")?;
+    }
+
+    // Local changes, against the state the remote changes left behind
+    let (buffers, synced) = state
+        .read(|conn| Ok((queries::get_all_buffers(conn)?, db_cloud::get_cloud_records(conn)?)))
+        .db_context("Failed to read sync state")?;
+    let mut buffers = active_buffers(buffers);
+    for entry in &synced {
+        let result = match buffers.remove(&entry.buffer_id) {
+            Some(buffer) if changed_locally(&buffer, entry) => {
+                push(state, db, &buffer, Some(entry), &mut report)
+            }
+            Some(buffer) if buffer.updated_at != entry.buffer_updated_at => {
+                // Touched without a content change: remember the new time
+                record(
+                    state,
+                    &CloudRecord {
+                        buffer_updated_at: buffer.updated_at,
+                        ..entry.clone()
+                    },
+                )
+            }
+            Some(_) => Ok(()),
+            // Archived, trashed, locked or deleted: take it out of the cloud
+            None => db.delete(&entry.buffer_id).and_then(|()| {
+                report.removed += 1;
+                forget(state, &entry.buffer_id)
+            }),
+        };
+        if let Err(e) = result {
+            warn!("iCloud sync failed for {}: {}", entry.buffer_id, e);
+            report.errors.push(format!("{}: {}", entry.buffer_id, e));
+        }
+    }
+
+    // Notes without a record get one
+    for buffer in buffers.values().filter(|b| !b.content.trim().is_empty()) {
+        if let Err(e) = push(state, db, buffer, None, &mut report) {
+            warn!("iCloud sync failed to save {}: {}", buffer.id, e);
+            report.errors.push(format!("{}: {}", buffer.id, e));
+        }
+    }
+
+    if !report.is_empty() {
+        info!(
+            "Synced with iCloud: {} imported, {} exported, {} pulled, {} pushed, {} trashed, {} removed, {} conflicts",
+            report.imported,
+            report.exported,
+            report.pulled,
+            report.pushed,
+            report.trashed,
+            report.removed,
+            report.conflicts
+        );
+    }
+    Ok(report)
+}
@@ -0,0 +1,474 @@
+// iCloud backend for `cloud`: notes are `Note` records in a custom zone of
+// the user's private CloudKit database, with their content in the record's
+// end-to-end encrypted values. The app must be signed with the iCloud
+// (CloudKit) entitlement for `CONTAINER_ID`; CloudKit raises an exception
+// without it, so macOS checks the entitlement before touching CloudKit.
+
+use super::cloud::{CloudChanges, CloudDatabase, CloudNote, SaveOutcome};
+use crate::error::{AppError, AppResult};
+use block::ConcreteBlock;
+use objc::runtime::{Object, BOOL, YES};
+use objc::{class, msg_send, sel, sel_impl};
+use parking_lot::Mutex;
+use std::ffi::{c_void, CStr};
+use std::os::raw::c_char;
+use std::ptr;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+type Id = *mut Object;
+
+/// iCloud container, as listed in the app's entitlements
+const CONTAINER_ID: &str = "iCloud.com.flashnotes.notes";
+const ZONE_NAME: &str = "Notes";
+const RECORD_TYPE: &str = "Note";
+/// Record fields; the content is encrypted
+const CONTENT_KEY: &str = "content";
+const MODIFIED_KEY: &str = "modifiedAt";
+
+/// How long to wait for a CloudKit reply
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// CKAccountStatusAvailable
+const ACCOUNT_AVAILABLE: isize = 1;
+/// CKErrorCode values
+const ERROR_UNKNOWN_ITEM: isize = 11;
+const ERROR_SERVER_RECORD_CHANGED: isize = 14;
+const ERROR_CHANGE_TOKEN_EXPIRED: isize = 21;
+const ERROR_ZONE_NOT_FOUND: isize = 26;
+const ERROR_USER_DELETED_ZONE: isize = 28;
+
+/// NSUTF8StringEncoding
+const UTF8_ENCODING: usize = 4;
+
+#[link(name = "Foundation", kind = "framework")]
+extern "C" {}
+
+#[link(name = "CloudKit", kind = "framework")]
+extern "C" {
+    static CKCurrentUserDefaultName: Id;
+    static CKRecordChangedErrorServerRecordKey: Id;
+}
+
+extern "C" {
+    fn objc_autoreleasePoolPush() -> *mut c_void;
+    fn objc_autoreleasePoolPop(pool: *mut c_void);
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "Security", kind = "framework")]
+extern "C" {
+    fn SecTaskCreateFromSelf(allocator: *const c_void) -> *mut c_void;
+    fn SecTaskCopyValueForEntitlement(task: *mut c_void, entitlement: Id, error: *mut *mut c_void) -> *mut c_void;
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFRelease(cf: *const c_void);
+}
+
+/// Whether the app is signed with the iCloud entitlement CloudKit needs
+#[cfg(target_os = "macos")]
+pub fn available() -> bool {
+    let _pool = AutoreleasePool::new();
+    unsafe {
+        let task = SecTaskCreateFromSelf(ptr::null());
+        if task.is_null() {
+            return false;
+        }
+        let entitlement = ns_string("com.apple.developer.icloud-services");
+        let value = SecTaskCopyValueForEntitlement(task, entitlement, ptr::null_mut());
+        CFRelease(task);
+        if value.is_null() {
+            return false;
+        }
+        CFRelease(value);
+        true
+    }
+}
+
+/// iOS apps can't read their own entitlements; release builds are signed with it
+#[cfg(target_os = "ios")]
+pub fn available() -> bool {
+    true
+}
+
+/// Releases the objects autoreleased while it lives
+struct AutoreleasePool(*mut c_void);
+
+impl AutoreleasePool {
+    fn new() -> Self {
+        Self(unsafe { objc_autoreleasePoolPush() })
+    }
+}
+
+impl Drop for AutoreleasePool {
+    fn drop(&mut self) {
+        unsafe { objc_autoreleasePoolPop(self.0) }
+    }
+}
+
+/// An NSError from CloudKit, copied out of its callback
+struct CloudError {
+    code: isize,
+    message: String,
+}
+
+impl CloudError {
+    /// None for a nil error
+    fn from_ns(error: Id) -> Option<Self> {
+        if error.is_null() {
+            return None;
+        }
+        unsafe {
+            let code: isize = msg_send![error, code];
+            let description: Id = msg_send![error, localizedDescription];
+            Some(Self {
+                code,
+                message: to_string(description).unwrap_or_default(),
+            })
+        }
+    }
+
+    fn timeout() -> Self {
+        Self {
+            code: 0,
+            message: "No reply from iCloud".to_string(),
+        }
+    }
+}
+
+impl From<CloudError> for AppError {
+    fn from(error: CloudError) -> Self {
+        AppError::Internal(format!("iCloud error {}: {}", error.code, error.message))
+    }
+}
+
+fn wait<T>(rx: &mpsc::Receiver<T>) -> Result<T, CloudError> {
+    rx.recv_timeout(TIMEOUT).map_err(|_| CloudError::timeout())
+}
+
+fn ns_string(value: &str) -> Id {
+    unsafe {
+        let string: Id = msg_send![class!(NSString), alloc];
+        let string: Id = msg_send![string, initWithBytes: value.as_ptr() as *const c_void
+                                                length: value.len()
+                                              encoding: UTF8_ENCODING];
+        msg_send![string, autorelease]
+    }
+}
+
+fn to_string(string: Id) -> Option<String> {
+    if string.is_null() {
+        return None;
+    }
+    unsafe {
+        let bytes: *const c_char = msg_send![string, UTF8String];
+        (!bytes.is_null()).then(|| CStr::from_ptr(bytes).to_string_lossy().into_owned())
+    }
+}
+
+fn ns_data(bytes: &[u8]) -> Id {
+    unsafe { msg_send![class!(NSData), dataWithBytes: bytes.as_ptr() as *const c_void length: bytes.len()] }
+}
+
+fn to_bytes(data: Id) -> Option<Vec<u8>> {
+    if data.is_null() {
+        return None;
+    }
+    unsafe {
+        let length: usize = msg_send![data, length];
+        let bytes: *const u8 = msg_send![data, bytes];
+        if length == 0 || bytes.is_null() {
+            return Some(Vec::new());
+        }
+        Some(std::slice::from_raw_parts(bytes, length).to_vec())
+    }
+}
+
+/// Archive a record's server state (change tag and the like)
+fn system_fields(record: Id) -> Option<Vec<u8>> {
+    if record.is_null() {
+        return None;
+    }
+    unsafe {
+        let coder: Id = msg_send![class!(NSKeyedArchiver), alloc];
+        let coder: Id = msg_send![coder, initRequiringSecureCoding: YES];
+        let _: () = msg_send![record, encodeSystemFieldsWithCoder: coder];
+        let _: () = msg_send![coder, finishEncoding];
+        let data: Id = msg_send![coder, encodedData];
+        let fields = to_bytes(data);
+        let _: () = msg_send![coder, release];
+        fields
+    }
+}
+
+/// A record with only its server state, to set fields on and save
+fn decode_record(fields: &[u8]) -> Id {
+    unsafe {
+        let mut error: Id = ptr::null_mut();
+        let coder: Id = msg_send![class!(NSKeyedUnarchiver), alloc];
+        let coder: Id = msg_send![coder, initForReadingFromData: ns_data(fields) error: &mut error];
+        if coder.is_null() {
+            return ptr::null_mut();
+        }
+        let record: Id = msg_send![class!(CKRecord), alloc];
+        let record: Id = msg_send![record, initWithCoder: coder];
+        let _: () = msg_send![coder, finishDecoding];
+        let _: () = msg_send![coder, release];
+        if record.is_null() {
+            return record;
+        }
+        msg_send![record, autorelease]
+    }
+}
+
+/// Copy a note out of its record
+fn read_record(record: Id) -> Option<CloudNote> {
+    unsafe {
+        let record_id: Id = msg_send![record, recordID];
+        let name: Id = msg_send![record_id, recordName];
+        let values: Id = msg_send![record, encryptedValues];
+        let content: Id = msg_send![values, objectForKey: ns_string(CONTENT_KEY)];
+        let modified: Id = msg_send![record, objectForKey: ns_string(MODIFIED_KEY)];
+        let modified_at: i64 = if modified.is_null() { 0 } else { msg_send![modified, longLongValue] };
+        Some(CloudNote {
+            id: to_string(name)?,
+            content: to_string(content).unwrap_or_default(),
+            modified_at,
+            system_fields: system_fields(record),
+        })
+    }
+}
+
+/// The server's version of a record a save conflicted with
+fn server_record(error: Id) -> Option<CloudNote> {
+    unsafe {
+        let info: Id = msg_send![error, userInfo];
+        let record: Id = msg_send![info, objectForKey: CKRecordChangedErrorServerRecordKey];
+        (!record.is_null()).then(|| read_record(record)).flatten()
+    }
+}
+
+fn archive_token(token: Id) -> Option<Vec<u8>> {
+    if token.is_null() {
+        return None;
+    }
+    unsafe {
+        let error: *mut Id = ptr::null_mut();
+        let data: Id = msg_send![class!(NSKeyedArchiver), archivedDataWithRootObject: token
+                                                               requiringSecureCoding: YES
+                                                                               error: error];
+        to_bytes(data)
+    }
+}
+
+fn unarchive_token(bytes: &[u8]) -> Id {
+    unsafe {
+        let error: *mut Id = ptr::null_mut();
+        msg_send![class!(NSKeyedUnarchiver), unarchivedObjectOfClass: class!(CKServerChangeToken)
+                                                            fromData: ns_data(bytes)
+                                                               error: error]
+    }
+}
+
+/// The app's private CloudKit database and its notes zone
+struct CloudKit {
+    database: Id,
+    zone_id: Id,
+}
+
+/// Run `f` against the private CloudKit database, within an autorelease pool
+/// for the objects created along the way
+pub fn with_database<T>(f: impl FnOnce(&dyn CloudDatabase) -> AppResult<T>) -> AppResult<T> {
+    if !available() {
+        return Err(AppError::Unsupported("iCloud sync"));
+    }
+    let _pool = AutoreleasePool::new();
+    let database = CloudKit::open()?;
+    f(&database)
+}
+
+impl CloudKit {
+    fn open() -> AppResult<Self> {
+        let container: Id =
+            unsafe { msg_send![class!(CKContainer), containerWithIdentifier: ns_string(CONTAINER_ID)] };
+
+        let (tx, rx) = mpsc::channel();
+        let reply = ConcreteBlock::new(move |status: isize, error: Id| {
+            let _ = tx.send(CloudError::from_ns(error).map_or(Ok(status), Err));
+        })
+        .copy();
+        unsafe {
+            let _: () = msg_send![container, accountStatusWithCompletionHandler: &*reply];
+        }
+        if wait(&rx)?? != ACCOUNT_AVAILABLE {
+            return Err(AppError::Validation("Sign in to iCloud to sync notes".to_string()));
+        }
+
+        unsafe {
+            let database: Id = msg_send![container, privateCloudDatabase];
+            let zone_id: Id = msg_send![class!(CKRecordZoneID), alloc];
+            let zone_id: Id = msg_send![zone_id, initWithZoneName: ns_string(ZONE_NAME)
+                                                        ownerName: CKCurrentUserDefaultName];
+            let zone_id: Id = msg_send![zone_id, autorelease];
+            Ok(Self { database, zone_id })
+        }
+    }
+
+    fn record_id(&self, name: &str) -> Id {
+        unsafe {
+            let record_id: Id = msg_send![class!(CKRecordID), alloc];
+            let record_id: Id = msg_send![record_id, initWithRecordName: ns_string(name) zoneID: self.zone_id];
+            msg_send![record_id, autorelease]
+        }
+    }
+
+    fn create_zone(&self) -> Result<(), CloudError> {
+        let (tx, rx) = mpsc::channel();
+        let reply = ConcreteBlock::new(move |_zone: Id, error: Id| {
+            let _ = tx.send(CloudError::from_ns(error));
+        })
+        .copy();
+        unsafe {
+            let zone: Id = msg_send![class!(CKRecordZone), alloc];
+            let zone: Id = msg_send![zone, initWithZoneID: self.zone_id];
+            let zone: Id = msg_send![zone, autorelease];
+            let _: () = msg_send![self.database, saveRecordZone: zone completionHandler: &*reply];
+        }
+        wait(&rx)?.map_or(Ok(()), Err)
+    }
+
+    /// Every change in the notes zone since `token`
+    fn fetch(&self, token: Option<&[u8]>) -> Result<CloudChanges, CloudError> {
+        let changes = Arc::new(Mutex::new(CloudChanges::default()));
+        let changed = {
+            let changes = changes.clone();
+            ConcreteBlock::new(move |record: Id| {
+                if let Some(note) = read_record(record) {
+                    changes.lock().changed.push(note);
+                }
+            })
+            .copy()
+        };
+        let deleted = {
+            let changes = changes.clone();
+            ConcreteBlock::new(move |record_id: Id, _record_type: Id| {
+                let name: Id = unsafe { msg_send![record_id, recordName] };
+                if let Some(name) = to_string(name) {
+                    changes.lock().deleted.push(name);
+                }
+            })
+            .copy()
+        };
+        let (zone_tx, zone_rx) = mpsc::channel();
+        let zone_done = ConcreteBlock::new(
+            move |_zone_id: Id, token: Id, _client_token: Id, _more_coming: BOOL, error: Id| {
+                let _ = zone_tx.send(CloudError::from_ns(error).map_or_else(|| Ok(archive_token(token)), Err));
+            },
+        )
+        .copy();
+        let (tx, rx) = mpsc::channel();
+        let done = ConcreteBlock::new(move |error: Id| {
+            let _ = tx.send(CloudError::from_ns(error));
+        })
+        .copy();
+
+        unsafe {
+            let config: Id = msg_send![class!(CKFetchRecordZoneChangesConfiguration), new];
+            let config: Id = msg_send![config, autorelease];
+            let previous = token.map_or(ptr::null_mut(), unarchive_token);
+            let _: () = msg_send![config, setPreviousServerChangeToken: previous];
+            let zones: Id = msg_send![class!(NSArray), arrayWithObject: self.zone_id];
+            let configs: Id = msg_send![class!(NSDictionary), dictionaryWithObject: config forKey: self.zone_id];
+
+            let operation: Id = msg_send![class!(CKFetchRecordZoneChangesOperation), alloc];
+            let operation: Id = msg_send![operation, initWithRecordZoneIDs: zones
+                                                 configurationsByRecordZoneID: configs];
+            let operation: Id = msg_send![operation, autorelease];
+            let _: () = msg_send![operation, setFetchAllChanges: YES];
+            let _: () = msg_send![operation, setRecordChangedBlock: &*changed];
+            let _: () = msg_send![operation, setRecordWithIDWasDeletedBlock: &*deleted];
+            let _: () = msg_send![operation, setRecordZoneFetchCompletionBlock: &*zone_done];
+            let _: () = msg_send![operation, setFetchRecordZoneChangesCompletionBlock: &*done];
+            let _: () = msg_send![self.database, addOperation: operation];
+        }
+
+        // The zone's own result (and error) arrives before the operation's
+        let operation_error = wait(&rx)?;
+        let token = match zone_rx.try_iter().last() {
+            Some(result) => result?,
+            None => return Err(operation_error.unwrap_or_else(CloudError::timeout)),
+        };
+        if let Some(error) = operation_error {
+            return Err(error);
+        }
+        let mut changes = std::mem::take(&mut *changes.lock());
+        changes.token = token;
+        Ok(changes)
+    }
+}
+
+impl CloudDatabase for CloudKit {
+    fn fetch_changes(&self, token: Option<&[u8]>) -> AppResult<CloudChanges> {
+        let result = match self.fetch(token) {
+            // Too old to resume from: fetch everything again
+            Err(e) if e.code == ERROR_CHANGE_TOKEN_EXPIRED => self.fetch(None),
+            // First sync, or the user deleted the app's iCloud data
+            Err(e) if e.code == ERROR_ZONE_NOT_FOUND || e.code == ERROR_USER_DELETED_ZONE => {
+                self.create_zone().and_then(|()| self.fetch(None))
+            }
+            result => result,
+        };
+        Ok(result?)
+    }
+
+    fn save(&self, note: &CloudNote) -> AppResult<SaveOutcome> {
+        let (tx, rx) = mpsc::channel();
+        let reply = ConcreteBlock::new(move |record: Id, error: Id| {
+            let outcome = match CloudError::from_ns(error) {
+                None => Ok(SaveOutcome::Saved(system_fields(record))),
+                Some(e) if e.code == ERROR_SERVER_RECORD_CHANGED => {
+                    server_record(error).map(SaveOutcome::Conflict).ok_or(e)
+                }
+                Some(e) => Err(e),
+            };
+            let _ = tx.send(outcome);
+        })
+        .copy();
+
+        unsafe {
+            // The last known server state lets CloudKit refuse to overwrite newer changes
+            let mut record = note.system_fields.as_deref().map_or(ptr::null_mut(), decode_record);
+            if record.is_null() {
+                record = msg_send![class!(CKRecord), alloc];
+                record = msg_send![record, initWithRecordType: ns_string(RECORD_TYPE)
+                                                     recordID: self.record_id(&note.id)];
+                record = msg_send![record, autorelease];
+            }
+            let values: Id = msg_send![record, encryptedValues];
+            let _: () = msg_send![values, setObject: ns_string(&note.content) forKey: ns_string(CONTENT_KEY)];
+            let modified: Id = msg_send![class!(NSNumber), numberWithLongLong: note.modified_at];
+            let _: () = msg_send![record, setObject: modified forKey: ns_string(MODIFIED_KEY)];
+            let _: () = msg_send![self.database, saveRecord: record completionHandler: &*reply];
+        }
+        Ok(wait(&rx)??)
+    }
+
+    fn delete(&self, id: &str) -> AppResult<()> {
+        let (tx, rx) = mpsc::channel();
+        let reply = ConcreteBlock::new(move |_record_id: Id, error: Id| {
+            let result = match CloudError::from_ns(error) {
+                Some(e) if e.code != ERROR_UNKNOWN_ITEM => Err(e),
+                _ => Ok(()),
+            };
+            let _ = tx.send(result);
+        })
+        .copy();
+        unsafe {
+            let _: () = msg_send![self.database, deleteRecordWithID: self.record_id(id) completionHandler: &*reply];
+        }
+        Ok(wait(&rx)??)
+    }
+}
@@ -0,0 +1,352 @@
+// One sync pass between the notes and the sync folder. Each side's changes
+// are found by comparing it with the state recorded in `sync_files` after
+// the previous pass. When both sides changed, their edits are merged against
+// the content they last had in common; without one (mappings recorded before
+// it was kept) the newer side wins.
+
+use super::files;
+use crate::commands::buffer::validate_buffer_size;
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::queries::{self, Buffer};
+use crate::db::revisions;
+use crate::db::sync::{self as db_sync, SyncedFile};
+use crate::embeddings::content_hash;
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::merge::{self, MergeLabels};
+use crate::state::AppState;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Conflict marker labels for merges of a file (ours) and its note (theirs)
+const MERGE_LABELS: MergeLabels<'static> = MergeLabels {
+    ours: "file",
+    theirs: "note",
+};
+
+/// What one pass changed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    /// New files imported as notes
+    pub imported: usize,
+    /// New notes written out as files
+    pub exported: usize,
+    /// Notes updated from edited files
+    pub pulled: usize,
+    /// Files rewritten from edited notes
+    pub pushed: usize,
+    /// Notes moved to the trash because their file was deleted
+    pub trashed: usize,
+    /// Files deleted because their note was archived, trashed or deleted
+    pub removed: usize,
+    /// Both sides changed: merged (also counted as pulled and pushed), or
+    /// the newer one kept when there was no common base
+    pub conflicts: usize,
+    /// Per-file failures, retried on the next pass
+    pub errors: Vec<String>,
+    /// Buffers whose content or existence changed (not serialized)
+    #[serde(skip)]
+    pub changed_ids: Vec<String>,
+}
+
+impl SyncReport {
+    pub fn is_empty(&self) -> bool {
+        self.imported + self.exported + self.pulled + self.pushed + self.trashed + self.removed == 0
+            && self.errors.is_empty()
+    }
+}
+
+fn read_file(dir: &Path, name: &str) -> AppResult<String> {
+    fs::read_to_string(dir.join(name)).map_err(|e| AppError::io(format!("Failed to read {}", name), e))
+}
+
+fn record(state: &AppState, synced: &SyncedFile) -> AppResult<()> {
+    db_sync::upsert_synced_file(&state.writer.lock(), synced).db_context("Failed to record sync state")
+}
+
+fn forget(state: &AppState, buffer_id: &str) -> AppResult<()> {
+    db_sync::delete_synced_file(&state.writer.lock(), buffer_id).db_context("Failed to record sync state")
+}
+
+/// Write a note to its file and record both sides as in sync
+fn push(state: &AppState, dir: &Path, buffer: &Buffer, file_name: &str) -> AppResult<()> {
+    let modified = files::write(dir, file_name, &buffer.content)
+        .map_err(|e| AppError::io(format!("Failed to write {}", file_name), e))?;
+    record(
+        state,
+        &SyncedFile {
+            buffer_id: buffer.id.clone(),
+            file_name: file_name.to_string(),
+            content_hash: content_hash(&buffer.content),
+            file_modified: modified,
+            buffer_updated_at: buffer.updated_at,
+            base_content: Some(buffer.content.clone()),
+        },
+    )
+}
+
+/// Replace a note's content with its edited file. Skipped (returns false)
+/// when the note was saved or locked since `buffer` was read; the next pass
+/// compares again.
+/// On a conflict the note's own edits are kept as a revision.
+fn pull(
+    state: &AppState,
+    buffer: &Buffer,
+    file_name: &str,
+    text: &str,
+    modified: i64,
+    conflict: bool,
+) -> AppResult<bool> {
+    validate_buffer_size(text)?;
+    let conn = state.writer.lock();
+    let current = queries::get_buffer_content(&conn, &buffer.id).db_context("Failed to get buffer")?;
+    if current.is_none_or(|c| c.updated_at != buffer.updated_at || c.is_locked) {
+        return Ok(false);
+    }
+
+    let timestamp = Utc::now().timestamp();
+    if conflict {
+        revisions::snapshot(&conn, &buffer.id, text, timestamp, true).db_context("Failed to save revision")?;
+    }
+    if !queries::update_buffer_content(&conn, &buffer.id, text, timestamp).db_context("Failed to save buffer")? {
+        return Ok(false);
+    }
+    let detail = format!("sync:{}", file_name);
+    activity::record(&conn, Some(&buffer.id), Operation::Save, Origin::Sync, Some(&detail));
+    db_sync::upsert_synced_file(
+        &conn,
+        &SyncedFile {
+            buffer_id: buffer.id.clone(),
+            file_name: file_name.to_string(),
+            content_hash: content_hash(text),
+            file_modified: modified,
+            buffer_updated_at: timestamp,
+            base_content: Some(text.to_string()),
+        },
+    )
+    .db_context("Failed to record sync state")?;
+    Ok(true)
+}
+
+/// Merge the edits made to a note and its file since `base`, the content both
+/// last had, and write the result to both sides. Overlapping edits are kept
+/// between conflict markers. The file is written first: if the note was saved
+/// meanwhile (the pull is skipped), the next pass merges again.
+fn merge_both(
+    state: &AppState,
+    dir: &Path,
+    buffer: &Buffer,
+    file_name: &str,
+    base: &str,
+    text: &str,
+) -> AppResult<bool> {
+    let merged = merge::merge3(base, text, &buffer.content, MERGE_LABELS);
+    validate_buffer_size(&merged.text)?;
+    if merged.conflicts > 0 {
+        warn!("Sync merge of {} left {} conflicts", file_name, merged.conflicts);
+    }
+    let modified = files::write(dir, file_name, &merged.text)
+        .map_err(|e| AppError::io(format!("Failed to write {}", file_name), e))?;
+    pull(state, buffer, file_name, &merged.text, modified, true)
+}
+
+/// Create a note from a file not mirrored yet. Empty files are left alone.
+fn import(state: &AppState, dir: &Path, file_name: &str, modified: i64) -> AppResult<Option<String>> {
+    let text = read_file(dir, file_name)?;
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    validate_buffer_size(&text)?;
+
+    let id = Uuid::new_v4().to_string();
+    let source = format!("sync:{}", file_name);
+    let timestamp = Utc::now().timestamp();
+    let conn = state.writer.lock();
+    queries::create_buffer(&conn, &id, &text, &source, timestamp).db_context("Failed to create buffer")?;
+    activity::record(&conn, Some(&id), Operation::Create, Origin::Sync, Some(&source));
+    db_sync::upsert_synced_file(
+        &conn,
+        &SyncedFile {
+            buffer_id: id.clone(),
+            file_name: file_name.to_string(),
+            content_hash: content_hash(&text),
+            file_modified: modified,
+            buffer_updated_at: timestamp,
+            base_content: Some(text.clone()),
+        },
+    )
+    .db_context("Failed to record sync state")?;
+    Ok(Some(id))
+}
+
+/// Bring one mapped note and its file back in sync. Returns the file name
+/// when the file should be imported as a new note instead.
+fn sync_mapped(
+    state: &AppState,
+    dir: &Path,
+    synced: &SyncedFile,
+    buffer: Option<&Buffer>,
+    modified: Option<i64>,
+    report: &mut SyncReport,
+) -> AppResult<Option<String>> {
+    match (buffer, modified) {
+        (None, None) => forget(state, &synced.buffer_id)?,
+        (None, Some(modified)) => {
+            forget(state, &synced.buffer_id)?;
+            if modified != synced.file_modified {
+                // Edited after its note went away: keep the edit as a new note
+                return Ok(Some(synced.file_name.clone()));
+            }
+            fs::remove_file(dir.join(&synced.file_name))
+                .map_err(|e| AppError::io(format!("Failed to remove {}", synced.file_name), e))?;
+            report.removed += 1;
+        }
+        (Some(buffer), None) => {
+            if buffer.updated_at != synced.buffer_updated_at {
+                // Edited since the file was deleted: write it out again
+                push(state, dir, buffer, &synced.file_name)?;
+                report.pushed += 1;
+                return Ok(None);
+            }
+            let conn = state.writer.lock();
+            queries::trash_buffer(&conn, &buffer.id, Utc::now().timestamp()).db_context("Failed to trash buffer")?;
+            activity::record(&conn, Some(&buffer.id), Operation::Trash, Origin::Sync, None);
+            db_sync::delete_synced_file(&conn, &buffer.id).db_context("Failed to record sync state")?;
+            report.trashed += 1;
+            report.changed_ids.push(buffer.id.clone());
+        }
+        (Some(buffer), Some(modified)) => {
+            let buffer_changed = buffer.updated_at != synced.buffer_updated_at
+                && content_hash(&buffer.content) != synced.content_hash;
+            let text = if modified != synced.file_modified {
+                Some(read_file(dir, &synced.file_name)?)
+            } else {
+                None
+            };
+            let file_changed = text.as_ref().is_some_and(|t| content_hash(t) != synced.content_hash);
+
+            let conflict = buffer_changed && file_changed;
+            if conflict {
+                report.conflicts += 1;
+            }
+            // Edits within the same second go to the file: the note's are kept as a revision
+            let file_wins = file_changed && (!buffer_changed || modified / 1000 >= buffer.updated_at);
+            match (text, synced.base_content.as_deref()) {
+                (Some(text), Some(base)) if conflict => {
+                    let merged = merge_both(state, dir, buffer, &synced.file_name, base, &text)?;
+                    if merged {
+                        report.pulled += 1;
+                        report.pushed += 1;
+                        report.changed_ids.push(buffer.id.clone());
+                    }
+                }
+                (Some(text), _) if file_wins => {
+                    let pulled = pull(state, buffer, &synced.file_name, &text, modified, conflict)?;
+                    if pulled {
+                        report.pulled += 1;
+                        report.changed_ids.push(buffer.id.clone());
+                    }
+                }
+                _ if buffer_changed => {
+                    push(state, dir, buffer, &synced.file_name)?;
+                    report.pushed += 1;
+                }
+                _ if modified != synced.file_modified || buffer.updated_at != synced.buffer_updated_at => {
+                    // Touched without a content change: remember the new times
+                    record(
+                        state,
+                        &SyncedFile {
+                            file_modified: modified,
+                            buffer_updated_at: buffer.updated_at,
+                            ..synced.clone()
+                        },
+                    )?;
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Run one sync pass between active (non-archived) notes and `dir`. Locked
+/// notes are left out both ways: their file is removed like an archived note's.
+pub fn run(state: &AppState, dir: &Path) -> AppResult<SyncReport> {
+    let (buffers, synced) = state
+        .read(|conn| Ok((queries::get_all_buffers(conn)?, db_sync::get_synced_files(conn)?)))
+        .db_context("Failed to read sync state")?;
+    let mut buffers: HashMap<String, Buffer> =
+        buffers.into_iter().filter(|b| !b.is_archived && !b.is_locked).map(|b| (b.id.clone(), b)).collect();
+    let mut files = files::list(dir).map_err(|e| AppError::io("Failed to read sync folder", e))?;
+    let mut taken: HashSet<String> = files
+        .keys()
+        .chain(synced.iter().map(|s| &s.file_name))
+        .map(|n| n.to_lowercase())
+        .collect();
+
+    let mut report = SyncReport::default();
+    let mut unclaimed = Vec::new();
+    for entry in &synced {
+        let buffer = buffers.remove(&entry.buffer_id);
+        let modified = files.remove(&entry.file_name);
+        match sync_mapped(state, dir, entry, buffer.as_ref(), modified, &mut report) {
+            Ok(Some(file_name)) => unclaimed.push((file_name, modified.unwrap_or_default())),
+            Ok(None) => {}
+            Err(e) => {
+                warn!("Sync failed for {}: {}", entry.file_name, e);
+                report.errors.push(format!("{}: {}", entry.file_name, e));
+            }
+        }
+    }
+
+    // Files nobody has seen yet become notes
+    unclaimed.extend(files);
+    for (file_name, modified) in unclaimed {
+        match import(state, dir, &file_name, modified) {
+            Ok(Some(id)) => {
+                report.imported += 1;
+                report.changed_ids.push(id);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                warn!("Sync failed to import {}: {}", file_name, e);
+                report.errors.push(format!("{}: {}", file_name, e));
+            }
+        }
+    }
+
+    // Notes without a file get one
+    for buffer in buffers.values() {
+        let title = buffer
+            .title_override
+            .clone()
+            .unwrap_or_else(|| queries::extract_title_preview(&buffer.content).0);
+        let file_name = files::unique_name(&mut taken, &title);
+        match push(state, dir, buffer, &file_name) {
+            Ok(()) => report.exported += 1,
+            Err(e) => {
+                warn!("Sync failed to write {}: {}", file_name, e);
+                report.errors.push(format!("{}: {}", file_name, e));
+            }
+        }
+    }
+
+    if !report.is_empty() {
+        info!(
+            "Synced {:?}: {} imported, {} exported, {} pulled, {} pushed, {} trashed, {} removed, {} conflicts",
+            dir,
+            report.imported,
+            report.exported,
+            report.pulled,
+            report.pushed,
+            report.trashed,
+            report.removed,
+            report.conflicts
+        );
+    }
+    Ok(report)
+}
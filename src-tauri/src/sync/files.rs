@@ -0,0 +1,62 @@
+// File system side of folder sync: listing the Markdown files in the sync
+// folder, reading them and writing notes out.
+
+use crate::export;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Extension of synced files
+const EXTENSION: &str = "md";
+
+/// Modification time in Unix milliseconds (seconds are too coarse to tell
+/// our own write from an edit made right after it)
+pub fn modified_ms(path: &Path) -> io::Result<i64> {
+    let modified = fs::metadata(path)?.modified()?;
+    Ok(modified.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0))
+}
+
+/// Markdown files directly in `dir` (hidden files skipped), by file name
+pub fn list(dir: &Path) -> io::Result<HashMap<String, i64>> {
+    let mut files = HashMap::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let is_markdown = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case(EXTENSION));
+        if name.starts_with('.') || !is_markdown || !path.is_file() {
+            continue;
+        }
+        files.insert(name.to_string(), modified_ms(&path)?);
+    }
+    Ok(files)
+}
+
+/// Write a note to `dir/name` through a temporary file, so other apps never
+/// see it half-written. Returns the new modification time.
+pub fn write(dir: &Path, name: &str, content: &str) -> io::Result<i64> {
+    let path = dir.join(name);
+    let temp = dir.join(format!(".{}.tmp", name));
+    fs::write(&temp, content)?;
+    fs::rename(&temp, &path)?;
+    modified_ms(&path)
+}
+
+/// A file name for a note titled `title` that isn't in `taken` (compared
+/// case-insensitively), which it is then added to
+pub fn unique_name(taken: &mut HashSet<String>, title: &str) -> String {
+    let stem = export::file_stem(title);
+    let mut name = format!("{}.{}", stem, EXTENSION);
+    let mut n = 2;
+    while !taken.insert(name.to_lowercase()) {
+        name = format!("{} {}.{}", stem, n, EXTENSION);
+        n += 1;
+    }
+    name
+}
@@ -0,0 +1,281 @@
+// Two-way sync between the notes and a folder of Markdown files (e.g. an
+// Obsidian vault). Every active note is mirrored to one `.md` file; edits on
+// either side are carried over, new files become notes, a deleted file sends
+// its note to the trash, and archiving or trashing a note removes its file.
+// The folder is polled like the inbox folder; see `engine` for one pass.
+//
+// iCloud sync (`cloud`, over CloudKit on macOS and iOS) works the same way
+// against the user's private database, polled less often; it is turned on
+// with a setting and runs independently of the folder.
+
+mod cloud;
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod cloudkit;
+mod engine;
+mod files;
+
+pub use engine::SyncReport;
+
+use crate::db::{cloud_sync as db_cloud, queries, sync as db_sync};
+use crate::error::{AppError, AppResult, DbResultExt};
+use crate::live_merge;
+use crate::state::AppState;
+use chrono::Utc;
+use parking_lot::Mutex;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tracing::{debug, warn};
+
+/// Setting holding the sync folder (empty = off)
+pub const SYNC_DIR_SETTING: &str = "sync_dir";
+
+/// Setting turning iCloud sync on
+pub const CLOUD_SYNC_SETTING: &str = "cloud_sync_enabled";
+
+/// How often the sync folder is compared with the notes
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often iCloud is asked for changes (CloudKit throttles busy clients)
+const CLOUD_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Outcome of the latest sync pass
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncStatus {
+    pub dir: String,
+    pub last_synced_at: Option<i64>,
+    /// Totals of the latest pass that changed something
+    pub last_changes: Option<SyncReport>,
+    pub last_error: Option<String>,
+}
+
+/// Status of the sync engine; passes never overlap
+pub struct FolderSync {
+    status: Mutex<SyncStatus>,
+    pass: Mutex<()>,
+}
+
+impl FolderSync {
+    pub fn new() -> Self {
+        Self {
+            status: Mutex::new(SyncStatus::default()),
+            pass: Mutex::new(()),
+        }
+    }
+
+    pub fn status(&self) -> SyncStatus {
+        self.status.lock().clone()
+    }
+}
+
+impl Default for FolderSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Outcome of the latest iCloud sync pass
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CloudSyncStatus {
+    /// Whether this build can sync with iCloud
+    pub available: bool,
+    pub last_synced_at: Option<i64>,
+    /// Totals of the latest pass that changed something
+    pub last_changes: Option<SyncReport>,
+    pub last_error: Option<String>,
+}
+
+/// Status of iCloud sync; passes never overlap
+pub struct CloudSync {
+    status: Mutex<CloudSyncStatus>,
+    pass: Mutex<()>,
+}
+
+impl CloudSync {
+    pub fn new() -> Self {
+        Self {
+            status: Mutex::new(CloudSyncStatus::default()),
+            pass: Mutex::new(()),
+        }
+    }
+
+    pub fn status(&self) -> CloudSyncStatus {
+        CloudSyncStatus {
+            available: cloud_available(),
+            ..self.status.lock().clone()
+        }
+    }
+}
+
+impl Default for CloudSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether iCloud sync can be turned on: an Apple platform and a build signed
+/// with the iCloud entitlement
+pub fn cloud_available() -> bool {
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    return cloudkit::available();
+    #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+    return false;
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+use cloudkit::with_database;
+
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+fn with_database<T>(_f: impl FnOnce(&dyn cloud::CloudDatabase) -> AppResult<T>) -> AppResult<T> {
+    Err(AppError::Unsupported("iCloud sync"))
+}
+
+/// Check a folder chosen for syncing
+pub fn validate_dir(value: &str) -> AppResult<()> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    let path = Path::new(value);
+    if !path.is_absolute() || !path.is_dir() {
+        return Err(AppError::Validation(format!("Sync folder must be an existing directory: {}", value)));
+    }
+    Ok(())
+}
+
+/// Start over with a new folder: forget which files mirrored which notes
+pub fn reset(state: &AppState, conn: &Connection) -> AppResult<()> {
+    *state.folder_sync.status.lock() = SyncStatus::default();
+    db_sync::clear(conn).db_context("Failed to reset sync state")
+}
+
+/// Forget which records mirrored which notes, e.g. when iCloud sync is
+/// turned off; turning it back on matches notes and records up again
+pub fn reset_cloud(state: &AppState, conn: &Connection) -> AppResult<()> {
+    *state.cloud_sync.status.lock() = CloudSyncStatus::default();
+    db_cloud::clear(conn).db_context("Failed to reset iCloud sync state")
+}
+
+/// Run one sync pass now, waiting for a running one to finish first.
+/// Returns None when sync is off.
+pub fn sync_now(app: &AppHandle) -> AppResult<Option<SyncReport>> {
+    let state = app.state::<AppState>();
+    let _pass = state.folder_sync.pass.lock();
+    run_pass(app, &state)
+}
+
+fn run_pass(app: &AppHandle, state: &AppState) -> AppResult<Option<SyncReport>> {
+    let dir = state.read(queries::get_settings).db_context("Failed to get settings")?.sync_dir;
+    if dir.is_empty() {
+        return Ok(None);
+    }
+    if !Path::new(&dir).is_dir() {
+        debug!("Sync folder {:?} is not available", dir);
+        return Ok(None);
+    }
+
+    let result = engine::run(state, Path::new(&dir));
+    let mut status = state.folder_sync.status.lock();
+    status.dir = dir;
+    status.last_synced_at = Some(Utc::now().timestamp());
+    match &result {
+        Ok(report) => {
+            status.last_error = None;
+            if !report.is_empty() {
+                status.last_changes = Some(report.clone());
+            }
+        }
+        Err(e) => status.last_error = Some(e.to_string()),
+    }
+    drop(status);
+
+    let report = result?;
+    if !report.changed_ids.is_empty() {
+        live_merge::notify(app, report.changed_ids.clone(), None);
+    }
+    Ok(Some(report))
+}
+
+/// One poll; skipped while another pass is running
+fn poll(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let Some(_pass) = state.folder_sync.pass.try_lock() else {
+        return;
+    };
+    if let Err(e) = run_pass(app, &state) {
+        warn!("Folder sync failed: {}", e);
+    }
+}
+
+/// Run one iCloud sync pass now, waiting for a running one to finish first.
+/// Returns None when iCloud sync is off.
+pub fn cloud_sync_now(app: &AppHandle) -> AppResult<Option<SyncReport>> {
+    let state = app.state::<AppState>();
+    let _pass = state.cloud_sync.pass.lock();
+    run_cloud_pass(app, &state)
+}
+
+fn run_cloud_pass(app: &AppHandle, state: &AppState) -> AppResult<Option<SyncReport>> {
+    let enabled = state.read(queries::get_settings).db_context("Failed to get settings")?.cloud_sync_enabled;
+    if !enabled {
+        return Ok(None);
+    }
+
+    let result = with_database(|db| cloud::run(state, db));
+    let mut status = state.cloud_sync.status.lock();
+    status.last_synced_at = Some(Utc::now().timestamp());
+    match &result {
+        Ok(report) => {
+            status.last_error = None;
+            if !report.is_empty() {
+                status.last_changes = Some(report.clone());
+            }
+        }
+        Err(e) => status.last_error = Some(e.to_string()),
+    }
+    drop(status);
+
+    let report = result?;
+    if !report.changed_ids.is_empty() {
+        live_merge::notify(app, report.changed_ids.clone(), None);
+    }
+    Ok(Some(report))
+}
+
+/// One iCloud poll; skipped while another pass is running
+fn poll_cloud(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let Some(_pass) = state.cloud_sync.pass.try_lock() else {
+        return;
+    };
+    if let Err(e) = run_cloud_pass(app, &state) {
+        warn!("iCloud sync failed: {}", e);
+    }
+}
+
+/// Spawn the thread polling the sync folder and iCloud (each idle while its
+/// setting is off)
+pub fn spawn(app: AppHandle) {
+    let spawned = thread::Builder::new()
+        .name("folder-sync".to_string())
+        .spawn(move || {
+            let mut last_cloud_poll: Option<Instant> = None;
+            loop {
+                // Skipped while the encrypted database is locked
+                if !app.state::<AppState>().ready.is_locked() {
+                    poll(&app);
+                    if last_cloud_poll.is_none_or(|at| at.elapsed() >= CLOUD_POLL_INTERVAL) {
+                        poll_cloud(&app);
+                        last_cloud_poll = Some(Instant::now());
+                    }
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+
+    if let Err(e) = spawned {
+        warn!("Failed to start folder sync: {}", e);
+    }
+}
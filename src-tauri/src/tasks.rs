@@ -0,0 +1,83 @@
+// Markdown checklist items and their inline due dates
+
+use chrono::NaiveDate;
+
+/// Inline due date markers, each followed by a `YYYY-MM-DD` date
+/// (`@due(2024-05-01)`, or the Obsidian Tasks style `📅 2024-05-01`)
+pub const DUE_MARKERS: [&str; 2] = ["@due(", "📅"];
+
+/// A checkbox list item (`- [ ] text`, `- [x] text`)
+#[derive(Debug)]
+pub struct Task<'a> {
+    /// 0-based line in the note
+    pub line: usize,
+    pub done: bool,
+    pub text: &'a str,
+}
+
+/// Parse a line as a checkbox item under a `-`, `*`, `+` or numbered list marker
+fn parse_task_line(line: &str) -> Option<(bool, &str)> {
+    let trimmed = line.trim_start();
+    let item = match trimmed.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = trimmed.len() - trimmed.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            match trimmed[digits..].strip_prefix(['.', ')']) {
+                Some(rest) if digits > 0 => rest,
+                _ => return None,
+            }
+        }
+    };
+    if !item.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    let item = item.trim_start();
+    let done = match item.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let text = &item[3..];
+    if !(text.is_empty() || text.starts_with(char::is_whitespace)) {
+        return None;
+    }
+    Some((done, text.trim()))
+}
+
+/// Checkbox items outside code fences, in order
+pub fn parse_tasks(content: &str) -> Vec<Task<'_>> {
+    let mut tasks = Vec::new();
+    let mut in_fence = false;
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if let Some((done, text)) = parse_task_line(line) {
+            tasks.push(Task { line: index, done, text });
+        }
+    }
+    tasks
+}
+
+/// Open and total checkbox items
+pub fn count(content: &str) -> (i64, i64) {
+    let tasks = parse_tasks(content);
+    let open = tasks.iter().filter(|t| !t.done).count();
+    (open as i64, tasks.len() as i64)
+}
+
+/// The first inline due date in a task's text
+pub fn due_date(text: &str) -> Option<NaiveDate> {
+    DUE_MARKERS.iter().find_map(|marker| {
+        text.match_indices(marker).find_map(|(i, _)| {
+            let date = text[i + marker.len()..].trim_start().get(..10)?;
+            NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+        })
+    })
+}
@@ -0,0 +1,74 @@
+// Soft deletion: a trashed note keeps its content and attachments but drops
+// out of the sidebar, archive, search and exports until it is restored.
+// Notes left in the trash longer than `trash_retention_days` are deleted for
+// good on startup; emptying the trash deletes them right away.
+
+use crate::attachments;
+use crate::db::activity::{self, Operation, Origin};
+use crate::db::attachments as db_attachments;
+use crate::db::{connection, queries};
+use crate::error::{AppResult, DbResultExt};
+use crate::journal;
+use crate::state::AppState;
+use chrono::Utc;
+use rusqlite::Connection;
+use std::path::Path;
+use tracing::{info, warn};
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// Permanently delete buffers along with their attachment files and journal
+/// entries. Returns the ids that existed.
+pub fn delete_permanently(
+    state: &AppState,
+    conn: &Connection,
+    ids: &[String],
+    origin: Origin,
+) -> AppResult<Vec<String>> {
+    let mut deleted = Vec::new();
+    let mut attachment_paths = Vec::new();
+    for id in ids {
+        // Attachment rows cascade; their files are removed below
+        let paths = db_attachments::get_stored_paths(conn, id).db_context("Failed to get attachments")?;
+        attachment_paths.extend(paths);
+        if queries::delete_buffer(conn, id).db_context("Failed to delete buffer")? {
+            deleted.push(id.clone());
+        }
+    }
+    if deleted.is_empty() {
+        return Ok(deleted);
+    }
+
+    activity::record_batch(conn, &deleted, Operation::Delete, origin, None);
+    for path in attachment_paths {
+        attachments::remove_file(Path::new(&path));
+    }
+    connection::scrub_after_delete(conn);
+
+    let journal_dir = journal::journal_dir(&state.app_data_dir);
+    for id in &deleted {
+        journal::remove_entry(&journal_dir, id);
+        state.mru.remove(id);
+    }
+    Ok(deleted)
+}
+
+/// Delete notes trashed more than `trash_retention_days` ago (0 = keep forever)
+pub fn purge_expired(state: &AppState) {
+    let days = state.read(queries::get_settings).map(|s| s.trash_retention_days).unwrap_or(0);
+    if days <= 0 {
+        return;
+    }
+
+    let cutoff = Utc::now().timestamp() - i64::from(days) * DAY_SECS;
+    let conn = state.writer.lock();
+    let result = queries::get_trashed_ids(&conn, Some(cutoff))
+        .db_context("Failed to get trashed buffers")
+        .and_then(|ids| delete_permanently(state, &conn, &ids, Origin::System));
+
+    match result {
+        Ok(ids) if ids.is_empty() => {}
+        Ok(ids) => info!("Purged {} buffers trashed more than {} days ago", ids.len(), days),
+        Err(e) => warn!("Failed to purge trash: {}", e),
+    }
+}
@@ -0,0 +1,180 @@
+// Menu bar / system tray icon. Its menu lists the first sidebar notes, a
+// "New Note" item and show/hide; it is rebuilt (debounced) on every buffer
+// change event (see `events`) and after a backup is restored.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+
+/// Emitted to the frontend to open a note picked from the tray or a deep link
+pub const OPEN_BUFFER_EVENT: &str = "open-buffer";
+
+/// Payload for `OPEN_BUFFER_EVENT`
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenBuffer {
+    pub id: String,
+}
+
+/// Bring the main window up and tell it to open a note (tray menu, deep links)
+pub fn open_buffer(app: &AppHandle, id: String) {
+    #[cfg(desktop)]
+    crate::hotkey::show_main_window(app);
+    if let Err(e) = app.emit(OPEN_BUFFER_EVENT, OpenBuffer { id }) {
+        warn!("Failed to emit {} event: {}", OPEN_BUFFER_EVENT, e);
+    }
+}
+
+#[cfg(desktop)]
+pub use desktop::install;
+
+#[cfg(desktop)]
+mod desktop {
+    use super::open_buffer;
+    use crate::commands::backup::DATABASE_RESTORED_EVENT;
+    use crate::commands::buffer::insert_buffer;
+    use crate::db::activity::Origin;
+    use crate::db::queries::{self, BufferSummary};
+    use crate::events::BUFFER_EVENTS;
+    use crate::hotkey;
+    use crate::i18n::t;
+    use crate::state::AppState;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::thread;
+    use std::time::Duration;
+    use tauri::menu::{Menu, MenuBuilder, MenuEvent, MenuItem, PredefinedMenuItem};
+    use tauri::tray::TrayIconBuilder;
+    use tauri::{AppHandle, Listener, Manager};
+    use tracing::{info, warn};
+
+    const TRAY_ID: &str = "main";
+
+    /// Notes listed in the tray menu
+    const RECENT_COUNT: usize = 10;
+
+    /// Longest note title shown in the menu
+    const MAX_TITLE_CHARS: usize = 40;
+
+    /// Quiet period before rebuilding the menu, so typing doesn't rebuild it per save
+    const REFRESH_DELAY: Duration = Duration::from_millis(500);
+
+    /// Menu item id prefix of a note, followed by its id
+    const NOTE_ITEM_PREFIX: &str = "tray:note:";
+
+    /// Latest refresh request; older pending refreshes are skipped
+    static REFRESH_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+    fn title(summary: &BufferSummary) -> String {
+        let title = summary.title.trim();
+        if title.is_empty() {
+            return t("Untitled").to_string();
+        }
+        match title.char_indices().nth(MAX_TITLE_CHARS) {
+            Some((end, _)) => format!("{}…", &title[..end]),
+            None => title.to_string(),
+        }
+    }
+
+    fn build_menu(app: &AppHandle) -> tauri::Result<Menu> {
+        let state = app.state::<AppState>();
+        let recent = state
+            .read(|conn| queries::get_sidebar_buffers(conn, RECENT_COUNT, 0))
+            .unwrap_or_else(|e| {
+                warn!("Failed to load notes for the tray menu: {}", e);
+                Vec::new()
+            });
+
+        let mut menu = MenuBuilder::new(app);
+        for summary in &recent {
+            let id = format!("{}{}", NOTE_ITEM_PREFIX, summary.id);
+            menu = menu.item(&MenuItem::with_id(app, id, title(summary), true, None::<&str>)?);
+        }
+        if !recent.is_empty() {
+            menu = menu.separator();
+        }
+        menu.item(&MenuItem::with_id(app, "tray:new", t("New Note"), true, None::<&str>)?)
+            .item(&MenuItem::with_id(app, "tray:toggle", t("Show/Hide Flashnotes"), true, None::<&str>)?)
+            .separator()
+            .item(&PredefinedMenuItem::quit(app, Some(t("Quit Flashnotes")))?)
+            .build()
+    }
+
+    fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+        let id = event.id().as_ref();
+        if let Some(buffer_id) = id.strip_prefix(NOTE_ITEM_PREFIX) {
+            open_buffer(app, buffer_id.to_string());
+            return;
+        }
+        match id {
+            "tray:new" => {
+                let state = app.state::<AppState>();
+                match insert_buffer(app, &state, "", "manual", Origin::Ui) {
+                    Ok(summary) => open_buffer(app, summary.id),
+                    Err(e) => warn!("Failed to create note from tray: {}", e),
+                }
+            }
+            "tray:toggle" => hotkey::toggle_main_window(app),
+            _ => {}
+        }
+    }
+
+    /// Rebuild the menu after a quiet period
+    fn schedule_refresh(app: &AppHandle) {
+        let generation = REFRESH_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        let app = app.clone();
+        let spawned = thread::Builder::new().name("tray-refresh".to_string()).spawn(move || {
+            thread::sleep(REFRESH_DELAY);
+            if REFRESH_GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            let Some(tray) = app.tray_by_id(TRAY_ID) else {
+                return;
+            };
+            match build_menu(&app) {
+                Ok(menu) => {
+                    if let Err(e) = tray.set_menu(Some(menu)) {
+                        warn!("Failed to update tray menu: {}", e);
+                    }
+                }
+                Err(e) => warn!("Failed to build tray menu: {}", e),
+            }
+        });
+        if let Err(e) = spawned {
+            warn!("Failed to schedule tray refresh: {}", e);
+        }
+    }
+
+    /// Create the tray icon once the database is ready; later calls (after a
+    /// restore or unlock) just refresh its menu
+    pub fn install(app: &AppHandle) {
+        if app.tray_by_id(TRAY_ID).is_some() {
+            schedule_refresh(app);
+            return;
+        }
+
+        let menu = match build_menu(app) {
+            Ok(menu) => menu,
+            Err(e) => {
+                warn!("Failed to build tray menu: {}", e);
+                return;
+            }
+        };
+        let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+            .menu(&menu)
+            .tooltip("Flashnotes")
+            .show_menu_on_left_click(true)
+            .on_menu_event(handle_menu_event);
+        if let Some(icon) = app.default_window_icon() {
+            builder = builder.icon(icon.clone());
+        }
+        if let Err(e) = builder.build(app) {
+            warn!("Failed to create tray icon: {}", e);
+            return;
+        }
+
+        for event in BUFFER_EVENTS.iter().chain([&DATABASE_RESTORED_EVENT]) {
+            let handle = app.clone();
+            app.listen(*event, move |_| schedule_refresh(&handle));
+        }
+        info!("Tray icon installed");
+    }
+}
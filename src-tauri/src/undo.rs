@@ -0,0 +1,177 @@
+// Undo/redo of structural operations (pin, archive, trash, reorder), driven by the
+// activity log. The log stays append-only: undo and redo are logged as
+// entries themselves, and the undo/redo stacks are rebuilt by replaying it.
+
+use crate::db::activity::{self, LoggedOperation, NewEntry, Operation, Origin};
+use crate::db::queries;
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+/// How far back in the log undo can reach
+const MAX_HISTORY: usize = 500;
+
+/// Operations undo/redo can reverse
+const UNDOABLE: [Operation; 7] = [
+    Operation::Pin,
+    Operation::Unpin,
+    Operation::Archive,
+    Operation::Unarchive,
+    Operation::Trash,
+    Operation::Restore,
+    Operation::Reorder,
+];
+
+/// Sort positions before and after a reorder, stored in the log entry
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReorderData {
+    pub before: Vec<(String, i64)>,
+    pub after: Vec<(String, i64)>,
+}
+
+/// What an undo or redo changed
+#[derive(Debug, Serialize)]
+pub struct UndoResult {
+    /// The operation undone or redone ("pin", "archive", "reorder", ...)
+    pub operation: String,
+    pub buffer_ids: Vec<String>,
+}
+
+/// One user-level step: a single entry, or every entry of a batch
+struct Step {
+    key: String,
+    entries: Vec<LoggedOperation>,
+}
+
+/// Group undoable entries into steps and replay undo/redo entries over
+/// them. Returns the (undo, redo) stacks, most recent last.
+fn stacks(log: Vec<LoggedOperation>) -> (Vec<Step>, Vec<Step>) {
+    let mut undo: Vec<Step> = Vec::new();
+    let mut redo: Vec<Step> = Vec::new();
+
+    for entry in log {
+        match entry.operation {
+            Operation::Undo => {
+                if let Some(step) = undo.pop() {
+                    redo.push(step);
+                }
+            }
+            Operation::Redo => {
+                if let Some(step) = redo.pop() {
+                    undo.push(step);
+                }
+            }
+            _ => {
+                let key = entry.batch_id.clone().unwrap_or_else(|| entry.id.to_string());
+                match undo.last_mut() {
+                    Some(step) if entry.batch_id.is_some() && step.key == key => step.entries.push(entry),
+                    _ => {
+                        redo.clear();
+                        undo.push(Step { key, entries: vec![entry] });
+                    }
+                }
+            }
+        }
+    }
+    (undo, redo)
+}
+
+/// Apply a step's entries backwards (`reverse`) or forwards again
+fn apply(conn: &Connection, step: &Step, reverse: bool) -> rusqlite::Result<Vec<String>> {
+    let mut ids: Vec<String> = Vec::new();
+    let mut touch = |id: &str| {
+        if !ids.iter().any(|i| i == id) {
+            ids.push(id.to_string());
+        }
+    };
+
+    let entries: Box<dyn Iterator<Item = &LoggedOperation>> = if reverse {
+        Box::new(step.entries.iter().rev())
+    } else {
+        Box::new(step.entries.iter())
+    };
+    for entry in entries {
+        match (entry.operation, entry.buffer_id.as_deref()) {
+            (Operation::Pin | Operation::Unpin, Some(id)) => {
+                let pinned = (entry.operation == Operation::Pin) != reverse;
+                if queries::set_pinned(conn, id, pinned)? {
+                    touch(id);
+                }
+            }
+            (Operation::Archive | Operation::Unarchive, Some(id)) => {
+                let archived = (entry.operation == Operation::Archive) != reverse;
+                if queries::set_archived(conn, id, archived, Utc::now().timestamp())? {
+                    touch(id);
+                }
+            }
+            (Operation::Trash | Operation::Restore, Some(id)) => {
+                let changed = if (entry.operation == Operation::Trash) != reverse {
+                    queries::trash_buffer(conn, id, Utc::now().timestamp())?
+                } else {
+                    queries::restore_buffer(conn, id)?
+                };
+                if changed {
+                    touch(id);
+                }
+            }
+            (Operation::Reorder, _) => {
+                let Some(data) = entry.data.as_deref().and_then(|d| serde_json::from_str::<ReorderData>(d).ok())
+                else {
+                    continue;
+                };
+                let orders = if reverse { &data.before } else { &data.after };
+                queries::set_sort_orders(conn, orders)?;
+                for (id, _) in orders {
+                    touch(id);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(ids)
+}
+
+/// Reverse the latest structural step (`redo` false), or re-apply the
+/// latest undone one. Returns None when there is nothing to undo or redo.
+fn run(conn: &mut Connection, redo: bool) -> rusqlite::Result<Option<UndoResult>> {
+    let mut operations = UNDOABLE.to_vec();
+    operations.extend([Operation::Undo, Operation::Redo]);
+    let log = activity::get_operations(conn, &operations, MAX_HISTORY)?;
+
+    let (undo_stack, redo_stack) = stacks(log);
+    let step = if redo { redo_stack.last() } else { undo_stack.last() };
+    let Some(step) = step else {
+        return Ok(None);
+    };
+
+    let tx = conn.transaction()?;
+    let buffer_ids = apply(&tx, step, !redo)?;
+    activity::insert_entry(
+        &tx,
+        &NewEntry {
+            buffer_id: None,
+            operation: if redo { Operation::Redo } else { Operation::Undo },
+            origin: Origin::Ui,
+            detail: Some(step.entries[0].operation.as_str()),
+            batch_id: None,
+            data: None,
+        },
+        Utc::now().timestamp(),
+    )?;
+    tx.commit()?;
+
+    Ok(Some(UndoResult {
+        operation: step.entries[0].operation.as_str().to_string(),
+        buffer_ids,
+    }))
+}
+
+/// Undo the latest pin, archive or reorder (a bulk operation as a whole)
+pub fn undo(conn: &mut Connection) -> rusqlite::Result<Option<UndoResult>> {
+    run(conn, false)
+}
+
+/// Redo the latest undone operation
+pub fn redo(conn: &mut Connection) -> rusqlite::Result<Option<UndoResult>> {
+    run(conn, true)
+}
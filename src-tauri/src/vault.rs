@@ -0,0 +1,335 @@
+// Obsidian vault import: turns a folder of Markdown files into buffers,
+// converting Obsidian links to flashnotes wiki links and collecting
+// embedded images to import as attachments
+
+use crate::db::queries::{self, Buffer};
+use crate::error::{AppError, AppResult};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+/// Extensions imported as attachments when embedded in a note
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "avif"];
+
+/// A vault note ready to store, with the image files it embeds
+pub struct VaultNote {
+    pub buffer: Buffer,
+    pub embeds: Vec<PathBuf>,
+}
+
+/// Result of reading a vault
+pub struct VaultScan {
+    pub notes: Vec<VaultNote>,
+    /// Embedded files that couldn't be found in the vault ("note.md: image.png")
+    pub missing_embeds: Vec<String>,
+}
+
+/// Markdown files and other files in a vault, skipping hidden folders
+/// (`.obsidian`, `.trash`, `.git`)
+fn walk(dir: &Path, notes: &mut Vec<PathBuf>, files: &mut Vec<PathBuf>) -> AppResult<()> {
+    let entries = fs::read_dir(dir).map_err(|e| AppError::io(format!("Failed to read {}", dir.display()), e))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            walk(&path, notes, files)?;
+        } else if is_markdown(&path) {
+            notes.push(path);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("md"))
+}
+
+fn is_image(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Vault files by lowercased name and by lowercased vault-relative path, the
+/// two ways Obsidian embeds refer to them
+fn index_files(root: &Path, files: &[PathBuf]) -> HashMap<String, PathBuf> {
+    let mut index = HashMap::new();
+    for path in files {
+        if let Ok(relative) = path.strip_prefix(root) {
+            index.insert(relative.to_string_lossy().replace('\\', "/").to_lowercase(), path.clone());
+        }
+        if let Some(name) = path.file_name() {
+            index.entry(name.to_string_lossy().to_lowercase()).or_insert_with(|| path.clone());
+        }
+    }
+    index
+}
+
+/// Note name of an Obsidian link target: the file name without folder or `.md`
+fn note_name(target: &str) -> &str {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    name.strip_suffix(".md").unwrap_or(name)
+}
+
+/// Minimal percent-decoding for Markdown image paths (`my%20image.png`)
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Converts one note's content, collecting embedded images
+struct Converter<'a> {
+    files: &'a HashMap<String, PathBuf>,
+    note_dir: &'a Path,
+    embeds: Vec<PathBuf>,
+    missing: Vec<String>,
+}
+
+impl Converter<'_> {
+    /// Resolve an embedded file by vault path, name, or path relative to the note
+    fn resolve(&self, target: &str) -> Option<PathBuf> {
+        let key = target.trim().trim_start_matches("./").to_lowercase();
+        self.files
+            .get(&key)
+            .or_else(|| self.files.get(note_name(&key)))
+            .cloned()
+            .or_else(|| Some(self.note_dir.join(target.trim())).filter(|p| p.is_file()))
+    }
+
+    /// Record an embedded image; false if it couldn't be found
+    fn embed(&mut self, target: &str) -> bool {
+        match self.resolve(target) {
+            Some(path) => {
+                if !self.embeds.contains(&path) {
+                    self.embeds.push(path);
+                }
+                true
+            }
+            None => {
+                self.missing.push(target.to_string());
+                false
+            }
+        }
+    }
+
+    /// Convert `[[links]]`, `![[embeds]]` and `![](image)` outside code fences
+    fn convert(&mut self, content: &str) -> String {
+        let mut out = String::with_capacity(content.len());
+        let mut in_fence = false;
+        for line in content.split_inclusive('\n') {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+            }
+            if in_fence {
+                out.push_str(line);
+            } else {
+                let line = self.convert_wiki_links(line);
+                out.push_str(&self.convert_image_links(&line));
+            }
+        }
+        out
+    }
+
+    fn convert_wiki_links(&mut self, line: &str) -> String {
+        let mut out = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("]]") else {
+                break;
+            };
+            let is_embed = rest[..start].ends_with('!');
+            out.push_str(if is_embed { &rest[..start - 1] } else { &rest[..start] });
+
+            let inner = &after[..end];
+            let (link, alias) = match inner.split_once('|') {
+                Some((link, alias)) => (link.trim(), Some(alias.trim())),
+                None => (inner.trim(), None),
+            };
+            let (target, heading) = match link.split_once('#') {
+                Some((target, heading)) => (target.trim(), Some(heading.trim())),
+                None => (link, None),
+            };
+
+            if is_embed && is_image(target) {
+                // Imported as an attachment; keep the embed if the file is missing
+                if !self.embed(target) {
+                    out.push_str(&format!("![[{}]]", inner));
+                }
+            } else {
+                // Links (and note transclusions) resolve by title in flashnotes
+                out.push_str("[[");
+                out.push_str(note_name(target));
+                if let Some(heading) = heading.filter(|h| !h.is_empty()) {
+                    out.push('#');
+                    out.push_str(heading);
+                }
+                if let Some(alias) = alias {
+                    out.push('|');
+                    out.push_str(alias);
+                }
+                out.push_str("]]");
+            }
+            rest = &after[end + 2..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// Import local images referenced as `![alt](path)`; remote ones stay links
+    fn convert_image_links(&mut self, line: &str) -> String {
+        let mut out = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(start) = rest.find("![") {
+            let after = &rest[start + 2..];
+            // `![[embed]]` left in place because its file is missing
+            if after.starts_with('[') {
+                out.push_str(&rest[..start + 2]);
+                rest = after;
+                continue;
+            }
+            let Some(close) = after.find("](") else {
+                break;
+            };
+            let Some(end) = after[close + 2..].find(')') else {
+                break;
+            };
+            let target = &after[close + 2..close + 2 + end];
+            let consumed = start + 2 + close + 2 + end + 1;
+
+            let path = percent_decode(target.trim().trim_matches(['<', '>']));
+            if !target.contains("://") && is_image(&path) && self.embed(&path) {
+                out.push_str(&rest[..start]);
+            } else {
+                out.push_str(&rest[..consumed]);
+            }
+            rest = &rest[consumed..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Hashtags for the folders a note is in ("Work/Client A" -> `#work #client-a`)
+fn folder_tags(relative: &Path) -> Vec<String> {
+    relative
+        .parent()
+        .map(|parent| {
+            parent
+                .components()
+                .map(|c| queries::slugify(&c.as_os_str().to_string_lossy()))
+                .filter(|tag| tag.chars().any(char::is_alphabetic))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn timestamp(time: std::io::Result<SystemTime>) -> Option<i64> {
+    time.ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+}
+
+/// Read every note in the vault at `root`. Each file becomes a buffer titled
+/// by its file name (as Obsidian titles it), with links converted, embedded
+/// images collected, and its folders appended as #hashtags.
+pub fn scan(root: &Path) -> AppResult<VaultScan> {
+    if !root.is_dir() {
+        return Err(AppError::Validation(format!("Not a folder: {}", root.display())));
+    }
+
+    let mut note_paths = Vec::new();
+    let mut files = Vec::new();
+    walk(root, &mut note_paths, &mut files)?;
+    note_paths.sort();
+    let files = index_files(root, &files);
+
+    let now = Utc::now().timestamp();
+    let mut scan = VaultScan {
+        notes: Vec::with_capacity(note_paths.len()),
+        missing_embeds: Vec::new(),
+    };
+    for path in note_paths {
+        let text = fs::read_to_string(&path).map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?;
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let relative_name = relative.to_string_lossy().replace('\\', "/");
+
+        let mut converter = Converter {
+            files: &files,
+            note_dir: path.parent().unwrap_or(root),
+            embeds: Vec::new(),
+            missing: Vec::new(),
+        };
+        let mut content = converter.convert(&text);
+
+        let existing_tags = queries::extract_hashtags(&content);
+        let tags: Vec<String> = folder_tags(relative)
+            .into_iter()
+            .filter(|tag| !existing_tags.contains(tag))
+            .map(|tag| format!("#{}", tag))
+            .collect();
+        if !tags.is_empty() {
+            let trimmed_len = content.trim_end().len();
+            content.truncate(trimmed_len);
+            if !content.is_empty() {
+                content.push_str("\n\n");
+            }
+            content.push_str(&tags.join(" "));
+            content.push('\n');
+        }
+
+        let title = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let metadata = fs::metadata(&path).ok();
+        let updated_at = metadata.as_ref().and_then(|m| timestamp(m.modified())).unwrap_or(now);
+        let created_at = metadata.as_ref().and_then(|m| timestamp(m.created())).unwrap_or(updated_at);
+
+        scan.missing_embeds
+            .extend(converter.missing.iter().map(|m| format!("{}: {}", relative_name, m)));
+        scan.notes.push(VaultNote {
+            buffer: Buffer {
+                id: Uuid::new_v4().to_string(),
+                title_override: (!title.is_empty() && queries::extract_title_preview(&content).0 != title)
+                    .then_some(title),
+                content,
+                created_at: created_at.min(updated_at),
+                updated_at,
+                accessed_at: updated_at,
+                is_archived: false,
+                is_pinned: false,
+                archived_at: None,
+                word_count: 0,
+                slug: String::new(),
+                source: format!("import:{}", relative_name),
+                is_locked: false,
+            },
+            embeds: converter.embeds,
+        });
+    }
+
+    Ok(scan)
+}
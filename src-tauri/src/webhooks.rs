@@ -0,0 +1,268 @@
+use crate::db::queries;
+use crate::db::webhooks::{self as db_webhooks, Webhook, WebhookEvent};
+use crate::debounce::Debouncer;
+use crate::state::AppState;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// `sha256=<hex HMAC of "<timestamp>.<body>">`, keyed with the webhook secret
+pub const SIGNATURE_HEADER: &str = "X-Flashnotes-Signature";
+
+/// Unix timestamp included in the signature (lets receivers reject replays)
+pub const TIMESTAMP_HEADER: &str = "X-Flashnotes-Timestamp";
+
+pub const EVENT_HEADER: &str = "X-Flashnotes-Event";
+
+/// Unique per delivery, identical across retries
+pub const DELIVERY_HEADER: &str = "X-Flashnotes-Delivery";
+
+/// Autosaves within this window collapse into one "updated" event
+const UPDATE_DEBOUNCE: Duration = Duration::from_secs(5);
+
+/// Delivery attempts per webhook before giving up
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Delay before the first retry; doubles on each further attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Buffer snapshot sent with created/updated/archived events
+#[derive(Serialize)]
+struct BufferPayload {
+    id: String,
+    title: String,
+    content: String,
+    slug: String,
+    source: String,
+    created_at: i64,
+    updated_at: i64,
+    is_pinned: bool,
+    is_archived: bool,
+}
+
+#[derive(Serialize)]
+struct Payload {
+    event: WebhookEvent,
+    buffer_id: String,
+    timestamp: i64,
+    /// None for deleted buffers
+    buffer: Option<BufferPayload>,
+}
+
+/// State for outgoing webhook events
+pub struct WebhookDispatcher {
+    /// Whether any webhook is enabled; skips all work when none are
+    active: AtomicBool,
+    updates: Debouncer,
+}
+
+impl WebhookDispatcher {
+    pub fn new() -> Self {
+        Self {
+            active: AtomicBool::new(false),
+            updates: Debouncer::new(),
+        }
+    }
+
+    /// Refresh the active flag from the configured webhooks
+    pub fn refresh(&self, conn: &rusqlite::Connection) {
+        match db_webhooks::list_webhooks(conn) {
+            Ok(webhooks) => self.active.store(webhooks.iter().any(|w| w.enabled), Ordering::Relaxed),
+            Err(e) => warn!("Failed to load webhooks: {}", e),
+        }
+    }
+}
+
+impl Default for WebhookDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generate a random signing secret
+pub fn generate_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Hex HMAC-SHA256 signature of a payload sent at `timestamp`
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Notify webhooks subscribed to `event` about a buffer, in the background.
+/// "updated" events are debounced per buffer.
+pub fn notify(app: &AppHandle, event: WebhookEvent, buffer_id: &str) {
+    let state = app.state::<AppState>();
+    if !state.webhooks.active.load(Ordering::Relaxed) {
+        return;
+    }
+    let generation = (event == WebhookEvent::Updated).then(|| state.webhooks.updates.bump(buffer_id));
+
+    let app = app.clone();
+    let buffer_id = buffer_id.to_string();
+    let spawned = thread::Builder::new().name("webhooks".to_string()).spawn(move || {
+        let state = app.state::<AppState>();
+        if let Some(generation) = generation {
+            thread::sleep(UPDATE_DEBOUNCE);
+            if !state.webhooks.updates.take_if_latest(&buffer_id, generation) {
+                return;
+            }
+        }
+        dispatch(&state, event, &buffer_id);
+    });
+
+    if let Err(e) = spawned {
+        warn!("Failed to start webhook delivery: {}", e);
+    }
+}
+
+/// Build the payload and deliver it to every subscribed webhook
+fn dispatch(state: &AppState, event: WebhookEvent, buffer_id: &str) {
+    let loaded = match state.get_reader() {
+        Ok(conn) => load(&conn, event, buffer_id),
+        Err(_) => {
+            let conn = state.writer.lock();
+            load(&conn, event, buffer_id)
+        }
+    };
+    let (webhooks, buffer) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            warn!("Failed to load webhooks: {}", e);
+            return;
+        }
+    };
+    if webhooks.is_empty() {
+        return;
+    }
+    // Gone before we got to it (e.g. deleted right after saving)
+    if event != WebhookEvent::Deleted && buffer.is_none() {
+        return;
+    }
+
+    let payload = Payload {
+        event,
+        buffer_id: buffer_id.to_string(),
+        timestamp: Utc::now().timestamp(),
+        buffer,
+    };
+    let body = match serde_json::to_string(&payload) {
+        Ok(body) => body,
+        Err(e) => {
+            warn!("Failed to encode webhook payload: {}", e);
+            return;
+        }
+    };
+
+    let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+    let delivery_id = Uuid::new_v4().to_string();
+    for webhook in &webhooks {
+        deliver(&agent, webhook, event, &delivery_id, &body);
+    }
+}
+
+/// Subscribed webhooks plus the buffer snapshot (if the buffer still exists)
+fn load(
+    conn: &rusqlite::Connection,
+    event: WebhookEvent,
+    buffer_id: &str,
+) -> rusqlite::Result<(Vec<Webhook>, Option<BufferPayload>)> {
+    let webhooks = db_webhooks::webhooks_for_event(conn, event)?;
+    if webhooks.is_empty() || event == WebhookEvent::Deleted {
+        return Ok((webhooks, None));
+    }
+
+    let buffer = queries::get_buffer_content(conn, buffer_id)?.map(|b| BufferPayload {
+        title: b
+            .title_override
+            .clone()
+            .unwrap_or_else(|| queries::extract_title_preview(&b.content).0),
+        id: b.id,
+        content: b.content,
+        slug: b.slug,
+        source: b.source,
+        created_at: b.created_at,
+        updated_at: b.updated_at,
+        is_pinned: b.is_pinned,
+        is_archived: b.is_archived,
+    });
+    Ok((webhooks, buffer))
+}
+
+/// POST a payload, retrying network errors, 429s and 5xx with exponential backoff
+fn deliver(agent: &ureq::Agent, webhook: &Webhook, event: WebhookEvent, delivery_id: &str, body: &str) {
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let timestamp = Utc::now().timestamp();
+        let result = agent
+            .post(&webhook.url)
+            .set("Content-Type", "application/json")
+            .set(EVENT_HEADER, event.as_str())
+            .set(DELIVERY_HEADER, delivery_id)
+            .set(TIMESTAMP_HEADER, &timestamp.to_string())
+            .set(SIGNATURE_HEADER, &format!("sha256={}", sign(&webhook.secret, timestamp, body)))
+            .send_string(body);
+
+        match result {
+            Ok(_) => {
+                debug!("Delivered {} webhook to {}", event.as_str(), webhook.url);
+                return;
+            }
+            Err(ureq::Error::Status(code, _)) if code != 429 && code < 500 => {
+                warn!("Webhook {} rejected {} event with status {}", webhook.url, event.as_str(), code);
+                return;
+            }
+            Err(e) => warn!(
+                "Webhook delivery to {} failed (attempt {}/{}): {}",
+                webhook.url, attempt, MAX_ATTEMPTS, e
+            ),
+        }
+
+        if attempt < MAX_ATTEMPTS {
+            thread::sleep(backoff);
+            backoff *= 2;
+        }
+    }
+
+    warn!("Giving up on {} webhook to {}", event.as_str(), webhook.url);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_timestamp_and_body_with_hmac_sha256() {
+        assert_eq!(
+            sign("secret", 1_700_000_000, r#"{"event":"buffer.created"}"#),
+            "7c33874d43ff07314c1507fdf42355ca8fd6607eeefe77de5b21b509ac010576"
+        );
+    }
+
+    #[test]
+    fn signature_depends_on_every_input() {
+        let signature = sign("secret", 1, "body");
+        assert_ne!(signature, sign("other", 1, "body"));
+        assert_ne!(signature, sign("secret", 2, "body"));
+        assert_ne!(signature, sign("secret", 1, "Body"));
+    }
+}
@@ -14,6 +14,8 @@ Your always-ready scratchpad. No files, no saving—everything persists automati
 | \`Cmd+W\` | Delete note |
 | \`Cmd+B\` | Toggle sidebar |
 | \`Cmd+Shift+↑/↓\` | Move note up/down |
+| \`Cmd+Option+↑/↓\` | Previous/next note |
+| \`Ctrl+Tab\` | Switch to recently used note |
 | \`Cmd+E\` | Toggle markdown preview |
 | \`Cmd+Shift+T\` | Toggle stay on top |
 | \`Cmd+Shift+C\` | Copy note to clipboard |
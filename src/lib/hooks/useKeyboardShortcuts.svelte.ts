@@ -11,10 +11,14 @@ export interface KeyboardShortcutHandlers {
   onTogglePreview: () => void;
   onMoveBufferUp: () => void;
   onMoveBufferDown: () => void;
+  onSelectPreviousBuffer: () => void;
+  onSelectNextBuffer: () => void;
+  onCycleRecentBuffer: (step: 1 | -1) => void;
   onClearSearch: () => void;
   onToggleAlwaysOnTop: () => void;
   onCopyToClipboard: () => void;
   onToggleSidebar: () => void;
+  onOpenInWindow: () => void;
 }
 
 export interface KeyboardShortcutState {
@@ -81,6 +85,27 @@ export function createKeyboardHandler(
       return;
     }
 
+    // Cmd+Alt+Up: Select previous buffer
+    if (e.key === 'ArrowUp' && e.metaKey && e.altKey && !e.shiftKey) {
+      e.preventDefault();
+      handlers.onSelectPreviousBuffer();
+      return;
+    }
+
+    // Cmd+Alt+Down: Select next buffer
+    if (e.key === 'ArrowDown' && e.metaKey && e.altKey && !e.shiftKey) {
+      e.preventDefault();
+      handlers.onSelectNextBuffer();
+      return;
+    }
+
+    // Ctrl+Tab / Ctrl+Shift+Tab: Cycle recently used buffers
+    if (e.key === 'Tab' && e.ctrlKey) {
+      e.preventDefault();
+      handlers.onCycleRecentBuffer(e.shiftKey ? -1 : 1);
+      return;
+    }
+
     // Escape: Clear search (when no modal open)
     if (e.key === 'Escape' && !state.isPaletteOpen && !state.isSettingsOpen) {
       handlers.onClearSearch();
@@ -107,5 +132,12 @@ export function createKeyboardHandler(
       handlers.onToggleSidebar();
       return;
     }
+
+    // Cmd+Shift+O: Open current buffer in its own window
+    if (e.key === 'o' && e.metaKey && e.shiftKey) {
+      e.preventDefault();
+      handlers.onOpenInWindow();
+      return;
+    }
   };
 }
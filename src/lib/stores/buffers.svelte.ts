@@ -1,28 +1,78 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { BufferSummary, SearchResult } from '$lib/types';
+import type { BufferSummary, RecoverableEdit, SearchResult } from '$lib/types';
+import { errorMessage } from '$lib/utils/errors';
+
+// Sidebar notes loaded per page (matches the backend default)
+const SIDEBAR_PAGE_SIZE = 100;
+
+// Strip markdown syntax from a single line (mirrors backend strip_markdown_line)
+function stripMarkdownLine(line: string): string {
+  let rest = line.trim();
+
+  // Block-level prefixes (may be stacked, e.g. "> - [ ] item")
+  for (;;) {
+    const before = rest;
+    rest = rest
+      .replace(/^#+/, '').trimStart()
+      .replace(/^>/, '').trimStart()
+      .replace(/^[-*+] /, '')
+      .replace(/^\d+[.)] /, '')
+      .replace(/^\[[ xX]\] /, '')
+      .trimStart();
+    if (rest === before) break;
+  }
 
-// Extract title and preview from content (mirrors backend logic)
-function extractTitlePreview(content: string): { title: string; preview: string } {
-  const lines = content.split('\n');
+  return rest
+    .replace(/!\[[^\]]*\](\([^)]*\))?/g, '')
+    .replace(/\[([^\]]*)\]\([^)]*\)/g, '$1')
+    .replace(/~~/g, '')
+    .replace(/(?<![\p{L}\p{N}])_+|_+(?![\p{L}\p{N}])/gu, '')
+    .replace(/[*`[\]]/g, '')
+    .trim();
+}
 
-  const titleLine = lines.find(line => line.trim().length > 0);
-  const title = titleLine?.trim().slice(0, 100) || 'Untitled';
+// Readable text lines: skips front matter, fenced code and horizontal rules
+function textLines(content: string): string[] {
+  let lines = content.split('\n');
 
-  // Find first non-empty line after title
-  let foundTitle = false;
-  let preview = '';
+  // Front matter only counts at the very start of the note
+  if (lines[0]?.trim() === '---') {
+    const end = lines.findIndex((l, i) => i > 0 && (l.trim() === '---' || l.trim() === '...'));
+    lines = lines.slice(end > 0 ? end + 1 : 1);
+  }
+
+  const result: string[] = [];
+  let fence: string | null = null;
   for (const line of lines) {
-    if (!foundTitle) {
-      if (line.trim().length > 0) foundTitle = true;
+    const trimmed = line.trim();
+    if (fence) {
+      if (trimmed.startsWith(fence)) fence = null;
       continue;
     }
-    if (line.trim().length > 0) {
-      preview = line.trim().slice(0, 100);
-      break;
+    if (trimmed.startsWith('```') || trimmed.startsWith('~~~')) {
+      fence = trimmed.slice(0, 3);
+      continue;
     }
+    if (/^[-*_ ]{3,}$/.test(trimmed)) continue;
+
+    const text = stripMarkdownLine(line);
+    if (text) result.push(text);
   }
+  return result;
+}
+
+// Extract title and preview from content (mirrors backend logic)
+function extractTitlePreview(content: string): { title: string; preview: string } {
+  const [title, preview] = textLines(content);
+  return {
+    title: title?.slice(0, 100) || 'Untitled',
+    preview: preview?.slice(0, 100) ?? ''
+  };
+}
 
-  return { title, preview };
+// Count whitespace-separated words (mirrors backend count_words)
+function countWords(content: string): number {
+  return content.split(/\s+/).filter(word => word.length > 0).length;
 }
 
 // Retry helper for save operations
@@ -52,22 +102,31 @@ class BufferStore {
   searchQuery = $state('');
   activeBufferId = $state<string | null>(null);
   activeContent = $state('');
+  // Content as last loaded or saved; sent with saves so the backend can
+  // merge edits made to the same note in another window
+  private baseContent = '';
   isDirty = $state(false);
   isLoading = $state(false);
   isLoadingMore = $state(false);
-  hasMoreBuffers = $state(true);
+  // Sidebar notes in total, loaded or not
+  totalBuffers = $state(0);
   lastError = $state<string | null>(null);
   saveError = $state<string | null>(null);
   saveRetryCount = $state(0);
+  archivedBuffers = $state<BufferSummary[]>([]);
+  trashedBuffers = $state<BufferSummary[]>([]);
+  // Sidebar shows only notes with this tag when set
+  tagFilter = $state<string | null>(null);
 
   // Derived state
   displayList = $derived(this.searchQuery.length > 0 ? this.searchResults : this.sidebarBuffers);
   isSearching = $derived(this.searchQuery.length > 0);
-  bufferCount = $derived(this.sidebarBuffers.length);
+  bufferCount = $derived(this.totalBuffers);
+  hasMoreBuffers = $derived(this.sidebarBuffers.length < this.totalBuffers);
 
   // Error handling utility
   private handleError(message: string, error: unknown): void {
-    const errorMsg = error instanceof Error ? error.message : String(error);
+    const errorMsg = errorMessage(error);
     this.lastError = `${message}: ${errorMsg}`;
     console.error(this.lastError);
   }
@@ -80,16 +139,28 @@ class BufferStore {
   }
 
   // Actions
+  // Reloads as many notes as are loaded already, so the sidebar keeps its scroll position
   async loadSidebarData(): Promise<void> {
     try {
       this.lastError = null;
-      this.sidebarBuffers = await invoke<BufferSummary[]>('get_sidebar_data', { offset: null });
-      this.hasMoreBuffers = this.sidebarBuffers.length >= 100;
+      const limit = Math.max(this.sidebarBuffers.length, SIDEBAR_PAGE_SIZE);
+      const [buffers, total] = await Promise.all([
+        invoke<BufferSummary[]>('get_sidebar_data', { offset: null, limit, tag: this.tagFilter }),
+        invoke<number>('get_buffer_count', { tag: this.tagFilter })
+      ]);
+      this.sidebarBuffers = buffers;
+      this.totalBuffers = total;
     } catch (error) {
       this.handleError('Failed to load sidebar data', error);
     }
   }
 
+  async setTagFilter(tag: string | null): Promise<void> {
+    this.tagFilter = tag;
+    this.sidebarBuffers = [];
+    await this.loadSidebarData();
+  }
+
   async loadMoreBuffers(): Promise<void> {
     if (this.isLoadingMore || !this.hasMoreBuffers || this.isSearching) return;
 
@@ -97,14 +168,17 @@ class BufferStore {
       this.isLoadingMore = true;
       this.lastError = null;
       const offset = this.sidebarBuffers.length;
-      const moreBuffers = await invoke<BufferSummary[]>('get_sidebar_data', { offset });
-
-      if (moreBuffers.length < 100) {
-        this.hasMoreBuffers = false;
-      }
+      const moreBuffers = await invoke<BufferSummary[]>('get_sidebar_data', {
+        offset,
+        limit: SIDEBAR_PAGE_SIZE,
+        tag: this.tagFilter
+      });
 
       if (moreBuffers.length > 0) {
         this.sidebarBuffers = [...this.sidebarBuffers, ...moreBuffers];
+      } else {
+        // Notes were removed since the count; stop asking for more
+        this.totalBuffers = this.sidebarBuffers.length;
       }
     } catch (error) {
       this.handleError('Failed to load more buffers', error);
@@ -133,7 +207,10 @@ class BufferStore {
     this.searchResults = [];
   }
 
-  async selectBuffer(id: string): Promise<void> {
+  // Ctrl+Tab cycling: a snapshot of MRU order taken when cycling starts
+  private recentCycle: { ids: string[]; index: number } | null = null;
+
+  async selectBuffer(id: string, markAccessed = true): Promise<void> {
     if (id === this.activeBufferId) return;
 
     // Save current buffer if dirty before switching
@@ -145,8 +222,14 @@ class BufferStore {
       this.isLoading = true;
       this.lastError = null;
       this.activeContent = await invoke<string>('get_buffer_content', { id });
+      this.baseContent = this.activeContent;
       this.activeBufferId = id;
       this.isDirty = false;
+
+      // Opening a note (unlike reading it) updates recently-used ordering
+      if (markAccessed) {
+        this.markAccessed(id);
+      }
     } catch (error) {
       this.handleError('Failed to get buffer content', error);
     } finally {
@@ -154,6 +237,20 @@ class BufferStore {
     }
   }
 
+  // Refresh after buffers changed outside the editor (e.g. by a plugin).
+  // Unsaved edits win over the external change.
+  async reloadBuffers(ids: string[]): Promise<void> {
+    await this.loadSidebarData();
+    if (!this.activeBufferId || !ids.includes(this.activeBufferId) || this.isDirty) return;
+
+    try {
+      this.activeContent = await invoke<string>('get_buffer_content', { id: this.activeBufferId });
+      this.baseContent = this.activeContent;
+    } catch (error) {
+      this.handleError('Failed to reload buffer', error);
+    }
+  }
+
   async createBuffer(initialContent?: string): Promise<string | null> {
     try {
       this.lastError = null;
@@ -164,8 +261,10 @@ class BufferStore {
 
       // Update UI directly - no refetch needed
       this.sidebarBuffers = [summary, ...this.sidebarBuffers];
+      this.totalBuffers++;
       this.activeBufferId = summary.id;
       this.activeContent = initialContent ?? '';
+      this.baseContent = this.activeContent;
       this.isDirty = false;
 
       return summary.id;
@@ -183,28 +282,36 @@ class BufferStore {
       this.saveError = null;
 
       // Use retry for save operations
-      const [title, preview] = await withRetry(
-        () => invoke<[string, string]>('save_buffer', {
+      const saved = this.activeContent;
+      const [title, preview, tasks_open, tasks_total, merged] = await withRetry(
+        () => invoke<[string, string, number, number, string | null]>('save_buffer', {
           id: this.activeBufferId,
-          content: this.activeContent
+          content: saved,
+          base: this.baseContent
         }),
         3, // max retries
         1000 // initial delay
       );
 
-      this.isDirty = false;
+      // Another window changed this note meanwhile; show the merged text
+      // unless the user kept typing during the save
+      this.baseContent = merged ?? saved;
+      if (merged !== null && this.activeContent === saved) {
+        this.activeContent = merged;
+      }
+      this.isDirty = this.activeContent !== this.baseContent;
       this.saveRetryCount = 0;
 
       // Update sidebar locally - no refetch needed
       this.sidebarBuffers = this.sidebarBuffers.map(b =>
         b.id === this.activeBufferId
-          ? { ...b, title, preview, updated_at: Date.now() / 1000 }
+          ? { ...b, title, preview, tasks_open, tasks_total, updated_at: Date.now() / 1000 }
           : b
       );
 
       return true;
     } catch (error) {
-      const errorMsg = error instanceof Error ? error.message : String(error);
+      const errorMsg = errorMessage(error);
       this.saveError = `Failed to save: ${errorMsg}`;
       this.saveRetryCount++;
       console.error('Save failed after retries:', error);
@@ -218,6 +325,48 @@ class BufferStore {
     return this.saveCurrentBuffer();
   }
 
+  // Write unsaved content to the crash-recovery journal (cheap, called often)
+  async journalCurrentBuffer(): Promise<void> {
+    if (!this.activeBufferId || !this.isDirty) return;
+
+    try {
+      await invoke('journal_edit', {
+        id: this.activeBufferId,
+        content: this.activeContent
+      });
+    } catch (error) {
+      console.error('Failed to journal edit:', error);
+    }
+  }
+
+  async getRecoverableEdits(): Promise<RecoverableEdit[]> {
+    try {
+      return await invoke<RecoverableEdit[]>('get_recoverable_edits');
+    } catch (error) {
+      console.error('Failed to check for recoverable edits:', error);
+      return [];
+    }
+  }
+
+  async recoverEdit(id: string): Promise<void> {
+    try {
+      const [title, preview] = await invoke<[string, string]>('recover_edit', { id });
+      this.sidebarBuffers = this.sidebarBuffers.map(b =>
+        b.id === id ? { ...b, title, preview, updated_at: Date.now() / 1000 } : b
+      );
+    } catch (error) {
+      this.handleError('Failed to recover edit', error);
+    }
+  }
+
+  async discardEdit(id: string): Promise<void> {
+    try {
+      await invoke('discard_edit', { id });
+    } catch (error) {
+      console.error('Failed to discard edit:', error);
+    }
+  }
+
   updateContent(content: string): void {
     if (content !== this.activeContent) {
       this.activeContent = content;
@@ -226,23 +375,49 @@ class BufferStore {
       // Update sidebar title/preview immediately (optimistic)
       if (this.activeBufferId) {
         const { title, preview } = extractTitlePreview(content);
+        const word_count = countWords(content);
         this.sidebarBuffers = this.sidebarBuffers.map(b =>
-          b.id === this.activeBufferId ? { ...b, title, preview } : b
+          b.id === this.activeBufferId
+            ? { ...b, title: b.has_title_override ? b.title : title, preview, word_count }
+            : b
         );
       }
     }
   }
 
+  // Set a custom title, or pass null to go back to the extracted one
+  async renameBuffer(id: string, title: string | null): Promise<void> {
+    try {
+      this.lastError = null;
+      const effectiveTitle = await invoke<string>('rename_buffer', { id, title });
+      const hasOverride = !!title?.trim();
+      this.sidebarBuffers = this.sidebarBuffers.map(b =>
+        b.id === id ? { ...b, title: effectiveTitle, has_title_override: hasOverride } : b
+      );
+    } catch (error) {
+      this.handleError('Failed to rename buffer', error);
+    }
+  }
+
   async deleteBuffer(id: string): Promise<void> {
     try {
       this.lastError = null;
       const wasActive = this.activeBufferId === id;
+      // Keep pending edits: the note can still be restored from the trash
+      if (wasActive && this.isDirty) {
+        await this.saveCurrentBuffer();
+      }
 
-      // Delete and get next buffer to select
-      const nextId = await invoke<string | null>('delete_buffer', { id });
+      // Move to the trash and get next buffer to select
+      const nextId = await invoke<string | null>('trash_buffer', { id });
 
       // Update sidebar locally
+      const trashed = this.sidebarBuffers.find(b => b.id === id);
       this.sidebarBuffers = this.sidebarBuffers.filter(b => b.id !== id);
+      if (trashed) {
+        this.trashedBuffers = [trashed, ...this.trashedBuffers];
+        this.totalBuffers--;
+      }
 
       // Select next buffer if we deleted the active one
       if (wasActive) {
@@ -278,6 +453,78 @@ class BufferStore {
     }
   }
 
+  // Archived view: notes moved out of the sidebar, most recently archived first
+  async loadArchivedBuffers(): Promise<void> {
+    try {
+      this.lastError = null;
+      this.archivedBuffers = await invoke<BufferSummary[]>('get_archived_buffers', { offset: 0 });
+    } catch (error) {
+      this.handleError('Failed to get archived buffers', error);
+    }
+  }
+
+  async archiveBuffer(id: string): Promise<void> {
+    try {
+      this.lastError = null;
+      // Save pending edits before the note leaves the sidebar
+      if (id === this.activeBufferId && this.isDirty) {
+        await this.saveCurrentBuffer();
+      }
+      await invoke('archive_buffer', { id });
+
+      const archived = this.sidebarBuffers.find(b => b.id === id);
+      this.sidebarBuffers = this.sidebarBuffers.filter(b => b.id !== id);
+      if (archived) {
+        this.archivedBuffers = [archived, ...this.archivedBuffers];
+        this.totalBuffers--;
+      }
+    } catch (error) {
+      this.handleError('Failed to archive buffer', error);
+    }
+  }
+
+  async unarchiveBuffer(id: string): Promise<void> {
+    try {
+      this.lastError = null;
+      await invoke('unarchive_buffer', { id });
+      this.archivedBuffers = this.archivedBuffers.filter(b => b.id !== id);
+      await this.loadSidebarData();
+    } catch (error) {
+      this.handleError('Failed to unarchive buffer', error);
+    }
+  }
+
+  // Trash view: deleted notes, restorable until purged, most recently deleted first
+  async loadTrashedBuffers(): Promise<void> {
+    try {
+      this.lastError = null;
+      this.trashedBuffers = await invoke<BufferSummary[]>('get_trashed_buffers', { offset: 0 });
+    } catch (error) {
+      this.handleError('Failed to get trashed buffers', error);
+    }
+  }
+
+  async restoreBuffer(id: string): Promise<void> {
+    try {
+      this.lastError = null;
+      await invoke('restore_buffer', { id });
+      this.trashedBuffers = this.trashedBuffers.filter(b => b.id !== id);
+      await this.loadSidebarData();
+    } catch (error) {
+      this.handleError('Failed to restore buffer', error);
+    }
+  }
+
+  async emptyTrash(): Promise<void> {
+    try {
+      this.lastError = null;
+      await invoke<number>('empty_trash');
+      this.trashedBuffers = [];
+    } catch (error) {
+      this.handleError('Failed to empty trash', error);
+    }
+  }
+
   async reorderBuffers(ids: string[]): Promise<void> {
     try {
       this.lastError = null;
@@ -324,6 +571,61 @@ class BufferStore {
     await this.reorderBuffers(ids);
     return true;
   }
+
+  // Switch to the next/previous buffer in sidebar or recently-used order
+  async selectAdjacentBuffer(
+    direction: 'next' | 'previous',
+    sortMode: 'sidebar' | 'recent' = 'sidebar'
+  ): Promise<void> {
+    if (!this.activeBufferId) return;
+
+    try {
+      const id = await invoke<string | null>('get_adjacent_buffer', {
+        id: this.activeBufferId,
+        direction,
+        sortMode,
+      });
+      if (id) {
+        await this.selectBuffer(id);
+      }
+    } catch (error) {
+      this.handleError('Failed to switch buffer', error);
+    }
+  }
+
+  private markAccessed(id: string): void {
+    invoke('mark_accessed', { id }).catch(error =>
+      console.error('Failed to mark buffer accessed:', error)
+    );
+  }
+
+  // Step through recently used buffers (Ctrl+Tab). The order is frozen until
+  // endRecentCycle so passing through notes doesn't reshuffle it.
+  async cycleRecentBuffer(step: 1 | -1 = 1): Promise<void> {
+    try {
+      if (!this.recentCycle) {
+        const recent = await invoke<BufferSummary[]>('get_mru_buffers', { n: 10 });
+        this.recentCycle = { ids: recent.map(b => b.id), index: 0 };
+      }
+
+      const { ids } = this.recentCycle;
+      if (ids.length < 2) return;
+
+      this.recentCycle.index = (this.recentCycle.index + step + ids.length) % ids.length;
+      await this.selectBuffer(ids[this.recentCycle.index], false);
+    } catch (error) {
+      this.handleError('Failed to switch buffer', error);
+    }
+  }
+
+  // Commit the buffer reached by cycling (on Ctrl release)
+  endRecentCycle(): void {
+    if (!this.recentCycle) return;
+    this.recentCycle = null;
+    if (this.activeBufferId) {
+      this.markAccessed(this.activeBufferId);
+    }
+  }
 }
 
 // Export singleton instance
@@ -4,6 +4,7 @@
  */
 import { invoke } from '@tauri-apps/api/core';
 import type { AppSettings } from '$lib/types';
+import { errorMessage } from '$lib/utils/errors';
 
 // Default settings
 const defaults: AppSettings = {
@@ -38,7 +39,7 @@ class SettingsStore {
   lastError = $state<string | null>(null);
 
   private handleError(message: string, error: unknown): void {
-    const errorMsg = error instanceof Error ? error.message : String(error);
+    const errorMsg = errorMessage(error);
     this.lastError = `${message}: ${errorMsg}`;
     console.error(this.lastError);
   }
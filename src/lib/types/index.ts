@@ -4,17 +4,390 @@ export interface BufferSummary {
   preview: string;
   updated_at: number;
   is_pinned: boolean;
+  word_count: number;
+  tasks_open: number;
+  tasks_total: number;
+  has_title_override: boolean;
+  is_locked: boolean;
 }
 
 export interface SearchResult {
   id: string;
   snippet: string;
   updated_at: number;
+  attachment_name: string | null;
+  tag: string | null;
+}
+
+export interface RecoverableEdit {
+  id: string;
+  title: string;
+  content: string;
+  journaled_at: number;
+  saved_at: number;
 }
 
 export interface Buffer {
   id: string;
   content: string;
+  created_at: number;
+  updated_at: number;
+  accessed_at: number;
+  is_archived: boolean;
+  is_pinned: boolean;
+  archived_at: number | null;
+  title_override: string | null;
+  word_count: number;
+  slug: string;
+  source: string;
+  is_locked: boolean;
+}
+
+export interface Card {
+  id: number;
+  buffer_id: string;
+  question: string;
+  answer: string;
+  ease: number;
+  interval_days: number;
+  repetitions: number;
+  due_at: number;
+  last_reviewed_at: number | null;
+}
+
+export type PluginCapability = 'read_buffers' | 'write_buffers';
+
+export interface PluginInfo {
+  name: string;
+  capabilities: PluginCapability[];
+  hooks: ('create' | 'save' | 'search')[];
+}
+
+export type WebhookEvent = 'created' | 'updated' | 'deleted' | 'archived';
+
+export interface Webhook {
+  id: string;
+  url: string;
+  /** Only present in the response to add_webhook */
+  secret?: string;
+  events: WebhookEvent[];
+  enabled: boolean;
+  created_at: number;
+}
+
+export interface Script {
+  id: string;
+  name: string;
+  source: string;
+  /** 0 = run on demand only */
+  schedule_minutes: number;
+  last_run_at: number | null;
+  created_at: number;
+  updated_at: number;
+}
+
+export interface ScriptReport {
+  output: string[];
+  result: string | null;
+  changed_ids: string[];
+}
+
+export interface LineChange {
+  line: number;
+  before: string;
+  after: string;
+}
+
+export interface BufferTransform {
+  id: string;
+  title: string;
+  replacements: number;
+  preview: LineChange[];
+}
+
+export interface BatchTransformReport {
+  buffers: BufferTransform[];
+  total_replacements: number;
+  missing_ids: string[];
+  dry_run: boolean;
+  backup_path: string | null;
+}
+
+export type BlogFlavor = 'hugo' | 'jekyll';
+
+export interface BlogExportReport {
+  created: number;
+  updated: number;
+}
+
+export interface VaultExportReport {
+  notes: number;
+  attachments: number;
+  missing_attachments: number;
+}
+
+export interface VaultImportReport {
+  buffers: BufferSummary[];
+  attachments: number;
+  missing_embeds: string[];
+}
+
+export interface DueTask {
+  buffer_id: string;
+  title: string;
+  line: number;
+  text: string;
+  due: string;
+  overdue: boolean;
+}
+
+export interface BoardColumn {
+  value: string;
+  cards: BufferSummary[];
+}
+
+export interface Board {
+  property: string;
+  columns: BoardColumn[];
+}
+
+// Incognito scratch buffer, kept only in memory (never saved or searched)
+export interface EphemeralBuffer {
+  id: string;
+  content: string;
+  created_at: number;
+  updated_at: number;
+}
+
+// Text streamed by an AI command called with a stream_id ("ai-stream" event)
+export interface AiStreamPayload {
+  stream_id: string;
+  delta: string;
+}
+
+export interface Translation {
+  text: string;
+  buffer: BufferSummary | null;
+}
+
+// Fused keyword + semantic search hit
+export interface HybridResult {
+  id: string;
+  title: string;
+  snippet: string;
+  updated_at: number;
+  attachment_name: string | null;
+  keyword_rank: number | null;
+  semantic_score: number | null;
+  score: number;
+}
+
+// "See also" suggestion with the signals behind it
+export interface RelatedNote {
+  id: string;
+  title: string;
+  score: number;
+  semantic_score: number | null;
+  shared_tags: string[];
+  link_distance: number | null;
+}
+
+export type PasteKind = 'url' | 'json' | 'stack_trace' | 'csv' | 'code' | 'text';
+
+// Suggested formatting for pasted text (null action for plain text)
+export interface PasteSuggestion {
+  kind: PasteKind;
+  action: string | null;
+  formatted: string | null;
+}
+
+export interface LanguageGuess {
+  language: string;
+  confidence: number;
+}
+
+export type LintRule = 'empty_title' | 'broken_wiki_link' | 'unclosed_code_fence' | 'stale_todo';
+
+export interface LintFinding {
+  buffer_id: string;
+  title: string;
+  rule: LintRule;
+  line: number | null;
+  message: string;
+}
+
+export interface BrokenLink {
+  buffer_id: string;
+  title: string;
+  line: number;
+  target: string;
+}
+
+export interface TagCount {
+  tag: string;
+  note_count: number;
+  last_used: number;
+}
+
+export interface TagPair {
+  a: string;
+  b: string;
+  note_count: number;
+}
+
+export interface TagStats {
+  tags: TagCount[];
+  pairs: TagPair[];
+}
+
+export type ActivityOperation =
+  | 'create'
+  | 'save'
+  | 'rename'
+  | 'delete'
+  | 'archive'
+  | 'unarchive'
+  | 'pin'
+  | 'unpin'
+  | 'reorder'
+  | 'undo'
+  | 'redo';
+
+export type ActivityOrigin = 'ui' | 'cli' | 'deep_link' | 'api' | 'sync' | 'script' | 'plugin' | 'system';
+
+export interface ActivityEntry {
+  id: number;
+  buffer_id: string | null;
+  operation: ActivityOperation;
+  origin: ActivityOrigin;
+  detail: string | null;
+  batch_id: string | null;
+  created_at: number;
+}
+
+export interface UndoResult {
+  operation: ActivityOperation;
+  buffer_ids: string[];
+}
+
+export interface TaskProgress {
+  task_id: string;
+  kind: string;
+  phase: string;
+  percent: number;
+  message: string | null;
+}
+
+export interface JobInfo {
+  id: string;
+  kind: string;
+  started_at: number;
+  cancelling: boolean;
+}
+
+// Temporary read-only link served on the local network
+export interface ShareLink {
+  token: string;
+  buffer_id: string;
+  url: string;
+  lan_url: string | null;
+  expires_at: number;
+}
+
+// QR code for a note: its text, or a share link for long notes
+export interface NoteQr {
+  svg: string;
+  payload: 'text' | 'link';
+  share: ShareLink | null;
+}
+
+// One file handled by the inbox folder watcher
+export interface InboxWatchResult {
+  file: string;
+  status: 'imported' | 'skipped' | 'failed';
+  buffer_id: string | null;
+  message: string | null;
+  at: number;
+}
+
+// Advisory edit lock; not acquired means another window is editing
+export interface EditLockStatus {
+  buffer_id: string;
+  acquired: boolean;
+  holder: string | null;
+}
+
+// Earlier version of a note (get_buffer_history)
+export interface RevisionSummary {
+  id: number;
+  buffer_id: string;
+  title: string;
+  preview: string;
+  size: number;
+  created_at: number;
+}
+
+// Tag with its number of active notes (list_tags)
+export interface TagSummary {
+  name: string;
+  note_count: number;
+}
+
+// Changes made by one folder or iCloud sync pass (sync_now, cloud_sync_now)
+export interface SyncReport {
+  imported: number;
+  exported: number;
+  pulled: number;
+  pushed: number;
+  trashed: number;
+  removed: number;
+  conflicts: number;
+  errors: string[];
+}
+
+// Folder sync state (get_sync_status)
+export interface SyncStatus {
+  dir: string;
+  last_synced_at: number | null;
+  last_changes: SyncReport | null;
+  last_error: string | null;
+}
+
+// iCloud sync state (get_cloud_sync_status)
+export interface CloudSyncStatus {
+  available: boolean;
+  last_synced_at: number | null;
+  last_changes: SyncReport | null;
+  last_error: string | null;
+}
+
+// Database backup that can be restored (list_backups)
+export interface BackupInfo {
+  path: string;
+  file_name: string;
+  kind: 'daily' | 'premigration' | 'preupdate' | 'prebatch' | 'prerestore';
+  created_at: number;
+  size: number;
+}
+
+// Outcome of the launch-time corruption check (get_recovery_report)
+export interface RecoveryReport {
+  problem: string;
+  corrupt_path: string | null;
+  restored_from: string | null;
+  backup_created_at: number | null;
+}
+
+// Touch ID availability and whether it is set up for the database or a note
+export interface BiometricStatus {
+  available: boolean;
+  enabled: boolean;
+}
+
+// Payload of the 'buffer-updated' event
+export interface BufferUpdated {
+  id: string;
+  change: 'content' | 'title' | 'pin' | 'archive' | 'unarchive' | 'restore' | 'lock' | 'unlock' | 'undo';
 }
 
 export interface AppSettings {
@@ -27,3 +400,21 @@ export interface AppSettings {
   vim_mode: boolean;
   always_on_top: boolean;
 }
+
+export type AppErrorCode =
+  | 'database'
+  | 'database_locked'
+  | 'database_corrupt'
+  | 'not_found'
+  | 'validation'
+  | 'conflict'
+  | 'io'
+  | 'unsupported'
+  | 'cancelled'
+  | 'internal';
+
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+  context: string | null;
+}
@@ -0,0 +1,13 @@
+import type { AppError } from '$lib/types';
+
+// Narrow an unknown rejection to the backend's structured error shape
+export function isAppError(error: unknown): error is AppError {
+  return typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+}
+
+// Extract a human-readable message from any thrown value
+export function errorMessage(error: unknown): string {
+  if (error instanceof Error) return error.message;
+  if (isAppError(error)) return error.message;
+  return String(error);
+}